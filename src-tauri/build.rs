@@ -1,3 +1,5 @@
 fn main() {
-    tauri_build::build()
+    // The library alone has no app to build
+    #[cfg(feature = "tauri")]
+    tauri_build::build();
 }
@@ -0,0 +1,87 @@
+use crate::events;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Change in overall internet reachability
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ConnectivityEvent {
+    /// Every external target failed for the configured number of cycles
+    Down { since: DateTime<Utc> },
+    /// An external target responded again after an outage
+    Up {
+        down_since: DateTime<Utc>,
+        restored_at: DateTime<Utc>,
+    },
+}
+
+impl ConnectivityEvent {
+    /// Name of the frontend event for this transition
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            Self::Down { .. } => events::INTERNET_DOWN,
+            Self::Up { .. } => events::INTERNET_UP,
+        }
+    }
+}
+
+/// Detects when all external (non-local) targets fail together
+#[derive(Debug, Clone, Default)]
+pub struct ConnectivityDetector {
+    failed_cycles: u32,
+    first_failure: Option<DateTime<Utc>>,
+    down_since: Option<DateTime<Utc>>,
+}
+
+impl ConnectivityDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the internet is currently considered down
+    pub fn is_down(&self) -> bool {
+        self.down_since.is_some()
+    }
+
+    /// Observe the outcome of one cycle of external probes
+    ///
+    /// `successes` holds one entry per external target probed this cycle. A
+    /// cycle without external targets gives no information and is ignored.
+    pub fn observe_cycle(
+        &mut self,
+        successes: &[bool],
+        threshold: u32,
+        now: DateTime<Utc>,
+    ) -> Option<ConnectivityEvent> {
+        if successes.is_empty() {
+            return None;
+        }
+
+        if successes.iter().any(|&success| success) {
+            self.failed_cycles = 0;
+            self.first_failure = None;
+            return self
+                .down_since
+                .take()
+                .map(|down_since| ConnectivityEvent::Up {
+                    down_since,
+                    restored_at: now,
+                });
+        }
+
+        self.failed_cycles = self.failed_cycles.saturating_add(1);
+        let since = *self.first_failure.get_or_insert(now);
+        if self.down_since.is_none() && self.failed_cycles >= threshold.max(1) {
+            self.down_since = Some(since);
+            return Some(ConnectivityEvent::Down { since });
+        }
+
+        None
+    }
+
+    /// Forget any failure history
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
@@ -0,0 +1,179 @@
+use super::failure::spawn_alert_command;
+use crate::metrics::http_post;
+use crate::models::{EscalationAction, EscalationTier, PingResult};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// A target's outage reaching an escalation tier
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct OutageEscalation {
+    pub target: String,
+    pub target_label: String,
+    /// Index of the tier in `escalation_tiers`
+    pub tier: usize,
+    pub action: EscalationAction,
+    pub started_at: DateTime<Utc>,
+    /// How long the target had been down when the tier fired
+    pub down_for_secs: u64,
+}
+
+/// Decides when ongoing outages reach each escalation tier
+///
+/// A tier fires with the first result at least `after_secs` into an
+/// outage, and only once until the target recovers.
+#[derive(Debug, Clone, Default)]
+pub struct Escalations {
+    /// Tiers fired during each target's ongoing outage
+    fired: HashMap<String, Vec<usize>>,
+}
+
+impl Escalations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Observe a result, returning the tiers it escalates to
+    ///
+    /// `down_since` is the start of the target's ongoing outage, `None`
+    /// while the target is up.
+    pub fn observe(
+        &mut self,
+        result: &PingResult,
+        down_since: Option<DateTime<Utc>>,
+        tiers: &[EscalationTier],
+    ) -> Vec<OutageEscalation> {
+        let Some(started_at) = down_since else {
+            self.fired.remove(&result.target);
+            return Vec::new();
+        };
+        let down_for_secs = (result.timestamp - started_at).num_seconds().max(0) as u64;
+
+        let fired = self.fired.entry(result.target.clone()).or_default();
+        let mut escalations = Vec::new();
+        for (tier, step) in tiers.iter().enumerate() {
+            if down_for_secs < step.after_secs || fired.contains(&tier) {
+                continue;
+            }
+            fired.push(tier);
+            escalations.push(OutageEscalation {
+                target: result.target.clone(),
+                target_label: result.target_label.clone(),
+                tier,
+                action: step.action.clone(),
+                started_at,
+                down_for_secs,
+            });
+        }
+        escalations
+    }
+
+    /// Forget a target's ongoing outage, e.g. when it is removed
+    pub fn remove_target(&mut self, target_address: &str) {
+        self.fired.remove(target_address);
+    }
+
+    /// Forget every ongoing outage
+    pub fn clear(&mut self) {
+        self.fired.clear();
+    }
+}
+
+/// Take an escalation's webhook or command action on a background thread
+///
+/// Commands get the failure command's environment plus `PING_DOWN_SECS`
+/// and `PING_TIER`. Notify tiers need nothing beyond the event.
+pub fn spawn_escalation_action(
+    escalation: &OutageEscalation,
+    result: &PingResult,
+    timeout: Duration,
+) -> std::io::Result<()> {
+    match escalation.action {
+        EscalationAction::Notify => {}
+        EscalationAction::Webhook { ref url } => {
+            let url = url.clone();
+            let target = escalation.target.clone();
+            let body = serde_json::to_string(escalation)?;
+            std::thread::Builder::new()
+                .name("escalation-webhook".to_string())
+                .spawn(move || {
+                    match http_post("Webhook", &url, "application/json", &body, timeout) {
+                        Ok(()) => log::info!("Sent escalation webhook for {}", target),
+                        Err(e) => log::error!("Escalation webhook for {} failed: {}", target, e),
+                    }
+                })?;
+        }
+        EscalationAction::Command { ref command } => {
+            spawn_alert_command(
+                "escalation command",
+                command,
+                result,
+                &[
+                    ("PING_DOWN_SECS", escalation.down_for_secs.to_string()),
+                    ("PING_TIER", escalation.tier.to_string()),
+                ],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PingTarget;
+    use chrono::Duration as ChronoDuration;
+
+    #[test]
+    fn test_each_tier_fires_once_at_its_threshold() {
+        let target = PingTarget::new("192.0.2.1".to_string(), "Test".to_string());
+        let tiers = vec![
+            EscalationTier {
+                after_secs: 30,
+                action: EscalationAction::Notify,
+            },
+            EscalationTier {
+                after_secs: 120,
+                action: EscalationAction::Webhook {
+                    url: "http://127.0.0.1:9/hook".to_string(),
+                },
+            },
+            EscalationTier {
+                after_secs: 600,
+                action: EscalationAction::Command {
+                    command: "true".to_string(),
+                },
+            },
+        ];
+        let start = Utc::now();
+        let mut escalations = Escalations::new();
+        // `down_since` is the outage's start in seconds, `None` for a success
+        let mut observe = |secs: i64, down_since: Option<i64>| {
+            let mut result = match down_since {
+                Some(_) => PingResult::failure(&target, "Timeout".to_string(), 0),
+                None => PingResult::success(&target, 10.0, 0),
+            };
+            result.timestamp = start + ChronoDuration::seconds(secs);
+            let down_since = down_since.map(|secs| start + ChronoDuration::seconds(secs));
+            escalations
+                .observe(&result, down_since, &tiers)
+                .into_iter()
+                .map(|e| (e.tier, e.down_for_secs))
+                .collect::<Vec<_>>()
+        };
+
+        // A lengthening outage, probed every 10 seconds
+        let mut fired = Vec::new();
+        for secs in (0..=900).step_by(10) {
+            fired.extend(observe(secs, Some(0)));
+        }
+        assert_eq!(fired, vec![(0, 30), (1, 120), (2, 600)]);
+
+        // Recovery ends the outage, so the next one escalates afresh
+        assert!(observe(910, None).is_empty());
+        assert!(observe(920, Some(920)).is_empty());
+        assert_eq!(observe(950, Some(920)), vec![(0, 30)]);
+    }
+}
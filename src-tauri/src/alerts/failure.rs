@@ -0,0 +1,219 @@
+use crate::models::PingResult;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::process::{Command, Stdio};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Failure streak of one target and when its action last ran
+#[derive(Debug, Clone, Copy, Default)]
+struct TargetFailures {
+    consecutive: u32,
+    last_fired: Option<DateTime<Utc>>,
+}
+
+/// Decides when a target's failure action should run
+///
+/// An action fires once when a target's consecutive failures reach the
+/// threshold, then not again until the target recovers and crosses the
+/// threshold anew, and never twice within the cooldown.
+#[derive(Debug, Clone, Default)]
+pub struct FailureActions {
+    targets: HashMap<String, TargetFailures>,
+}
+
+impl FailureActions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Observe a result, returning the failure count if the action should run
+    pub fn observe(
+        &mut self,
+        result: &PingResult,
+        threshold: u32,
+        cooldown: Duration,
+    ) -> Option<u32> {
+        let entry = self.targets.entry(result.target.clone()).or_default();
+        if result.success {
+            entry.consecutive = 0;
+            return None;
+        }
+
+        entry.consecutive = entry.consecutive.saturating_add(1);
+        if entry.consecutive != threshold.max(1) {
+            return None;
+        }
+        if let Some(last) = entry.last_fired {
+            let elapsed = (result.timestamp - last).to_std().unwrap_or_default();
+            if elapsed < cooldown {
+                log::info!(
+                    "Skipping failure action for {}: within cooldown",
+                    result.target
+                );
+                return None;
+            }
+        }
+        entry.last_fired = Some(result.timestamp);
+        Some(entry.consecutive)
+    }
+
+    /// Forget a target's failure history, e.g. when it is removed
+    pub fn remove_target(&mut self, target_address: &str) {
+        self.targets.remove(target_address);
+    }
+
+    /// Forget all failure history
+    pub fn clear(&mut self) {
+        self.targets.clear();
+    }
+}
+
+/// Run a failure action in the system shell on a background thread
+///
+/// Target details are passed as `PING_TARGET`, `PING_TARGET_LABEL`,
+/// `PING_FAILURES`, `PING_ERROR` and `PING_TIMESTAMP`. The returned handle
+/// finishes once the command exits.
+pub fn spawn_failure_command(
+    command: &str,
+    result: &PingResult,
+    failures: u32,
+) -> std::io::Result<JoinHandle<()>> {
+    spawn_alert_command(
+        "failure action",
+        command,
+        result,
+        &[("PING_FAILURES", failures.to_string())],
+    )
+}
+
+/// Run an alert's shell command on a background thread
+///
+/// `PING_TARGET`, `PING_TARGET_LABEL`, `PING_ERROR` and `PING_TIMESTAMP`
+/// are set from `result`, along with `env`. `name` describes the command in
+/// logs.
+pub(super) fn spawn_alert_command(
+    name: &'static str,
+    command: &str,
+    result: &PingResult,
+    env: &[(&str, String)],
+) -> std::io::Result<JoinHandle<()>> {
+    let mut cmd = shell_command(command);
+    cmd.env("PING_TARGET", &result.target)
+        .env("PING_TARGET_LABEL", &result.target_label)
+        .env("PING_ERROR", result.error.as_deref().unwrap_or(""))
+        .env("PING_TIMESTAMP", result.timestamp.to_rfc3339())
+        .envs(env.iter().cloned())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    let target = result.target.clone();
+    std::thread::Builder::new()
+        .name(name.replace(' ', "-"))
+        .spawn(move || match cmd.status() {
+            Ok(status) if status.success() => log::info!("Ran {} for {}", name, target),
+            Ok(status) => log::warn!("The {} for {} exited with {}", name, target, status),
+            Err(e) => log::error!("Failed to run {} for {}: {}", name, target, e),
+        })
+}
+
+#[cfg(windows)]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.args(["/C", command]);
+    cmd
+}
+
+#[cfg(not(windows))]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", command]);
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PingTarget;
+    use chrono::Duration as ChronoDuration;
+
+    fn failure_at(target: &PingTarget, start: DateTime<Utc>, secs: i64) -> PingResult {
+        let mut result = PingResult::failure(target, "Timeout".to_string(), 0);
+        result.timestamp = start + ChronoDuration::seconds(secs);
+        result
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_runs_once_per_crossing() {
+        let dir = std::env::temp_dir().join(format!("failure-action-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let out = dir.join("invocations.txt");
+        let command = format!(
+            "echo \"$PING_TARGET $PING_FAILURES\" >> '{}'",
+            out.display()
+        );
+
+        let target = PingTarget::new("192.0.2.1".to_string(), "Test".to_string());
+        let start = Utc::now();
+        let mut actions = FailureActions::new();
+        let mut handles = Vec::new();
+        let mut observe = |actions: &mut FailureActions, result: PingResult| {
+            if let Some(failures) = actions.observe(&result, 3, Duration::ZERO) {
+                handles.push(spawn_failure_command(&command, &result, failures).unwrap());
+            }
+        };
+
+        // Six failures in a row cross the threshold once
+        for secs in 0..6 {
+            observe(&mut actions, failure_at(&target, start, secs));
+        }
+        // Recovery, then a second crossing
+        observe(&mut actions, PingResult::success(&target, 10.0, 0));
+        for secs in 10..13 {
+            observe(&mut actions, failure_at(&target, start, secs));
+        }
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        let lines = std::fs::read_to_string(&out).unwrap();
+        assert_eq!(
+            lines.lines().collect::<Vec<_>>(),
+            vec!["192.0.2.1 3", "192.0.2.1 3"]
+        );
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_cooldown_suppresses_repeat_crossings() {
+        let target = PingTarget::new("192.0.2.1".to_string(), "Test".to_string());
+        let start = Utc::now();
+        let cooldown = Duration::from_secs(5 * 60);
+        let mut actions = FailureActions::new();
+
+        let mut fired = 0;
+        for round in 0..3 {
+            let base = round * 60;
+            for secs in base..base + 2 {
+                if actions
+                    .observe(&failure_at(&target, start, secs), 2, cooldown)
+                    .is_some()
+                {
+                    fired += 1;
+                }
+            }
+            actions.observe(&PingResult::success(&target, 10.0, 0), 2, cooldown);
+        }
+        assert_eq!(fired, 1);
+
+        // Past the cooldown the action may run again
+        let later = 10 * 60;
+        actions.observe(&failure_at(&target, start, later), 2, cooldown);
+        assert_eq!(
+            actions.observe(&failure_at(&target, start, later + 1), 2, cooldown),
+            Some(2)
+        );
+    }
+}
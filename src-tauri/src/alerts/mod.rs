@@ -0,0 +1,11 @@
+mod connectivity;
+mod escalation;
+mod failure;
+mod quorum;
+mod sound;
+
+pub use connectivity::{ConnectivityDetector, ConnectivityEvent};
+pub use escalation::{spawn_escalation_action, Escalations, OutageEscalation};
+pub use failure::{spawn_failure_command, FailureActions};
+pub use quorum::{QuorumAlertEvent, QuorumAlerts};
+pub use sound::{SoundAlert, SoundAlerts};
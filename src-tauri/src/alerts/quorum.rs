@@ -0,0 +1,231 @@
+use crate::events;
+use crate::models::{PingResult, PingTarget, QuorumAlertRule};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// A quorum rule starting or stopping to hold
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum QuorumAlertEvent {
+    /// At least `quorum` of the rule's targets are down
+    Triggered {
+        rule: String,
+        /// Addresses of the rule's targets that are down
+        down: Vec<String>,
+        since: DateTime<Utc>,
+    },
+    /// Fewer than `quorum` of the rule's targets are down again
+    Cleared {
+        rule: String,
+        down_since: DateTime<Utc>,
+        restored_at: DateTime<Utc>,
+    },
+}
+
+impl QuorumAlertEvent {
+    /// Name of the frontend event for this transition
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            Self::Triggered { .. } => events::QUORUM_ALERT,
+            Self::Cleared { .. } => events::QUORUM_CLEARED,
+        }
+    }
+}
+
+/// Failure streak of one target
+#[derive(Debug, Clone, Copy)]
+struct Streak {
+    failures: u32,
+    since: DateTime<Utc>,
+}
+
+/// A rule whose quorum currently holds
+#[derive(Debug, Clone, Copy)]
+struct Firing {
+    since: DateTime<Utc>,
+    /// Whether `Triggered` was sent, rather than held back by the cooldown
+    announced: bool,
+}
+
+/// Decides when quorum alert rules trigger and clear
+///
+/// A target counts as down once it has failed `threshold` times in a row.
+/// A rule triggers when at least `quorum` of its targets are down at once
+/// and clears when fewer are; it is announced at most once per cooldown.
+#[derive(Debug, Clone, Default)]
+pub struct QuorumAlerts {
+    streaks: HashMap<String, Streak>,
+    firing: HashMap<String, Firing>,
+    last_announced: HashMap<String, DateTime<Utc>>,
+}
+
+impl QuorumAlerts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Observe a cycle's results and evaluate every rule
+    ///
+    /// `targets` resolves the rules' target ids to addresses; ids of unknown
+    /// targets are ignored.
+    pub fn observe(
+        &mut self,
+        results: &[PingResult],
+        rules: &[QuorumAlertRule],
+        targets: &[PingTarget],
+        threshold: u32,
+        cooldown: Duration,
+    ) -> Vec<QuorumAlertEvent> {
+        for result in results {
+            if result.success {
+                self.streaks.remove(&result.target);
+                continue;
+            }
+            let streak = self.streaks.entry(result.target.clone()).or_insert(Streak {
+                failures: 0,
+                since: result.timestamp,
+            });
+            streak.failures = streak.failures.saturating_add(1);
+        }
+        self.firing
+            .retain(|name, _| rules.iter().any(|rule| rule.name == *name));
+
+        let Some(now) = results.iter().map(|r| r.timestamp).max() else {
+            return Vec::new();
+        };
+        let mut events = Vec::new();
+        for rule in rules {
+            let down: Vec<(&String, DateTime<Utc>)> = rule
+                .target_ids
+                .iter()
+                .filter_map(|id| targets.iter().find(|t| t.id == *id))
+                .filter_map(|t| {
+                    self.streaks
+                        .get(&t.address)
+                        .filter(|s| s.failures >= threshold.max(1))
+                        .map(|s| (&t.address, s.since))
+                })
+                .collect();
+
+            if down.len() < rule.quorum.max(1) {
+                if let Some(firing) = self.firing.remove(&rule.name) {
+                    if firing.announced {
+                        events.push(QuorumAlertEvent::Cleared {
+                            rule: rule.name.clone(),
+                            down_since: firing.since,
+                            restored_at: now,
+                        });
+                    }
+                }
+                continue;
+            }
+            if self.firing.contains_key(&rule.name) {
+                continue;
+            }
+
+            // Down since the last of the down targets started failing
+            let since = down.iter().map(|(_, since)| *since).max().unwrap_or(now);
+            let announced = self.last_announced.get(&rule.name).map_or(true, |last| {
+                (now - *last).to_std().unwrap_or_default() >= cooldown
+            });
+            self.firing
+                .insert(rule.name.clone(), Firing { since, announced });
+            if !announced {
+                log::info!("Skipping quorum alert {}: within cooldown", rule.name);
+                continue;
+            }
+            self.last_announced.insert(rule.name.clone(), now);
+            events.push(QuorumAlertEvent::Triggered {
+                rule: rule.name.clone(),
+                down: down
+                    .iter()
+                    .map(|(address, _)| address.to_string())
+                    .collect(),
+                since,
+            });
+        }
+        events
+    }
+
+    /// Forget a target's failure streak, e.g. when it is removed
+    pub fn remove_target(&mut self, target_address: &str) {
+        self.streaks.remove(target_address);
+    }
+
+    /// Forget all failure history
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cycle(targets: &[PingTarget], down: &[usize], secs: i64) -> Vec<PingResult> {
+        let at = DateTime::<Utc>::from_timestamp(1_700_000_000 + secs, 0).unwrap();
+        targets
+            .iter()
+            .enumerate()
+            .map(|(i, target)| {
+                let mut result = if down.contains(&i) {
+                    PingResult::failure(target, "Timeout".to_string(), 0)
+                } else {
+                    PingResult::success(target, 10.0, 0)
+                };
+                result.timestamp = at;
+                result
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_alerts_only_when_quorum_of_targets_is_down() {
+        let targets: Vec<PingTarget> = (1..=3)
+            .map(|i| PingTarget::new(format!("192.0.2.{}", i), format!("DNS {}", i)))
+            .collect();
+        let rules = vec![QuorumAlertRule {
+            name: "DNS servers".to_string(),
+            target_ids: targets.iter().map(|t| t.id.clone()).collect(),
+            quorum: 2,
+        }];
+        let mut alerts = QuorumAlerts::new();
+        let mut observe = |down: &[usize], secs: i64| {
+            alerts.observe(
+                &cycle(&targets, down, secs),
+                &rules,
+                &targets,
+                2,
+                Duration::ZERO,
+            )
+        };
+
+        // One server down, however long, is not enough
+        for secs in 0..5 {
+            assert!(observe(&[0], secs).is_empty());
+        }
+
+        // A second server reaching the threshold triggers the rule once
+        assert!(observe(&[0, 1], 5).is_empty());
+        let events = observe(&[0, 1], 6);
+        assert_eq!(
+            events,
+            vec![QuorumAlertEvent::Triggered {
+                rule: "DNS servers".to_string(),
+                down: vec!["192.0.2.1".to_string(), "192.0.2.2".to_string()],
+                since: DateTime::<Utc>::from_timestamp(1_700_000_005, 0).unwrap(),
+            }]
+        );
+        assert!(observe(&[0, 1, 2], 7).is_empty());
+
+        // Back below the quorum clears it
+        let events = observe(&[2], 8);
+        assert!(matches!(
+            events.as_slice(),
+            [QuorumAlertEvent::Cleared { rule, .. }] if rule == "DNS servers"
+        ));
+    }
+}
@@ -0,0 +1,72 @@
+use super::FailureActions;
+use crate::models::{PingResult, PingTarget};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::time::Duration;
+
+/// A sound-enabled target going down, for the frontend to play a sound
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct SoundAlert {
+    pub target_id: String,
+    pub target: String,
+    pub target_label: String,
+    /// Failures in a row when the target counted as down
+    pub failures: u32,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Decides when targets with `sound_alert` should sound
+///
+/// Follows the failure action rules, once per crossing of the threshold and
+/// never twice within the cooldown, but keeps its own cooldown so sounds and
+/// commands don't hold each other back.
+#[derive(Debug, Clone, Default)]
+pub struct SoundAlerts {
+    failures: FailureActions,
+}
+
+impl SoundAlerts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Observe a cycle's results, returning the targets that just went down
+    ///
+    /// Results are matched to targets by address, so `targets` may list
+    /// targets that were not probed this cycle.
+    pub fn observe(
+        &mut self,
+        targets: &[PingTarget],
+        results: &[PingResult],
+        threshold: u32,
+        cooldown: Duration,
+    ) -> Vec<SoundAlert> {
+        results
+            .iter()
+            .filter_map(|result| {
+                let target = targets
+                    .iter()
+                    .find(|target| target.address == result.target && target.sound_alert)?;
+                let failures = self.failures.observe(result, threshold, cooldown)?;
+                Some(SoundAlert {
+                    target_id: target.id.clone(),
+                    target: result.target.clone(),
+                    target_label: result.target_label.clone(),
+                    failures,
+                    timestamp: result.timestamp,
+                })
+            })
+            .collect()
+    }
+
+    /// Forget a target's failure history, e.g. when it is removed
+    pub fn remove_target(&mut self, target_address: &str) {
+        self.failures.remove_target(target_address);
+    }
+
+    /// Forget all failure history
+    pub fn clear(&mut self) {
+        self.failures.clear();
+    }
+}
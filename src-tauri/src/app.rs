@@ -0,0 +1,177 @@
+use crate::state::AppState;
+use crate::{commands, logging, monitor};
+use std::sync::Arc;
+use tauri::menu::{Menu, MenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{App, AppHandle, Manager, Runtime, WindowEvent};
+
+/// Label of the main window
+const MAIN_WINDOW: &str = "main";
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    // Initialize logger
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("debug"))
+        .format_timestamp_millis()
+        .init();
+
+    log::info!("Starting Ping Connectivity Monitor");
+
+    let state = Arc::new(AppState::new());
+    match state.restore_recent_snapshot(&logging::default_snapshot_path()) {
+        Ok(0) => {}
+        Ok(count) => log::info!("Restored {} recent results from before restart", count),
+        Err(e) => log::error!("Failed to restore recent results: {}", e),
+    }
+    if let Err(e) = state.load_lifetime_stats(&logging::default_lifetime_path()) {
+        log::error!("Failed to load lifetime statistics: {}", e);
+    }
+    if let Err(e) = state.load_config(&logging::default_config_path()) {
+        log::error!("Failed to load config: {}", e);
+    }
+    if let Err(e) = state.load_monitor_switches(&logging::default_switches_path()) {
+        log::error!("Failed to load monitoring switches: {}", e);
+    }
+    let exit_state = Arc::clone(&state);
+
+    tauri::Builder::default()
+        .plugin(tauri_plugin_shell::init())
+        .manage(Arc::clone(&state))
+        .setup(move |app| {
+            // Route backend events to the frontend
+            state.set_event_sink(Arc::new(app.handle().clone()));
+            setup_tray(app)?;
+            let auto = Arc::clone(&state);
+            tauri::async_runtime::spawn(async move {
+                monitor::auto_start(&auto, AppState::create_backend);
+            });
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            // Keep monitoring in the tray instead of exiting
+            if let WindowEvent::CloseRequested { api, .. } = event {
+                api.prevent_close();
+                if let Err(e) = window.hide() {
+                    log::error!("Failed to hide window: {}", e);
+                }
+            }
+        })
+        .invoke_handler(tauri::generate_handler![
+            commands::start_pinging,
+            commands::stop_pinging,
+            commands::quit,
+            commands::replay_log,
+            commands::pause_pinging,
+            commands::resume_pinging,
+            commands::set_monitoring_enabled,
+            commands::is_monitoring_enabled,
+            commands::get_ping_state,
+            commands::get_snapshot,
+            commands::get_statistics,
+            commands::query_statistics,
+            commands::get_resolved_ip_statistics,
+            commands::get_statistics_for_target,
+            commands::get_target_summary,
+            commands::get_latency_histogram,
+            commands::get_target_errors,
+            commands::get_failure_breakdown,
+            commands::get_path_report,
+            commands::probe_path,
+            commands::focus_target,
+            commands::set_session_label,
+            commands::get_session_summary,
+            commands::get_status_line,
+            commands::set_event_subscriptions,
+            commands::get_recent_pings,
+            commands::get_recent_outages,
+            commands::get_alarming_targets,
+            commands::get_log_path,
+            commands::analyze_log,
+            commands::convert_log,
+            commands::purge_target_logs,
+            commands::compact_logs,
+            commands::import_ping_output,
+            commands::compare_periods,
+            commands::list_sessions,
+            commands::analyze_session,
+            commands::correlate_targets,
+            commands::get_hourly_profile,
+            commands::get_quality_timeseries,
+            commands::set_ping_interval,
+            commands::get_targets,
+            commands::add_target,
+            commands::add_targets_from_template,
+            commands::remove_target,
+            commands::add_probe_group,
+            commands::remove_probe_group,
+            commands::get_group_statistics,
+            commands::update_target,
+            commands::toggle_target,
+            commands::get_config,
+            commands::save_config,
+            commands::patch_config,
+            commands::apply_low_resource_preset,
+            commands::apply_high_resolution_preset,
+            commands::validate_config,
+            commands::get_event_schemas,
+            commands::capture_baseline,
+            commands::get_inflight_count,
+            commands::get_scheduler_stats,
+            commands::get_resource_stats,
+            commands::get_probe_counters,
+            commands::get_resolved_addresses,
+            commands::benchmark_backend,
+            commands::run_diagnostic,
+            commands::run_self_test,
+            commands::check_icmp_permission,
+            commands::get_preset_targets,
+            commands::reset_statistics,
+            commands::reset_target_latency,
+            commands::open_log_directory,
+        ])
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(move |_app, event| {
+            if let tauri::RunEvent::Exit = event {
+                exit_state.shutdown(&logging::default_snapshot_path());
+            }
+        });
+}
+
+/// Add the tray icon with its show/hide and quit menu
+fn setup_tray<R: Runtime>(app: &App<R>) -> tauri::Result<()> {
+    let toggle = MenuItem::with_id(app, "toggle", "Show/Hide", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let menu = Menu::with_items(app, &[&toggle, &quit])?;
+
+    let mut tray = TrayIconBuilder::new()
+        .menu(&menu)
+        .tooltip("Ping Connectivity Monitor")
+        .on_menu_event(|app, event| match event.id.as_ref() {
+            "toggle" => toggle_main_window(app),
+            // Exiting runs the shutdown in the exit event handler
+            "quit" => app.exit(0),
+            _ => {}
+        });
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app)?;
+    Ok(())
+}
+
+/// Show the main window if hidden, otherwise hide it
+fn toggle_main_window<R: Runtime>(app: &AppHandle<R>) {
+    let Some(window) = app.get_webview_window(MAIN_WINDOW) else {
+        return;
+    };
+
+    let result = if window.is_visible().unwrap_or(false) {
+        window.hide()
+    } else {
+        window.show().and_then(|_| window.set_focus())
+    };
+    if let Err(e) = result {
+        log::error!("Failed to toggle main window: {}", e);
+    }
+}
@@ -0,0 +1,126 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError, error::TryRecvError};
+
+/// Default number of messages buffered for each subscriber
+pub const DEFAULT_BROADCAST_CAPACITY: usize = 256;
+
+/// Sending half of a broadcast channel
+///
+/// Every subscriber gets its own copy of each message. A subscriber that
+/// falls more than `capacity` messages behind skips the oldest ones; the
+/// skip is logged and counted rather than surfacing as an error.
+#[derive(Debug)]
+pub struct Broadcaster<T> {
+    name: &'static str,
+    tx: broadcast::Sender<T>,
+    capacity: usize,
+}
+
+impl<T: Clone> Broadcaster<T> {
+    pub fn new(name: &'static str, capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        let (tx, _) = broadcast::channel(capacity);
+        Self { name, tx, capacity }
+    }
+
+    /// Messages buffered per subscriber
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Send a message to every subscriber, returning how many there are
+    pub fn send(&self, message: T) -> usize {
+        self.tx.send(message).unwrap_or(0)
+    }
+
+    /// Receive messages sent from now on
+    pub fn subscribe(&self) -> Subscriber<T> {
+        Subscriber {
+            name: self.name,
+            rx: self.tx.subscribe(),
+            skipped: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Number of live subscribers
+    pub fn subscriber_count(&self) -> usize {
+        self.tx.receiver_count()
+    }
+}
+
+/// Receiving half of a broadcast channel
+#[derive(Debug)]
+pub struct Subscriber<T> {
+    name: &'static str,
+    rx: broadcast::Receiver<T>,
+    skipped: Arc<AtomicU64>,
+}
+
+impl<T: Clone> Subscriber<T> {
+    /// Wait for the next message, or `None` once the sender is gone
+    ///
+    /// Messages lost to lag are skipped with a warning.
+    pub async fn recv(&mut self) -> Option<T> {
+        loop {
+            match self.rx.recv().await {
+                Ok(message) => return Some(message),
+                Err(RecvError::Lagged(count)) => self.record_lag(count),
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// Take the next message if one is waiting
+    pub fn try_recv(&mut self) -> Option<T> {
+        loop {
+            match self.rx.try_recv() {
+                Ok(message) => return Some(message),
+                Err(TryRecvError::Lagged(count)) => self.record_lag(count),
+                Err(TryRecvError::Empty | TryRecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// Total messages this subscriber has missed by lagging
+    pub fn skipped(&self) -> u64 {
+        self.skipped.load(Ordering::Relaxed)
+    }
+
+    fn record_lag(&self, count: u64) {
+        self.skipped.fetch_add(count, Ordering::Relaxed);
+        log::warn!(
+            "Subscriber to {} fell behind and skipped {} messages",
+            self.name,
+            count
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_lagging_subscriber_recovers() {
+        let broadcaster = Broadcaster::new("test", 2);
+        let mut subscriber = broadcaster.subscribe();
+
+        for n in 1..=5 {
+            broadcaster.send(n);
+        }
+
+        // Only the newest messages are still buffered
+        assert_eq!(subscriber.recv().await, Some(4));
+        assert_eq!(subscriber.skipped(), 3);
+        assert_eq!(subscriber.try_recv(), Some(5));
+        assert_eq!(subscriber.try_recv(), None);
+
+        // Later messages arrive normally
+        broadcaster.send(6);
+        assert_eq!(subscriber.recv().await, Some(6));
+
+        drop(broadcaster);
+        assert_eq!(subscriber.recv().await, None);
+    }
+}
@@ -1,97 +1,79 @@
-use crate::models::{AppConfig, PingResult, PingState, PingStatistics, PingTarget};
+use crate::channels::Broadcaster;
+use crate::logging::{self, stream_results};
+use crate::models::{
+    AlarmInfo, AppConfig, AppSnapshot, BackendBenchmark, BackendKind, ConfigIssue, Correlation,
+    CsvExportOptions, DiagnosticReport, ExportFormat, GroupPolicy, HourStat, IcmpCapability,
+    LogCompaction, LoggedSession, Outage, PathReport, PeriodComparison, PingError, PingResult,
+    PingState, PingStatistics, PingTarget, ProbeCounters, ProbeGroup, QualityPoint, ResolvedAddress,
+    ResourceStats, SchedulerStats, SelfTestResult, SessionSummary, StatField, StatFilter,
+    TargetAdded, TargetError, TargetSummary, TargetUpdate, TimeRange,
+};
+use crate::monitor;
+use crate::ping;
+use crate::schema::{self, SchemaCatalog};
 use crate::state::AppState;
+use crate::stats::{self, HistogramBucket, LatencyBounds, StatsCalculator};
+use crate::template;
+use crate::validation::{self, MIN_PING_INTERVAL_MS};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use std::sync::Arc;
-use std::time::Duration;
-use tauri::{AppHandle, Emitter, State};
-use tokio::sync::broadcast;
+use tauri::{AppHandle, State};
+
+/// Run blocking work, such as reading logs, off the async runtime
+async fn run_blocking<T, F>(task: &str, work: F) -> Result<T, String>
+where
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+    T: Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(work)
+        .await
+        .map_err(|e| format!("{} failed: {}", task, e))?
+}
 
 /// Start continuous ping monitoring
 #[tauri::command]
-pub async fn start_pinging(
-    app: AppHandle,
+pub async fn start_pinging(state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    monitor::start_monitoring(&state, AppState::create_backend)
+}
+
+/// Replay a log file as live events at `speed` times its original pace
+///
+/// Sends no probes and leaves live statistics alone. Stop it like live
+/// monitoring, with `stop_pinging`.
+#[tauri::command]
+pub async fn replay_log(
+    path: String,
+    speed: f64,
     state: State<'_, Arc<AppState>>,
 ) -> Result<(), String> {
-    let current_state = state.get_ping_state();
-    if current_state == PingState::Running {
-        return Err("Pinging is already running".to_string());
+    if !speed.is_finite() || speed <= 0.0 {
+        return Err("Speed must be a positive number".to_string());
     }
-
-    // Create stop signal channel
-    let (tx, _) = broadcast::channel::<()>(1);
-    *state.stop_signal.write() = Some(tx.clone());
-
-    // Reset stats if starting fresh
-    if current_state == PingState::Stopped {
-        state.reset_stats();
+    if state.get_ping_state() != PingState::Stopped {
+        return Err("Stop monitoring before replaying a log".to_string());
     }
 
-    state.set_ping_state(PingState::Running);
+    let results: Vec<_> = run_blocking("Reading the log", move || {
+        stream_results(Path::new(&path))
+            .map(Iterator::collect)
+            .map_err(|e| format!("Failed to read log file: {}", e))
+    })
+    .await?;
 
-    // Clone what we need for the async task
-    let state_clone = Arc::clone(&state);
-    let app_clone = app.clone();
-
-    // Spawn the ping loop
-    tokio::spawn(async move {
-        let mut rx = tx.subscribe();
-        
-        loop {
-            // Check for stop signal
-            if rx.try_recv().is_ok() {
-                break;
-            }
-
-            // Check if still running
-            if state_clone.get_ping_state() != PingState::Running {
-                break;
-            }
-
-            // Get enabled targets
-            let targets = state_clone.get_enabled_targets();
-            if targets.is_empty() {
-                tokio::time::sleep(Duration::from_millis(100)).await;
-                continue;
-            }
-
-            // Create pinger
-            let pinger = state_clone.create_pinger();
-            let sequence = state_clone.next_sequence();
-
-            // Ping all enabled targets
-            for target in targets {
-                // Execute ping synchronously (it's already fast)
-                let result = pinger.ping(&target, sequence);
-                
-                log::debug!("Ping result for {}: {:?}", target.address, result);
-                
-                // Add result to state
-                state_clone.add_result(result.clone());
-                
-                // Emit event to frontend
-                match app_clone.emit("ping-result", &result) {
-                    Ok(_) => log::debug!("Emitted ping-result event for {}", target.address),
-                    Err(e) => log::error!("Failed to emit ping-result event: {}", e),
-                }
-            }
-
-            // Emit stats update
-            let stats = state_clone.get_all_stats();
-            log::debug!("Emitting stats-update with {} stats", stats.len());
-            match app_clone.emit("stats-update", &stats) {
-                Ok(_) => log::debug!("Emitted stats-update event"),
-                Err(e) => log::error!("Failed to emit stats-update event: {}", e),
-            }
-
-            // Wait for next interval
-            let interval_ms = state_clone.get_ping_interval();
-            tokio::time::sleep(Duration::from_millis(interval_ms)).await;
-        }
+    let stop_signal = Broadcaster::new("stop signal", 1);
+    let stop = stop_signal.subscribe();
+    *state.stop_signal.write() = Some(stop_signal);
+    state.set_ping_state(PingState::Running);
+    let generation = state.begin_loop();
 
-        // Update state when loop ends
-        if state_clone.get_ping_state() == PingState::Running {
-            state_clone.set_ping_state(PingState::Stopped);
-        }
-    });
+    tokio::spawn(monitor::replay_results(
+        Arc::clone(&state),
+        generation,
+        stop,
+        results,
+        speed,
+    ));
 
     Ok(())
 }
@@ -100,32 +82,55 @@ pub async fn start_pinging(
 #[tauri::command]
 pub async fn stop_pinging(state: State<'_, Arc<AppState>>) -> Result<(), String> {
     state.set_ping_state(PingState::Stopped);
+    state.schedule.lock().clear();
+    state.end_session();
     
     // Send stop signal
     if let Some(tx) = state.stop_signal.read().as_ref() {
-        let _ = tx.send(());
+        tx.send(());
     }
     
     Ok(())
 }
 
+/// Stop monitoring, save state and exit the app
+///
+/// Closing the window only hides it to the tray; this is the way out.
+#[tauri::command]
+pub fn quit(app: AppHandle, state: State<'_, Arc<AppState>>) {
+    state.shutdown(&logging::default_snapshot_path());
+    app.exit(0);
+}
+
+/// Turn all probing on or off without stopping the ping loop
+///
+/// While disabled no network traffic is sent, whatever the ping state.
+#[tauri::command]
+pub fn set_monitoring_enabled(enabled: bool, state: State<'_, Arc<AppState>>) {
+    state.set_monitoring_enabled(enabled);
+}
+
+/// Check whether probing is enabled
+#[tauri::command]
+pub fn is_monitoring_enabled(state: State<'_, Arc<AppState>>) -> bool {
+    state.is_monitoring_enabled()
+}
+
 /// Pause ping monitoring
 #[tauri::command]
 pub async fn pause_pinging(state: State<'_, Arc<AppState>>) -> Result<(), String> {
     if state.get_ping_state() == PingState::Running {
         state.set_ping_state(PingState::Paused);
+        state.pause_schedule();
     }
     Ok(())
 }
 
 /// Resume ping monitoring
 #[tauri::command]
-pub async fn resume_pinging(
-    app: AppHandle,
-    state: State<'_, Arc<AppState>>,
-) -> Result<(), String> {
+pub async fn resume_pinging(state: State<'_, Arc<AppState>>) -> Result<(), String> {
     if state.get_ping_state() == PingState::Paused {
-        start_pinging(app, state).await?;
+        start_pinging(state).await?;
     }
     Ok(())
 }
@@ -136,12 +141,39 @@ pub fn get_ping_state(state: State<'_, Arc<AppState>>) -> PingState {
     state.get_ping_state()
 }
 
-/// Get statistics for all targets
+/// Get ping state, statistics, recent results and config in one consistent
+/// view
+#[tauri::command]
+pub fn get_snapshot(state: State<'_, Arc<AppState>>) -> AppSnapshot {
+    state.get_snapshot()
+}
+
+/// Get statistics for all targets, e.g. on load before `stats-update` deltas
 #[tauri::command]
 pub fn get_statistics(state: State<'_, Arc<AppState>>) -> Vec<PingStatistics> {
     state.get_all_stats()
 }
 
+/// Get a hostname target's statistics per resolved IP
+#[tauri::command]
+pub fn get_resolved_ip_statistics(
+    target: String,
+    state: State<'_, Arc<AppState>>,
+) -> Vec<PingStatistics> {
+    state.get_resolved_ip_stats(&target)
+}
+
+/// Get statistics filtered and sorted for a large target table
+#[tauri::command]
+pub fn query_statistics(
+    sort_by: StatField,
+    descending: bool,
+    filter: Option<StatFilter>,
+    state: State<'_, Arc<AppState>>,
+) -> Vec<PingStatistics> {
+    state.query_statistics(sort_by, descending, filter)
+}
+
 /// Get statistics for a specific target
 #[tauri::command]
 pub fn get_statistics_for_target(
@@ -151,13 +183,135 @@ pub fn get_statistics_for_target(
     state.get_stats_for_target(&target)
 }
 
-/// Get recent ping results
+/// Get a compact latency summary for one target, e.g. for a chart tooltip
+#[tauri::command]
+pub fn get_target_summary(
+    id: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<TargetSummary, String> {
+    state
+        .get_target_summary(&id)
+        .ok_or_else(|| "Target not found".to_string())
+}
+
+/// Get a target's latency distribution as histogram buckets
+#[tauri::command]
+pub fn get_latency_histogram(
+    id: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<HistogramBucket>, String> {
+    state
+        .get_latency_histogram(&id)
+        .ok_or_else(|| "Target not found".to_string())
+}
+
+/// Get the distinct errors a target failed with recently, newest first
+#[tauri::command]
+pub fn get_target_errors(
+    id: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<TargetError>, String> {
+    state
+        .get_target_errors(&id)
+        .ok_or_else(|| "Target not found".to_string())
+}
+
+/// Count a target's failures by classified error kind, e.g. for a pie chart
+///
+/// Covers the current session, or the logged results within `range` if given.
+#[tauri::command]
+pub async fn get_failure_breakdown(
+    id: String,
+    range: Option<TimeRange>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<HashMap<PingError, u64>, String> {
+    let Some(range) = range else {
+        return state
+            .get_failure_breakdown(&id)
+            .ok_or_else(|| "Target not found".to_string());
+    };
+
+    let target = state
+        .get_targets()
+        .into_iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| "Target not found".to_string())?;
+    let log_dir = state.get_log_path();
+    run_blocking("Failure breakdown", move || {
+        let results = logging::query_logs(&log_dir, Some(&target.address), None, range)
+            .map_err(|e| format!("Failed to read logs: {}", e))?;
+        Ok(StatsCalculator::from_results(results)
+            .failure_breakdown(&target.address)
+            .unwrap_or_default())
+    })
+    .await
+}
+
+/// Get per-hop statistics of a target's traced path
+#[tauri::command]
+pub fn get_path_report(
+    id: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<PathReport, String> {
+    state
+        .get_path_report(&id)
+        .ok_or_else(|| "Target not found".to_string())
+}
+
+/// Trace the path to a target now and return its per-hop statistics
+#[tauri::command]
+pub async fn probe_path(
+    id: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<PathReport, String> {
+    let target = state
+        .get_targets()
+        .into_iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| "Target not found".to_string())?;
+    let probe = state.create_path_probe();
+    let state = Arc::clone(&state);
+
+    tokio::task::spawn_blocking(move || state.trace_path(&probe, &target))
+        .await
+        .map_err(|e| format!("Path trace failed: {}", e))?
+}
+
+/// Label the monitoring session, e.g. "after router swap"
+///
+/// A changed label starts a sub-session, marked with a `session-marker` event.
+#[tauri::command]
+pub fn set_session_label(label: Option<String>, state: State<'_, Arc<AppState>>) {
+    state.set_session_label(label);
+}
+
+/// Get an overview of the current session
+#[tauri::command]
+pub fn get_session_summary(state: State<'_, Arc<AppState>>) -> SessionSummary {
+    state.get_session_summary()
+}
+
+/// One-line status such as `Running | 3/4 up | avg 18ms | loss 0.4% | 2 alarms`,
+/// for menu bars and scripts
+#[tauri::command]
+pub fn get_status_line(state: State<'_, Arc<AppState>>) -> String {
+    state.get_status_line()
+}
+
+/// Get the latest ping results, newest first unless `newest_first` is false
 #[tauri::command]
 pub fn get_recent_pings(
     count: Option<usize>,
+    newest_first: Option<bool>,
     state: State<'_, Arc<AppState>>,
 ) -> Vec<PingResult> {
-    state.get_recent_results(count)
+    state.get_recent_results(count, newest_first.unwrap_or(true))
+}
+
+/// Get the most recent outages, newest first
+#[tauri::command]
+pub fn get_recent_outages(count: Option<usize>, state: State<'_, Arc<AppState>>) -> Vec<Outage> {
+    state.get_recent_outages(count.unwrap_or(usize::MAX))
 }
 
 /// Get log directory path
@@ -166,11 +320,251 @@ pub fn get_log_path(state: State<'_, Arc<AppState>>) -> String {
     state.get_log_path().to_string_lossy().to_string()
 }
 
+/// Compute statistics from a past log file
+#[tauri::command]
+pub async fn analyze_log(path: String) -> Result<Vec<PingStatistics>, String> {
+    run_blocking("Log analysis", move || {
+        let results = stream_results(Path::new(&path))
+            .map_err(|e| format!("Failed to read log file: {}", e))?;
+        Ok(StatsCalculator::from_results(results).get_all_stats())
+    })
+    .await
+}
+
+/// Convert a log file (JSON Lines or binary) to JSON Lines or CSV
+///
+/// The converted file is written next to the source and its path returned.
+/// `csv_options` choose the CSV latency unit, precision, failure marker and
+/// timestamp format; they default to milliseconds at full precision, empty
+/// cells and RFC 3339.
+#[tauri::command]
+pub async fn convert_log(
+    path: String,
+    format: ExportFormat,
+    csv_options: Option<CsvExportOptions>,
+) -> Result<String, String> {
+    run_blocking("Log conversion", move || {
+        write_converted_log(Path::new(&path), format, csv_options)
+    })
+    .await
+}
+
+/// Write a log file converted to `format` next to it, returning its path
+fn write_converted_log(
+    source: &Path,
+    format: ExportFormat,
+    csv_options: Option<CsvExportOptions>,
+) -> Result<String, String> {
+    let extension = match format {
+        ExportFormat::Jsonl => "jsonl",
+        ExportFormat::Csv => "csv",
+    };
+    let destination = source.with_extension(extension);
+    if destination == source {
+        return Err("Log file is already in that format".to_string());
+    }
+
+    let results = stream_results(source).map_err(|e| format!("Failed to read log file: {}", e))?;
+    let file = std::fs::File::create(&destination)
+        .map_err(|e| format!("Failed to create {}: {}", destination.display(), e))?;
+    let mut writer = std::io::BufWriter::new(file);
+    let written = match format {
+        ExportFormat::Jsonl => logging::write_jsonl(&mut writer, results),
+        ExportFormat::Csv => {
+            logging::write_csv(&mut writer, results, &csv_options.unwrap_or_default())
+        }
+    };
+    written
+        .and_then(|_| std::io::Write::flush(&mut writer))
+        .map_err(|e| format!("Failed to write {}: {}", destination.display(), e))?;
+
+    Ok(destination.to_string_lossy().to_string())
+}
+
+/// Delete a target's logged results between `from` and `to` from disk
+///
+/// `target` is a target id or address. Returns the number of results removed.
+#[tauri::command]
+pub async fn purge_target_logs(
+    target: String,
+    from: chrono::DateTime<chrono::Utc>,
+    to: chrono::DateTime<chrono::Utc>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<usize, String> {
+    let state = Arc::clone(&state);
+    run_blocking("Purging logs", move || {
+        state.purge_target_logs(&target, &TimeRange::new(from, to))
+    })
+    .await
+}
+
+/// Merge a date's log fragments into one sorted file without duplicates
+#[tauri::command]
+pub async fn compact_logs(
+    date: chrono::NaiveDate,
+    state: State<'_, Arc<AppState>>,
+) -> Result<LogCompaction, String> {
+    let state = Arc::clone(&state);
+    run_blocking("Log compaction", move || state.compact_logs(date)).await
+}
+
+/// Backfill the logs from a saved `ping` or `fping` transcript
+///
+/// Returns the number of results imported.
+#[tauri::command]
+pub async fn import_ping_output(
+    path: String,
+    target_label: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<usize, String> {
+    let state = Arc::clone(&state);
+    tokio::task::spawn_blocking(move || state.import_ping_output(Path::new(&path), &target_label))
+        .await
+        .map_err(|e| format!("Import failed: {}", e))?
+}
+
+/// Emit only the named events, e.g. to save work when a view hides the chart
+///
+/// Every event is emitted until this is first called.
+#[tauri::command]
+pub fn set_event_subscriptions(
+    names: Vec<String>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<(), String> {
+    state.set_event_subscriptions(names)
+}
+
+/// Compare a target's logged results over two time periods
+#[tauri::command]
+pub async fn compare_periods(
+    target: String,
+    period_a: TimeRange,
+    period_b: TimeRange,
+    state: State<'_, Arc<AppState>>,
+) -> Result<PeriodComparison, String> {
+    let log_dir = state.get_log_path();
+    run_blocking("Period comparison", move || {
+        stats::compare_periods(&log_dir, &target, period_a, period_b)
+            .map_err(|e| format!("Failed to read logs: {}", e))
+    })
+    .await
+}
+
+/// List the monitoring runs started within `range`, found in the logs
+#[tauri::command]
+pub async fn list_sessions(
+    range: TimeRange,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<LoggedSession>, String> {
+    let log_dir = state.get_log_path();
+    run_blocking("Session listing", move || {
+        logging::list_sessions(&log_dir, range).map_err(|e| format!("Failed to read logs: {}", e))
+    })
+    .await
+}
+
+/// Statistics of one logged monitoring run, per target
+///
+/// `range` bounds the logs searched, e.g. from the session's start to its end.
+#[tauri::command]
+pub async fn analyze_session(
+    session_id: String,
+    range: TimeRange,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<PingStatistics>, String> {
+    let log_dir = state.get_log_path();
+    run_blocking("Session analysis", move || {
+        let results = logging::query_logs(&log_dir, None, Some(&session_id), range)
+            .map_err(|e| format!("Failed to read logs: {}", e))?;
+        Ok(StatsCalculator::from_results(results).get_all_stats())
+    })
+    .await
+}
+
+/// Correlate two targets' logged results over the last `window_minutes`
+///
+/// A high co-failure rate points at a shared upstream problem, a low one at
+/// separate paths.
+#[tauri::command]
+pub async fn correlate_targets(
+    id_a: String,
+    id_b: String,
+    window_minutes: u32,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Correlation, String> {
+    let targets = state.get_targets();
+    let find = |id: &str| {
+        targets
+            .iter()
+            .find(|t| t.id == id)
+            .ok_or_else(|| format!("Target not found: {}", id))
+    };
+    let (a, b) = (find(&id_a)?, find(&id_b)?);
+
+    // Results of one cycle may be up to an interval apart when staggered
+    let default_interval = state.get_ping_interval();
+    let max_skew = a
+        .interval_ms
+        .unwrap_or(default_interval)
+        .max(b.interval_ms.unwrap_or(default_interval));
+
+    let now = chrono::Utc::now();
+    let range = TimeRange::new(now - chrono::Duration::minutes(window_minutes as i64), now);
+    let (log_dir, a, b) = (state.get_log_path(), a.address.clone(), b.address.clone());
+    run_blocking("Correlation", move || {
+        stats::correlate_from_logs(
+            &log_dir,
+            &a,
+            &b,
+            range,
+            chrono::Duration::milliseconds(max_skew as i64),
+        )
+        .map_err(|e| format!("Failed to read logs: {}", e))
+    })
+    .await
+}
+
+/// Get a target's average latency and loss by hour of day over the last `days`
+#[tauri::command]
+pub async fn get_hourly_profile(
+    target: String,
+    days: u32,
+    state: State<'_, Arc<AppState>>,
+) -> Result<[HourStat; 24], String> {
+    let log_dir = state.get_log_path();
+    run_blocking("Hourly profile", move || {
+        stats::hourly_profile_from_logs(&log_dir, &target, days, chrono::Utc::now())
+            .map_err(|e| format!("Failed to read logs: {}", e))
+    })
+    .await
+}
+
+/// Get a target's connection quality per `bucket_secs` over `range`, from
+/// logs, e.g. minute by minute for today
+#[tauri::command]
+pub async fn get_quality_timeseries(
+    target: String,
+    range: TimeRange,
+    bucket_secs: u32,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<QualityPoint>, String> {
+    let bounds = {
+        let config = state.get_config();
+        LatencyBounds::new(config.latency_warn_ms, config.latency_crit_ms)
+    };
+    let bucket = chrono::Duration::seconds(i64::from(bucket_secs));
+    let log_dir = state.get_log_path();
+    run_blocking("Quality timeseries", move || {
+        stats::quality_timeseries_from_logs(&log_dir, &target, range, bucket, &bounds)
+    })
+    .await
+}
+
 /// Set ping interval
 #[tauri::command]
 pub fn set_ping_interval(interval_ms: u64, state: State<'_, Arc<AppState>>) -> Result<(), String> {
-    if interval_ms < 100 {
-        return Err("Interval must be at least 100ms".to_string());
+    if interval_ms < MIN_PING_INTERVAL_MS {
+        return Err(format!("Interval must be at least {}ms", MIN_PING_INTERVAL_MS));
     }
     state.set_ping_interval(interval_ms);
     Ok(())
@@ -188,7 +582,7 @@ pub fn add_target(
     address: String,
     label: String,
     state: State<'_, Arc<AppState>>,
-) -> Result<PingTarget, String> {
+) -> Result<TargetAdded, String> {
     if address.is_empty() {
         return Err("Address cannot be empty".to_string());
     }
@@ -197,6 +591,65 @@ pub fn add_target(
     Ok(state.add_target(target))
 }
 
+/// Add every target produced by expanding an address template
+///
+/// Addresses already configured are skipped. Nothing is added if any
+/// expanded address is invalid.
+#[tauri::command]
+pub fn add_targets_from_template(
+    template: String,
+    label_template: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<PingTarget>, String> {
+    let expanded = template::expand_template(&template, &label_template)?;
+    for (address, _) in &expanded {
+        validation::validate_address(address)?;
+    }
+
+    let existing: HashSet<String> = state.get_targets().into_iter().map(|t| t.address).collect();
+    Ok(expanded
+        .into_iter()
+        .filter(|(address, _)| !existing.contains(address))
+        .map(|(address, label)| state.add_target(PingTarget::new(address, label)).target)
+        .collect())
+}
+
+/// Add a probe group whose members are reported together
+#[tauri::command]
+pub fn add_probe_group(
+    name: String,
+    members: Vec<String>,
+    policy: GroupPolicy,
+    state: State<'_, Arc<AppState>>,
+) -> Result<ProbeGroup, String> {
+    if members.is_empty() {
+        return Err("A group needs at least one member".to_string());
+    }
+
+    let targets = state.get_targets();
+    if let Some(unknown) = members.iter().find(|id| !targets.iter().any(|t| &t.id == *id)) {
+        return Err(format!("Unknown target: {}", unknown));
+    }
+
+    Ok(state.add_group(ProbeGroup::new(name, members, policy)))
+}
+
+/// Remove a probe group
+#[tauri::command]
+pub fn remove_probe_group(id: String, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    if state.remove_group(&id) {
+        Ok(())
+    } else {
+        Err("Group not found".to_string())
+    }
+}
+
+/// Get combined statistics for each probe group
+#[tauri::command]
+pub fn get_group_statistics(state: State<'_, Arc<AppState>>) -> Vec<PingStatistics> {
+    state.get_group_stats()
+}
+
 /// Remove a ping target
 #[tauri::command]
 pub fn remove_target(id: String, state: State<'_, Arc<AppState>>) -> Result<(), String> {
@@ -208,13 +661,16 @@ pub fn remove_target(id: String, state: State<'_, Arc<AppState>>) -> Result<(),
 }
 
 /// Update a ping target
+///
+/// Changing the address resets the target's statistics; `stats_reset` tells
+/// the UI to say so.
 #[tauri::command]
 pub fn update_target(
     id: String,
     address: String,
     label: String,
     state: State<'_, Arc<AppState>>,
-) -> Result<PingTarget, String> {
+) -> Result<TargetUpdate, String> {
     state
         .update_target(&id, address, label)
         .ok_or_else(|| "Target not found".to_string())
@@ -237,23 +693,187 @@ pub fn get_config(state: State<'_, Arc<AppState>>) -> AppConfig {
 /// Update configuration
 #[tauri::command]
 pub fn save_config(config: AppConfig, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    validation::check_config(&config, &state.get_log_path())?;
     state.update_config(config);
     Ok(())
 }
 
+/// Update only the given configuration fields, returning the new config
+#[tauri::command]
+pub fn patch_config(
+    patch: serde_json::Value,
+    state: State<'_, Arc<AppState>>,
+) -> Result<AppConfig, String> {
+    state.patch_config(&patch)
+}
+
+/// Tune the config for a Raspberry Pi or a laptop on battery, returning it
+#[tauri::command]
+pub fn apply_low_resource_preset(state: State<'_, Arc<AppState>>) -> AppConfig {
+    state.update_config_with(AppConfig::apply_low_resource_preset)
+}
+
+/// Tune the config for frequent probing on a powerful desktop, returning it
+#[tauri::command]
+pub fn apply_high_resolution_preset(state: State<'_, Arc<AppState>>) -> AppConfig {
+    state.update_config_with(AppConfig::apply_high_resolution_preset)
+}
+
+/// Check a configuration for problems without saving it
+#[tauri::command]
+pub fn validate_config(config: AppConfig, state: State<'_, Arc<AppState>>) -> Vec<ConfigIssue> {
+    validation::validate_config(&config, &state.get_log_path())
+}
+
+/// Measure a target's normal performance and store it as its baseline
+#[tauri::command]
+pub async fn capture_baseline(
+    id: String,
+    samples: u32,
+    state: State<'_, Arc<AppState>>,
+) -> Result<PingTarget, String> {
+    let state = Arc::clone(&state);
+    tokio::task::spawn_blocking(move || state.capture_baseline(&id, samples))
+        .await
+        .map_err(|e| format!("Baseline capture failed: {}", e))?
+}
+
+/// Emit live results for only one target, or for all with `None`
+#[tauri::command]
+pub fn focus_target(id: Option<String>, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    if let Some(id) = &id {
+        if !state.get_targets().iter().any(|t| &t.id == id) {
+            return Err("Target not found".to_string());
+        }
+    }
+    state.focus_target(id);
+    Ok(())
+}
+
+/// Get the number of probes currently in flight
+#[tauri::command]
+pub fn get_inflight_count(state: State<'_, Arc<AppState>>) -> u32 {
+    state.get_inflight_count()
+}
+
+/// Get exact sent/received counts for probes sent by the native backend
+#[tauri::command]
+pub fn get_probe_counters(state: State<'_, Arc<AppState>>) -> Vec<ProbeCounters> {
+    state.get_probe_counters()
+}
+
+/// Get the IP each hostname target was last resolved to, and how long ago
+#[tauri::command]
+pub fn get_resolved_addresses(state: State<'_, Arc<AppState>>) -> Vec<ResolvedAddress> {
+    state.get_resolved_addresses()
+}
+
+/// Check whether the native backend can open ICMP sockets, without sending probes
+#[tauri::command]
+pub fn check_icmp_permission() -> IcmpCapability {
+    ping::check_icmp_permission()
+}
+
+/// Time a backend's per-probe overhead by pinging localhost `iterations` times
+///
+/// Runs apart from the monitor, which keeps probing undisturbed.
+#[tauri::command]
+pub async fn benchmark_backend(
+    backend: BackendKind,
+    iterations: u32,
+    state: State<'_, Arc<AppState>>,
+) -> Result<BackendBenchmark, String> {
+    let state = Arc::clone(&state);
+    tokio::task::spawn_blocking(move || state.benchmark_backend(backend, iterations))
+        .await
+        .map_err(|e| format!("Benchmark failed: {}", e))?
+}
+
+/// Targets currently down, flapping, too slow or losing too many pings
+#[tauri::command]
+pub fn get_alarming_targets(state: State<'_, Arc<AppState>>) -> Vec<AlarmInfo> {
+    state.get_alarming_targets()
+}
+
+/// Probe the given targets, or all enabled ones, `samples` times each and
+/// judge the local network and the internet connection
+///
+/// Runs apart from the monitor; nothing is recorded in statistics or logs.
+#[tauri::command]
+pub async fn run_diagnostic(
+    targets: Option<Vec<String>>,
+    samples: u32,
+    state: State<'_, Arc<AppState>>,
+) -> Result<DiagnosticReport, String> {
+    let state = Arc::clone(&state);
+    tokio::task::spawn_blocking(move || state.run_diagnostic(targets, samples))
+        .await
+        .map_err(|e| format!("Diagnostic failed: {}", e))?
+}
+
+/// Check that pinging, reply parsing and logging work, against loopback
+///
+/// A first triage step: if this fails, the problem is the app or its
+/// permissions rather than the network.
+#[tauri::command]
+pub async fn run_self_test(state: State<'_, Arc<AppState>>) -> Result<SelfTestResult, String> {
+    let state = Arc::clone(&state);
+    tokio::task::spawn_blocking(move || state.run_self_test())
+        .await
+        .map_err(|e| format!("Self-test failed: {}", e))?
+}
+
+/// Get a snapshot of the probe scheduler
+#[tauri::command]
+pub fn get_scheduler_stats(state: State<'_, Arc<AppState>>) -> SchedulerStats {
+    state.get_scheduler_stats()
+}
+
+/// Get approximate memory used by buffers and statistics, and open handles
+#[tauri::command]
+pub fn get_resource_stats(state: State<'_, Arc<AppState>>) -> ResourceStats {
+    state.get_resource_stats()
+}
+
+/// Get JSON schemas for every emitted event and the main command types
+#[tauri::command]
+pub fn get_event_schemas() -> SchemaCatalog {
+    schema::schema_catalog()
+}
+
 /// Get preset targets
 #[tauri::command]
 pub fn get_preset_targets() -> Vec<PingTarget> {
     PingTarget::presets()
 }
 
-/// Reset all statistics
+/// Reset the session statistics, and lifetime counts too if `include_lifetime`
 #[tauri::command]
-pub fn reset_statistics(state: State<'_, Arc<AppState>>) -> Result<(), String> {
+pub fn reset_statistics(
+    include_lifetime: Option<bool>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<(), String> {
     state.reset_stats();
+    if include_lifetime.unwrap_or(false) {
+        state
+            .reset_lifetime_stats()
+            .map_err(|e| format!("Failed to save lifetime statistics: {}", e))?;
+    }
     Ok(())
 }
 
+/// Start a target's latency distribution afresh, e.g. after switching Wi-Fi
+///
+/// Ping and loss counts are kept.
+#[tauri::command]
+pub fn reset_target_latency(id: String, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    if state.reset_target_latency(&id) {
+        Ok(())
+    } else {
+        Err("Target not found".to_string())
+    }
+}
+
 /// Open log directory in file explorer
 #[tauri::command]
 pub async fn open_log_directory(state: State<'_, Arc<AppState>>) -> Result<(), String> {
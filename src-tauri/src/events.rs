@@ -0,0 +1,144 @@
+use std::collections::HashSet;
+
+/// A single ping result (`PingResult`)
+pub const PING_RESULT: &str = "ping-result";
+/// A target's first result since its statistics started (`TargetFirstResult`)
+pub const TARGET_FIRST_RESULT: &str = "target-first-result";
+/// Statistics of the targets that changed in a probe cycle (`Vec<PingStatistics>`)
+pub const STATS_UPDATE: &str = "stats-update";
+/// Log writes keep failing (`LogHealthEvent`)
+pub const LOGGING_DEGRADED: &str = "logging-degraded";
+/// Log writes succeed again (`LogHealthEvent`)
+pub const LOGGING_RESTORED: &str = "logging-restored";
+/// Every internet target is failing (`ConnectivityEvent`)
+pub const INTERNET_DOWN: &str = "internet-down";
+/// An internet target responds again (`ConnectivityEvent`)
+pub const INTERNET_UP: &str = "internet-up";
+/// Enough targets of a quorum alert rule are down (`QuorumAlertEvent`)
+pub const QUORUM_ALERT: &str = "quorum-alert";
+/// A quorum alert rule no longer holds (`QuorumAlertEvent`)
+pub const QUORUM_CLEARED: &str = "quorum-cleared";
+/// An outage lasted long enough to reach an escalation tier (`OutageEscalation`)
+pub const OUTAGE_ESCALATED: &str = "outage-escalated";
+/// A target with `sound_alert` went down (`SoundAlert`)
+pub const PLAY_SOUND: &str = "play-sound";
+/// The monitoring kill switch is off (no payload)
+pub const MONITORING_DISABLED: &str = "monitoring-disabled";
+/// The session label changed (`SessionMarker`)
+pub const SESSION_MARKER: &str = "session-marker";
+
+/// Pushes to the Pushgateway keep failing (`LogHealthEvent`)
+pub const PUSH_DEGRADED: &str = "push-degraded";
+/// Pushes to the Pushgateway succeed again (`LogHealthEvent`)
+pub const PUSH_RESTORED: &str = "push-restored";
+/// Buffers were shrunk to stay within the memory budget (`MemoryPressure`)
+pub const MEMORY_PRESSURE: &str = "memory-pressure";
+
+/// Every event the backend emits
+pub const EVENT_NAMES: &[&str] = &[
+    PING_RESULT,
+    TARGET_FIRST_RESULT,
+    STATS_UPDATE,
+    LOGGING_DEGRADED,
+    LOGGING_RESTORED,
+    INTERNET_DOWN,
+    INTERNET_UP,
+    QUORUM_ALERT,
+    QUORUM_CLEARED,
+    OUTAGE_ESCALATED,
+    PLAY_SOUND,
+    MONITORING_DISABLED,
+    SESSION_MARKER,
+    PUSH_DEGRADED,
+    PUSH_RESTORED,
+    MEMORY_PRESSURE,
+];
+
+/// Keep only the `fields` of a serialized object
+pub fn project_fields(value: &mut serde_json::Value, fields: &[String]) {
+    if let Some(object) = value.as_object_mut() {
+        object.retain(|key, _| fields.iter().any(|field| field == key));
+    }
+}
+
+/// Events the frontend listens to; all of them until it says otherwise
+#[derive(Debug, Default)]
+pub struct EventSubscriptions {
+    /// Subscribed event names; `None` is every event
+    names: Option<HashSet<String>>,
+}
+
+impl EventSubscriptions {
+    /// Listen to exactly these events, rejecting names that are never emitted
+    pub fn set(&mut self, names: Vec<String>) -> Result<(), String> {
+        if let Some(unknown) = names.iter().find(|n| !EVENT_NAMES.contains(&n.as_str())) {
+            return Err(format!("Unknown event: {}", unknown));
+        }
+        self.names = Some(names.into_iter().collect());
+        Ok(())
+    }
+
+    /// Whether the frontend listens to `event`
+    pub fn contains(&self, event: &str) -> bool {
+        self.names.as_ref().map_or(true, |names| names.contains(event))
+    }
+}
+
+/// Destination for events sent to the frontend
+pub trait EventSink: Send + Sync {
+    /// Emit an event with an already serialized payload
+    fn emit_value(&self, event: &str, payload: serde_json::Value) -> Result<(), String>;
+}
+
+#[cfg(feature = "tauri")]
+impl<R: tauri::Runtime> EventSink for tauri::AppHandle<R> {
+    fn emit_value(&self, event: &str, payload: serde_json::Value) -> Result<(), String> {
+        tauri::Emitter::emit(self, event, payload).map_err(|e| e.to_string())
+    }
+}
+
+/// Event sink that records every event, for use in tests
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct RecordingSink {
+    events: parking_lot::Mutex<Vec<(String, serde_json::Value)>>,
+}
+
+#[cfg(test)]
+impl RecordingSink {
+    /// Names of all recorded events, in emission order
+    pub fn names(&self) -> Vec<String> {
+        self.events
+            .lock()
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Payloads of all recorded events with the given name
+    pub fn payloads(&self, event: &str) -> Vec<serde_json::Value> {
+        self.events
+            .lock()
+            .iter()
+            .filter(|(name, _)| name == event)
+            .map(|(_, payload)| payload.clone())
+            .collect()
+    }
+
+    /// Number of recorded events with the given name
+    pub fn count(&self, event: &str) -> usize {
+        self.events
+            .lock()
+            .iter()
+            .filter(|(name, _)| name == event)
+            .count()
+    }
+}
+
+#[cfg(test)]
+impl EventSink for RecordingSink {
+    fn emit_value(&self, event: &str, payload: serde_json::Value) -> Result<(), String> {
+        self.events.lock().push((event.to_string(), payload));
+        Ok(())
+    }
+}
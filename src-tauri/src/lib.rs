@@ -1,47 +1,55 @@
+//! Network connectivity monitoring: pinging, statistics and result logs
+//!
+//! The desktop app is built on these modules with the `tauri` feature (on
+//! by default). Without it, the crate is a plain library:
+//!
+//! ```toml
+//! ping-connectivity = { version = "1", default-features = false }
+//! ```
+//!
+//! ```
+//! use ping_connectivity_lib::{PingTarget, Pinger, StatsCalculator};
+//!
+//! let target = PingTarget::new("127.0.0.1".to_string(), "Loopback".to_string());
+//! let pinger = Pinger::new(1000);
+//! let mut stats = StatsCalculator::new();
+//!
+//! for sequence in 1..=3 {
+//!     // Failed pings are results too, e.g. without a `ping` binary
+//!     let result = pinger.ping(&target, sequence);
+//!     stats.update(&result);
+//! }
+//!
+//! let summary = stats.get_stats("127.0.0.1").unwrap();
+//! assert_eq!(summary.total_pings, 3);
+//! println!("{}% loss, avg {:?} ms", summary.packet_loss_percent, summary.avg_latency_ms);
+//! ```
+
+pub mod alerts;
+#[cfg(feature = "tauri")]
+mod app;
+pub mod channels;
+#[cfg(feature = "tauri")]
 pub mod commands;
+pub mod events;
 pub mod logging;
+pub mod metrics;
 pub mod models;
+pub mod monitor;
 pub mod ping;
+pub mod processing;
+pub mod scheduler;
+pub mod schema;
+pub mod selftest;
 pub mod state;
 pub mod stats;
+pub mod template;
+pub mod validation;
 
-use state::AppState;
-use std::sync::Arc;
-
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    // Initialize logger
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("debug"))
-        .format_timestamp_millis()
-        .init();
-
-    log::info!("Starting Ping Connectivity Monitor");
+pub use logging::JsonLogger;
+pub use models::{AppConfig, PingResult, PingStatistics, PingTarget};
+pub use ping::{NativePinger, PingBackend, Pinger};
+pub use stats::StatsCalculator;
 
-    tauri::Builder::default()
-        .plugin(tauri_plugin_shell::init())
-        .manage(Arc::new(AppState::new()))
-        .invoke_handler(tauri::generate_handler![
-            commands::start_pinging,
-            commands::stop_pinging,
-            commands::pause_pinging,
-            commands::resume_pinging,
-            commands::get_ping_state,
-            commands::get_statistics,
-            commands::get_statistics_for_target,
-            commands::get_recent_pings,
-            commands::get_log_path,
-            commands::set_ping_interval,
-            commands::get_targets,
-            commands::add_target,
-            commands::remove_target,
-            commands::update_target,
-            commands::toggle_target,
-            commands::get_config,
-            commands::save_config,
-            commands::get_preset_targets,
-            commands::reset_statistics,
-            commands::open_log_directory,
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
-}
+#[cfg(feature = "tauri")]
+pub use app::run;
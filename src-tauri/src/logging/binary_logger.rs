@@ -0,0 +1,423 @@
+use super::ResultLogger;
+use crate::models::{PingResult, SessionBoundary, SessionBoundaryKind};
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Version byte at the start of every record payload
+const RECORD_VERSION: u8 = 1;
+
+/// First byte of a session marker payload, in place of the version
+const BOUNDARY_RECORD: u8 = 0x80;
+
+/// Longest payload a record can have: the fixed fields of a result and four
+/// strings of at most `u16::MAX` bytes. A longer length prefix means the
+/// file is corrupt.
+const MAX_RECORD_LEN: usize = 1 + 8 + 8 + 1 + 4 + 4 + 4 * (2 + u16::MAX as usize);
+
+const FLAG_SUCCESS: u8 = 0b001;
+const FLAG_HAS_ERROR: u8 = 0b010;
+const FLAG_HAS_SESSION: u8 = 0b100;
+
+/// Compact binary logger with daily file rotation
+///
+/// Each record is a little-endian `u32` payload length followed by the
+/// payload, so readers can skip records of an unknown version. Only the core
+/// measurement fields are stored: timestamp (to the microsecond), target,
+/// label, latency, success, sequence, attempts, error and session id.
+/// Session markers are records of their own, which result readers skip.
+pub struct BinaryLogger {
+    log_dir: PathBuf,
+    current_date: Mutex<Option<NaiveDate>>,
+    writer: Mutex<Option<BufWriter<File>>>,
+}
+
+impl BinaryLogger {
+    /// Create a new binary logger
+    pub fn new(log_dir: PathBuf) -> Result<Self, std::io::Error> {
+        fs::create_dir_all(&log_dir)?;
+
+        Ok(Self {
+            log_dir,
+            current_date: Mutex::new(None),
+            writer: Mutex::new(None),
+        })
+    }
+
+    /// Log a ping result
+    pub fn log(&self, result: &PingResult) -> Result<(), std::io::Error> {
+        self.write_record(&encode_record(result))
+    }
+
+    /// Log a session start or end marker
+    pub fn log_boundary(&self, boundary: &SessionBoundary) -> Result<(), std::io::Error> {
+        self.write_record(&encode_boundary(boundary))
+    }
+
+    /// Append an encoded record to today's file
+    fn write_record(&self, record: &[u8]) -> Result<(), std::io::Error> {
+        let today = Local::now().date_naive();
+        let mut current_date = self.current_date.lock().unwrap();
+        let mut writer = self.writer.lock().unwrap();
+
+        if current_date.map(|d| d != today).unwrap_or(true) {
+            if let Some(ref mut w) = *writer {
+                w.flush()?;
+            }
+
+            let file_path = self.log_file_path(today);
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&file_path)?;
+
+            *writer = Some(BufWriter::new(file));
+            *current_date = Some(today);
+
+            log::info!("Opened log file: {:?}", file_path);
+        }
+
+        if let Some(ref mut w) = *writer {
+            w.write_all(record)?;
+            w.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the log file path for a specific date
+    fn log_file_path(&self, date: NaiveDate) -> PathBuf {
+        self.log_dir
+            .join(format!("ping-{}.bin", date.format("%Y-%m-%d")))
+    }
+
+    /// Stream ping results from a binary log file
+    pub fn stream_log_file(
+        path: &Path,
+    ) -> Result<impl Iterator<Item = PingResult>, std::io::Error> {
+        Ok(BinaryLogReader::new(BufReader::new(File::open(path)?)))
+    }
+
+    /// Stream the session start and end markers of a binary log file
+    pub fn stream_boundaries(
+        path: &Path,
+    ) -> Result<impl Iterator<Item = SessionBoundary>, std::io::Error> {
+        let mut reader = BufReader::new(File::open(path)?);
+        Ok(std::iter::from_fn(move || loop {
+            let payload = read_payload(&mut reader)?;
+            if let Some(boundary) = decode_boundary(&payload) {
+                return Some(boundary);
+            }
+        }))
+    }
+}
+
+impl ResultLogger for BinaryLogger {
+    fn log(&self, result: &PingResult) -> Result<(), std::io::Error> {
+        BinaryLogger::log(self, result)
+    }
+
+    fn log_boundary(&self, boundary: &SessionBoundary) -> Result<(), std::io::Error> {
+        BinaryLogger::log_boundary(self, boundary)
+    }
+
+    fn log_dir(&self) -> &PathBuf {
+        &self.log_dir
+    }
+
+    fn open_files(&self) -> usize {
+        self.writer
+            .lock()
+            .map_or(0, |writer| usize::from(writer.is_some()))
+    }
+}
+
+impl Drop for BinaryLogger {
+    fn drop(&mut self) {
+        if let Ok(mut writer) = self.writer.lock() {
+            if let Some(ref mut w) = *writer {
+                let _ = w.flush();
+            }
+        }
+    }
+}
+
+/// Iterator over the records of a binary log
+///
+/// Stops at the end of the input, at a truncated record or at a length prefix
+/// beyond any valid record, and skips records it cannot decode.
+pub struct BinaryLogReader<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> BinaryLogReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+}
+
+impl<R: Read> Iterator for BinaryLogReader<R> {
+    type Item = PingResult;
+
+    fn next(&mut self) -> Option<PingResult> {
+        loop {
+            let payload = read_payload(&mut self.reader)?;
+            if payload.first() == Some(&BOUNDARY_RECORD) {
+                continue;
+            }
+
+            match decode_payload(&payload) {
+                Some(result) => return Some(result),
+                None => log::warn!("Skipping undecodable binary log record"),
+            }
+        }
+    }
+}
+
+/// Read the next record's payload, `None` at the end, a truncated record or
+/// a corrupt length prefix
+fn read_payload<R: Read>(reader: &mut R) -> Option<Vec<u8>> {
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len).ok()?;
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_RECORD_LEN {
+        log::warn!("Binary log record of {} bytes is corrupt, stopping", len);
+        return None;
+    }
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload).ok()?;
+    Some(payload)
+}
+
+/// Encode a result as a length-prefixed record
+pub fn encode_record(result: &PingResult) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(64);
+    payload.push(RECORD_VERSION);
+    payload.extend_from_slice(&result.timestamp.timestamp_micros().to_le_bytes());
+    payload.extend_from_slice(&result.latency_ms.unwrap_or(f64::NAN).to_le_bytes());
+
+    let mut flags = 0;
+    if result.success {
+        flags |= FLAG_SUCCESS;
+    }
+    if result.error.is_some() {
+        flags |= FLAG_HAS_ERROR;
+    }
+    if result.session_id.is_some() {
+        flags |= FLAG_HAS_SESSION;
+    }
+    payload.push(flags);
+    payload.extend_from_slice(&result.sequence.to_le_bytes());
+    payload.extend_from_slice(&result.attempts.to_le_bytes());
+    put_str(&mut payload, &result.target);
+    put_str(&mut payload, &result.target_label);
+    if let Some(ref error) = result.error {
+        put_str(&mut payload, error);
+    }
+    if let Some(ref session_id) = result.session_id {
+        put_str(&mut payload, session_id);
+    }
+
+    length_prefixed(payload)
+}
+
+/// Encode a session marker as a length-prefixed record
+pub fn encode_boundary(boundary: &SessionBoundary) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(48);
+    payload.push(BOUNDARY_RECORD);
+    payload.extend_from_slice(&boundary.timestamp.timestamp_micros().to_le_bytes());
+    payload.push(match boundary.boundary {
+        SessionBoundaryKind::Start => 0,
+        SessionBoundaryKind::End => 1,
+    });
+    put_str(&mut payload, &boundary.session_id);
+
+    length_prefixed(payload)
+}
+
+fn length_prefixed(payload: Vec<u8>) -> Vec<u8> {
+    let mut record = Vec::with_capacity(payload.len() + 4);
+    record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    record.extend_from_slice(&payload);
+    record
+}
+
+fn put_str(buf: &mut Vec<u8>, s: &str) {
+    let bytes = &s.as_bytes()[..s.len().min(u16::MAX as usize)];
+    buf.extend_from_slice(&(bytes.len() as u16).to_le_bytes());
+    buf.extend_from_slice(bytes);
+}
+
+/// Decode a record payload (without its length prefix)
+pub(super) fn decode_payload(payload: &[u8]) -> Option<PingResult> {
+    let mut cursor = Cursor { buf: payload };
+    if cursor.take(1)?[0] != RECORD_VERSION {
+        return None;
+    }
+
+    let micros = i64::from_le_bytes(cursor.take(8)?.try_into().ok()?);
+    let latency = f64::from_le_bytes(cursor.take(8)?.try_into().ok()?);
+    let flags = cursor.take(1)?[0];
+    let sequence = u32::from_le_bytes(cursor.take(4)?.try_into().ok()?);
+    let attempts = u32::from_le_bytes(cursor.take(4)?.try_into().ok()?);
+    let target = cursor.take_str()?;
+    let target_label = cursor.take_str()?;
+    let error = if flags & FLAG_HAS_ERROR != 0 {
+        Some(cursor.take_str()?)
+    } else {
+        None
+    };
+    let session_id = if flags & FLAG_HAS_SESSION != 0 {
+        Some(cursor.take_str()?)
+    } else {
+        None
+    };
+
+    Some(PingResult {
+        timestamp: DateTime::<Utc>::from_timestamp_micros(micros)?,
+        target,
+        target_label,
+        latency_ms: (!latency.is_nan()).then_some(latency),
+        success: flags & FLAG_SUCCESS != 0,
+        sequence,
+        error,
+        attempts,
+        session_id,
+        ..Default::default()
+    })
+}
+
+/// Decode a session marker payload, `None` for any other record
+fn decode_boundary(payload: &[u8]) -> Option<SessionBoundary> {
+    let mut cursor = Cursor { buf: payload };
+    if cursor.take(1)?[0] != BOUNDARY_RECORD {
+        return None;
+    }
+
+    let micros = i64::from_le_bytes(cursor.take(8)?.try_into().ok()?);
+    let boundary = match cursor.take(1)?[0] {
+        0 => SessionBoundaryKind::Start,
+        1 => SessionBoundaryKind::End,
+        _ => return None,
+    };
+    Some(SessionBoundary {
+        timestamp: DateTime::<Utc>::from_timestamp_micros(micros)?,
+        session_id: cursor.take_str()?,
+        boundary,
+    })
+}
+
+struct Cursor<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.buf.len() < n {
+            return None;
+        }
+        let (head, tail) = self.buf.split_at(n);
+        self.buf = tail;
+        Some(head)
+    }
+
+    fn take_str(&mut self) -> Option<String> {
+        let len = u16::from_le_bytes(self.take(2)?.try_into().ok()?) as usize;
+        String::from_utf8(self.take(len)?.to_vec()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PingTarget;
+
+    #[test]
+    fn test_binary_round_trip() {
+        let dir = std::env::temp_dir().join(format!("ping-bin-test-{}", uuid::Uuid::new_v4()));
+        let logger = BinaryLogger::new(dir.clone()).unwrap();
+        let target = PingTarget::new("1.1.1.1".to_string(), "Cloudflare DNS".to_string());
+        let written = vec![
+            PingResult::success(&target, 12.345, 1),
+            PingResult::failure(&target, "Request timed out".to_string(), 2).with_attempts(3),
+            PingResult::success(&target, 0.25, 3),
+        ];
+        for result in &written {
+            logger.log(result).unwrap();
+        }
+
+        let path = logger.log_file_path(Local::now().date_naive());
+        let read: Vec<PingResult> = BinaryLogger::stream_log_file(&path).unwrap().collect();
+
+        assert_eq!(read.len(), written.len());
+        for (read, written) in read.iter().zip(&written) {
+            // Timestamps are stored with microsecond precision
+            assert_eq!(
+                read.timestamp.timestamp_micros(),
+                written.timestamp.timestamp_micros()
+            );
+            assert_eq!(read.target, written.target);
+            assert_eq!(read.target_label, written.target_label);
+            assert_eq!(read.latency_ms, written.latency_ms);
+            assert_eq!(read.success, written.success);
+            assert_eq!(read.sequence, written.sequence);
+            assert_eq!(read.attempts, written.attempts);
+            assert_eq!(read.error, written.error);
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_session_boundaries_round_trip() {
+        let dir = std::env::temp_dir().join(format!("ping-bin-test-{}", uuid::Uuid::new_v4()));
+        let logger = BinaryLogger::new(dir.clone()).unwrap();
+        let target = PingTarget::new("1.1.1.1".to_string(), "Cloudflare DNS".to_string());
+        let mut tagged = PingResult::success(&target, 12.0, 1);
+        tagged.session_id = Some("run-1".to_string());
+
+        logger
+            .log_boundary(&SessionBoundary::new("run-1", SessionBoundaryKind::Start))
+            .unwrap();
+        logger.log(&tagged).unwrap();
+        logger
+            .log_boundary(&SessionBoundary::new("run-1", SessionBoundaryKind::End))
+            .unwrap();
+
+        // Result readers skip the markers, and the markers keep their order
+        let path = logger.log_file_path(Local::now().date_naive());
+        let read: Vec<PingResult> = BinaryLogger::stream_log_file(&path).unwrap().collect();
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0].session_id.as_deref(), Some("run-1"));
+
+        let boundaries: Vec<SessionBoundary> =
+            BinaryLogger::stream_boundaries(&path).unwrap().collect();
+        assert_eq!(boundaries.len(), 2);
+        assert!(boundaries.iter().all(|b| b.session_id == "run-1"));
+        assert_eq!(boundaries[0].boundary, SessionBoundaryKind::Start);
+        assert_eq!(boundaries[1].boundary, SessionBoundaryKind::End);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_length_prefix_stops_reading() {
+        let target = PingTarget::new("1.1.1.1".to_string(), "Cloudflare DNS".to_string());
+        let record = encode_record(&PingResult::success(&target, 12.0, 1));
+
+        // A bogus prefix ends the log rather than allocating gigabytes
+        let mut log = record.clone();
+        log.extend_from_slice(&u32::MAX.to_le_bytes());
+        log.extend_from_slice(&record);
+        let read: Vec<PingResult> = BinaryLogReader::new(log.as_slice()).collect();
+        assert_eq!(read.len(), 1);
+
+        // So does a payload shorter than its prefix
+        let mut log = record.clone();
+        log.extend_from_slice(&record[..record.len() - 1]);
+        let read: Vec<PingResult> = BinaryLogReader::new(log.as_slice()).collect();
+        assert_eq!(read.len(), 1);
+    }
+}
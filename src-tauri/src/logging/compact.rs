@@ -0,0 +1,257 @@
+use super::query::is_gzip;
+use crate::models::{LogCompaction, PingResult, SessionBoundary};
+use chrono::{DateTime, NaiveDate, Utc};
+use flate2::read::GzDecoder;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// A record read from a JSON log, with its text as it was logged
+enum Record {
+    Result(Box<PingResult>, String),
+    Boundary(SessionBoundary, String),
+    /// Text that is neither
+    Unreadable(String),
+}
+
+/// Merge a date's JSON log fragments into one `ping-YYYY-MM-DD.jsonl`
+///
+/// Fragments are the date's `ping-YYYY-MM-DD*.jsonl` and `.jsonl.gz` files,
+/// e.g. left by imports. Their records are sorted by time, and results
+/// logged more than once (same target, sequence and timestamp) are kept
+/// once. Records keep their text as logged, and text that isn't a record
+/// stays after the record it followed. The merged file
+/// is written beside the fragments and synced to disk before it replaces
+/// them, so an interrupted compaction loses nothing.
+pub fn compact_logs(log_dir: &Path, date: NaiveDate) -> Result<LogCompaction, std::io::Error> {
+    let merged_path = log_dir.join(format!("ping-{}.jsonl", date.format("%Y-%m-%d")));
+    let fragments = log_fragments(log_dir, date)?;
+    let mut compaction = LogCompaction {
+        date,
+        path: merged_path.to_string_lossy().to_string(),
+        fragments: fragments.len(),
+        records: 0,
+        duplicates_removed: 0,
+    };
+    if fragments.is_empty() {
+        return Ok(compaction);
+    }
+
+    let mut records: Vec<(DateTime<Utc>, String)> = Vec::new();
+    let mut seen_results = HashSet::new();
+    let mut seen_boundaries = HashSet::new();
+    for path in &fragments {
+        let file = File::open(path)?;
+        let reader: Box<dyn BufRead> = if is_gzip(path) {
+            Box::new(BufReader::new(GzDecoder::new(file)))
+        } else {
+            Box::new(BufReader::new(file))
+        };
+
+        // Unreadable text sorts with the record before it
+        let mut previous = DateTime::<Utc>::MIN_UTC;
+        for record in read_records(reader)? {
+            let (timestamp, mut text) = match record {
+                Record::Result(result, text) => {
+                    previous = result.timestamp;
+                    let key = (result.target.clone(), result.sequence, result.timestamp);
+                    if !seen_results.insert(key) {
+                        compaction.duplicates_removed += 1;
+                        continue;
+                    }
+                    compaction.records += 1;
+                    (result.timestamp, text)
+                }
+                Record::Boundary(boundary, text) => {
+                    previous = boundary.timestamp;
+                    if !seen_boundaries.insert(serde_json::to_string(&boundary)?) {
+                        compaction.duplicates_removed += 1;
+                        continue;
+                    }
+                    compaction.records += 1;
+                    (boundary.timestamp, text)
+                }
+                Record::Unreadable(text) => (previous, text),
+            };
+            if !text.ends_with('\n') {
+                text.push('\n');
+            }
+            records.push((timestamp, text));
+        }
+    }
+    // Stable, so records logged at the same instant keep their order
+    records.sort_by_key(|(timestamp, _)| *timestamp);
+
+    let temp = merged_path.with_extension("jsonl.compact.tmp");
+    let written = (|| {
+        let mut writer = BufWriter::new(File::create(&temp)?);
+        for (_, text) in &records {
+            writer.write_all(text.as_bytes())?;
+        }
+        writer.into_inner().map_err(|e| e.into_error())?.sync_all()
+    })();
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp);
+        return Err(e);
+    }
+    fs::rename(&temp, &merged_path)?;
+    sync_dir(log_dir)?;
+
+    for path in fragments.iter().filter(|path| **path != merged_path) {
+        fs::remove_file(path)?;
+    }
+    Ok(compaction)
+}
+
+/// The date's JSON log files, sorted by name
+fn log_fragments(log_dir: &Path, date: NaiveDate) -> Result<Vec<PathBuf>, std::io::Error> {
+    let prefix = format!("ping-{}", date.format("%Y-%m-%d"));
+    let mut fragments = Vec::new();
+    if log_dir.exists() {
+        for entry in fs::read_dir(log_dir)? {
+            let path = entry?.path();
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            if name.starts_with(&prefix) && (name.ends_with(".jsonl") || is_gzip(&path)) {
+                fragments.push(path);
+            }
+        }
+    }
+    fragments.sort();
+    Ok(fragments)
+}
+
+/// Read the records of a JSON log, compact or pretty
+fn read_records<R: BufRead>(mut reader: R) -> Result<Vec<Record>, std::io::Error> {
+    let mut records = Vec::new();
+    let mut record = String::new();
+    let mut line = String::new();
+
+    while reader.read_line(&mut line)? > 0 {
+        record.push_str(&line);
+        line.clear();
+
+        match serde_json::from_str::<PingResult>(&record) {
+            Ok(result) => records.push(Record::Result(Box::new(result), record.clone())),
+            // A pretty record continues on the next line
+            Err(e) if e.is_eof() => continue,
+            Err(_) => match serde_json::from_str::<SessionBoundary>(&record) {
+                Ok(boundary) => records.push(Record::Boundary(boundary, record.clone())),
+                Err(_) if record.trim().is_empty() => {}
+                Err(_) => records.push(Record::Unreadable(record.clone())),
+            },
+        }
+        record.clear();
+    }
+
+    if !record.trim().is_empty() {
+        records.push(Record::Unreadable(record));
+    }
+    Ok(records)
+}
+
+/// Make a rename in `dir` durable
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> Result<(), std::io::Error> {
+    File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> Result<(), std::io::Error> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logging::stream_results;
+    use crate::models::PingTarget;
+    use chrono::{Duration, TimeZone};
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    #[test]
+    fn test_overlapping_fragments_merge_into_one_sorted_file() {
+        let dir = std::env::temp_dir().join(format!("ping-compact-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        let target = PingTarget::new("192.0.2.1".to_string(), "Router".to_string());
+        let result = |sequence: u32| {
+            let mut result = PingResult::success(&target, 10.0, sequence);
+            result.timestamp = start + Duration::seconds(i64::from(sequence));
+            serde_json::to_string(&result).unwrap() + "\n"
+        };
+
+        // Sequences 1-4, then an import of 3-6 in reverse and compressed
+        let first: String = (1..=4).map(result).collect();
+        fs::write(dir.join("ping-2024-03-01.jsonl"), first).unwrap();
+        let second: String = (3..=6).rev().map(result).collect::<String>() + "not json\n";
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(second.as_bytes()).unwrap();
+        fs::write(
+            dir.join("ping-2024-03-01-import.jsonl.gz"),
+            gz.finish().unwrap(),
+        )
+        .unwrap();
+        fs::write(dir.join("ping-2024-03-02.jsonl"), result(7)).unwrap();
+
+        let compaction = compact_logs(&dir, date).unwrap();
+        assert_eq!(compaction.fragments, 2);
+        assert_eq!(compaction.records, 6);
+        assert_eq!(compaction.duplicates_removed, 2);
+
+        let merged = dir.join("ping-2024-03-01.jsonl");
+        let sequences: Vec<u32> = stream_results(&merged)
+            .unwrap()
+            .map(|r| r.sequence)
+            .collect();
+        assert_eq!(sequences, [1, 2, 3, 4, 5, 6]);
+        // The unreadable line stays after the record it followed
+        let text = fs::read_to_string(&merged).unwrap();
+        assert!(text.contains(&(result(3) + "not json\n" + &result(4))));
+
+        // Other dates are left alone
+        let mut files: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        files.sort();
+        assert_eq!(files, ["ping-2024-03-01.jsonl", "ping-2024-03-02.jsonl"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_records_keep_their_text_and_order() {
+        let dir = std::env::temp_dir().join(format!("ping-compact-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let at = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        let line = |address: &str| {
+            let target = PingTarget::new(address.to_string(), address.to_string());
+            let mut result = PingResult::success(&target, 10.0, 1);
+            result.timestamp = at;
+            // Spacing that re-serializing would lose
+            serde_json::to_string(&result).unwrap().replace(",\"", ", \"") + "\n"
+        };
+
+        // Three results logged at the same instant, with an unreadable line
+        // after the first, then an earlier result from an import
+        let logged = line("192.0.2.1") + "garbled\n" + &line("192.0.2.2") + &line("192.0.2.3");
+        fs::write(dir.join("ping-2024-03-01.jsonl"), &logged).unwrap();
+        let mut earlier = PingResult::success(&PingTarget::new("a".into(), "a".into()), 1.0, 1);
+        earlier.timestamp = at - Duration::seconds(1);
+        let earlier = serde_json::to_string(&earlier).unwrap() + "\n";
+        fs::write(dir.join("ping-2024-03-01-import.jsonl"), &earlier).unwrap();
+
+        let compaction = compact_logs(&dir, date).unwrap();
+        assert_eq!(compaction.records, 4);
+        let merged = fs::read_to_string(dir.join("ping-2024-03-01.jsonl")).unwrap();
+        assert_eq!(merged, earlier + &logged);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,161 @@
+use crate::models::{CsvExportOptions, FailedLatency, LatencyUnit, PingResult, TimestampFormat};
+use std::io::Write;
+
+/// Header columns for CSV exports, after the timestamp, target and label
+const CSV_HEADER_TAIL: &str = "success,sequence,attempts,error";
+
+/// Write results as JSON Lines
+pub fn write_jsonl<W, I>(writer: &mut W, results: I) -> Result<usize, std::io::Error>
+where
+    W: Write,
+    I: IntoIterator<Item = PingResult>,
+{
+    let mut count = 0;
+    for result in results {
+        writeln!(writer, "{}", serde_json::to_string(&result)?)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Write results as CSV with a header row
+///
+/// The latency column is named after its unit, e.g. `latency_us`.
+pub fn write_csv<W, I>(
+    writer: &mut W,
+    results: I,
+    options: &CsvExportOptions,
+) -> Result<usize, std::io::Error>
+where
+    W: Write,
+    I: IntoIterator<Item = PingResult>,
+{
+    let unit = match options.latency_unit {
+        LatencyUnit::Ms => "ms",
+        LatencyUnit::Us => "us",
+    };
+    writeln!(
+        writer,
+        "timestamp,target,target_label,latency_{},{}",
+        unit, CSV_HEADER_TAIL
+    )?;
+    let mut count = 0;
+    for result in results {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{}",
+            csv_timestamp(&result, options.timestamp_format),
+            csv_field(&result.target),
+            csv_field(&result.target_label),
+            csv_latency(result.latency_ms, options),
+            result.success,
+            result.sequence,
+            result.attempts,
+            csv_field(result.error.as_deref().unwrap_or("")),
+        )?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn csv_timestamp(result: &PingResult, format: TimestampFormat) -> String {
+    match format {
+        TimestampFormat::Iso8601 => result.timestamp.to_rfc3339(),
+        TimestampFormat::EpochSeconds => result.timestamp.timestamp().to_string(),
+        TimestampFormat::EpochMillis => result.timestamp.timestamp_millis().to_string(),
+    }
+}
+
+fn csv_latency(latency_ms: Option<f64>, options: &CsvExportOptions) -> String {
+    let Some(latency_ms) = latency_ms else {
+        return match options.failed_latency {
+            FailedLatency::Empty => String::new(),
+            FailedLatency::Zero => "0".to_string(),
+            FailedLatency::Fail => "FAIL".to_string(),
+        };
+    };
+    let latency = match options.latency_unit {
+        LatencyUnit::Ms => latency_ms,
+        LatencyUnit::Us => latency_ms * 1000.0,
+    };
+    match options.precision {
+        Some(precision) => format!("{:.*}", precision, latency),
+        None => latency.to_string(),
+    }
+}
+
+/// Quote a CSV field if it contains separators, quotes or newlines
+pub(super) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PingTarget;
+    use chrono::{TimeZone, Utc};
+
+    fn export(options: &CsvExportOptions) -> Vec<String> {
+        let target = PingTarget::new("1.1.1.1".to_string(), "Cloudflare".to_string());
+        let timestamp = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let mut success = PingResult::success(&target, 12.3456, 1);
+        success.timestamp = timestamp;
+        let mut failure = PingResult::failure(&target, "Timeout".to_string(), 2);
+        failure.timestamp = timestamp;
+
+        let mut out = Vec::new();
+        write_csv(&mut out, vec![success, failure], options).unwrap();
+        String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_default_options_keep_full_precision_and_empty_failures() {
+        let rows = export(&CsvExportOptions::default());
+        assert_eq!(
+            rows,
+            vec![
+                "timestamp,target,target_label,latency_ms,success,sequence,attempts,error",
+                "2024-01-01T12:00:00+00:00,1.1.1.1,Cloudflare,12.3456,true,1,1,",
+                "2024-01-01T12:00:00+00:00,1.1.1.1,Cloudflare,,false,2,1,Timeout",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_microseconds_with_precision_epoch_and_fail_marker() {
+        let options = CsvExportOptions {
+            latency_unit: LatencyUnit::Us,
+            precision: Some(1),
+            failed_latency: FailedLatency::Fail,
+            timestamp_format: TimestampFormat::EpochMillis,
+        };
+        let rows = export(&options);
+        assert!(rows[0].contains(",latency_us,"));
+        assert_eq!(
+            rows[1],
+            "1704110400000,1.1.1.1,Cloudflare,12345.6,true,1,1,"
+        );
+        assert_eq!(
+            rows[2],
+            "1704110400000,1.1.1.1,Cloudflare,FAIL,false,2,1,Timeout"
+        );
+
+        let options = CsvExportOptions {
+            precision: Some(0),
+            failed_latency: FailedLatency::Zero,
+            timestamp_format: TimestampFormat::EpochSeconds,
+            ..CsvExportOptions::default()
+        };
+        let rows = export(&options);
+        assert_eq!(rows[1], "1704110400,1.1.1.1,Cloudflare,12,true,1,1,");
+        assert_eq!(rows[2], "1704110400,1.1.1.1,Cloudflare,0,false,2,1,Timeout");
+    }
+}
@@ -0,0 +1,147 @@
+use crate::events;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// Consecutive write failures before logging is considered degraded
+const DEFAULT_FAILURE_THRESHOLD: u32 = 3;
+/// First retry delay once logging is degraded
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_secs(5);
+/// Upper bound for the retry delay
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Transition in logging health that should be reported to the frontend
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum LogHealthEvent {
+    /// Writes keep failing; logging is suspended and retried periodically
+    Degraded { error: String },
+    /// A retry succeeded and logging resumed
+    Restored,
+}
+
+impl LogHealthEvent {
+    /// Name of the frontend event for this transition
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            Self::Degraded { .. } => events::LOGGING_DEGRADED,
+            Self::Restored => events::LOGGING_RESTORED,
+        }
+    }
+}
+
+/// Tracks log write failures and backs off while the log is unwritable
+#[derive(Debug, Clone)]
+pub struct LogHealth {
+    failure_threshold: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    consecutive_failures: u32,
+    degraded: bool,
+    backoff: Duration,
+    next_retry: Option<Instant>,
+}
+
+impl LogHealth {
+    pub fn new(failure_threshold: u32, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            initial_backoff,
+            max_backoff,
+            consecutive_failures: 0,
+            degraded: false,
+            backoff: initial_backoff,
+            next_retry: None,
+        }
+    }
+
+    /// Whether logging is currently suspended because of repeated failures
+    pub fn is_degraded(&self) -> bool {
+        self.degraded
+    }
+
+    /// Whether a write should be attempted at `now`
+    pub fn should_attempt(&self, now: Instant) -> bool {
+        match self.next_retry {
+            Some(next_retry) if self.degraded => now >= next_retry,
+            _ => true,
+        }
+    }
+
+    /// Record a successful write
+    pub fn record_success(&mut self) -> Option<LogHealthEvent> {
+        let was_degraded = self.degraded;
+        self.consecutive_failures = 0;
+        self.degraded = false;
+        self.backoff = self.initial_backoff;
+        self.next_retry = None;
+
+        was_degraded.then_some(LogHealthEvent::Restored)
+    }
+
+    /// Record a failed write, returning an event when logging becomes degraded
+    pub fn record_failure(&mut self, now: Instant, error: &str) -> Option<LogHealthEvent> {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+
+        if self.degraded {
+            // Still failing: wait longer before the next retry
+            self.backoff = (self.backoff * 2).min(self.max_backoff);
+            self.next_retry = Some(now + self.backoff);
+            return None;
+        }
+
+        if self.consecutive_failures >= self.failure_threshold {
+            self.degraded = true;
+            self.backoff = self.initial_backoff;
+            self.next_retry = Some(now + self.backoff);
+            return Some(LogHealthEvent::Degraded {
+                error: error.to_string(),
+            });
+        }
+
+        None
+    }
+}
+
+impl Default for LogHealth {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_FAILURE_THRESHOLD,
+            DEFAULT_INITIAL_BACKOFF,
+            DEFAULT_MAX_BACKOFF,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_degrades_after_threshold_and_backs_off() {
+        let mut health = LogHealth::new(2, Duration::from_secs(1), Duration::from_secs(4));
+        let now = Instant::now();
+
+        assert_eq!(health.record_failure(now, "disk full"), None);
+        assert_eq!(
+            health.record_failure(now, "disk full"),
+            Some(LogHealthEvent::Degraded {
+                error: "disk full".to_string()
+            })
+        );
+        assert!(!health.should_attempt(now));
+        assert!(health.should_attempt(now + Duration::from_secs(1)));
+
+        // Further failures stay silent and double the delay up to the cap
+        assert_eq!(health.record_failure(now, "disk full"), None);
+        assert!(!health.should_attempt(now + Duration::from_secs(1)));
+        assert!(health.should_attempt(now + Duration::from_secs(2)));
+        health.record_failure(now, "disk full");
+        health.record_failure(now, "disk full");
+        assert!(health.should_attempt(now + Duration::from_secs(4)));
+
+        assert_eq!(health.record_success(), Some(LogHealthEvent::Restored));
+        assert_eq!(health.record_success(), None);
+        assert!(!health.is_degraded());
+    }
+}
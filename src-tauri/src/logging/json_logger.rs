@@ -1,8 +1,11 @@
-use crate::models::PingResult;
+use super::ResultLogger;
+use crate::models::{PingResult, SessionBoundary};
 use chrono::{Local, NaiveDate};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use std::fs::{self, File, OpenOptions};
-use std::io::{BufWriter, Write};
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
 /// JSON logger with daily file rotation
@@ -10,6 +13,7 @@ pub struct JsonLogger {
     log_dir: PathBuf,
     current_date: Mutex<Option<NaiveDate>>,
     writer: Mutex<Option<BufWriter<File>>>,
+    pretty: bool,
 }
 
 impl JsonLogger {
@@ -22,9 +26,16 @@ impl JsonLogger {
             log_dir,
             current_date: Mutex::new(None),
             writer: Mutex::new(None),
+            pretty: false,
         })
     }
 
+    /// Write indented, multi-line JSON records instead of compact NDJSON
+    pub fn with_pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+
     /// Get the default log directory for the application
     pub fn default_log_dir() -> PathBuf {
         dirs::data_dir()
@@ -40,6 +51,16 @@ impl JsonLogger {
 
     /// Log a ping result
     pub fn log(&self, result: &PingResult) -> Result<(), std::io::Error> {
+        self.write_record(result)
+    }
+
+    /// Log a session start or end marker
+    pub fn log_boundary(&self, boundary: &SessionBoundary) -> Result<(), std::io::Error> {
+        self.write_record(boundary)
+    }
+
+    /// Append a record to today's file
+    fn write_record<T: Serialize>(&self, record: &T) -> Result<(), std::io::Error> {
         let today = Local::now().date_naive();
         
         // Check if we need to rotate the log file
@@ -70,7 +91,11 @@ impl JsonLogger {
         // Write the log entry
         let mut writer = self.writer.lock().unwrap();
         if let Some(ref mut w) = *writer {
-            let json = serde_json::to_string(result)?;
+            let json = if self.pretty {
+                serde_json::to_string_pretty(record)?
+            } else {
+                serde_json::to_string(record)?
+            };
             writeln!(w, "{}", json)?;
             w.flush()?;
         }
@@ -102,17 +127,71 @@ impl JsonLogger {
     }
 
     /// Read ping results from a log file
-    pub fn read_log_file(&self, path: &PathBuf) -> Result<Vec<PingResult>, std::io::Error> {
-        let content = fs::read_to_string(path)?;
-        let mut results = Vec::new();
-        
-        for line in content.lines() {
-            if let Ok(result) = serde_json::from_str::<PingResult>(line) {
-                results.push(result);
+    pub fn read_log_file(&self, path: &Path) -> Result<Vec<PingResult>, std::io::Error> {
+        Ok(Self::stream_log_file(path)?.collect())
+    }
+
+    /// Stream ping results from a log file one record at a time
+    ///
+    /// Handles both compact and pretty-printed records. Lines that are not
+    /// valid ping results, such as session markers, are skipped.
+    pub fn stream_log_file(
+        path: &Path,
+    ) -> Result<impl Iterator<Item = PingResult>, std::io::Error> {
+        Self::stream_records(path)
+    }
+
+    /// Stream the session start and end markers of a log file
+    pub fn stream_boundaries(
+        path: &Path,
+    ) -> Result<impl Iterator<Item = SessionBoundary>, std::io::Error> {
+        Self::stream_records(path)
+    }
+
+    /// Stream the records of one type from a log file, skipping all others
+    fn stream_records<T: DeserializeOwned>(
+        path: &Path,
+    ) -> Result<impl Iterator<Item = T>, std::io::Error> {
+        let mut lines = BufReader::new(File::open(path)?).lines().map_while(Result::ok);
+        let mut record = String::new();
+
+        Ok(std::iter::from_fn(move || {
+            for line in lines.by_ref() {
+                record.push_str(&line);
+                record.push('\n');
+
+                match serde_json::from_str::<T>(&record) {
+                    Ok(result) => {
+                        record.clear();
+                        return Some(result);
+                    }
+                    // A pretty record continues on the next line
+                    Err(e) if e.is_eof() => {}
+                    Err(_) => record.clear(),
+                }
             }
-        }
-        
-        Ok(results)
+            None
+        }))
+    }
+}
+
+impl ResultLogger for JsonLogger {
+    fn log(&self, result: &PingResult) -> Result<(), std::io::Error> {
+        JsonLogger::log(self, result)
+    }
+
+    fn log_boundary(&self, boundary: &SessionBoundary) -> Result<(), std::io::Error> {
+        JsonLogger::log_boundary(self, boundary)
+    }
+
+    fn log_dir(&self) -> &PathBuf {
+        JsonLogger::log_dir(self)
+    }
+
+    fn open_files(&self) -> usize {
+        self.writer
+            .lock()
+            .map_or(0, |writer| usize::from(writer.is_some()))
     }
 }
 
@@ -126,3 +205,81 @@ impl Drop for JsonLogger {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PingTarget;
+    use crate::stats::StatsCalculator;
+
+    #[test]
+    fn test_analyze_fabricated_log_file() {
+        let dir = std::env::temp_dir().join(format!("ping-log-test-{}", uuid::Uuid::new_v4()));
+        let logger = JsonLogger::new(dir.clone()).unwrap();
+        let cloudflare = PingTarget::new("1.1.1.1".to_string(), "Cloudflare".to_string());
+        let google = PingTarget::new("8.8.8.8".to_string(), "Google".to_string());
+
+        logger.log(&PingResult::success(&cloudflare, 10.0, 1)).unwrap();
+        logger.log(&PingResult::success(&google, 30.0, 1)).unwrap();
+        logger.log(&PingResult::success(&cloudflare, 20.0, 2)).unwrap();
+        logger.log(&PingResult::failure(&google, "Timeout".to_string(), 2)).unwrap();
+
+        // Garbage lines are skipped rather than aborting the analysis
+        let path = logger.log_file_path(Local::now().date_naive());
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        writeln!(file, "not json").unwrap();
+        drop(file);
+
+        let stats = StatsCalculator::from_results(JsonLogger::stream_log_file(&path).unwrap());
+        let cloudflare_stats = stats.get_stats("1.1.1.1").unwrap();
+        let google_stats = stats.get_stats("8.8.8.8").unwrap();
+
+        assert_eq!(stats.get_all_stats().len(), 2);
+        assert_eq!(cloudflare_stats.total_pings, 2);
+        assert_eq!(cloudflare_stats.avg_latency_ms, Some(15.0));
+        assert_eq!(google_stats.total_pings, 2);
+        assert_eq!(google_stats.packet_loss_percent, 50.0);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    fn round_trip(pretty: bool) {
+        let dir = std::env::temp_dir().join(format!("ping-log-test-{}", uuid::Uuid::new_v4()));
+        let logger = JsonLogger::new(dir.clone()).unwrap().with_pretty(pretty);
+        let target = PingTarget::new("1.1.1.1".to_string(), "Cloudflare".to_string());
+        let written = vec![
+            PingResult::success(&target, 12.5, 1),
+            PingResult::failure(&target, "Timeout".to_string(), 2),
+            PingResult::success(&target, 0.123456, 3).with_latency_us(),
+        ];
+
+        for result in &written {
+            logger.log(result).unwrap();
+        }
+
+        let path = logger.log_file_path(Local::now().date_naive());
+        let contents = fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.lines().count() > written.len(), pretty);
+
+        let read = logger.read_log_file(&path).unwrap();
+        assert_eq!(read.len(), written.len());
+        for (read, written) in read.iter().zip(&written) {
+            assert_eq!(read.sequence, written.sequence);
+            assert_eq!(read.latency_ms, written.latency_ms);
+            assert_eq!(read.error, written.error);
+            assert_eq!(read.latency_us, written.latency_us);
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_compact_round_trip() {
+        round_trip(false);
+    }
+
+    #[test]
+    fn test_pretty_round_trip() {
+        round_trip(true);
+    }
+}
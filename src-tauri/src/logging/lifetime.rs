@@ -0,0 +1,38 @@
+use crate::models::LifetimeCounters;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Where lifetime counters are kept between runs
+pub fn default_lifetime_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("ping-connectivity")
+        .join("lifetime-stats.json")
+}
+
+/// Save lifetime counters keyed by target address
+///
+/// The counters go to a temporary file that is then renamed over `path`, so
+/// a crash mid-write keeps the previous counters.
+pub fn write_lifetime(
+    path: &Path,
+    counters: &HashMap<String, LifetimeCounters>,
+) -> Result<(), std::io::Error> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let partial = path.with_extension("json.tmp");
+    let mut writer = BufWriter::new(File::create(&partial)?);
+    serde_json::to_writer(&mut writer, counters)?;
+    writer.flush()?;
+    drop(writer);
+    fs::rename(&partial, path)
+}
+
+/// Load lifetime counters saved with [`write_lifetime`]
+pub fn read_lifetime(path: &Path) -> Result<HashMap<String, LifetimeCounters>, std::io::Error> {
+    Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+}
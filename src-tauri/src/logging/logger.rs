@@ -0,0 +1,33 @@
+use super::{BinaryLogger, JsonLogger};
+use crate::models::{AppConfig, LogFormat, PingResult, SessionBoundary};
+use std::path::PathBuf;
+
+/// A destination that persists ping results
+pub trait ResultLogger: Send + Sync {
+    /// Persist a single ping result
+    fn log(&self, result: &PingResult) -> Result<(), std::io::Error>;
+
+    /// Persist a session start or end marker
+    fn log_boundary(&self, _boundary: &SessionBoundary) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
+    /// Directory the logger writes into
+    fn log_dir(&self) -> &PathBuf;
+
+    /// Files the logger currently holds open
+    fn open_files(&self) -> usize {
+        0
+    }
+}
+
+/// Create a logger writing the configured format into `log_dir`
+pub fn create_logger(
+    config: &AppConfig,
+    log_dir: PathBuf,
+) -> Result<Box<dyn ResultLogger>, std::io::Error> {
+    Ok(match config.log_format {
+        LogFormat::Json => Box::new(JsonLogger::new(log_dir)?.with_pretty(config.pretty_logs)),
+        LogFormat::Binary => Box::new(BinaryLogger::new(log_dir)?),
+    })
+}
@@ -1,3 +1,30 @@
+mod binary_logger;
+mod compact;
+mod export;
+mod health;
 mod json_logger;
+mod lifetime;
+mod logger;
+mod purge;
+mod query;
+mod settings;
+mod snapshot;
+mod summary;
+mod switches;
 
+pub use binary_logger::{encode_boundary, encode_record, BinaryLogReader, BinaryLogger};
+pub use compact::compact_logs;
+pub use export::{write_csv, write_jsonl};
+pub use health::{LogHealth, LogHealthEvent};
 pub use json_logger::JsonLogger;
+pub use lifetime::{default_lifetime_path, read_lifetime, write_lifetime};
+pub use logger::{create_logger, ResultLogger};
+pub use purge::purge_target_logs;
+pub use query::{
+    list_sessions, log_file_date, log_file_format, log_files_in_range, query_logs,
+    stream_boundaries, stream_results,
+};
+pub use settings::{default_config_path, read_config, write_config};
+pub use snapshot::{default_snapshot_path, read_snapshot, write_snapshot};
+pub use summary::{replace_summary_csv, write_summary_csv, SUMMARY_CSV_FILE};
+pub use switches::{default_switches_path, read_switches, write_switches};
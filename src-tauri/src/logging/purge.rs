@@ -0,0 +1,244 @@
+use super::binary_logger::decode_payload;
+use super::query::{gzip_logs_in_range, is_gzip};
+use super::{log_file_format, log_files_in_range};
+use crate::models::{LogFormat, PingResult, TimeRange};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Remove one target's logged results within `range`, returning how many went
+///
+/// Only the matching records are dropped; other targets' records and any
+/// unreadable lines are kept byte for byte. Each affected file is rewritten
+/// to a temporary file beside it and renamed over the original, so an
+/// interrupted purge leaves either the old or the new file. Gzipped JSON
+/// logs (`.jsonl.gz`, e.g. left by imports) are purged too and stay gzipped;
+/// files in other formats are left alone.
+pub fn purge_target_logs(
+    log_dir: &Path,
+    target: &str,
+    range: &TimeRange,
+) -> Result<usize, std::io::Error> {
+    let mut removed = 0;
+    let mut files = log_files_in_range(log_dir, range)?;
+    files.extend(gzip_logs_in_range(log_dir, range)?);
+    for path in files {
+        let matches =
+            |result: &PingResult| result.target == target && range.contains(result.timestamp);
+        removed += match log_file_format(&path) {
+            _ if is_gzip(&path) => rewrite(&path, |reader, writer| {
+                let mut decoded = BufReader::new(GzDecoder::new(reader));
+                let mut encoder = GzEncoder::new(writer, Compression::default());
+                let removed = purge_json(&mut decoded, &mut encoder, matches)?;
+                encoder.finish()?;
+                Ok(removed)
+            })?,
+            Some(LogFormat::Binary) => rewrite(&path, |reader, writer| {
+                purge_binary(reader, writer, matches)
+            })?,
+            _ => rewrite(&path, |reader, writer| purge_json(reader, writer, matches))?,
+        };
+    }
+    Ok(removed)
+}
+
+/// Filter `path` through `purge` into a temporary file, replacing the
+/// original only if something was removed
+fn rewrite<F>(path: &Path, purge: F) -> Result<usize, std::io::Error>
+where
+    F: FnOnce(&mut BufReader<File>, &mut BufWriter<File>) -> Result<usize, std::io::Error>,
+{
+    let temp = path.with_extension("purge.tmp");
+    let outcome = (|| {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut writer = BufWriter::new(File::create(&temp)?);
+        let removed = purge(&mut reader, &mut writer)?;
+        writer
+            .into_inner()
+            .map_err(|e| e.into_error())?
+            .sync_all()?;
+        Ok(removed)
+    })();
+
+    match outcome {
+        Ok(removed) if removed > 0 => {
+            fs::rename(&temp, path)?;
+            Ok(removed)
+        }
+        other => {
+            let _ = fs::remove_file(&temp);
+            other
+        }
+    }
+}
+
+/// Copy JSON records, compact or pretty, skipping those that match
+fn purge_json<R, W, F>(reader: &mut R, writer: &mut W, matches: F) -> Result<usize, std::io::Error>
+where
+    R: BufRead,
+    W: Write,
+    F: Fn(&PingResult) -> bool,
+{
+    let mut removed = 0;
+    let mut record = String::new();
+    let mut line = String::new();
+
+    while reader.read_line(&mut line)? > 0 {
+        record.push_str(&line);
+        line.clear();
+
+        match serde_json::from_str::<PingResult>(&record) {
+            Ok(result) if matches(&result) => removed += 1,
+            // A pretty record continues on the next line
+            Err(e) if e.is_eof() => continue,
+            _ => writer.write_all(record.as_bytes())?,
+        }
+        record.clear();
+    }
+
+    // Keep a trailing partial record as it was
+    writer.write_all(record.as_bytes())?;
+    Ok(removed)
+}
+
+/// Copy length-prefixed binary records, skipping those that match
+fn purge_binary<R, W, F>(
+    reader: &mut R,
+    writer: &mut W,
+    matches: F,
+) -> Result<usize, std::io::Error>
+where
+    R: Read,
+    W: Write,
+    F: Fn(&PingResult) -> bool,
+{
+    let mut removed = 0;
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest)?;
+    let mut buf = rest.as_slice();
+
+    while buf.len() >= 4 {
+        let len = u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as usize;
+        if buf.len() < 4 + len {
+            break;
+        }
+        let (record, tail) = buf.split_at(4 + len);
+        match decode_payload(&record[4..]) {
+            Some(result) if matches(&result) => removed += 1,
+            _ => writer.write_all(record)?,
+        }
+        buf = tail;
+    }
+
+    // Keep a truncated trailing record as it was
+    writer.write_all(buf)?;
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logging::{encode_record, stream_results};
+    use crate::models::PingTarget;
+    use chrono::{Duration, Local, Utc};
+
+    fn at(mut result: PingResult, minutes_ago: i64) -> PingResult {
+        result.timestamp = Utc::now() - Duration::minutes(minutes_ago);
+        result
+    }
+
+    #[test]
+    fn test_purge_removes_only_one_targets_lines_in_range() {
+        let dir = std::env::temp_dir().join(format!("ping-purge-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let day = Local::now().date_naive().format("%Y-%m-%d");
+        let json_path = dir.join(format!("ping-{}.jsonl", day));
+        let binary_path = dir.join(format!("ping-{}.bin", day));
+
+        let private = PingTarget::new("192.0.2.1".to_string(), "Private".to_string());
+        let other = PingTarget::new("192.0.2.2".to_string(), "Other".to_string());
+        let results = vec![
+            at(PingResult::success(&private, 10.0, 1), 30),
+            at(PingResult::success(&other, 20.0, 1), 30),
+            at(PingResult::failure(&private, "Timeout".to_string(), 2), 5),
+            at(PingResult::success(&other, 21.0, 2), 5),
+        ];
+
+        let mut json = String::new();
+        for result in &results {
+            json.push_str(&serde_json::to_string(result).unwrap());
+            json.push('\n');
+        }
+        json.push_str("not json\n");
+        fs::write(&json_path, &json).unwrap();
+        let binary: Vec<u8> = results.iter().flat_map(encode_record).collect();
+        fs::write(&binary_path, binary).unwrap();
+
+        // Only the last ten minutes of the private target go
+        let range = TimeRange::new(Utc::now() - Duration::minutes(10), Utc::now());
+        assert_eq!(purge_target_logs(&dir, "192.0.2.1", &range).unwrap(), 2);
+
+        for path in [&json_path, &binary_path] {
+            let kept: Vec<(String, u32)> = stream_results(path)
+                .unwrap()
+                .map(|r| (r.target, r.sequence))
+                .collect();
+            assert_eq!(
+                kept,
+                [
+                    ("192.0.2.1".to_string(), 1),
+                    ("192.0.2.2".to_string(), 1),
+                    ("192.0.2.2".to_string(), 2)
+                ]
+            );
+        }
+        let rewritten = fs::read_to_string(&json_path).unwrap();
+        assert!(rewritten.ends_with("not json\n"));
+        assert!(json.contains(rewritten.lines().nth(1).unwrap()));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        // Nothing left to purge leaves the files untouched
+        assert_eq!(purge_target_logs(&dir, "192.0.2.1", &range).unwrap(), 0);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_purge_rewrites_gzipped_fragments() {
+        let dir = std::env::temp_dir().join(format!("ping-purge-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let day = Local::now().date_naive().format("%Y-%m-%d");
+        let path = dir.join(format!("ping-{}-import.jsonl.gz", day));
+
+        let private = PingTarget::new("192.0.2.1".to_string(), "Private".to_string());
+        let other = PingTarget::new("192.0.2.2".to_string(), "Other".to_string());
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        for result in [
+            at(PingResult::success(&private, 10.0, 1), 5),
+            at(PingResult::success(&other, 20.0, 1), 5),
+        ] {
+            writeln!(gz, "{}", serde_json::to_string(&result).unwrap()).unwrap();
+        }
+        fs::write(&path, gz.finish().unwrap()).unwrap();
+
+        let range = TimeRange::new(Utc::now() - Duration::minutes(10), Utc::now());
+        assert_eq!(purge_target_logs(&dir, "192.0.2.1", &range).unwrap(), 1);
+
+        let mut text = String::new();
+        GzDecoder::new(File::open(&path).unwrap())
+            .read_to_string(&mut text)
+            .unwrap();
+        let kept: Vec<PingResult> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].target, "192.0.2.2");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,170 @@
+use super::{BinaryLogger, JsonLogger};
+use crate::models::{
+    LogFormat, LoggedSession, PingResult, SessionBoundary, SessionBoundaryKind, TimeRange,
+};
+use chrono::{Duration, NaiveDate};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Parse the date from a `ping-YYYY-MM-DD.jsonl` file name
+pub fn log_file_date(path: &Path) -> Option<NaiveDate> {
+    let name = path.file_name()?.to_str()?;
+    let date = name.strip_prefix("ping-")?.get(..10)?;
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
+
+/// Format of a log file, judged by its extension
+pub fn log_file_format(path: &Path) -> Option<LogFormat> {
+    match path.extension()?.to_str()? {
+        "jsonl" => Some(LogFormat::Json),
+        "bin" => Some(LogFormat::Binary),
+        _ => None,
+    }
+}
+
+/// Stream ping results from a log file of any supported format
+pub fn stream_results(path: &Path) -> Result<Box<dyn Iterator<Item = PingResult>>, std::io::Error> {
+    match log_file_format(path) {
+        Some(LogFormat::Binary) => Ok(Box::new(BinaryLogger::stream_log_file(path)?)),
+        _ => Ok(Box::new(JsonLogger::stream_log_file(path)?)),
+    }
+}
+
+/// Stream the session start and end markers of a log file of any supported format
+pub fn stream_boundaries(
+    path: &Path,
+) -> Result<Box<dyn Iterator<Item = SessionBoundary>>, std::io::Error> {
+    match log_file_format(path) {
+        Some(LogFormat::Binary) => Ok(Box::new(BinaryLogger::stream_boundaries(path)?)),
+        _ => Ok(Box::new(JsonLogger::stream_boundaries(path)?)),
+    }
+}
+
+/// Log files that may contain results within `range`, oldest first
+///
+/// Files are named by local date, so a day of slack is allowed on each side
+/// to cover any UTC offset.
+pub fn log_files_in_range(
+    log_dir: &Path,
+    range: &TimeRange,
+) -> Result<Vec<PathBuf>, std::io::Error> {
+    files_in_range(log_dir, range, |path| log_file_format(path).is_some())
+}
+
+/// Gzipped JSON logs, e.g. left by imports, that may contain results within
+/// `range`, oldest first
+pub(super) fn gzip_logs_in_range(
+    log_dir: &Path,
+    range: &TimeRange,
+) -> Result<Vec<PathBuf>, std::io::Error> {
+    files_in_range(log_dir, range, is_gzip)
+}
+
+/// Whether a log file is gzipped JSON (`.jsonl.gz`)
+pub(super) fn is_gzip(path: &Path) -> bool {
+    path.to_str()
+        .is_some_and(|path| path.ends_with(".jsonl.gz"))
+}
+
+/// Dated log files accepted by `keep` that may hold results within `range`
+fn files_in_range<F>(
+    log_dir: &Path,
+    range: &TimeRange,
+    keep: F,
+) -> Result<Vec<PathBuf>, std::io::Error>
+where
+    F: Fn(&Path) -> bool,
+{
+    let first = range.from.date_naive() - Duration::days(1);
+    let last = range.to.date_naive() + Duration::days(1);
+    let mut files = Vec::new();
+
+    if log_dir.exists() {
+        for entry in fs::read_dir(log_dir)? {
+            let path = entry?.path();
+            if !keep(&path) {
+                continue;
+            }
+            if let Some(date) = log_file_date(&path) {
+                if date >= first && date <= last {
+                    files.push(path);
+                }
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Stream logged results within `range`, optionally limited to one target
+/// address and one session
+pub fn query_logs(
+    log_dir: &Path,
+    target: Option<&str>,
+    session: Option<&str>,
+    range: TimeRange,
+) -> Result<impl Iterator<Item = PingResult>, std::io::Error> {
+    let target = target.map(str::to_string);
+    let session = session.map(str::to_string);
+    let files = log_files_in_range(log_dir, &range)?;
+
+    Ok(files
+        .into_iter()
+        .filter_map(|path| match stream_results(&path) {
+            Ok(results) => Some(results),
+            Err(e) => {
+                log::warn!("Skipping unreadable log file {:?}: {}", path, e);
+                None
+            }
+        })
+        .flatten()
+        .filter(move |result| {
+            range.contains(result.timestamp)
+                && target
+                    .as_deref()
+                    .map(|t| t == result.target)
+                    .unwrap_or(true)
+                && session
+                    .as_deref()
+                    .map_or(true, |s| result.session_id.as_deref() == Some(s))
+        }))
+}
+
+/// Sessions started within `range`, oldest first
+pub fn list_sessions(
+    log_dir: &Path,
+    range: TimeRange,
+) -> Result<Vec<LoggedSession>, std::io::Error> {
+    let mut sessions: Vec<LoggedSession> = Vec::new();
+    for path in log_files_in_range(log_dir, &range)? {
+        let boundaries = match stream_boundaries(&path) {
+            Ok(boundaries) => boundaries,
+            Err(e) => {
+                log::warn!("Skipping unreadable log file {:?}: {}", path, e);
+                continue;
+            }
+        };
+        for boundary in boundaries {
+            match boundary.boundary {
+                SessionBoundaryKind::Start if range.contains(boundary.timestamp) => {
+                    sessions.push(LoggedSession {
+                        id: boundary.session_id,
+                        started_at: boundary.timestamp,
+                        ended_at: None,
+                    })
+                }
+                SessionBoundaryKind::Start => {}
+                SessionBoundaryKind::End => {
+                    if let Some(session) = sessions.iter_mut().find(|s| s.id == boundary.session_id)
+                    {
+                        session.ended_at = Some(boundary.timestamp);
+                    }
+                }
+            }
+        }
+    }
+
+    sessions.sort_by_key(|s| s.started_at);
+    Ok(sessions)
+}
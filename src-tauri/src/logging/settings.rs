@@ -0,0 +1,34 @@
+use crate::models::AppConfig;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Where the configuration is kept between runs
+pub fn default_config_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("ping-connectivity")
+        .join("config.json")
+}
+
+/// Save the configuration
+///
+/// Written through a temporary file like the lifetime counters, so a crash
+/// mid-write keeps the previous configuration.
+pub fn write_config(path: &Path, config: &AppConfig) -> Result<(), std::io::Error> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let partial = path.with_extension("json.tmp");
+    let mut writer = BufWriter::new(File::create(&partial)?);
+    serde_json::to_writer_pretty(&mut writer, config)?;
+    writer.flush()?;
+    drop(writer);
+    fs::rename(&partial, path)
+}
+
+/// Load a configuration saved with [`write_config`]
+pub fn read_config(path: &Path) -> Result<AppConfig, std::io::Error> {
+    Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+}
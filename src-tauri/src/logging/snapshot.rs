@@ -0,0 +1,39 @@
+use crate::models::PingResult;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Where the recent results buffer is kept between runs
+pub fn default_snapshot_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("ping-connectivity")
+        .join("recent-results.json")
+}
+
+/// Save results, oldest first, so they can be restored after a restart
+pub fn write_snapshot(path: &Path, results: &[PingResult]) -> Result<(), std::io::Error> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer(&mut writer, results)?;
+    writer.flush()
+}
+
+/// Load a saved snapshot, keeping the newest `max_size` results
+///
+/// Restored results are marked as `restored` so the chart can set them
+/// apart from results of the current run.
+pub fn read_snapshot(path: &Path, max_size: usize) -> Result<Vec<PingResult>, std::io::Error> {
+    let mut results: Vec<PingResult> = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+
+    let excess = results.len().saturating_sub(max_size);
+    results.drain(..excess);
+    for result in &mut results {
+        result.restored = true;
+    }
+
+    Ok(results)
+}
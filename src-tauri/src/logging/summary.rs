@@ -0,0 +1,59 @@
+use super::export::csv_field;
+use crate::models::PingStatistics;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Name of the summary file in the log directory
+pub const SUMMARY_CSV_FILE: &str = "summary.csv";
+
+/// Header row of the summary file
+const SUMMARY_HEADER: &str = "target,target_label,total_pings,successful_pings,failed_pings,\
+packet_loss_percent,min_latency_ms,avg_latency_ms,max_latency_ms,jitter_ms,last_ping";
+
+/// Write statistics as CSV with a header row, one row per target
+pub fn write_summary_csv<W: Write>(
+    writer: &mut W,
+    stats: &[PingStatistics],
+) -> Result<(), std::io::Error> {
+    let latency = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+
+    writeln!(writer, "{}", SUMMARY_HEADER)?;
+    for stat in stats {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{},{},{},{}",
+            csv_field(&stat.target),
+            csv_field(&stat.target_label),
+            stat.total_pings,
+            stat.successful_pings,
+            stat.failed_pings,
+            stat.packet_loss_percent,
+            latency(stat.min_latency_ms),
+            latency(stat.avg_latency_ms),
+            latency(stat.max_latency_ms),
+            latency(stat.jitter_ms),
+            stat.last_ping.map(|t| t.to_rfc3339()).unwrap_or_default(),
+        )?;
+    }
+    Ok(())
+}
+
+/// Replace the summary file at `path` with the given statistics
+///
+/// The rows go to a temporary file that is then renamed over `path`, so
+/// readers see either the previous summary or the new one, never a part.
+pub fn replace_summary_csv(path: &Path, stats: &[PingStatistics]) -> Result<(), std::io::Error> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let partial = path.with_extension("csv.tmp");
+    let mut writer = BufWriter::new(File::create(&partial)?);
+    write_summary_csv(&mut writer, stats)?;
+    writer
+        .into_inner()
+        .map_err(|e| e.into_error())?
+        .sync_all()?;
+    fs::rename(&partial, path)
+}
@@ -0,0 +1,34 @@
+use crate::models::MonitorSwitches;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Where the kill switch and pause state are kept between runs
+pub fn default_switches_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("ping-connectivity")
+        .join("monitor-switches.json")
+}
+
+/// Save the kill switch and pause state
+///
+/// Written through a temporary file like the lifetime counters, so a crash
+/// mid-write keeps the previous state.
+pub fn write_switches(path: &Path, switches: &MonitorSwitches) -> Result<(), std::io::Error> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let partial = path.with_extension("json.tmp");
+    let mut writer = BufWriter::new(File::create(&partial)?);
+    serde_json::to_writer(&mut writer, switches)?;
+    writer.flush()?;
+    drop(writer);
+    fs::rename(&partial, path)
+}
+
+/// Load state saved with [`write_switches`]
+pub fn read_switches(path: &Path) -> Result<MonitorSwitches, std::io::Error> {
+    Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+}
@@ -0,0 +1,5 @@
+mod prometheus;
+mod push;
+
+pub use prometheus::format_metrics;
+pub use push::{http_post, push_metrics, PushHealthEvent, DEFAULT_PUSH_JOB};
@@ -0,0 +1,125 @@
+use crate::models::PingStatistics;
+use std::fmt::Write;
+
+/// A metric family and how to read it from a target's statistics
+struct Family {
+    name: &'static str,
+    help: &'static str,
+    kind: &'static str,
+    value: fn(&PingStatistics) -> Option<f64>,
+}
+
+const FAMILIES: &[Family] = &[
+    Family {
+        name: "ping_sent_total",
+        help: "Pings sent",
+        kind: "counter",
+        value: |s| Some(s.total_pings as f64),
+    },
+    Family {
+        name: "ping_received_total",
+        help: "Pings answered",
+        kind: "counter",
+        value: |s| Some(s.successful_pings as f64),
+    },
+    Family {
+        name: "ping_packet_loss_percent",
+        help: "Share of pings lost, 0-100",
+        kind: "gauge",
+        value: |s| Some(s.packet_loss_percent),
+    },
+    Family {
+        name: "ping_latency_min_ms",
+        help: "Lowest latency",
+        kind: "gauge",
+        value: |s| s.min_latency_ms,
+    },
+    Family {
+        name: "ping_latency_avg_ms",
+        help: "Average latency",
+        kind: "gauge",
+        value: |s| s.avg_latency_ms,
+    },
+    Family {
+        name: "ping_latency_max_ms",
+        help: "Highest latency",
+        kind: "gauge",
+        value: |s| s.max_latency_ms,
+    },
+    Family {
+        name: "ping_jitter_ms",
+        help: "Standard deviation of latency",
+        kind: "gauge",
+        value: |s| s.jitter_ms,
+    },
+    Family {
+        name: "ping_congestion_ms",
+        help: "Recent average latency above the recent minimum",
+        kind: "gauge",
+        value: |s| s.congestion_ms,
+    },
+];
+
+/// Render statistics in the Prometheus text exposition format (version 0.0.4)
+///
+/// Each target is labelled with its address and label. Latency gauges are
+/// left out for targets that have no successful ping yet.
+pub fn format_metrics(stats: &[PingStatistics]) -> String {
+    let mut out = String::new();
+    for family in FAMILIES {
+        let _ = writeln!(out, "# HELP {} {}", family.name, family.help);
+        let _ = writeln!(out, "# TYPE {} {}", family.name, family.kind);
+        for target_stats in stats {
+            if let Some(value) = (family.value)(target_stats) {
+                let _ = writeln!(
+                    out,
+                    "{}{{target=\"{}\",label=\"{}\"}} {}",
+                    family.name,
+                    escape_label(&target_stats.target),
+                    escape_label(&target_stats.target_label),
+                    value
+                );
+            }
+        }
+    }
+    out
+}
+
+/// Escape a label value: backslash, double quote and newline
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{PingResult, PingTarget};
+    use crate::stats::StatsCalculator;
+
+    #[test]
+    fn test_format_metrics() {
+        let target = PingTarget::new("192.0.2.1".to_string(), "Office \"VPN\"".to_string());
+        let silent = PingTarget::new("192.0.2.2".to_string(), "Silent".to_string());
+        let stats = StatsCalculator::from_results([
+            PingResult::success(&target, 10.0, 1),
+            PingResult::failure(&target, "Timeout".to_string(), 2),
+            PingResult::failure(&silent, "Timeout".to_string(), 1),
+        ]);
+
+        let text = format_metrics(&stats.get_all_stats());
+        assert!(text.contains("# TYPE ping_sent_total counter\n"));
+        assert!(
+            text.contains("ping_sent_total{target=\"192.0.2.1\",label=\"Office \\\"VPN\\\"\"} 2\n")
+        );
+        assert!(
+            text.contains("ping_packet_loss_percent{target=\"192.0.2.2\",label=\"Silent\"} 100\n")
+        );
+        assert!(text.contains(
+            "ping_latency_avg_ms{target=\"192.0.2.1\",label=\"Office \\\"VPN\\\"\"} 10\n"
+        ));
+        assert!(!text.contains("ping_latency_avg_ms{target=\"192.0.2.2\""));
+    }
+}
@@ -0,0 +1,193 @@
+use crate::events;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// Job name metrics are grouped under unless configured otherwise
+pub const DEFAULT_PUSH_JOB: &str = "ping_connectivity";
+
+/// Change in the health of pushes to the Pushgateway
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum PushHealthEvent {
+    /// Pushes for `job` keep failing; they are retried with backoff
+    Degraded { job: String, error: String },
+    /// A push for `job` succeeded again
+    Restored { job: String },
+}
+
+impl PushHealthEvent {
+    /// Name of the frontend event for this transition
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            Self::Degraded { .. } => events::PUSH_DEGRADED,
+            Self::Restored { .. } => events::PUSH_RESTORED,
+        }
+    }
+}
+
+/// Push metrics in the text format to a Prometheus Pushgateway
+///
+/// POSTs `body` to `<url>/metrics/job/<job>`, replacing the metrics of the
+/// same names pushed earlier for the job. Only plain `http://` URLs are
+/// supported.
+pub fn push_metrics(url: &str, job: &str, body: &str, timeout: Duration) -> Result<(), String> {
+    let url = format!("{}/metrics/job/{}", url.trim_end_matches('/'), job);
+    http_post(
+        "Pushgateway",
+        &url,
+        "text/plain; version=0.0.4",
+        body,
+        timeout,
+    )
+}
+
+/// POST `body` to a plain `http://` URL, expecting a 2xx answer
+///
+/// `service` names the receiver in error messages.
+pub fn http_post(
+    service: &str,
+    url: &str,
+    content_type: &str,
+    body: &str,
+    timeout: Duration,
+) -> Result<(), String> {
+    let (authority, path) = parse_http_url(service, url)?;
+    let path = if path.is_empty() { "/" } else { path };
+
+    let has_port = if authority.starts_with('[') {
+        authority.contains("]:")
+    } else {
+        authority.contains(':')
+    };
+    let address = if has_port {
+        authority.to_string()
+    } else {
+        format!("{}:80", authority)
+    };
+    let socket = address
+        .to_socket_addrs()
+        .map_err(|e| format!("Cannot resolve {}: {}", authority, e))?
+        .next()
+        .ok_or_else(|| format!("Cannot resolve {}", authority))?;
+
+    let mut stream = TcpStream::connect_timeout(&socket, timeout)
+        .map_err(|e| format!("Cannot connect to {}: {}", authority, e))?;
+    stream
+        .set_read_timeout(Some(timeout))
+        .map_err(|e| e.to_string())?;
+    stream
+        .set_write_timeout(Some(timeout))
+        .map_err(|e| e.to_string())?;
+
+    let request = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: {}\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        path,
+        authority,
+        content_type,
+        body.len(),
+        body
+    );
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|e| format!("Post to {} failed: {}", service, e))?;
+
+    let mut response = Vec::new();
+    stream
+        .read_to_end(&mut response)
+        .map_err(|e| format!("Post to {} failed: {}", service, e))?;
+    let response = String::from_utf8_lossy(&response);
+    let status_line = response.lines().next().unwrap_or_default();
+    match status_line.split_whitespace().nth(1) {
+        Some(code) if code.starts_with('2') => Ok(()),
+        Some(_) => Err(format!("{} answered {}", service, status_line.trim())),
+        None => Err(format!("{} sent no response", service)),
+    }
+}
+
+/// Split an `http://` URL into its authority and path
+fn parse_http_url<'a>(service: &str, url: &'a str) -> Result<(&'a str, &'a str), String> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| format!("Only http:// {} URLs are supported: {}", service, url))?;
+    let (authority, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, ""),
+    };
+    if authority.is_empty() {
+        return Err(format!("{} URL has no host: {}", service, url));
+    }
+    Ok((authority, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    /// Accept one request, answer with `status` and hand back the request line and body
+    fn mock_receiver(status: &'static str) -> (String, mpsc::Receiver<(String, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/base/", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+
+            let mut length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some(value) = header.to_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            let mut stream = reader.into_inner();
+            write!(stream, "HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status).unwrap();
+            tx.send((
+                request_line.trim().to_string(),
+                String::from_utf8(body).unwrap(),
+            ))
+            .unwrap();
+        });
+        (url, rx)
+    }
+
+    #[test]
+    fn test_push_posts_body_to_job_path() {
+        let (url, received) = mock_receiver("200 OK");
+        let body = "ping_sent_total{target=\"192.0.2.1\",label=\"Test\"} 3\n";
+
+        push_metrics(&url, DEFAULT_PUSH_JOB, body, Duration::from_secs(2)).unwrap();
+
+        let (request_line, pushed) = received.recv().unwrap();
+        assert_eq!(
+            request_line,
+            "POST /base/metrics/job/ping_connectivity HTTP/1.1"
+        );
+        assert_eq!(pushed, body);
+    }
+
+    #[test]
+    fn test_push_reports_rejections() {
+        let (url, _received) = mock_receiver("400 Bad Request");
+        let error = push_metrics(&url, "job", "bad", Duration::from_secs(2)).unwrap_err();
+        assert_eq!(error, "Pushgateway answered HTTP/1.1 400 Bad Request");
+
+        let error = push_metrics("https://example.com", "job", "", Duration::from_secs(1));
+        assert!(error.unwrap_err().starts_with("Only http://"));
+    }
+}
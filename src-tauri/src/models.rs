@@ -1,14 +1,95 @@
-use chrono::{DateTime, Utc};
+use crate::channels::DEFAULT_BROADCAST_CAPACITY;
+use crate::metrics::DEFAULT_PUSH_JOB;
+use crate::stats::{
+    DEFAULT_CONFIDENCE_HIGH_SAMPLES, DEFAULT_CONFIDENCE_LOW_SAMPLES, DEFAULT_LOSS_ALPHA,
+    DEFAULT_LOSS_DECIMALS, DEFAULT_OUTAGE_CAPACITY, ERROR_HISTORY_CAPACITY,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
 use uuid::Uuid;
 
 /// Configuration for a ping target
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PingTarget {
     pub id: String,
     pub address: String,
     pub label: String,
     pub enabled: bool,
+    /// Retries before a probe is recorded as failed (overrides the config default)
+    #[serde(default)]
+    pub retries: Option<u32>,
+    /// Whether the target is on the local network (e.g. the gateway) rather
+    /// than on the internet
+    #[serde(default)]
+    pub local: bool,
+    /// ICMP payload size in bytes (system default when unset)
+    #[serde(default)]
+    pub packet_size: Option<u32>,
+    /// Set the don't-fragment bit, so oversized probes fail instead of being
+    /// fragmented (for path MTU testing)
+    #[serde(default)]
+    pub dont_fragment: bool,
+    /// Probe interval for this target (overrides the config interval)
+    #[serde(default)]
+    pub interval_ms: Option<u64>,
+    /// Rank when sharing out `max_pings_per_minute`: higher priorities keep
+    /// their full rate longest, ties go by list order
+    #[serde(default)]
+    pub priority: u32,
+    /// Normal performance measured with `capture_baseline`
+    #[serde(default)]
+    pub baseline: Option<Baseline>,
+    /// Shell command run when this target keeps failing (overrides the
+    /// config command)
+    #[serde(default)]
+    pub on_failure_command: Option<String>,
+    /// Trace the path to this target every `path_probe_interval_secs`
+    #[serde(default)]
+    pub trace_path: bool,
+    /// Probe with this DNS query instead of a ping
+    #[serde(default)]
+    pub dns_query: Option<DnsQuery>,
+    /// Resolve a hostname address before every probe, so DNS failures count
+    /// as probe failures. When off, the address is resolved every
+    /// `resolve_interval_secs` and probes go to the cached IP.
+    #[serde(default = "default_resolve_each_probe")]
+    pub resolve_each_probe: bool,
+    /// Latency this target normally has, e.g. 1ms for the gateway, for
+    /// grading it on its own scale rather than the global thresholds
+    #[serde(default)]
+    pub expected_latency_ms: Option<f64>,
+    /// Write this target's results to the log files. When off, the target
+    /// still feeds statistics and events.
+    #[serde(default = "default_log_enabled")]
+    pub log_enabled: bool,
+    /// Ask the frontend to play a sound when this target goes down
+    #[serde(default)]
+    pub sound_alert: bool,
+    /// Name to show for the target: its label, with the address appended
+    /// when another target has the same label. Set by the targets getter and
+    /// ignored in input.
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub display_label: Option<String>,
+}
+
+fn default_resolve_each_probe() -> bool {
+    true
+}
+
+fn default_log_enabled() -> bool {
+    true
+}
+
+/// A DNS lookup sent straight to one resolver, timed as a probe
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DnsQuery {
+    /// Name whose A records are requested
+    pub name: String,
+    /// Server the query is sent to, on port 53
+    pub resolver: IpAddr,
 }
 
 impl PingTarget {
@@ -18,7 +99,49 @@ impl PingTarget {
             address,
             label,
             enabled: true,
+            retries: None,
+            local: false,
+            packet_size: None,
+            dont_fragment: false,
+            interval_ms: None,
+            priority: 0,
+            baseline: None,
+            on_failure_command: None,
+            trace_path: false,
+            dns_query: None,
+            resolve_each_probe: true,
+            expected_latency_ms: None,
+            log_enabled: true,
+            sound_alert: false,
+            display_label: None,
+        }
+    }
+
+    /// Key under which labels count as the same, ignoring case and
+    /// surrounding space
+    pub fn label_key(&self) -> String {
+        self.label.trim().to_lowercase()
+    }
+
+    /// Names that tell `targets` apart, in order
+    ///
+    /// A label shared by several targets gets each one's address appended,
+    /// e.g. `DNS (1.1.1.1)`; other labels are kept as they are.
+    pub fn display_labels(targets: &[PingTarget]) -> Vec<String> {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for target in targets {
+            *counts.entry(target.label_key()).or_default() += 1;
         }
+        targets
+            .iter()
+            .map(|target| {
+                if counts[&target.label_key()] > 1 {
+                    format!("{} ({})", target.label, target.address)
+                } else {
+                    target.label.clone()
+                }
+            })
+            .collect()
     }
 
     /// Create default targets
@@ -40,28 +163,789 @@ impl PingTarget {
     }
 }
 
+/// When a probe group counts as up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupPolicy {
+    /// At least one member responds
+    #[default]
+    Any,
+    /// More than half of the members respond
+    Majority,
+    /// Every member responds
+    All,
+}
+
+/// Targets that together represent one service, e.g. a pool of NTP servers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ProbeGroup {
+    pub id: String,
+    pub name: String,
+    /// Ids of the member targets
+    pub members: Vec<String>,
+    #[serde(default)]
+    pub policy: GroupPolicy,
+}
+
+/// Outcome of adding a target
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TargetAdded {
+    pub target: PingTarget,
+    /// Set when another target has the same label
+    #[serde(default)]
+    pub label_warning: Option<String>,
+}
+
+/// Outcome of editing a target
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TargetUpdate {
+    pub target: PingTarget,
+    /// Whether the address changed, so the target's statistics, outages and
+    /// alarms started afresh
+    pub stats_reset: bool,
+    /// Address before the change, if it changed
+    pub previous_address: Option<String>,
+    /// Set when another target has the same label
+    #[serde(default)]
+    pub label_warning: Option<String>,
+}
+
+/// Alert raised only when enough of a set of independent targets fail
+/// together, e.g. 2 of 3 DNS servers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct QuorumAlertRule {
+    pub name: String,
+    /// Ids of the watched targets
+    pub target_ids: Vec<String>,
+    /// Watched targets that must be down at once for the alert to fire
+    pub quorum: usize,
+}
+
+/// What an escalation tier does when an outage reaches it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EscalationAction {
+    /// Only the `outage-escalated` event, e.g. for a desktop notification
+    Notify,
+    /// POST the escalation as JSON to an `http://` URL
+    Webhook { url: String },
+    /// Run a shell command, like the failure command
+    Command { command: String },
+}
+
+/// Alerting step taken once a target's outage has lasted `after_secs`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct EscalationTier {
+    pub after_secs: u64,
+    pub action: EscalationAction,
+}
+
+/// Built-in step results pass through before they are recorded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ResultProcessorConfig {
+    /// Round latencies to `decimals` places
+    RoundLatency { decimals: u32 },
+    /// Tag results of IP targets within a subnet like `192.168.0.0/16`
+    TagSubnet { subnet: String, tag: String },
+}
+
+impl ProbeGroup {
+    pub fn new(name: String, members: Vec<String>, policy: GroupPolicy) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            members,
+            policy,
+        }
+    }
+}
+
+/// Exact sent/received counts for probes sent by the native backend
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ProbeCounters {
+    pub target: String,
+    pub sent: u64,
+    pub received: u64,
+    /// Probes that timed out and have not been answered since
+    pub lost: u64,
+    /// Replies that arrived after their probe timed out
+    pub late: u64,
+    /// Probes still waiting for a reply
+    pub outstanding_pings: u64,
+    pub packet_loss_percent: f64,
+}
+
+impl ProbeCounters {
+    pub fn new(target: &str) -> Self {
+        Self {
+            target: target.to_string(),
+            sent: 0,
+            received: 0,
+            lost: 0,
+            late: 0,
+            outstanding_pings: 0,
+            packet_loss_percent: 0.0,
+        }
+    }
+}
+
+/// Snapshot of the probe scheduler
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SchedulerStats {
+    /// Targets currently on the schedule
+    pub scheduled_targets: usize,
+    /// Time until the next probe is due
+    pub next_probe_in_ms: Option<u64>,
+    /// Probes started but not yet finished. A steadily rising count means
+    /// targets respond slower than their interval.
+    pub inflight_probes: u32,
+    /// Fires skipped because the target had `max_outstanding_probes` in flight
+    #[serde(default)]
+    pub skipped_probes: u64,
+    /// Time between two probes of each target, by id. In round-robin mode
+    /// this is the interval times the number of turns in a round.
+    #[serde(default)]
+    pub effective_interval_ms: HashMap<String, u64>,
+    /// Budget of probes per minute across all targets
+    #[serde(default)]
+    pub max_pings_per_minute: Option<u32>,
+    /// Probes started in the last minute
+    #[serde(default)]
+    pub pings_last_minute: u32,
+    /// Times a target was slowed down or had a fire dropped to stay within
+    /// `max_pings_per_minute`, counted once per target each time the budget
+    /// changes
+    #[serde(default)]
+    pub throttled_probes: u64,
+    /// Ids of targets the budget probes less often than their interval
+    #[serde(default)]
+    pub throttled_targets: Vec<String>,
+    /// Longest time between two consecutive results of a target since
+    /// statistics were reset, pauses left out. Far above the interval, the
+    /// monitor itself stalled.
+    #[serde(default)]
+    pub max_gap_ms: Option<f64>,
+    /// Average time between two consecutive results of a target
+    #[serde(default)]
+    pub avg_gap_ms: Option<f64>,
+}
+
+/// How the probes of a cycle are spread over the targets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SchedulingMode {
+    /// Probe every target each interval
+    #[default]
+    ParallelBatch,
+    /// Probe `round_robin_batch_size` targets each interval, taking turns
+    RoundRobin,
+}
+
+/// A target's normal performance, measured with a burst of pings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Baseline {
+    pub avg_latency_ms: Option<f64>,
+    pub p95_latency_ms: Option<f64>,
+    pub packet_loss_percent: f64,
+    pub samples: u32,
+    pub captured_at: DateTime<Utc>,
+}
+
+/// Current statistics relative to a target's baseline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct BaselineDeviation {
+    /// Current average latency as a multiple of the baseline average
+    pub latency_ratio: Option<f64>,
+    /// Change in packet loss from the baseline, in percentage points
+    pub loss_change_points: f64,
+}
+
+/// Current statistics relative to a target's expected latency
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ExpectedLatencyDeviation {
+    pub expected_latency_ms: f64,
+    /// Average latency minus the expected latency
+    pub difference_ms: f64,
+    /// Average latency as a multiple of the expected latency
+    pub ratio: f64,
+}
+
+/// How probes are sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    /// Run the system `ping` command
+    #[default]
+    System,
+    /// Send ICMP echo requests from the app's own socket
+    Native,
+}
+
+/// Whether the native backend can open an ICMP socket on this host
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum IcmpCapability {
+    /// An unprivileged datagram or a raw ICMP socket can be opened
+    Available,
+    /// Opening an ICMP socket needs more privileges, as `hint` explains
+    RequiresElevation { hint: String },
+    /// The platform offers no ICMP sockets
+    Unsupported { hint: String },
+}
+
+/// On-disk format for result logs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// JSON Lines (`ping-YYYY-MM-DD.jsonl`)
+    #[default]
+    Json,
+    /// Compact length-prefixed binary records (`ping-YYYY-MM-DD.bin`)
+    Binary,
+}
+
+/// Format to convert a log file into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Jsonl,
+    Csv,
+}
+
+/// Unit of latencies in CSV exports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LatencyUnit {
+    #[default]
+    Ms,
+    /// Microseconds
+    Us,
+}
+
+/// What the latency cell of a failed ping holds in CSV exports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FailedLatency {
+    /// An empty cell
+    #[default]
+    Empty,
+    /// `0`
+    Zero,
+    /// `FAIL`
+    Fail,
+}
+
+/// Format of timestamps in CSV exports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampFormat {
+    /// RFC 3339, e.g. `2024-01-01T12:00:00+00:00`
+    #[default]
+    Iso8601,
+    /// Seconds since the Unix epoch
+    EpochSeconds,
+    /// Milliseconds since the Unix epoch
+    EpochMillis,
+}
+
+/// How results are written in CSV exports, for the tool that reads them
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CsvExportOptions {
+    #[serde(default)]
+    pub latency_unit: LatencyUnit,
+    /// Decimal places of latencies; full precision when unset
+    #[serde(default)]
+    pub precision: Option<usize>,
+    #[serde(default)]
+    pub failed_latency: FailedLatency,
+    #[serde(default)]
+    pub timestamp_format: TimestampFormat,
+}
+
 /// Application configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AppConfig {
     pub targets: Vec<PingTarget>,
+    /// Targets reported together as one service
+    #[serde(default)]
+    pub groups: Vec<ProbeGroup>,
+    /// Alerts fired when enough of a set of targets are down together. A
+    /// target is down after `failure_action_cycles` failures in a row.
+    #[serde(default)]
+    pub quorum_alerts: Vec<QuorumAlertRule>,
+    /// Alerting that intensifies as an outage goes on, e.g. notify after
+    /// 30s, call a webhook after 2min and run a command after 10min. Each
+    /// tier fires once per outage.
+    #[serde(default)]
+    pub escalation_tiers: Vec<EscalationTier>,
+    /// Processors applied in order to every result before it is recorded
+    #[serde(default)]
+    pub result_processors: Vec<ResultProcessorConfig>,
     pub ping_interval_ms: u64,
+    /// Most each gap between a target's pings is randomly moved either way,
+    /// so probes don't line up on exact interval boundaries (0 disables)
+    #[serde(default)]
+    pub interval_jitter_ms: u64,
     pub timeout_ms: u64,
     pub max_history_size: usize,
+    /// Outages kept for the recent outages panel, across all targets
+    #[serde(default = "default_outage_history_size")]
+    pub outage_history_size: usize,
+    /// Distinct recent errors kept per target
+    #[serde(default = "default_error_history_size")]
+    pub error_history_size: usize,
+    /// Default number of retries before a probe is recorded as failed
+    #[serde(default)]
+    pub retries: u32,
+    /// Sequence number given to the first ping of a session
+    #[serde(default = "default_sequence_start")]
+    pub sequence_start: u32,
+    /// Consecutive cycles in which every internet target fails before the
+    /// internet is reported as down
+    #[serde(default = "default_internet_down_cycles")]
+    pub internet_down_cycles: u32,
+    /// Whether time spent paused counts as downtime. When off, pausing
+    /// freezes the session and ongoing outages resume where they left off.
+    #[serde(default)]
+    pub pause_counts_as_gap: bool,
+    /// Format of newly written result logs
+    #[serde(default)]
+    pub log_format: LogFormat,
+    /// How probes are sent
+    #[serde(default)]
+    pub backend: BackendKind,
+    /// Follow each answered native echo with an ICMP timestamp request, whose
+    /// remote clock readings hint at path asymmetry. IPv4 only; needs a raw
+    /// socket.
+    #[serde(default)]
+    pub icmp_timestamps: bool,
+    /// Write indented JSON records instead of one record per line.
+    /// Pretty logs are no longer strict NDJSON.
+    #[serde(default)]
+    pub pretty_logs: bool,
+    /// Save the live chart's recent results on exit and restore them on the
+    /// next start
+    #[serde(default)]
+    pub persist_recent_results: bool,
+    /// Results buffered for each live-result subscriber before the oldest are
+    /// skipped
+    #[serde(default = "default_broadcast_capacity")]
+    pub broadcast_capacity: usize,
+    /// Shell command run when a target fails `failure_action_cycles` times
+    /// in a row
+    #[serde(default)]
+    pub on_failure_command: Option<String>,
+    /// Consecutive failures that trigger the failure command
+    #[serde(default = "default_failure_action_cycles")]
+    pub failure_action_cycles: u32,
+    /// Minimum time between two failure commands for the same target, and
+    /// between two alerts of the same quorum rule
+    #[serde(default = "default_alert_cooldown_secs")]
+    pub alert_cooldown_secs: u64,
+    /// Decimal places of the displayed packet loss
+    #[serde(default = "default_loss_display_decimals")]
+    pub loss_display_decimals: u32,
+    /// Weight of each new result in `smoothed_loss_percent`, in (0, 1]
+    #[serde(default = "default_loss_smoothing_alpha")]
+    pub loss_smoothing_alpha: f64,
+    /// Time between path traces of targets with `trace_path` (0 disables)
+    #[serde(default = "default_path_probe_interval_secs")]
+    pub path_probe_interval_secs: u64,
+    /// Most hops a path trace follows
+    #[serde(default = "default_path_max_hops")]
+    pub path_max_hops: u8,
+    /// Add the session label to each logged result and outage
+    #[serde(default)]
+    pub embed_session_label: bool,
+    /// Probe every enabled target as soon as monitoring starts, instead of
+    /// spreading the first round across the interval
+    #[serde(default = "default_probe_on_start")]
+    pub probe_on_start: bool,
+    /// Start monitoring as soon as the app launches, without pressing Start
+    #[serde(default)]
+    pub auto_start: bool,
+    /// Probes to one target that may be outstanding at once. When the
+    /// timeout exceeds the interval, probes overlap up to this many and
+    /// further fires are skipped.
+    #[serde(default = "default_max_outstanding_probes")]
+    pub max_outstanding_probes: u32,
+    /// Latency at which a target is shown as degraded
+    #[serde(default = "default_latency_warn_ms")]
+    pub latency_warn_ms: f64,
+    /// Latency at which a target is shown as critical
+    #[serde(default = "default_latency_crit_ms")]
+    pub latency_crit_ms: f64,
+    /// Smoothed packet loss, 0-100, at which a target is reported as alarming
+    #[serde(default = "default_loss_alarm_percent")]
+    pub loss_alarm_percent: f64,
+    /// How long a hostname target without `resolve_each_probe` keeps its
+    /// resolved IP before it is looked up again
+    #[serde(default = "default_resolve_interval_secs")]
+    pub resolve_interval_secs: u64,
+    /// Also keep statistics per IP a hostname target resolved to, e.g. to
+    /// compare the edges of a CDN or anycast address. A hostname's own
+    /// statistics cover all its IPs either way.
+    #[serde(default)]
+    pub track_per_resolved_ip: bool,
+    /// Prometheus Pushgateway that statistics are pushed to, e.g.
+    /// `http://localhost:9091` (unset disables pushing)
+    #[serde(default)]
+    pub pushgateway_url: Option<String>,
+    /// Job name the pushed metrics are grouped under
+    #[serde(default = "default_pushgateway_job")]
+    pub pushgateway_job: String,
+    /// Time between two pushes to the Pushgateway
+    #[serde(default = "default_push_interval_secs")]
+    pub push_interval_secs: u64,
+    /// Soft limit on the memory held by buffers and statistics. Nearing it
+    /// shrinks the live chart buffer and the recent latency windows (unset
+    /// lets them grow to their configured sizes).
+    #[serde(default)]
+    pub max_memory_mb: Option<u64>,
+    /// Time between rewrites of `summary.csv` in the log directory, with one
+    /// row of current statistics per target (0 disables)
+    #[serde(default)]
+    pub summary_csv_interval_secs: u64,
+    /// Run the system `ping` in the C locale, so its messages are English
+    /// and errors are classified whatever the system language
+    #[serde(default = "default_ping_c_locale")]
+    pub ping_c_locale: bool,
+    /// Report the monitoring run's start as every target's `session_start`,
+    /// so targets added later share it, instead of their first result
+    #[serde(default)]
+    pub shared_session_start: bool,
+    /// Time after a monitoring run starts during which failures are logged
+    /// but not counted towards loss or alerts, e.g. while Wi-Fi comes up
+    /// (0 disables)
+    #[serde(default)]
+    pub startup_grace_ms: u64,
+    /// Append the address to labels that several targets share, in the
+    /// targets' and statistics' `display_label`
+    #[serde(default = "default_disambiguate_labels")]
+    pub disambiguate_labels: bool,
+    /// Most targets a diagnostic run probes at once
+    #[serde(default = "default_max_concurrent_probes")]
+    pub max_concurrent_probes: usize,
+    /// Whether every target is probed each interval or targets take turns
+    #[serde(default)]
+    pub scheduling_mode: SchedulingMode,
+    /// Targets probed per interval in round-robin mode
+    #[serde(default = "default_round_robin_batch_size")]
+    pub round_robin_batch_size: usize,
+    /// Most probes started per minute across all targets, for connections
+    /// with data caps or rate limits. Targets earlier in the list keep their
+    /// rate; later ones are spaced out or not probed.
+    #[serde(default)]
+    pub max_pings_per_minute: Option<u32>,
+    /// Latency samples a target needs before its statistics have `low`
+    /// rather than `insufficient` confidence
+    #[serde(default = "default_confidence_low_samples")]
+    pub confidence_low_samples: u64,
+    /// Latency samples a target needs for `high` confidence
+    #[serde(default = "default_confidence_high_samples")]
+    pub confidence_high_samples: u64,
+    /// `PingResult` fields sent to the frontend in result events, to keep
+    /// payloads small for dense charts; all fields when unset. Logs always
+    /// get full records.
+    #[serde(default)]
+    pub result_emit_fields: Option<Vec<String>>,
+}
+
+fn default_sequence_start() -> u32 {
+    1
+}
+
+fn default_internet_down_cycles() -> u32 {
+    3
+}
+
+fn default_broadcast_capacity() -> usize {
+    DEFAULT_BROADCAST_CAPACITY
+}
+
+fn default_failure_action_cycles() -> u32 {
+    3
+}
+
+fn default_alert_cooldown_secs() -> u64 {
+    300
+}
+
+fn default_loss_display_decimals() -> u32 {
+    DEFAULT_LOSS_DECIMALS
+}
+
+fn default_loss_smoothing_alpha() -> f64 {
+    DEFAULT_LOSS_ALPHA
+}
+
+fn default_path_probe_interval_secs() -> u64 {
+    300
+}
+
+fn default_path_max_hops() -> u8 {
+    30
+}
+
+fn default_probe_on_start() -> bool {
+    true
+}
+
+fn default_max_outstanding_probes() -> u32 {
+    3
+}
+
+fn default_latency_warn_ms() -> f64 {
+    50.0
+}
+
+fn default_outage_history_size() -> usize {
+    DEFAULT_OUTAGE_CAPACITY
+}
+
+fn default_error_history_size() -> usize {
+    ERROR_HISTORY_CAPACITY
+}
+
+fn default_latency_crit_ms() -> f64 {
+    100.0
+}
+
+fn default_loss_alarm_percent() -> f64 {
+    10.0
+}
+
+fn default_resolve_interval_secs() -> u64 {
+    300
+}
+
+fn default_pushgateway_job() -> String {
+    DEFAULT_PUSH_JOB.to_string()
+}
+
+fn default_push_interval_secs() -> u64 {
+    15
+}
+
+fn default_max_concurrent_probes() -> usize {
+    8
+}
+
+fn default_ping_c_locale() -> bool {
+    true
+}
+
+fn default_disambiguate_labels() -> bool {
+    true
+}
+
+fn default_round_robin_batch_size() -> usize {
+    10
+}
+
+fn default_confidence_low_samples() -> u64 {
+    DEFAULT_CONFIDENCE_LOW_SAMPLES
+}
+
+fn default_confidence_high_samples() -> u64 {
+    DEFAULT_CONFIDENCE_HIGH_SAMPLES
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
             targets: PingTarget::defaults(),
+            groups: Vec::new(),
+            quorum_alerts: Vec::new(),
+            escalation_tiers: Vec::new(),
+            result_processors: Vec::new(),
             ping_interval_ms: 1000,
+            interval_jitter_ms: 0,
             timeout_ms: 5000,
             max_history_size: 100,
+            outage_history_size: default_outage_history_size(),
+            error_history_size: default_error_history_size(),
+            retries: 0,
+            sequence_start: default_sequence_start(),
+            internet_down_cycles: default_internet_down_cycles(),
+            pause_counts_as_gap: false,
+            log_format: LogFormat::default(),
+            backend: BackendKind::default(),
+            pretty_logs: false,
+            persist_recent_results: false,
+            broadcast_capacity: default_broadcast_capacity(),
+            on_failure_command: None,
+            failure_action_cycles: default_failure_action_cycles(),
+            alert_cooldown_secs: default_alert_cooldown_secs(),
+            loss_display_decimals: default_loss_display_decimals(),
+            loss_smoothing_alpha: default_loss_smoothing_alpha(),
+            path_probe_interval_secs: default_path_probe_interval_secs(),
+            path_max_hops: default_path_max_hops(),
+            embed_session_label: false,
+            probe_on_start: default_probe_on_start(),
+            auto_start: false,
+            max_outstanding_probes: default_max_outstanding_probes(),
+            latency_warn_ms: default_latency_warn_ms(),
+            latency_crit_ms: default_latency_crit_ms(),
+            loss_alarm_percent: default_loss_alarm_percent(),
+            resolve_interval_secs: default_resolve_interval_secs(),
+            track_per_resolved_ip: false,
+            pushgateway_url: None,
+            pushgateway_job: default_pushgateway_job(),
+            push_interval_secs: default_push_interval_secs(),
+            max_memory_mb: None,
+            summary_csv_interval_secs: 0,
+            ping_c_locale: default_ping_c_locale(),
+            icmp_timestamps: false,
+            shared_session_start: false,
+            startup_grace_ms: 0,
+            disambiguate_labels: default_disambiguate_labels(),
+            max_concurrent_probes: default_max_concurrent_probes(),
+            scheduling_mode: SchedulingMode::default(),
+            round_robin_batch_size: default_round_robin_batch_size(),
+            max_pings_per_minute: None,
+            confidence_low_samples: default_confidence_low_samples(),
+            confidence_high_samples: default_confidence_high_samples(),
+            result_emit_fields: None,
+        }
+    }
+}
+
+/// Placeholder for settings left out of shared diagnostics
+pub const REDACTED: &str = "<redacted>";
+
+impl AppConfig {
+    /// Targets probed per interval, if targets take turns
+    pub fn round_robin_batch(&self) -> Option<usize> {
+        match self.scheduling_mode {
+            SchedulingMode::ParallelBatch => None,
+            SchedulingMode::RoundRobin => Some(self.round_robin_batch_size),
+        }
+    }
+
+    /// Display label of each target, in order
+    pub fn display_labels(&self) -> Vec<String> {
+        if self.disambiguate_labels {
+            PingTarget::display_labels(&self.targets)
+        } else {
+            self.targets.iter().map(|t| t.label.clone()).collect()
+        }
+    }
+
+    /// Copy of the config that is safe to include in snapshots and exports
+    ///
+    /// Failure and escalation commands and webhook URLs may contain
+    /// credentials or local paths, so they are replaced with a placeholder.
+    pub fn redacted(&self) -> Self {
+        let redact = |command: &mut Option<String>| {
+            if command.is_some() {
+                *command = Some(REDACTED.to_string());
+            }
+        };
+
+        let mut config = self.clone();
+        redact(&mut config.on_failure_command);
+        for target in &mut config.targets {
+            redact(&mut target.on_failure_command);
+        }
+        for tier in &mut config.escalation_tiers {
+            match tier.action {
+                EscalationAction::Notify => {}
+                EscalationAction::Webhook { ref mut url } => *url = REDACTED.to_string(),
+                EscalationAction::Command { ref mut command } => {
+                    *command = REDACTED.to_string()
+                }
+            }
+        }
+        config
+    }
+
+    /// Tune for a Raspberry Pi or a laptop on battery
+    ///
+    /// Probes every 5 seconds, taking turns four targets at a time with at
+    /// most one probe in flight per target, keeps smaller buffers within
+    /// 16 MB and writes compact binary logs. Targets are left as they are.
+    ///
+    /// `max_outstanding_probes` is what caps the monitor loop;
+    /// `max_concurrent_probes` only limits `run_diagnostic`, which the preset
+    /// keeps to two targets at once.
+    ///
+    /// There are no reservoir-sampled percentiles or batched async logging to
+    /// switch on: percentiles already come from a fixed-size histogram, and
+    /// binary logs stand in for batching by cutting the bytes written per
+    /// result.
+    pub fn apply_low_resource_preset(&mut self) {
+        self.ping_interval_ms = 5000;
+        self.interval_jitter_ms = self.interval_jitter_ms.min(self.ping_interval_ms / 4);
+        self.max_history_size = 50;
+        self.scheduling_mode = SchedulingMode::RoundRobin;
+        self.round_robin_batch_size = 4;
+        self.max_concurrent_probes = 2;
+        self.max_outstanding_probes = 1;
+        self.max_memory_mb = Some(16);
+        self.log_format = LogFormat::Binary;
+        self.pretty_logs = false;
+        self.path_probe_interval_secs = self.path_probe_interval_secs.max(900);
+    }
+
+    /// Tune for a powerful desktop
+    ///
+    /// Probes every target every 250ms with a 2 second timeout, keeps a
+    /// longer chart history, allows more probes in flight per target and in
+    /// `run_diagnostic`, and lifts the memory and ping budgets. Targets are
+    /// left as they are.
+    pub fn apply_high_resolution_preset(&mut self) {
+        self.ping_interval_ms = 250;
+        self.interval_jitter_ms = self.interval_jitter_ms.min(self.ping_interval_ms / 4);
+        self.timeout_ms = self.timeout_ms.min(2000);
+        self.max_history_size = 1000;
+        self.scheduling_mode = SchedulingMode::ParallelBatch;
+        self.max_concurrent_probes = 32;
+        self.max_outstanding_probes = 8;
+        self.max_memory_mb = None;
+        self.max_pings_per_minute = None;
+    }
+}
+
+/// Category of a configuration problem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigIssueKind {
+    InvalidAddress,
+    DuplicateAddress,
+    /// Another target has the same label; a warning, not an error
+    DuplicateLabel,
+    IntervalTooLow,
+    InvalidValue,
+    LogDirUnwritable,
+}
+
+/// A problem found while validating a configuration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ConfigIssue {
+    pub kind: ConfigIssueKind,
+    /// Path of the offending field, e.g. `targets[2].address`
+    pub field: String,
+    pub message: String,
+}
+
+impl ConfigIssue {
+    pub fn new(
+        kind: ConfigIssueKind,
+        field: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            kind,
+            field: field.into(),
+            message: message.into(),
         }
     }
 }
 
 /// Result of a single ping operation
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
 pub struct PingResult {
     pub timestamp: DateTime<Utc>,
     pub target: String,
@@ -70,21 +954,136 @@ pub struct PingResult {
     pub success: bool,
     pub sequence: u32,
     pub error: Option<String>,
+    /// Number of probe attempts made, including retries
+    #[serde(default = "default_attempts")]
+    pub attempts: u32,
+    /// Latency graded onto 0-1 against the configured warn/crit thresholds
+    /// (1.0 for failures)
+    #[serde(default)]
+    pub latency_normalized: Option<f32>,
+    /// Recorded before the app last restarted and restored from a snapshot
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub restored: bool,
+    /// Label of the session the result was recorded in, if embedded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_label: Option<String>,
+    /// Monitoring run the result belongs to, from start until stop
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// Time between the dispatch of this target's previous probe and this
+    /// one. Well above the interval, it means the monitor itself stalled
+    /// rather than the network.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gap_since_previous_ms: Option<f64>,
+    /// Latency in whole microseconds, from backends that time each probe
+    /// themselves (native). `latency_ms` keeps full precision either way.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_us: Option<u64>,
+    /// Labels added by result processors, e.g. `lan`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Remote clock readings from an ICMP timestamp exchange, with
+    /// `icmp_timestamps` on and the native backend
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icmp_timestamps: Option<IcmpTimestamps>,
+    /// Failed within `startup_grace_ms` of the session start, so logged and
+    /// charted but left out of statistics, outages and alerts
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub startup_grace: bool,
+    /// IP probed for a hostname target
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_ip: Option<IpAddr>,
+}
+
+/// Outcome of asking a target for its ICMP timestamps
+///
+/// The one-way figures include the offset between the two clocks, so only
+/// their difference and changes over time are meaningful: a growing
+/// `outbound_ms` with a steady `inbound_ms` points at the outbound path.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum IcmpTimestamps {
+    /// Times are milliseconds since midnight UT
+    Available {
+        /// When the request was sent, by our clock
+        originate_ms: u32,
+        /// When the target received it, by its clock
+        receive_ms: u32,
+        /// When the target replied, by its clock
+        transmit_ms: u32,
+        /// When the reply arrived, by our clock
+        returned_ms: u32,
+        /// `receive_ms - originate_ms`: the outbound trip plus the clock offset
+        outbound_ms: i64,
+        /// `returned_ms - transmit_ms`: the return trip minus the clock offset
+        inbound_ms: i64,
+    },
+    /// No usable timestamps, e.g. the target ignores timestamp requests;
+    /// the latency comes from the echo alone
+    Unavailable { reason: String },
+}
+
+fn default_attempts() -> u32 {
+    1
 }
 
 impl PingResult {
+    /// Approximate bytes held by this result, including its strings
+    pub fn approx_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.target.capacity()
+            + self.target_label.capacity()
+            + self.error.as_ref().map_or(0, String::capacity)
+            + self.session_label.as_ref().map_or(0, String::capacity)
+            + self.session_id.as_ref().map_or(0, String::capacity)
+            + self.tags.iter().map(String::capacity).sum::<usize>()
+            + match &self.icmp_timestamps {
+                Some(IcmpTimestamps::Unavailable { reason }) => reason.capacity(),
+                _ => 0,
+            }
+    }
+
+    /// A successful ping; a non-finite latency is dropped rather than recorded
     pub fn success(target: &PingTarget, latency_ms: f64, sequence: u32) -> Self {
+        let latency_ms = if latency_ms.is_finite() {
+            Some(latency_ms)
+        } else {
+            log::warn!(
+                "Dropping non-finite latency {} for {}",
+                latency_ms,
+                target.address
+            );
+            None
+        };
+
         Self {
             timestamp: Utc::now(),
             target: target.address.clone(),
             target_label: target.label.clone(),
-            latency_ms: Some(latency_ms),
+            latency_ms,
             success: true,
             sequence,
             error: None,
+            attempts: 1,
+            latency_normalized: None,
+            restored: false,
+            session_label: None,
+            session_id: None,
+            gap_since_previous_ms: None,
+            latency_us: None,
+            tags: Vec::new(),
+            icmp_timestamps: None,
+            startup_grace: false,
+            resolved_ip: None,
         }
     }
 
+    /// Also report the latency in microseconds
+    pub fn with_latency_us(mut self) -> Self {
+        self.latency_us = self.latency_ms.map(|ms| (ms * 1000.0).round() as u64);
+        self
+    }
+
     pub fn failure(target: &PingTarget, error: String, sequence: u32) -> Self {
         Self {
             timestamp: Utc::now(),
@@ -94,25 +1093,103 @@ impl PingResult {
             success: false,
             sequence,
             error: Some(error),
+            attempts: 1,
+            latency_normalized: None,
+            restored: false,
+            session_label: None,
+            session_id: None,
+            gap_since_previous_ms: None,
+            latency_us: None,
+            tags: Vec::new(),
+            icmp_timestamps: None,
+            startup_grace: false,
+            resolved_ip: None,
         }
     }
+
+    /// Record how many attempts the probe took
+    pub fn with_attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts;
+        self
+    }
 }
 
 /// Statistics for a specific target
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
 pub struct PingStatistics {
     pub target: String,
     pub target_label: String,
+    /// The target's `display_label`
+    #[serde(default)]
+    pub display_label: String,
+    /// Pings since monitoring started or statistics were last reset; see
+    /// `lifetime` for counts across restarts
     pub total_pings: u64,
     pub successful_pings: u64,
     pub failed_pings: u64,
+    /// Share of pings lost, 0-100, at full precision
     pub packet_loss_percent: f64,
+    /// `packet_loss_percent` rounded to `loss_display_decimals` places
+    #[serde(default)]
+    pub packet_loss_display: f64,
+    /// Share of pings lost, 0-1
+    #[serde(default)]
+    pub loss_ratio: f64,
+    /// Packet loss smoothed with an exponentially weighted moving average,
+    /// 0-100, for a steady live gauge
+    #[serde(default)]
+    pub smoothed_loss_percent: Option<f64>,
+    /// Successful pings whose negative latency, e.g. from a clock step, was
+    /// left out of the latency figures
+    #[serde(default)]
+    pub clock_anomalies: u64,
     pub min_latency_ms: Option<f64>,
     pub max_latency_ms: Option<f64>,
     pub avg_latency_ms: Option<f64>,
     pub jitter_ms: Option<f64>,
+    /// How far the latency figures can be trusted, given how many latencies
+    /// they are computed from
+    #[serde(default)]
+    pub sample_confidence: Confidence,
+    /// Lowest latency among the latest successful pings, approximating the
+    /// unloaded path latency
+    pub recent_min_latency_ms: Option<f64>,
+    /// Average of the latest successful pings minus `recent_min_latency_ms`.
+    /// A growing value points at bufferbloat or congestion.
+    pub congestion_ms: Option<f64>,
+    /// Average latency graded onto 0-1 (1.0 when every ping failed)
+    pub latency_normalized: Option<f32>,
+    /// Comparison with the target's baseline, if one was captured
+    pub deviation_from_baseline: Option<BaselineDeviation>,
+    /// Comparison with the target's expected latency, if it has one
+    #[serde(default)]
+    pub latency_vs_expected: Option<ExpectedLatencyDeviation>,
+    /// Counts kept across resets and restarts, until reset explicitly
+    #[serde(default)]
+    pub lifetime: Option<LifetimeCounters>,
+    /// `monitor_session_start` with `shared_session_start` set, otherwise
+    /// `first_seen`
     pub session_start: Option<DateTime<Utc>>,
+    /// When the current monitoring run started, or statistics were last reset
+    /// during it; the same for every target
+    #[serde(default)]
+    pub monitor_session_start: Option<DateTime<Utc>>,
+    /// Timestamp of the target's first result since start or reset
+    #[serde(default)]
+    pub first_seen: Option<DateTime<Utc>>,
+    /// When the highest latency was measured, and the latency
+    #[serde(default)]
+    pub slowest_ping: Option<(DateTime<Utc>, f64)>,
+    /// When the lowest latency was measured, and the latency
+    #[serde(default)]
+    pub fastest_ping: Option<(DateTime<Utc>, f64)>,
     pub last_ping: Option<DateTime<Utc>>,
+    /// Error of the most recent failed ping
+    #[serde(default)]
+    pub last_error: Option<String>,
+    /// Cause of the most recent failed ping
+    #[serde(default)]
+    pub last_error_kind: Option<PingError>,
 }
 
 impl PingStatistics {
@@ -125,28 +1202,656 @@ impl PingStatistics {
     }
 }
 
+/// How far statistics can be trusted, by the number of samples behind them
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema, Default,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Confidence {
+    /// Fewer than `confidence_low_samples`; percentiles and jitter are noise
+    #[default]
+    Insufficient,
+    /// Fewer than `confidence_high_samples`
+    Low,
+    High,
+}
+
+/// Kill switch and pause state, kept across restarts
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonitorSwitches {
+    /// Off while `set_monitoring_enabled(false)` is in effect
+    pub monitoring_enabled: bool,
+    /// Whether monitoring was paused, so `auto_start` comes back paused
+    pub paused: bool,
+}
+
+impl Default for MonitorSwitches {
+    fn default() -> Self {
+        Self {
+            monitoring_enabled: true,
+            paused: false,
+        }
+    }
+}
+
+/// Ping counts of a target kept across restarts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct LifetimeCounters {
+    pub total_pings: u64,
+    pub successful_pings: u64,
+    pub failed_pings: u64,
+    /// Share of these pings lost, 0-100
+    pub packet_loss_percent: f64,
+    /// First ping counted since the lifetime counters were last reset
+    pub since: DateTime<Utc>,
+}
+
+/// How healthy a target, or a group of targets, looks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthVerdict {
+    /// Every probe answered, at acceptable latency
+    Fine,
+    /// Some probes were lost, or latency reached `latency_crit_ms`
+    Degraded,
+    /// No probe answered
+    Down,
+}
+
+/// One target's part of a diagnostic run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TargetDiagnostic {
+    pub id: String,
+    pub target: String,
+    pub target_label: String,
+    pub local: bool,
+    pub sent: u32,
+    pub received: u32,
+    pub packet_loss_percent: f64,
+    pub min_latency_ms: Option<f64>,
+    pub avg_latency_ms: Option<f64>,
+    pub p50_latency_ms: Option<f64>,
+    pub p95_latency_ms: Option<f64>,
+    pub max_latency_ms: Option<f64>,
+    pub verdict: HealthVerdict,
+    /// Most frequent cause of the lost probes
+    pub failure_kind: Option<PingError>,
+    /// Why the target could not be diagnosed, e.g. its probing panicked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Statistic that `query_statistics` sorts by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StatField {
+    /// `display_label`, ignoring case
+    Label,
+    PacketLoss,
+    AvgLatency,
+    Jitter,
+    TotalPings,
+    LastPing,
+}
+
+/// Which statistics `query_statistics` returns
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StatFilter {
+    /// Targets with a `down` alarm
+    Down,
+    /// Targets with any alarm
+    Alarming,
+    /// Packet loss above `percent`
+    LossAbove { percent: f64 },
+    /// Average latency above `ms`
+    LatencyAbove { ms: f64 },
+}
+
+/// Why a target needs attention
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum AlarmReason {
+    /// Failed `failure_action_cycles` times in a row
+    Down,
+    /// Keeps switching between answering and failing
+    Flapping,
+    /// Successive pings at or above `latency_crit_ms`
+    HighLatency,
+    /// Smoothed packet loss at or above `loss_alarm_percent`
+    HighLoss,
+}
+
+/// A target currently in an alarm state, once per reason
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct AlarmInfo {
+    pub id: String,
+    pub target: String,
+    pub target_label: String,
+    pub reason: AlarmReason,
+    /// When the alarm was raised
+    pub since: DateTime<Utc>,
+    /// The reason in words, e.g. "3 failed pings in a row"
+    pub detail: String,
+}
+
+/// Outcome of an on-demand health check, apart from monitoring
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DiagnosticReport {
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    /// Probes sent to each target
+    pub samples: u32,
+    pub targets: Vec<TargetDiagnostic>,
+    /// Verdict over local targets; `None` without any
+    pub local: Option<HealthVerdict>,
+    /// Verdict over internet targets; `None` without any
+    pub internet: Option<HealthVerdict>,
+    /// Both verdicts in words, e.g. "local network fine, internet degraded"
+    pub summary: String,
+}
+
+/// Compact latency summary for one target, e.g. for a chart tooltip
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TargetSummary {
+    pub id: String,
+    pub target: String,
+    pub target_label: String,
+    /// The target's `display_label`
+    #[serde(default)]
+    pub display_label: String,
+    /// Latency of the latest ping; `None` if it failed or none was sent
+    pub current_latency_ms: Option<f64>,
+    pub min_latency_ms: Option<f64>,
+    pub avg_latency_ms: Option<f64>,
+    pub p95_latency_ms: Option<f64>,
+    pub max_latency_ms: Option<f64>,
+    pub packet_loss_percent: f64,
+    pub jitter_ms: Option<f64>,
+    pub last_ping: Option<DateTime<Utc>>,
+}
+
+/// Why a ping failed, as far as its error message tells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PingError {
+    /// No reply arrived in time
+    Timeout,
+    /// A router reported the host or network unreachable, or filtered the probe
+    Unreachable,
+    /// The address could not be resolved
+    UnknownHost,
+    /// A DNS server failed to answer (SERVFAIL)
+    ServerFailure,
+    /// The server refused the query
+    Refused,
+    /// The packet exceeded the path MTU with don't-fragment set
+    PacketTooLarge,
+    /// The probe could not be sent for lack of privileges
+    PermissionDenied,
+    Other,
+}
+
+impl PingError {
+    /// Classify an error message from any backend or platform
+    ///
+    /// The most specific causes are checked first, so "refused: port
+    /// unreachable" is a refusal rather than an unreachable host.
+    pub fn classify(error: &str) -> Self {
+        let error = error.to_lowercase();
+        let mentions = |needles: &[&str]| needles.iter().any(|n| error.contains(n));
+
+        if mentions(&["servfail"]) {
+            PingError::ServerFailure
+        } else if mentions(&["refused"]) {
+            PingError::Refused
+        } else if mentions(&[
+            "cannot resolve",
+            "nxdomain",
+            "unknown host",
+            "could not find host",
+            "name or service not known",
+            "nodename nor servname",
+        ]) {
+            PingError::UnknownHost
+        } else if mentions(&["too large", "too long", "fragment"]) {
+            PingError::PacketTooLarge
+        } else if mentions(&["permission denied", "operation not permitted"]) {
+            PingError::PermissionDenied
+        } else if mentions(&["unreachable", "filtered", "prohibited"]) {
+            PingError::Unreachable
+        } else if mentions(&["timed out", "timeout"]) {
+            PingError::Timeout
+        } else {
+            PingError::Other
+        }
+    }
+}
+
+/// A distinct error seen on a target and how often it occurred
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TargetError {
+    pub error: String,
+    pub kind: PingError,
+    pub count: u64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Approximate memory and handles held by the monitor
+///
+/// Sizes are estimates from buffer lengths and capacities, meant to show
+/// which buffer grows rather than to match the process's resident memory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ResourceStats {
+    /// Results kept for the live chart (`max_history_size` at most)
+    pub recent_results: usize,
+    pub recent_results_bytes: usize,
+    /// Targets, groups and resolved IPs with statistics
+    pub stats_targets: usize,
+    /// Latencies recorded in statistics histograms and recent windows
+    pub latency_samples: u64,
+    pub latency_buffers_bytes: usize,
+    /// Recent results plus latency samples
+    pub samples_retained: u64,
+    /// Sum of the sizes above
+    pub approx_total_bytes: usize,
+    /// Log files and sockets held open
+    pub open_files: usize,
+    /// Results the chart buffer may hold, lowered under memory pressure
+    pub history_limit: usize,
+    /// Successful pings kept per target for the recent-window figures
+    pub latency_window: usize,
+}
+
+/// First result of a target since its statistics started, so the UI can
+/// switch its row from loading to live
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TargetFirstResult {
+    pub target_id: String,
+    pub result: PingResult,
+}
+
+/// Buffers were shrunk to stay within `max_memory_mb`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct MemoryPressure {
+    pub budget_bytes: usize,
+    /// Estimated usage that triggered the shrink
+    pub used_bytes: usize,
+    /// New cap on the chart buffer
+    pub history_limit: usize,
+    /// New recent latency window per target
+    pub latency_window: usize,
+}
+
+/// One step of the self-test
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SelfTestCheck {
+    /// `ping_ipv4`, `ping_ipv6`, `parse` or `logging`
+    pub name: String,
+    pub passed: bool,
+    /// Whether the self-test fails when this check does
+    pub required: bool,
+    pub detail: String,
+}
+
+impl SelfTestCheck {
+    pub fn new(name: &str, passed: bool, required: bool, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            passed,
+            required,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Outcome of checking the monitoring pipeline against loopback
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SelfTestResult {
+    /// Whether every required check passed
+    pub passed: bool,
+    pub backend: BackendKind,
+    pub checks: Vec<SelfTestCheck>,
+    pub ran_at: DateTime<Utc>,
+}
+
+/// Per-probe cost of a backend, measured against localhost
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct BackendBenchmark {
+    pub backend: BackendKind,
+    /// Probes sent
+    pub iterations: u32,
+    /// Probes answered; only these are timed
+    pub successful: u32,
+    /// Time spent per probe beyond its round-trip time
+    pub min_overhead_ms: Option<f64>,
+    pub avg_overhead_ms: Option<f64>,
+    pub max_overhead_ms: Option<f64>,
+    /// Round-trip time the backend reported, for comparison
+    pub avg_rtt_ms: Option<f64>,
+}
+
+/// IP address a hostname target was last resolved to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ResolvedAddress {
+    /// Hostname as configured
+    pub target: String,
+    pub ip: IpAddr,
+    pub resolved_at: DateTime<Utc>,
+    /// Time since the lookup
+    pub age_ms: u64,
+}
+
+/// A period during which a target failed to respond
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Outage {
+    pub target: String,
+    pub target_label: String,
+    pub started_at: DateTime<Utc>,
+    /// `None` while the outage is ongoing
+    pub ended_at: Option<DateTime<Utc>>,
+    /// Length of the outage, or its length so far if ongoing
+    pub duration_ms: u64,
+    /// Label of the session the outage started in, if embedded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_label: Option<String>,
+}
+
+/// Point at which the session label changed, starting a sub-session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SessionMarker {
+    /// New label, `None` when it was cleared
+    pub label: Option<String>,
+    pub at: DateTime<Utc>,
+}
+
+/// Overview of the current monitoring session, e.g. for a report
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SessionSummary {
+    /// Current label, e.g. "after router swap"
+    pub label: Option<String>,
+    /// First ping of the session
+    pub started_at: Option<DateTime<Utc>>,
+    pub total_pings: u64,
+    pub failed_pings: u64,
+    pub packet_loss_percent: f64,
+    /// Outages recorded this session
+    pub outages: usize,
+    /// Label changes during the session, oldest first
+    pub markers: Vec<SessionMarker>,
+}
+
+/// Latency and loss for one hour of the day, aggregated over several days
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct HourStat {
+    /// Local hour of day, 0-23
+    pub hour: u8,
+    pub total_pings: u64,
+    pub successful_pings: u64,
+    pub latency_samples: u64,
+    pub avg_latency_ms: Option<f64>,
+    pub packet_loss_percent: f64,
+}
+
+impl HourStat {
+    pub fn new(hour: u8) -> Self {
+        Self {
+            hour,
+            total_pings: 0,
+            successful_pings: 0,
+            latency_samples: 0,
+            avg_latency_ms: None,
+            packet_loss_percent: 0.0,
+        }
+    }
+}
+
+/// Plain-language grade of a connection quality score
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityRating {
+    /// 90 and above
+    Excellent,
+    /// 70 to 90
+    Good,
+    /// 50 to 70
+    Fair,
+    /// Below 50
+    Poor,
+}
+
+/// Connection quality over one bucket of a quality series
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct QualityPoint {
+    pub start: DateTime<Utc>,
+    pub total_pings: u64,
+    /// 0-100, `None` for a bucket without pings
+    pub score: Option<f64>,
+    pub rating: Option<QualityRating>,
+    pub avg_latency_ms: Option<f64>,
+    pub jitter_ms: Option<f64>,
+    pub packet_loss_percent: Option<f64>,
+}
+
+/// Whether a session boundary opens or closes a session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionBoundaryKind {
+    Start,
+    End,
+}
+
+/// Marker written to the log when monitoring starts or stops
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SessionBoundary {
+    pub timestamp: DateTime<Utc>,
+    pub session_id: String,
+    pub boundary: SessionBoundaryKind,
+}
+
+impl SessionBoundary {
+    pub fn new(session_id: &str, boundary: SessionBoundaryKind) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            session_id: session_id.to_string(),
+            boundary,
+        }
+    }
+}
+
+/// A monitoring run found in the logs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct LoggedSession {
+    pub id: String,
+    pub started_at: DateTime<Utc>,
+    /// `None` if no end marker was logged, e.g. still running or crashed
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+/// Outcome of merging a date's log fragments
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct LogCompaction {
+    pub date: NaiveDate,
+    /// The merged file
+    pub path: String,
+    /// Files merged, including any previous file at `path`
+    pub fragments: usize,
+    /// Results and session markers in the merged file
+    pub records: usize,
+    /// Records left out because another fragment had them too
+    pub duplicates_removed: usize,
+}
+
+/// A span of time, including `from` and excluding `to`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct TimeRange {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+impl TimeRange {
+    pub fn new(from: DateTime<Utc>, to: DateTime<Utc>) -> Self {
+        Self { from, to }
+    }
+
+    /// Whether the timestamp falls within the range
+    pub fn contains(&self, timestamp: DateTime<Utc>) -> bool {
+        timestamp >= self.from && timestamp < self.to
+    }
+}
+
+/// Statistics for one period of a comparison
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PeriodSummary {
+    pub range: TimeRange,
+    /// `None` when nothing was logged for the target in this period
+    pub stats: Option<PingStatistics>,
+}
+
+/// Side-by-side comparison of a target over two periods
+///
+/// Changes are measured from period A to period B and are `None` when either
+/// period has no data for the metric.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PeriodComparison {
+    pub target: String,
+    pub period_a: PeriodSummary,
+    pub period_b: PeriodSummary,
+    /// Change in average latency, in milliseconds
+    pub latency_change_ms: Option<f64>,
+    /// Change in average latency relative to period A, in percent
+    pub latency_change_percent: Option<f64>,
+    /// Change in packet loss, in percentage points
+    pub loss_change_points: Option<f64>,
+    /// Change in jitter, in milliseconds
+    pub jitter_change_ms: Option<f64>,
+}
+
+/// Statistics of one hop on the path to a target
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PathHop {
+    /// Distance from this host, starting at 1
+    pub hop: u8,
+    /// Latest address to answer at this hop
+    pub address: Option<String>,
+    pub sent: u64,
+    pub received: u64,
+    pub packet_loss_percent: f64,
+    pub avg_latency_ms: Option<f64>,
+    pub min_latency_ms: Option<f64>,
+    pub max_latency_ms: Option<f64>,
+    pub last_latency_ms: Option<f64>,
+}
+
+/// Per-hop statistics of a target's path, from repeated traces
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PathReport {
+    pub target: String,
+    /// Number of traces recorded
+    pub runs: u32,
+    pub last_run: Option<DateTime<Utc>>,
+    /// Nearest hop first
+    pub hops: Vec<PathHop>,
+}
+
+/// How closely two targets' results move together
+///
+/// Results are paired when they were taken within one probe interval of
+/// each other.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Correlation {
+    pub target_a: String,
+    pub target_b: String,
+    /// Number of result pairs compared
+    pub samples: u32,
+    /// Pearson correlation of latency over pairs where both succeeded
+    pub latency_correlation: Option<f64>,
+    /// Share of pairs with a failure in which both targets failed. Near 1
+    /// points upstream (e.g. the ISP); near 0 points to separate paths.
+    pub co_failure_rate: Option<f64>,
+}
+
 /// Event payload for ping results
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PingResultEvent {
     pub result: PingResult,
 }
 
 /// Event payload for statistics updates
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct StatsUpdateEvent {
     pub stats: Vec<PingStatistics>,
 }
 
+/// Everything the dashboard shows, read together so the parts agree
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AppSnapshot {
+    pub ping_state: PingState,
+    /// Statistics of all targets, in config order
+    pub statistics: Vec<PingStatistics>,
+    /// Buffered results, newest first
+    pub recent_results: Vec<PingResult>,
+    pub config: AppConfig,
+    pub taken_at: DateTime<Utc>,
+}
+
 /// Application state for pinging
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
 pub enum PingState {
+    #[default]
     Stopped,
     Running,
     Paused,
 }
 
-impl Default for PingState {
-    fn default() -> Self {
-        Self::Stopped
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::validate_config;
+
+    #[test]
+    fn test_presets_set_their_fields_and_stay_valid() {
+        let mut config = AppConfig {
+            interval_jitter_ms: 500,
+            ..AppConfig::default()
+        };
+        config.apply_low_resource_preset();
+        assert_eq!(config.ping_interval_ms, 5000);
+        assert_eq!(config.max_history_size, 50);
+        assert_eq!(config.round_robin_batch(), Some(4));
+        assert_eq!(config.max_concurrent_probes, 2);
+        assert_eq!(config.max_outstanding_probes, 1);
+        assert_eq!(config.max_memory_mb, Some(16));
+        assert_eq!(config.log_format, LogFormat::Binary);
+        assert_eq!(config.targets.len(), AppConfig::default().targets.len());
+        let issues = validate_config(&config, &std::env::temp_dir());
+        assert!(issues.is_empty(), "{:?}", issues);
+
+        // The faster interval pulls the jitter in with it
+        config.apply_high_resolution_preset();
+        assert_eq!(config.ping_interval_ms, 250);
+        assert_eq!(config.interval_jitter_ms, 62);
+        assert_eq!(config.timeout_ms, 2000);
+        assert_eq!(config.max_history_size, 1000);
+        assert_eq!(config.round_robin_batch(), None);
+        assert_eq!(config.max_concurrent_probes, 32);
+        assert_eq!(config.max_memory_mb, None);
+        let issues = validate_config(&config, &std::env::temp_dir());
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+
+    #[test]
+    fn test_approx_size_counts_the_timestamp_reason() {
+        let target = PingTarget::new("192.0.2.1".to_string(), "A".to_string());
+        let mut result = PingResult::success(&target, 10.0, 1);
+        let before = result.approx_size();
+        result.icmp_timestamps = Some(IcmpTimestamps::Unavailable {
+            reason: "x".repeat(100),
+        });
+        assert!(result.approx_size() >= before + 100);
     }
 }
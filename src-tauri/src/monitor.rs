@@ -0,0 +1,651 @@
+use crate::channels::{Broadcaster, Subscriber};
+use crate::events;
+use crate::models::{PingResult, PingState};
+use crate::ping::PingBackend;
+use crate::state::AppState;
+use crate::stats::StatsCalculator;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Longest the ping loop sleeps before re-checking its state
+const MAX_IDLE_WAIT: Duration = Duration::from_millis(100);
+
+/// Start monitoring afresh, or continue a paused run, on a new ping loop
+pub fn start_monitoring<B, F>(state: &Arc<AppState>, make_backend: F) -> Result<(), String>
+where
+    B: PingBackend + 'static,
+    F: Fn(&AppState) -> B + Send + 'static,
+{
+    let current_state = state.get_ping_state();
+    if current_state == PingState::Running {
+        return Err("Pinging is already running".to_string());
+    }
+
+    // Create stop signal channel
+    let stop_signal = Broadcaster::new("stop signal", 1);
+    let stop = stop_signal.subscribe();
+    *state.stop_signal.write() = Some(stop_signal);
+
+    // Reset stats if starting fresh, otherwise continue the paused schedule
+    if current_state == PingState::Stopped {
+        state.reset_stats();
+        state.restart_schedule();
+        state.start_session();
+    } else {
+        state.resume_schedule();
+    }
+
+    state.set_ping_state(PingState::Running);
+    let generation = state.begin_loop();
+
+    // Spawn the ping loop
+    tokio::spawn(run_ping_loop(
+        Arc::clone(state),
+        generation,
+        stop,
+        make_backend,
+    ));
+
+    Ok(())
+}
+
+/// Start monitoring on launch if `auto_start` is set, returning whether it did
+///
+/// A run that is already underway or paused is left alone, and so is a
+/// disabled monitor: it stays off until `set_monitoring_enabled(true)`. If
+/// the previous run exited paused, a new session starts paused instead.
+pub fn auto_start<B, F>(state: &Arc<AppState>, make_backend: F) -> bool
+where
+    B: PingBackend + 'static,
+    F: Fn(&AppState) -> B + Send + 'static,
+{
+    if !state.get_config().auto_start
+        || !state.is_monitoring_enabled()
+        || state.get_ping_state() != PingState::Stopped
+    {
+        return false;
+    }
+    if state.take_launch_paused() {
+        state.restart_schedule();
+        state.start_session();
+        state.set_ping_state(PingState::Paused);
+        state.pause_schedule();
+        log::info!("Monitoring restored paused");
+        return false;
+    }
+    match start_monitoring(state, make_backend) {
+        Ok(()) => {
+            log::info!("Monitoring started automatically");
+            true
+        }
+        Err(e) => {
+            log::error!("Failed to start monitoring automatically: {}", e);
+            false
+        }
+    }
+}
+
+/// Probe due targets until stopped or replaced by a newer loop
+///
+/// `make_backend` builds the backend for each cycle, so config changes such
+/// as the timeout apply from the next cycle on. While monitoring is disabled
+/// the loop keeps running but sends no probes.
+///
+/// Each cycle runs on the blocking thread pool, so a slow ping never stalls
+/// the async runtime and a stop is honoured while probes are in flight.
+/// Cycles start on schedule even while earlier ones are still running, so a
+/// timeout longer than the interval doesn't stretch the interval; the
+/// scheduler caps the probes outstanding per target instead.
+pub async fn run_ping_loop<B, F>(
+    state: Arc<AppState>,
+    generation: u64,
+    mut stop: Subscriber<()>,
+    make_backend: F,
+) where
+    B: PingBackend + 'static,
+    F: Fn(&AppState) -> B,
+{
+    let mut disabled = false;
+
+    loop {
+        // Check for stop signal
+        if stop.try_recv().is_some() {
+            break;
+        }
+
+        // Check if still running, and not replaced by a newer loop
+        if state.get_ping_state() != PingState::Running || !state.is_current_loop(generation) {
+            break;
+        }
+
+        // Idle without touching the network while the kill switch is off
+        if !state.is_monitoring_enabled() {
+            if !disabled {
+                disabled = true;
+                state.emit(events::MONITORING_DISABLED, &());
+            }
+            tokio::time::sleep(MAX_IDLE_WAIT).await;
+            continue;
+        }
+        disabled = false;
+
+        // Emit stats of the targets that changed, e.g. by a finished cycle
+        if state.is_subscribed(events::STATS_UPDATE) {
+            let stats = state.take_changed_stats();
+            if !stats.is_empty() {
+                log::debug!("Emitting stats-update with {} stats", stats.len());
+                state.emit(events::STATS_UPDATE, &stats);
+            }
+        }
+
+        // Push metrics now and then, even while no target is due
+        if let Some((url, job, run)) = state.take_due_push() {
+            let state = Arc::clone(&state);
+            tokio::task::spawn_blocking(move || {
+                let _run = run;
+                if let Err(e) = state.push_metrics(&url, &job) {
+                    log::debug!("Pushing metrics to {} failed: {}", url, e);
+                }
+            });
+        }
+
+        // Keep the summary CSV current for spreadsheets and dashboards
+        if let Some(run) = state.take_due_summary() {
+            let state = Arc::clone(&state);
+            tokio::task::spawn_blocking(move || {
+                let _run = run;
+                if let Err(e) = state.write_summary() {
+                    log::error!("Failed to write summary CSV: {}", e);
+                }
+            });
+        }
+
+        // Save lifetime counts now and then, not only on exit
+        if let Some(run) = state.take_due_lifetime_save() {
+            let state = Arc::clone(&state);
+            tokio::task::spawn_blocking(move || {
+                let _run = run;
+                if let Err(e) = state.save_lifetime_stats() {
+                    log::error!("Failed to save lifetime statistics: {}", e);
+                }
+            });
+        }
+
+        // Get the targets due for a ping
+        let targets = state.take_due_targets();
+        if targets.is_empty() {
+            let wait = state
+                .time_until_next_probe()
+                .unwrap_or(MAX_IDLE_WAIT)
+                .min(MAX_IDLE_WAIT);
+            tokio::time::sleep(wait).await;
+            continue;
+        }
+
+        // Ping all due targets without waiting for the cycle to finish
+        let backend = make_backend(&state);
+        let dispatch = state.dispatch(generation);
+        let cycle_state = Arc::clone(&state);
+        tokio::task::spawn_blocking(move || {
+            cycle_state.run_dispatched_cycle(&backend, &targets, &dispatch);
+        });
+
+        // Trace paths now and then, off the loop so pings stay on time
+        if let Some((path_targets, run)) = state.take_due_path_targets() {
+            let state = Arc::clone(&state);
+            tokio::task::spawn_blocking(move || {
+                let _run = run;
+                state.run_path_probes(&path_targets);
+            });
+        }
+    }
+
+    // Update state when loop ends
+    if state.get_ping_state() == PingState::Running && state.is_current_loop(generation) {
+        state.set_ping_state(PingState::Stopped);
+    }
+}
+
+/// Re-emit logged results as live events, without any network I/O
+///
+/// Gaps between results are kept, divided by `speed`. Results feed a fresh
+/// statistics calculator, so live statistics and logs are left untouched.
+/// Stops like the ping loop: on the stop signal, when no longer running, or
+/// when replaced by a newer loop.
+pub async fn replay_results(
+    state: Arc<AppState>,
+    generation: u64,
+    mut stop: Subscriber<()>,
+    results: Vec<PingResult>,
+    speed: f64,
+) {
+    let mut stats = StatsCalculator::new();
+    let mut previous: Option<DateTime<Utc>> = None;
+
+    'replay: for result in results {
+        // Wait out the original gap, checking for a stop as often as the loop
+        let gap = previous
+            .and_then(|previous| (result.timestamp - previous).to_std().ok())
+            .unwrap_or_default();
+        let mut remaining = gap.div_f64(speed);
+        loop {
+            if stop.try_recv().is_some()
+                || state.get_ping_state() != PingState::Running
+                || !state.is_current_loop(generation)
+            {
+                break 'replay;
+            }
+            if remaining.is_zero() {
+                break;
+            }
+            let wait = remaining.min(MAX_IDLE_WAIT);
+            tokio::time::sleep(wait).await;
+            remaining -= wait;
+        }
+        previous = Some(result.timestamp);
+
+        stats.update(&result);
+        state.emit_projected(events::PING_RESULT, &result, None);
+        if let Some(target_stats) = stats.get_stats(&result.target) {
+            state.emit(events::STATS_UPDATE, &vec![target_stats]);
+        }
+    }
+
+    if state.get_ping_state() == PingState::Running && state.is_current_loop(generation) {
+        state.set_ping_state(PingState::Stopped);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::RecordingSink;
+    use crate::models::PingTarget;
+    use crate::ping::MockBackend;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_auto_start_begins_the_loop_without_a_command() {
+        let state = Arc::new(AppState::with_logger(None));
+        let backend = Arc::new(MockBackend::new(vec![Some(10.0)]));
+        let make_backend = |backend: &Arc<MockBackend>| {
+            let backend = Arc::clone(backend);
+            move |_: &AppState| Arc::clone(&backend)
+        };
+
+        // Off by default, and never while monitoring is disabled
+        assert!(!auto_start(&state, make_backend(&backend)));
+        let mut config = state.get_config();
+        config.auto_start = true;
+        state.update_config(config);
+        state.set_monitoring_enabled(false);
+        assert!(!auto_start(&state, make_backend(&backend)));
+        assert_eq!(state.get_ping_state(), PingState::Stopped);
+
+        state.set_monitoring_enabled(true);
+        assert!(auto_start(&state, make_backend(&backend)));
+        assert_eq!(state.get_ping_state(), PingState::Running);
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert!(backend.calls() > 0);
+        assert!(state.session_id.read().is_some());
+
+        // A run already underway is left alone
+        assert!(!auto_start(&state, make_backend(&backend)));
+        state.set_ping_state(PingState::Stopped);
+    }
+
+    #[tokio::test]
+    async fn test_auto_start_survives_a_relaunch() {
+        let dir = std::env::temp_dir().join(format!("ping-config-test-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("config.json");
+        let backend = Arc::new(MockBackend::new(vec![Some(10.0)]));
+
+        let state = AppState::with_logger(None);
+        assert!(!state.load_config(&path).unwrap());
+        let mut config = state.get_config();
+        config.auto_start = true;
+        state.update_config(config);
+
+        // A fresh launch reads the setting back and starts on its own
+        let state = Arc::new(AppState::with_logger(None));
+        assert!(state.load_config(&path).unwrap());
+        assert!(state.get_config().auto_start);
+        let started = {
+            let backend = Arc::clone(&backend);
+            auto_start(&state, move |_: &AppState| Arc::clone(&backend))
+        };
+        assert!(started);
+        assert_eq!(state.get_ping_state(), PingState::Running);
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert!(backend.calls() > 0);
+
+        state.set_ping_state(PingState::Stopped);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_kill_switch_and_pause_survive_a_relaunch() {
+        let dir = std::env::temp_dir().join(format!("ping-switches-test-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("monitor-switches.json");
+        let backend = Arc::new(MockBackend::new(vec![Some(10.0)]));
+        let launch = || {
+            let state = Arc::new(AppState::with_logger(None));
+            state.load_monitor_switches(&path).unwrap();
+            let mut config = state.get_config();
+            config.auto_start = true;
+            state.update_config(config);
+            let backend = Arc::clone(&backend);
+            let started = auto_start(&state, move |_: &AppState| Arc::clone(&backend));
+            (state, started)
+        };
+
+        // Disabled before quitting: the next launch stays off
+        let (state, started) = launch();
+        assert!(started);
+        state.set_monitoring_enabled(false);
+        state.shutdown(&dir.join("recent.json"));
+        let (state, started) = launch();
+        assert!(!started);
+        assert!(!state.is_monitoring_enabled());
+        assert_eq!(state.get_ping_state(), PingState::Stopped);
+
+        // Paused before quitting: the next launch comes back paused
+        state.set_monitoring_enabled(true);
+        assert!(auto_start(&state, {
+            let backend = Arc::clone(&backend);
+            move |_: &AppState| Arc::clone(&backend)
+        }));
+        state.set_ping_state(PingState::Paused);
+        state.pause_schedule();
+        state.shutdown(&dir.join("recent.json"));
+        let calls = backend.calls();
+        let (state, started) = launch();
+        assert!(!started);
+        assert!(state.is_monitoring_enabled());
+        assert_eq!(state.get_ping_state(), PingState::Paused);
+        assert!(state.session_id.read().is_some());
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(backend.calls(), calls);
+
+        // Only that launch: the one after it starts normally
+        state.set_ping_state(PingState::Stopped);
+        let (state, started) = launch();
+        assert!(started);
+        state.set_ping_state(PingState::Stopped);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_monitoring_sends_no_probes_but_loop_stays_alive() {
+        let state = Arc::new(AppState::with_logger(None));
+        let sink = Arc::new(RecordingSink::default());
+        state.set_event_sink(sink.clone());
+        state.set_monitoring_enabled(false);
+        state.set_ping_state(PingState::Running);
+        let generation = state.begin_loop();
+
+        let backend = Arc::new(MockBackend::new(vec![Some(10.0)]));
+        let stop = crate::channels::Broadcaster::new("stop signal", 1);
+        let rx = stop.subscribe();
+        let loop_backend = Arc::clone(&backend);
+        let handle = tokio::spawn(run_ping_loop(
+            Arc::clone(&state),
+            generation,
+            rx,
+            move |_: &AppState| Arc::clone(&loop_backend),
+        ));
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert_eq!(backend.calls(), 0);
+        assert_eq!(sink.count("monitoring-disabled"), 1);
+        assert!(!handle.is_finished());
+        assert_eq!(state.get_ping_state(), PingState::Running);
+
+        // Probing picks up again once re-enabled
+        state.set_monitoring_enabled(true);
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert!(backend.calls() > 0);
+
+        stop.send(());
+        handle.await.unwrap();
+        assert_eq!(state.get_ping_state(), PingState::Stopped);
+    }
+
+    /// Backend whose every probe blocks its thread for a while
+    #[derive(Default)]
+    struct SlowBackend {
+        delay: Duration,
+        started: AtomicUsize,
+        running: AtomicUsize,
+        max_running: AtomicUsize,
+    }
+
+    impl SlowBackend {
+        fn new(delay: Duration) -> Self {
+            Self {
+                delay,
+                ..Default::default()
+            }
+        }
+    }
+
+    impl PingBackend for SlowBackend {
+        fn ping(&self, target: &PingTarget, sequence: u32) -> PingResult {
+            self.started.fetch_add(1, Ordering::SeqCst);
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_running.fetch_max(running, Ordering::SeqCst);
+            std::thread::sleep(self.delay);
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            PingResult::success(target, self.delay.as_millis() as f64, sequence)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_loop_stops_while_ping_is_blocking() {
+        let state = Arc::new(AppState::with_logger(None));
+        state.restart_schedule();
+        state.set_ping_state(PingState::Running);
+        let generation = state.begin_loop();
+
+        let stop = crate::channels::Broadcaster::new("stop signal", 1);
+        let handle = tokio::spawn(run_ping_loop(
+            Arc::clone(&state),
+            generation,
+            stop.subscribe(),
+            |_: &AppState| SlowBackend::new(Duration::from_millis(700)),
+        ));
+
+        // The runtime stays free to run timers while the probe blocks
+        let started = std::time::Instant::now();
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert!(started.elapsed() < Duration::from_millis(500));
+        assert!(!handle.is_finished());
+        let first = state.get_targets()[0].id.clone();
+        assert_eq!(state.schedule.lock().outstanding(&first), 1);
+
+        stop.send(());
+        tokio::time::timeout(Duration::from_millis(300), handle)
+            .await
+            .expect("loop did not stop while a ping was blocking")
+            .unwrap();
+        assert_eq!(state.get_ping_state(), PingState::Stopped);
+
+        // The probe completes after a newer loop took over: it is released
+        // from the schedule but not recorded
+        state.begin_loop();
+        tokio::time::sleep(Duration::from_millis(800)).await;
+        assert_eq!(state.schedule.lock().outstanding(&first), 0);
+        assert!(state.get_all_stats().iter().all(|s| s.total_pings == 0));
+        assert!(state.get_recent_results(None, false).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_probe_on_start_pings_every_target_at_once() {
+        let state = Arc::new(AppState::with_logger(None));
+        let mut config = state.get_config();
+        config.ping_interval_ms = 10_000;
+        config.targets.push(PingTarget::new(
+            "192.0.2.1".to_string(),
+            "Third".to_string(),
+        ));
+        state.update_config(config);
+        let targets = state.get_enabled_targets().len();
+        state.restart_schedule();
+        state.set_ping_state(PingState::Running);
+        let generation = state.begin_loop();
+
+        let backend = Arc::new(MockBackend::new(vec![Some(10.0)]));
+        let stop = crate::channels::Broadcaster::new("stop signal", 1);
+        let loop_backend = Arc::clone(&backend);
+        let handle = tokio::spawn(run_ping_loop(
+            Arc::clone(&state),
+            generation,
+            stop.subscribe(),
+            move |_: &AppState| Arc::clone(&loop_backend),
+        ));
+
+        // Well before the 10s interval, every target has a result
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(backend.calls(), targets);
+        assert!(state.get_all_stats().iter().all(|s| s.total_pings == 1));
+
+        stop.send(());
+        handle.await.unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_probes_overlap_on_schedule_up_to_the_cap() {
+        let state = Arc::new(AppState::with_logger(None));
+        let mut config = state.get_config();
+        let mut target = PingTarget::new("192.0.2.1".to_string(), "Slow".to_string());
+        target.interval_ms = Some(100);
+        config.targets = vec![target];
+        config.max_outstanding_probes = 2;
+        state.update_config(config);
+        state.set_ping_state(PingState::Running);
+        let generation = state.begin_loop();
+
+        // Each probe takes 350ms against a 100ms interval
+        let backend = Arc::new(SlowBackend::new(Duration::from_millis(350)));
+        let stop = crate::channels::Broadcaster::new("stop signal", 1);
+        let loop_backend = Arc::clone(&backend);
+        let handle = tokio::spawn(run_ping_loop(
+            Arc::clone(&state),
+            generation,
+            stop.subscribe(),
+            move |_: &AppState| Arc::clone(&loop_backend),
+        ));
+
+        // A second probe starts on schedule while the first is outstanding
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert_eq!(backend.started.load(Ordering::SeqCst), 2);
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert_eq!(backend.max_running.load(Ordering::SeqCst), 2);
+        assert!(state.get_scheduler_stats().skipped_probes > 0);
+
+        stop.send(());
+        handle.await.unwrap();
+    }
+
+    fn logged(target: &PingTarget, sequence: u32, offset_ms: i64) -> PingResult {
+        let mut result = PingResult::success(target, 10.0 + sequence as f64, sequence);
+        result.timestamp = chrono::Utc::now() + chrono::Duration::milliseconds(offset_ms);
+        result
+    }
+
+    #[tokio::test]
+    async fn test_replay_emits_logged_results_in_order() {
+        let state = Arc::new(AppState::with_logger(None));
+        let sink = Arc::new(RecordingSink::default());
+        state.set_event_sink(sink.clone());
+        state.set_ping_state(PingState::Running);
+        let generation = state.begin_loop();
+
+        let a = PingTarget::new("192.0.2.1".to_string(), "A".to_string());
+        let b = PingTarget::new("192.0.2.2".to_string(), "B".to_string());
+        let results = vec![logged(&a, 1, 0), logged(&b, 2, 100), logged(&a, 3, 200)];
+
+        let stop = crate::channels::Broadcaster::new("stop signal", 1);
+        // Ten times faster: the 200ms timeline takes about 20ms
+        replay_results(
+            Arc::clone(&state),
+            generation,
+            stop.subscribe(),
+            results,
+            10.0,
+        )
+        .await;
+
+        assert_eq!(
+            sink.names(),
+            [
+                "ping-result",
+                "stats-update",
+                "ping-result",
+                "stats-update",
+                "ping-result",
+                "stats-update"
+            ]
+        );
+        let sequences: Vec<u64> = sink
+            .payloads(events::PING_RESULT)
+            .iter()
+            .map(|p| p["sequence"].as_u64().unwrap())
+            .collect();
+        assert_eq!(sequences, [1, 2, 3]);
+        let last_stats = sink.payloads(events::STATS_UPDATE).pop().unwrap();
+        assert_eq!(last_stats[0]["target"], "192.0.2.1");
+        assert_eq!(last_stats[0]["total_pings"], 2);
+
+        // Live statistics are untouched
+        assert!(state.get_all_stats().iter().all(|s| s.total_pings == 0));
+        assert_eq!(state.get_ping_state(), PingState::Stopped);
+    }
+
+    #[tokio::test]
+    async fn test_replay_keeps_only_result_emit_fields() {
+        let state = Arc::new(AppState::with_logger(None));
+        let sink = Arc::new(RecordingSink::default());
+        state.set_event_sink(sink.clone());
+        let mut config = state.get_config();
+        config.result_emit_fields = Some(vec!["sequence".to_string()]);
+        state.update_config(config);
+        state.set_ping_state(PingState::Running);
+        let generation = state.begin_loop();
+
+        let target = PingTarget::new("192.0.2.1".to_string(), "A".to_string());
+        let stop = crate::channels::Broadcaster::new("stop signal", 1);
+        let results = vec![logged(&target, 1, 0)];
+        replay_results(state, generation, stop.subscribe(), results, 1.0).await;
+
+        let emitted = sink.payloads(events::PING_RESULT);
+        assert_eq!(emitted, [serde_json::json!({ "sequence": 1 })]);
+    }
+
+    #[tokio::test]
+    async fn test_replay_stops_on_signal() {
+        let state = Arc::new(AppState::with_logger(None));
+        state.set_ping_state(PingState::Running);
+        let generation = state.begin_loop();
+
+        let target = PingTarget::new("192.0.2.1".to_string(), "A".to_string());
+        let results = vec![logged(&target, 1, 0), logged(&target, 2, 60_000)];
+        let stop = crate::channels::Broadcaster::new("stop signal", 1);
+        let handle = tokio::spawn(replay_results(
+            Arc::clone(&state),
+            generation,
+            stop.subscribe(),
+            results,
+            1.0,
+        ));
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert!(!handle.is_finished());
+        stop.send(());
+        tokio::time::timeout(Duration::from_secs(1), handle)
+            .await
+            .expect("replay did not stop")
+            .unwrap();
+    }
+}
@@ -0,0 +1,243 @@
+use crate::models::ProbeCounters;
+use chrono::{DateTime, Utc};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+/// How long a lost probe can still be reconciled by a late reply
+const LATE_REPLY_WINDOW: Duration = Duration::from_secs(60);
+
+/// A probe that was sent and has not been answered
+struct Sent {
+    target: String,
+    ip: IpAddr,
+    sent_at: Instant,
+    /// Already counted as lost after timing out
+    lost: bool,
+}
+
+/// What a received reply meant
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplyOutcome {
+    /// Answered a probe that was still waiting
+    OnTime { target: String, latency_ms: f64 },
+    /// Answered a probe already counted as lost
+    Late { target: String, latency_ms: f64 },
+    /// Duplicate, expired or not ours
+    Unknown,
+}
+
+/// Exact sent/received bookkeeping for probes sent on our own socket
+///
+/// Probes are matched to replies by sequence number, which the sender keeps
+/// unique across targets, and by the address that answered. Counters are kept
+/// per target address. A probe that times out is counted as lost, but a reply
+/// arriving later un-loses it.
+#[derive(Default)]
+pub struct ProbeAccounting {
+    pending: HashMap<u16, Sent>,
+    counters: HashMap<String, ProbeCounters>,
+    /// Late replies not yet applied to statistics
+    late: Vec<(String, f64, DateTime<Utc>)>,
+}
+
+/// Milliseconds in `duration`, keeping its nanosecond resolution
+fn duration_ms(duration: Duration) -> f64 {
+    duration.as_nanos() as f64 / 1_000_000.0
+}
+
+impl ProbeAccounting {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a probe sent to `ip` on behalf of `target`
+    pub fn record_sent(&mut self, target: &str, ip: IpAddr, sequence: u16, now: Instant) {
+        self.pending
+            .retain(|_, sent| !sent.lost || now.duration_since(sent.sent_at) < LATE_REPLY_WINDOW);
+        self.pending.insert(
+            sequence,
+            Sent {
+                target: target.to_string(),
+                ip,
+                sent_at: now,
+                lost: false,
+            },
+        );
+        self.counter(target).sent += 1;
+    }
+
+    /// Record a reply from `ip`
+    pub fn record_reply(&mut self, ip: IpAddr, sequence: u16, now: Instant) -> ReplyOutcome {
+        let sent = match self.pending.entry(sequence) {
+            Entry::Occupied(entry) if entry.get().ip == ip => entry.remove(),
+            _ => return ReplyOutcome::Unknown,
+        };
+
+        let latency_ms = duration_ms(now.duration_since(sent.sent_at));
+        let counter = self.counter(&sent.target);
+        counter.received += 1;
+
+        if sent.lost {
+            counter.lost -= 1;
+            counter.late += 1;
+            self.late
+                .push((sent.target.clone(), latency_ms, Utc::now()));
+            ReplyOutcome::Late {
+                target: sent.target,
+                latency_ms,
+            }
+        } else {
+            ReplyOutcome::OnTime {
+                target: sent.target,
+                latency_ms,
+            }
+        }
+    }
+
+    /// Count a probe as lost after waiting the full timeout
+    pub fn record_timeout(&mut self, sequence: u16) {
+        if let Some(sent) = self.pending.get_mut(&sequence) {
+            if !sent.lost {
+                sent.lost = true;
+                let target = sent.target.clone();
+                self.counter(&target).lost += 1;
+            }
+        }
+    }
+
+    /// Late replies received since the last call, as (target, latency,
+    /// time received)
+    pub fn take_late_replies(&mut self) -> Vec<(String, f64, DateTime<Utc>)> {
+        std::mem::take(&mut self.late)
+    }
+
+    /// Counters for each target, with probes still awaiting a reply
+    pub fn counters(&self) -> Vec<ProbeCounters> {
+        self.counters
+            .values()
+            .map(|counter| {
+                let mut counter = counter.clone();
+                counter.outstanding_pings = counter.sent - counter.received - counter.lost;
+                counter.packet_loss_percent = if counter.sent > 0 {
+                    counter.lost as f64 / counter.sent as f64 * 100.0
+                } else {
+                    0.0
+                };
+                counter
+            })
+            .collect()
+    }
+
+    /// Forget everything, e.g. when statistics are reset
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    fn counter(&mut self, target: &str) -> &mut ProbeCounters {
+        self.counters
+            .entry(target.to_string())
+            .or_insert_with(|| ProbeCounters::new(target))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{PingResult, PingTarget};
+    use std::net::Ipv4Addr;
+
+    fn counters_for(accounting: &ProbeAccounting, target: &str) -> ProbeCounters {
+        accounting
+            .counters()
+            .into_iter()
+            .find(|c| c.target == target)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_sub_millisecond_latency_keeps_its_precision() {
+        let mut accounting = ProbeAccounting::new();
+        let ip = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1));
+        let start = Instant::now();
+
+        accounting.record_sent("192.168.1.1", ip, 1, start);
+        let outcome = accounting.record_reply(ip, 1, start + Duration::from_nanos(123_400));
+        let ReplyOutcome::OnTime { latency_ms, .. } = outcome else {
+            panic!("expected an on-time reply, got {:?}", outcome);
+        };
+        assert_eq!(latency_ms, 0.1234);
+
+        let target = PingTarget::new("192.168.1.1".to_string(), "Router".to_string());
+        let result = PingResult::success(&target, latency_ms, 1).with_latency_us();
+        assert_eq!(result.latency_ms, Some(0.1234));
+        assert_eq!(result.latency_us, Some(123));
+    }
+
+    #[test]
+    fn test_send_and_ack_accounting() {
+        let mut accounting = ProbeAccounting::new();
+        let ip = IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1));
+        let start = Instant::now();
+
+        accounting.record_sent("1.1.1.1", ip, 1, start);
+        accounting.record_sent("1.1.1.1", ip, 2, start);
+        assert_eq!(counters_for(&accounting, "1.1.1.1").outstanding_pings, 2);
+
+        let outcome = accounting.record_reply(ip, 1, start + Duration::from_millis(12));
+        assert_eq!(
+            outcome,
+            ReplyOutcome::OnTime {
+                target: "1.1.1.1".to_string(),
+                latency_ms: 12.0
+            }
+        );
+        // Duplicates and strangers don't count
+        assert_eq!(accounting.record_reply(ip, 1, start), ReplyOutcome::Unknown);
+        assert_eq!(
+            accounting.record_reply(ip, 99, start),
+            ReplyOutcome::Unknown
+        );
+        // Nor does a matching sequence from another address
+        let other = IpAddr::V4(Ipv4Addr::new(1, 0, 0, 1));
+        assert_eq!(accounting.record_reply(other, 2, start), ReplyOutcome::Unknown);
+
+        let counters = counters_for(&accounting, "1.1.1.1");
+        assert_eq!((counters.sent, counters.received), (2, 1));
+        assert_eq!(counters.outstanding_pings, 1);
+        assert_eq!(counters.packet_loss_percent, 0.0);
+    }
+
+    #[test]
+    fn test_late_reply_unloses_packet() {
+        let mut accounting = ProbeAccounting::new();
+        let ip = IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8));
+        let start = Instant::now();
+
+        accounting.record_sent("8.8.8.8", ip, 1, start);
+        accounting.record_sent("8.8.8.8", ip, 2, start);
+        accounting.record_timeout(1);
+        accounting.record_timeout(1);
+        accounting.record_reply(ip, 2, start + Duration::from_millis(5));
+
+        let counters = counters_for(&accounting, "8.8.8.8");
+        assert_eq!(counters.lost, 1);
+        assert_eq!(counters.packet_loss_percent, 50.0);
+
+        // The reply to the first probe shows up after its timeout
+        let outcome = accounting.record_reply(ip, 1, start + Duration::from_secs(6));
+        assert!(matches!(outcome, ReplyOutcome::Late { .. }));
+
+        let counters = counters_for(&accounting, "8.8.8.8");
+        assert_eq!((counters.sent, counters.received, counters.lost), (2, 2, 0));
+        assert_eq!(counters.late, 1);
+        assert_eq!(counters.outstanding_pings, 0);
+        assert_eq!(counters.packet_loss_percent, 0.0);
+        let late = accounting.take_late_replies();
+        assert_eq!(late.len(), 1);
+        assert_eq!((late[0].0.as_str(), late[0].1), ("8.8.8.8", 6000.0));
+        assert!(late[0].2 <= Utc::now());
+        assert!(accounting.take_late_replies().is_empty());
+    }
+}
@@ -0,0 +1,80 @@
+use super::Pinger;
+use crate::models::{PingResult, PingTarget};
+use std::net::IpAddr;
+
+/// Something that can probe a target
+pub trait PingBackend: Send + Sync {
+    /// Probe the target once and return the result
+    fn ping(&self, target: &PingTarget, sequence: u32) -> PingResult;
+
+    /// Probe the target at an already resolved address
+    ///
+    /// The result still names the target's own address.
+    fn ping_ip(&self, target: &PingTarget, ip: IpAddr, sequence: u32) -> PingResult {
+        let mut resolved = target.clone();
+        resolved.address = ip.to_string();
+        let mut result = self.ping(&resolved, sequence);
+        result.target = target.address.clone();
+        result
+    }
+}
+
+impl PingBackend for Pinger {
+    fn ping(&self, target: &PingTarget, sequence: u32) -> PingResult {
+        Pinger::ping(self, target, sequence)
+    }
+}
+
+impl<B: PingBackend + ?Sized> PingBackend for Box<B> {
+    fn ping(&self, target: &PingTarget, sequence: u32) -> PingResult {
+        (**self).ping(target, sequence)
+    }
+
+    fn ping_ip(&self, target: &PingTarget, ip: IpAddr, sequence: u32) -> PingResult {
+        (**self).ping_ip(target, ip, sequence)
+    }
+}
+
+impl<B: PingBackend + ?Sized> PingBackend for std::sync::Arc<B> {
+    fn ping(&self, target: &PingTarget, sequence: u32) -> PingResult {
+        (**self).ping(target, sequence)
+    }
+
+    fn ping_ip(&self, target: &PingTarget, ip: IpAddr, sequence: u32) -> PingResult {
+        (**self).ping_ip(target, ip, sequence)
+    }
+}
+
+/// Backend that replays scripted latencies instead of touching the network
+#[cfg(test)]
+pub(crate) struct MockBackend {
+    /// Latency per probe in order; `None` is a timeout. Repeats when exhausted.
+    script: Vec<Option<f64>>,
+    calls: std::sync::atomic::AtomicUsize,
+}
+
+#[cfg(test)]
+impl MockBackend {
+    pub fn new(script: Vec<Option<f64>>) -> Self {
+        Self {
+            script,
+            calls: std::sync::atomic::AtomicUsize::new(0),
+        }
+    }
+
+    /// Number of probes performed so far
+    pub fn calls(&self) -> usize {
+        self.calls.load(std::sync::atomic::Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+impl PingBackend for MockBackend {
+    fn ping(&self, target: &PingTarget, sequence: u32) -> PingResult {
+        let call = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        match self.script[call % self.script.len()] {
+            Some(latency) => PingResult::success(target, latency, sequence),
+            None => PingResult::failure(target, "Request timed out".to_string(), sequence),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use super::PingBackend;
+use crate::models::{BackendBenchmark, BackendKind, PingTarget};
+use std::time::Instant;
+
+/// Address probed by a benchmark, so network latency stays negligible
+pub const BENCHMARK_ADDRESS: &str = "127.0.0.1";
+
+/// Most probes a single benchmark may send
+pub const MAX_BENCHMARK_ITERATIONS: u32 = 1000;
+
+/// Time the backend's own cost per probe by pinging localhost repeatedly
+///
+/// Overhead is the wall time of each probe minus the round-trip time it
+/// reports, e.g. spawning the `ping` process and parsing its output. Failed
+/// probes are counted but left out of the timings.
+pub fn benchmark_backend<B: PingBackend + ?Sized>(
+    backend: &B,
+    kind: BackendKind,
+    iterations: u32,
+) -> BackendBenchmark {
+    let target = PingTarget::new(BENCHMARK_ADDRESS.to_string(), "Benchmark".to_string());
+    let mut overheads = Vec::with_capacity(iterations as usize);
+    let mut rtts = Vec::with_capacity(iterations as usize);
+
+    for sequence in 1..=iterations {
+        let start = Instant::now();
+        let result = backend.ping(&target, sequence);
+        let elapsed_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+        if let (true, Some(rtt)) = (result.success, result.latency_ms) {
+            overheads.push((elapsed_ms - rtt).max(0.0));
+            rtts.push(rtt);
+        }
+    }
+
+    let average = |values: &[f64]| {
+        (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+    };
+    BackendBenchmark {
+        backend: kind,
+        iterations,
+        successful: overheads.len() as u32,
+        min_overhead_ms: overheads.iter().copied().reduce(f64::min),
+        avg_overhead_ms: average(&overheads),
+        max_overhead_ms: overheads.iter().copied().reduce(f64::max),
+        avg_rtt_ms: average(&rtts),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ping::MockBackend;
+
+    #[test]
+    fn test_benchmark_fields_are_populated() {
+        let backend = MockBackend::new(vec![Some(0.5), None, Some(1.5)]);
+
+        let benchmark = benchmark_backend(&backend, BackendKind::Native, 6);
+
+        assert_eq!(backend.calls(), 6);
+        assert_eq!(benchmark.backend, BackendKind::Native);
+        assert_eq!(benchmark.iterations, 6);
+        assert_eq!(benchmark.successful, 4);
+        assert_eq!(benchmark.avg_rtt_ms, Some(1.0));
+        let min = benchmark.min_overhead_ms.unwrap();
+        let avg = benchmark.avg_overhead_ms.unwrap();
+        let max = benchmark.max_overhead_ms.unwrap();
+        assert!(min >= 0.0 && min <= avg && avg <= max);
+    }
+
+    #[test]
+    fn test_benchmark_without_replies_has_no_timings() {
+        let backend = MockBackend::new(vec![None]);
+        let benchmark = benchmark_backend(&backend, BackendKind::System, 3);
+        assert_eq!(benchmark.successful, 0);
+        assert_eq!(benchmark.min_overhead_ms, None);
+        assert_eq!(benchmark.avg_overhead_ms, None);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::models::PingTarget;
+
+/// Operating system flavour of the `ping` command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Windows,
+    MacOs,
+    Linux,
+    Other,
+}
+
+impl Platform {
+    /// Platform the application was built for
+    pub fn current() -> Self {
+        if cfg!(target_os = "windows") {
+            Self::Windows
+        } else if cfg!(target_os = "macos") {
+            Self::MacOs
+        } else if cfg!(target_os = "linux") {
+            Self::Linux
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// Build the arguments for a single-packet `ping` of the target
+pub fn ping_args(platform: Platform, target: &PingTarget, timeout_ms: u64) -> Vec<String> {
+    let timeout_secs = (timeout_ms / 1000).max(1);
+    let mut args: Vec<String> = Vec::new();
+
+    match platform {
+        Platform::Windows => {
+            args.extend(["-n", "1", "-w"].map(String::from));
+            args.push(timeout_ms.to_string());
+            if let Some(size) = target.packet_size {
+                args.extend(["-l".to_string(), size.to_string()]);
+            }
+            if target.dont_fragment {
+                args.push("-f".to_string());
+            }
+        }
+        Platform::MacOs => {
+            args.extend(["-c", "1", "-t"].map(String::from));
+            args.push(timeout_secs.to_string());
+            if let Some(size) = target.packet_size {
+                args.extend(["-s".to_string(), size.to_string()]);
+            }
+            if target.dont_fragment {
+                args.push("-D".to_string());
+            }
+        }
+        Platform::Linux => {
+            args.extend(["-c", "1", "-W"].map(String::from));
+            args.push(timeout_secs.to_string());
+            if let Some(size) = target.packet_size {
+                args.extend(["-s".to_string(), size.to_string()]);
+            }
+            if target.dont_fragment {
+                args.extend(["-M", "do"].map(String::from));
+            }
+        }
+        Platform::Other => {
+            args.extend(["-c", "1"].map(String::from));
+        }
+    }
+
+    args.push(target.address.clone());
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pmtu_target() -> PingTarget {
+        let mut target = PingTarget::new("1.1.1.1".to_string(), "Test".to_string());
+        target.packet_size = Some(1472);
+        target.dont_fragment = true;
+        target
+    }
+
+    #[test]
+    fn test_default_args_per_platform() {
+        let target = PingTarget::new("1.1.1.1".to_string(), "Test".to_string());
+
+        assert_eq!(
+            ping_args(Platform::Windows, &target, 5000),
+            ["-n", "1", "-w", "5000", "1.1.1.1"]
+        );
+        assert_eq!(
+            ping_args(Platform::MacOs, &target, 5000),
+            ["-c", "1", "-t", "5", "1.1.1.1"]
+        );
+        assert_eq!(
+            ping_args(Platform::Linux, &target, 500),
+            ["-c", "1", "-W", "1", "1.1.1.1"]
+        );
+    }
+
+    #[test]
+    fn test_dont_fragment_args_per_platform() {
+        let target = pmtu_target();
+
+        assert_eq!(
+            ping_args(Platform::Windows, &target, 5000),
+            ["-n", "1", "-w", "5000", "-l", "1472", "-f", "1.1.1.1"]
+        );
+        assert_eq!(
+            ping_args(Platform::MacOs, &target, 5000),
+            ["-c", "1", "-t", "5", "-s", "1472", "-D", "1.1.1.1"]
+        );
+        assert_eq!(
+            ping_args(Platform::Linux, &target, 5000),
+            ["-c", "1", "-W", "5", "-s", "1472", "-M", "do", "1.1.1.1"]
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use super::PingBackend;
+use crate::models::{PingResult, PingTarget};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// Port DNS servers listen on
+pub const DNS_PORT: u16 = 53;
+
+/// Record type A (IPv4 address)
+const QTYPE_A: u16 = 1;
+/// Class IN (internet)
+const QCLASS_IN: u16 = 1;
+/// Recursion desired
+const FLAG_RD: u16 = 0x0100;
+/// Set in responses
+const FLAG_QR: u16 = 0x8000;
+
+/// Outcome of a DNS query that got an answer
+#[derive(Debug, Clone, PartialEq)]
+pub struct DnsAnswer {
+    /// Round-trip time of the query
+    pub latency_ms: f64,
+    /// Number of records in the answer section
+    pub answers: u16,
+}
+
+/// Probe that times a DNS query sent straight to one resolver
+///
+/// Bypasses the system resolver and its cache, so the time measured is that
+/// of the chosen server. Only answers with NOERROR count as success;
+/// SERVFAIL, NXDOMAIN, REFUSED and timeouts are reported distinctly.
+pub struct DnsProbe {
+    timeout: Duration,
+}
+
+impl DnsProbe {
+    pub fn new(timeout_ms: u64) -> Self {
+        Self {
+            timeout: Duration::from_millis(timeout_ms.max(1)),
+        }
+    }
+
+    /// Query `server` for the A records of `name`
+    pub fn query(&self, server: SocketAddr, name: &str) -> Result<DnsAnswer, String> {
+        let random = *uuid::Uuid::new_v4().as_bytes();
+        let id = u16::from_be_bytes([random[0], random[1]]);
+        let query = encode_query(id, name)?;
+
+        let local: SocketAddr = match server {
+            SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+            SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+        };
+        let socket = UdpSocket::bind(local).map_err(|e| format!("Cannot open socket: {}", e))?;
+        socket
+            .connect(server)
+            .map_err(|e| format!("Cannot reach {}: {}", server, e))?;
+
+        let started = Instant::now();
+        socket
+            .send(&query)
+            .map_err(|e| format!("Send failed: {}", e))?;
+
+        // Skip stray datagrams until our answer arrives or time runs out
+        let mut buf = [0u8; 512];
+        loop {
+            let remaining = self
+                .timeout
+                .checked_sub(started.elapsed())
+                .filter(|d| !d.is_zero())
+                .ok_or_else(|| "DNS query timed out".to_string())?;
+            socket
+                .set_read_timeout(Some(remaining))
+                .map_err(|e| e.to_string())?;
+
+            let len = match socket.recv(&mut buf) {
+                Ok(len) => len,
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    return Err("DNS query timed out".to_string())
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+                    return Err("DNS query refused: port unreachable".to_string())
+                }
+                Err(e) => return Err(format!("Receive failed: {}", e)),
+            };
+            let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+            if let Some(answers) = parse_response(id, &buf[..len]) {
+                return answers.map(|answers| DnsAnswer {
+                    latency_ms,
+                    answers,
+                });
+            }
+        }
+    }
+}
+
+impl PingBackend for DnsProbe {
+    fn ping(&self, target: &PingTarget, sequence: u32) -> PingResult {
+        let Some(ref dns) = target.dns_query else {
+            return PingResult::failure(target, "Target has no DNS query".to_string(), sequence);
+        };
+        match self.query(SocketAddr::new(dns.resolver, DNS_PORT), &dns.name) {
+            Ok(answer) => PingResult::success(target, answer.latency_ms, sequence),
+            Err(error) => PingResult::failure(target, error, sequence),
+        }
+    }
+}
+
+/// Encode a recursive query for the A records of `name`
+pub fn encode_query(id: u16, name: &str) -> Result<Vec<u8>, String> {
+    let mut packet = Vec::with_capacity(18 + name.len());
+    packet.extend_from_slice(&id.to_be_bytes());
+    packet.extend_from_slice(&FLAG_RD.to_be_bytes());
+    // One question, no answer, authority or additional records
+    packet.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+
+    for label in name.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(format!("Invalid DNS name: {}", name));
+        }
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&QTYPE_A.to_be_bytes());
+    packet.extend_from_slice(&QCLASS_IN.to_be_bytes());
+    Ok(packet)
+}
+
+/// Read the outcome of a response to query `id`
+///
+/// `None` if the datagram is not a response to that query, otherwise the
+/// answer count or a description of the failure.
+pub fn parse_response(id: u16, packet: &[u8]) -> Option<Result<u16, String>> {
+    if packet.len() < 12 || u16::from_be_bytes([packet[0], packet[1]]) != id {
+        return None;
+    }
+    let flags = u16::from_be_bytes([packet[2], packet[3]]);
+    if flags & FLAG_QR == 0 {
+        return None;
+    }
+
+    Some(match flags & 0x000f {
+        0 => Ok(u16::from_be_bytes([packet[6], packet[7]])),
+        2 => Err("DNS server failure (SERVFAIL)".to_string()),
+        3 => Err("DNS name does not exist (NXDOMAIN)".to_string()),
+        5 => Err("DNS query refused (REFUSED)".to_string()),
+        code => Err(format!("DNS error code {}", code)),
+    })
+}
+
+/// Sends DNS-query targets to a [`DnsProbe`] and everything else to `icmp`
+pub struct ProbeRouter<B> {
+    icmp: B,
+    dns: DnsProbe,
+}
+
+impl<B: PingBackend> ProbeRouter<B> {
+    pub fn new(icmp: B, dns: DnsProbe) -> Self {
+        Self { icmp, dns }
+    }
+}
+
+impl<B: PingBackend> PingBackend for ProbeRouter<B> {
+    fn ping(&self, target: &PingTarget, sequence: u32) -> PingResult {
+        if target.dns_query.is_some() {
+            self.dns.ping(target, sequence)
+        } else {
+            self.icmp.ping(target, sequence)
+        }
+    }
+}
+
+/// Whether an address can be used as a resolver
+pub fn is_valid_resolver(resolver: IpAddr) -> bool {
+    !resolver.is_unspecified() && !resolver.is_multicast()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PingError;
+
+    /// Answer one query on a local socket with the given response code
+    fn stub_server(rcode: u16, answers: u16) -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = socket.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let mut buf = [0u8; 512];
+            let (len, peer) = socket.recv_from(&mut buf).unwrap();
+            let mut response = buf[..len].to_vec();
+            let flags = FLAG_QR | FLAG_RD | 0x0080 | rcode;
+            response[2..4].copy_from_slice(&flags.to_be_bytes());
+            response[6..8].copy_from_slice(&answers.to_be_bytes());
+
+            // A stray datagram with the wrong id is ignored by the probe
+            let mut stray = response.clone();
+            stray[0] ^= 0xff;
+            socket.send_to(&stray, peer).unwrap();
+            socket.send_to(&response, peer).unwrap();
+        });
+        address
+    }
+
+    #[test]
+    fn test_query_against_stub_server() {
+        let probe = DnsProbe::new(2000);
+
+        let answer = probe.query(stub_server(0, 2), "example.com").unwrap();
+        assert_eq!(answer.answers, 2);
+        assert!(answer.latency_ms >= 0.0 && answer.latency_ms < 2000.0);
+
+        let servfail = probe.query(stub_server(2, 0), "example.com").unwrap_err();
+        assert_eq!(servfail, "DNS server failure (SERVFAIL)");
+        let refused = probe.query(stub_server(5, 0), "example.com").unwrap_err();
+        assert_eq!(refused, "DNS query refused (REFUSED)");
+        assert_eq!(PingError::classify(&refused), PingError::Refused);
+        // A closed port is a refusal too, not an unreachable host
+        assert_eq!(
+            PingError::classify("DNS query refused: port unreachable"),
+            PingError::Refused
+        );
+
+        // A server that never answers times out
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let probe = DnsProbe::new(100);
+        let timeout = probe
+            .query(silent.local_addr().unwrap(), "example.com")
+            .unwrap_err();
+        assert_eq!(timeout, "DNS query timed out");
+    }
+
+    #[test]
+    fn test_encode_query() {
+        let packet = encode_query(0x1234, "dns.google.").unwrap();
+        assert_eq!(&packet[..4], &[0x12, 0x34, 0x01, 0x00]);
+        assert_eq!(&packet[12..], b"\x03dns\x06google\x00\x00\x01\x00\x01");
+        assert!(encode_query(1, "bad..name").is_err());
+    }
+}
@@ -0,0 +1,256 @@
+/// ICMPv4 echo request type
+pub const ECHO_REQUEST_V4: u8 = 8;
+/// ICMPv4 echo reply type
+pub const ECHO_REPLY_V4: u8 = 0;
+/// ICMPv4 timestamp request type
+pub const TIMESTAMP_REQUEST_V4: u8 = 13;
+/// ICMPv4 timestamp reply type
+pub const TIMESTAMP_REPLY_V4: u8 = 14;
+/// ICMPv6 echo request type
+pub const ECHO_REQUEST_V6: u8 = 128;
+/// ICMPv6 echo reply type
+pub const ECHO_REPLY_V6: u8 = 129;
+
+/// Length of an ICMP echo header
+const HEADER_LEN: usize = 8;
+/// Length of an ICMP timestamp message: the header and three timestamps
+const TIMESTAMP_LEN: usize = HEADER_LEN + 12;
+/// Set in a timestamp that isn't milliseconds since midnight UT (RFC 792)
+const NON_STANDARD_TIMESTAMP: u32 = 1 << 31;
+
+/// Internet checksum (RFC 1071) over `data`
+pub fn checksum(data: &[u8]) -> u16 {
+    let mut sum: u32 = data
+        .chunks(2)
+        .map(|pair| match *pair {
+            [high, low] => u16::from_be_bytes([high, low]) as u32,
+            [high] => u16::from_be_bytes([high, 0]) as u32,
+            _ => 0,
+        })
+        .sum();
+
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// Build an echo request packet
+///
+/// ICMPv6 checksums cover a pseudo-header only the kernel knows, so they are
+/// left for the kernel to fill in.
+pub fn encode_echo_request(v6: bool, identifier: u16, sequence: u16, payload: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(HEADER_LEN + payload.len());
+    packet.push(if v6 { ECHO_REQUEST_V6 } else { ECHO_REQUEST_V4 });
+    packet.push(0);
+    packet.extend_from_slice(&[0, 0]);
+    packet.extend_from_slice(&identifier.to_be_bytes());
+    packet.extend_from_slice(&sequence.to_be_bytes());
+    packet.extend_from_slice(payload);
+
+    if !v6 {
+        let sum = checksum(&packet);
+        packet[2..4].copy_from_slice(&sum.to_be_bytes());
+    }
+    packet
+}
+
+/// Fields of a received echo reply
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EchoReply {
+    pub identifier: u16,
+    pub sequence: u16,
+}
+
+/// Parse an echo reply, skipping the IPv4 header raw sockets include
+///
+/// Returns `None` for anything other than an echo reply.
+pub fn parse_echo_reply(v6: bool, packet: &[u8]) -> Option<EchoReply> {
+    let icmp = if v6 { packet } else { skip_ipv4_header(packet)? };
+
+    if icmp.len() < HEADER_LEN {
+        return None;
+    }
+    let reply_type = if v6 { ECHO_REPLY_V6 } else { ECHO_REPLY_V4 };
+    if icmp[0] != reply_type || icmp[1] != 0 {
+        return None;
+    }
+
+    Some(EchoReply {
+        identifier: u16::from_be_bytes([icmp[4], icmp[5]]),
+        sequence: u16::from_be_bytes([icmp[6], icmp[7]]),
+    })
+}
+
+/// Skip the IPv4 header of a packet from a raw socket, if it has one
+fn skip_ipv4_header(packet: &[u8]) -> Option<&[u8]> {
+    if packet.first().map(|b| b >> 4) == Some(4) {
+        let header_len = ((packet[0] & 0x0f) as usize) * 4;
+        packet.get(header_len..)
+    } else {
+        Some(packet)
+    }
+}
+
+/// Build an ICMPv4 timestamp request
+///
+/// `originate` is the send time in milliseconds since midnight UT.
+pub fn encode_timestamp_request(identifier: u16, sequence: u16, originate: u32) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(TIMESTAMP_LEN);
+    packet.extend_from_slice(&[TIMESTAMP_REQUEST_V4, 0, 0, 0]);
+    packet.extend_from_slice(&identifier.to_be_bytes());
+    packet.extend_from_slice(&sequence.to_be_bytes());
+    packet.extend_from_slice(&originate.to_be_bytes());
+    packet.extend_from_slice(&[0; 8]);
+
+    let sum = checksum(&packet);
+    packet[2..4].copy_from_slice(&sum.to_be_bytes());
+    packet
+}
+
+/// Fields of a received timestamp reply
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimestampReply {
+    pub identifier: u16,
+    pub sequence: u16,
+    /// Our send time, echoed back
+    pub originate: u32,
+    /// When the remote host received the request
+    pub receive: u32,
+    /// When the remote host sent the reply
+    pub transmit: u32,
+}
+
+impl TimestampReply {
+    /// Whether the remote reports milliseconds since midnight UT, as the
+    /// offsets assume
+    pub fn is_standard(&self) -> bool {
+        (self.receive | self.transmit) & NON_STANDARD_TIMESTAMP == 0
+    }
+}
+
+/// Parse an ICMPv4 timestamp reply, skipping the IPv4 header if present
+///
+/// Returns `None` for anything other than a timestamp reply.
+pub fn parse_timestamp_reply(packet: &[u8]) -> Option<TimestampReply> {
+    let icmp = skip_ipv4_header(packet)?;
+    if icmp.len() < TIMESTAMP_LEN || icmp[0] != TIMESTAMP_REPLY_V4 || icmp[1] != 0 {
+        return None;
+    }
+
+    let word = |at: usize| u32::from_be_bytes([icmp[at], icmp[at + 1], icmp[at + 2], icmp[at + 3]]);
+    Some(TimestampReply {
+        identifier: u16::from_be_bytes([icmp[4], icmp[5]]),
+        sequence: u16::from_be_bytes([icmp[6], icmp[7]]),
+        originate: word(8),
+        receive: word(12),
+        transmit: word(16),
+    })
+}
+
+/// Milliseconds from timestamp `from` to `to`, across midnight if shorter
+pub fn timestamp_offset_ms(from: u32, to: u32) -> i64 {
+    const DAY_MS: i64 = 86_400_000;
+    let offset = (i64::from(to) - i64::from(from)).rem_euclid(DAY_MS);
+    if offset > DAY_MS / 2 {
+        offset - DAY_MS
+    } else {
+        offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_echo_request_encoding() {
+        let packet = encode_echo_request(false, 0x1234, 7, b"ping");
+
+        assert_eq!(&packet[..2], &[ECHO_REQUEST_V4, 0]);
+        assert_eq!(&packet[4..8], &[0x12, 0x34, 0, 7]);
+        assert_eq!(&packet[8..], b"ping");
+        // A packet including its own checksum sums to zero
+        assert_eq!(checksum(&packet), 0);
+    }
+
+    #[test]
+    fn test_parse_echo_reply() {
+        let mut reply = encode_echo_request(false, 0x1234, 7, b"ping");
+        reply[0] = ECHO_REPLY_V4;
+        let expected = EchoReply {
+            identifier: 0x1234,
+            sequence: 7,
+        };
+        assert_eq!(parse_echo_reply(false, &reply), Some(expected));
+
+        // Raw sockets deliver the IPv4 header first
+        let mut with_header = vec![0x45];
+        with_header.extend_from_slice(&[0; 19]);
+        with_header.extend_from_slice(&reply);
+        assert_eq!(parse_echo_reply(false, &with_header), Some(expected));
+
+        let mut v6_reply = encode_echo_request(true, 1, 2, &[]);
+        v6_reply[0] = ECHO_REPLY_V6;
+        assert_eq!(
+            parse_echo_reply(true, &v6_reply).map(|r| r.sequence),
+            Some(2)
+        );
+
+        // Requests and truncated packets are not replies
+        assert_eq!(
+            parse_echo_reply(false, &encode_echo_request(false, 1, 1, &[])),
+            None
+        );
+        assert_eq!(parse_echo_reply(false, &reply[..6]), None);
+    }
+
+    #[test]
+    fn test_timestamp_request_and_reply() {
+        let request = encode_timestamp_request(0x1234, 7, 45_000_000);
+        assert_eq!(request.len(), 20);
+        assert_eq!(&request[..2], &[TIMESTAMP_REQUEST_V4, 0]);
+        assert_eq!(&request[4..12], &[0x12, 0x34, 0, 7, 0x02, 0xae, 0xa5, 0x40]);
+        assert_eq!(&request[12..], &[0; 8]);
+        assert_eq!(checksum(&request), 0);
+
+        // Canned reply from a raw socket: IPv4 header, then the ICMP message
+        let reply = [
+            0x45, 0, 0, 40, 0, 0, 0, 0, 64, 1, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2, // IPv4
+            14, 0, 0xe7, 0xdf, 0x12, 0x34, 0, 7, // type, code, checksum, id, seq
+            0x02, 0xae, 0xa5, 0x40, // originate 45_000_000
+            0x02, 0xae, 0xa5, 0x4c, // receive 45_000_012
+            0x02, 0xae, 0xa5, 0x4d, // transmit 45_000_013
+        ];
+        let parsed = parse_timestamp_reply(&reply).unwrap();
+        assert_eq!(
+            parsed,
+            TimestampReply {
+                identifier: 0x1234,
+                sequence: 7,
+                originate: 45_000_000,
+                receive: 45_000_012,
+                transmit: 45_000_013,
+            }
+        );
+        assert!(parsed.is_standard());
+        assert_eq!(checksum(&reply[20..]), 0);
+
+        let mut non_standard = reply;
+        non_standard[32] |= 0x80;
+        assert!(!parse_timestamp_reply(&non_standard).unwrap().is_standard());
+
+        // Echo replies, requests and truncated packets are not timestamp replies
+        let mut echo = encode_echo_request(false, 0x1234, 7, &[0; 12]);
+        echo[0] = ECHO_REPLY_V4;
+        assert_eq!(parse_timestamp_reply(&echo), None);
+        assert_eq!(parse_timestamp_reply(&request), None);
+        assert_eq!(parse_timestamp_reply(&reply[..36]), None);
+
+        assert_eq!(timestamp_offset_ms(45_000_000, 45_000_012), 12);
+        assert_eq!(timestamp_offset_ms(45_000_012, 45_000_000), -12);
+        // Just before and after midnight
+        assert_eq!(timestamp_offset_ms(86_399_990, 5), 15);
+        assert_eq!(timestamp_offset_ms(5, 86_399_990), -15);
+    }
+}
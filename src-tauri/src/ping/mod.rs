@@ -1,3 +1,32 @@
+mod accounting;
+mod backend;
+mod benchmark;
+mod command;
+mod dns;
+mod icmp;
+mod native;
+mod path;
 mod pinger;
+mod resolve;
+mod transcript;
+mod zone;
 
+pub use accounting::{ProbeAccounting, ReplyOutcome};
+#[cfg(test)]
+pub(crate) use backend::MockBackend;
+pub use backend::PingBackend;
+pub use benchmark::{benchmark_backend, BENCHMARK_ADDRESS, MAX_BENCHMARK_ITERATIONS};
+pub use command::{ping_args, Platform};
+pub use dns::{
+    encode_query, is_valid_resolver, parse_response, DnsAnswer, DnsProbe, ProbeRouter, DNS_PORT,
+};
+pub use icmp::{
+    encode_echo_request, encode_timestamp_request, parse_echo_reply, parse_timestamp_reply,
+    timestamp_offset_ms, EchoReply, TimestampReply,
+};
+pub use native::{check_icmp_permission, NativePinger};
+pub use path::{parse_traceroute, traceroute_command, HopSample, PathProbe, MAX_PATH_HOPS};
 pub use pinger::Pinger;
+pub use resolve::{Resolver, ResolverCache, ResolvingBackend, SystemResolver};
+pub use transcript::parse_ping_output;
+pub use zone::{check_zone, is_link_local, ZonedAddress};
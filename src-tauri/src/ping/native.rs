@@ -0,0 +1,402 @@
+use super::accounting::{ProbeAccounting, ReplyOutcome};
+use super::icmp::{
+    encode_echo_request, encode_timestamp_request, parse_echo_reply, parse_timestamp_reply,
+    timestamp_offset_ms,
+};
+use super::resolve::resolve;
+use super::zone::ZonedAddress;
+use super::PingBackend;
+use crate::models::{IcmpCapability, IcmpTimestamps, PingResult, PingTarget};
+use chrono::Utc;
+use parking_lot::Mutex;
+use socket2::{Domain, Protocol, Socket, Type};
+use std::io::ErrorKind;
+use std::net::{IpAddr, SocketAddr, SocketAddrV6, UdpSocket};
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Echo payload size when the target doesn't set one, as with system ping
+const DEFAULT_PAYLOAD_LEN: usize = 56;
+/// Shortest wait for a timestamp reply, however fast the echo was
+const MIN_TIMESTAMP_WAIT: Duration = Duration::from_millis(50);
+
+/// An open ICMP socket
+struct IcmpSocket {
+    socket: UdpSocket,
+    /// Raw sockets see every echo reply on the host, so replies must be
+    /// matched on our identifier. Datagram sockets only see their own, with
+    /// the identifier rewritten by the kernel.
+    checks_identifier: bool,
+}
+
+/// Pinger that sends ICMP echo requests from its own socket
+///
+/// Sockets stay open between probes, so a reply arriving after its timeout
+/// is still seen and reconciled in the shared [`ProbeAccounting`].
+pub struct NativePinger {
+    timeout_ms: AtomicU64,
+    /// Retries for targets without their own setting
+    retries: AtomicU32,
+    identifier: u16,
+    /// Sequence number of the next echo request, unique across targets
+    next_sequence: AtomicU16,
+    v4: Mutex<Option<IcmpSocket>>,
+    v6: Mutex<Option<IcmpSocket>>,
+    accounting: Arc<Mutex<ProbeAccounting>>,
+    /// Follow answered echoes with a timestamp request
+    timestamps: AtomicBool,
+    /// Idle raw IPv4 socket for timestamp requests, which datagram ICMP
+    /// sockets don't carry. A request takes it while waiting for its reply,
+    /// so requests running at the same time open sockets of their own.
+    timestamp_socket: Mutex<Option<UdpSocket>>,
+    /// Why raw sockets can't be opened, so it isn't retried on every probe
+    timestamps_unavailable: OnceLock<String>,
+}
+
+impl NativePinger {
+    pub fn new(timeout_ms: u64, accounting: Arc<Mutex<ProbeAccounting>>) -> Self {
+        Self {
+            timeout_ms: AtomicU64::new(timeout_ms),
+            retries: AtomicU32::new(0),
+            identifier: std::process::id() as u16,
+            next_sequence: AtomicU16::new(0),
+            v4: Mutex::new(None),
+            v6: Mutex::new(None),
+            accounting,
+            timestamps: AtomicBool::new(false),
+            timestamp_socket: Mutex::new(None),
+            timestamps_unavailable: OnceLock::new(),
+        }
+    }
+
+    /// Number of ICMP sockets currently open
+    pub fn open_sockets(&self) -> usize {
+        usize::from(self.v4.lock().is_some())
+            + usize::from(self.v6.lock().is_some())
+            + usize::from(self.timestamp_socket.lock().is_some())
+    }
+
+    /// Change how long to wait for each reply
+    pub fn set_timeout(&self, timeout_ms: u64) {
+        self.timeout_ms.store(timeout_ms, Ordering::Relaxed);
+    }
+
+    /// Change the default number of retries for targets without their own
+    /// setting
+    pub fn set_retries(&self, retries: u32) {
+        self.retries.store(retries, Ordering::Relaxed);
+    }
+
+    /// Whether to follow each answered echo with a timestamp request
+    pub fn set_timestamps(&self, enabled: bool) {
+        self.timestamps.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Ping `destination` on behalf of `target`, retrying failed attempts
+    fn ping_at(&self, target: &PingTarget, destination: SocketAddr, sequence: u32) -> PingResult {
+        let max_attempts = target
+            .retries
+            .unwrap_or_else(|| self.retries.load(Ordering::Relaxed))
+            .saturating_add(1);
+        let mut attempts = 0;
+        let outcome = loop {
+            attempts += 1;
+            match self.probe(target, destination) {
+                Err(error) if attempts < max_attempts => log::debug!(
+                    "Attempt {} to {} failed, retrying: {}",
+                    attempts,
+                    target.address,
+                    error
+                ),
+                outcome => break outcome,
+            }
+        };
+
+        match outcome {
+            Ok(latency) => {
+                let mut result = PingResult::success(target, latency, sequence)
+                    .with_attempts(attempts)
+                    .with_latency_us();
+                if self.timestamps.load(Ordering::Relaxed) {
+                    let timeout = Duration::from_millis(self.timeout_ms.load(Ordering::Relaxed));
+                    // A host that answers timestamps does so about as fast as echoes
+                    let wait = Duration::from_secs_f64(latency.max(0.0) * 4.0 / 1000.0)
+                        .max(MIN_TIMESTAMP_WAIT)
+                        .min(timeout);
+                    result.icmp_timestamps = Some(self.request_timestamps(destination.ip(), wait));
+                }
+                result
+            }
+            Err(error) => PingResult::failure(target, error, sequence).with_attempts(attempts),
+        }
+    }
+
+    /// Ask `ip` for its clock readings, waiting up to `wait` for the reply
+    fn request_timestamps(&self, ip: IpAddr, wait: Duration) -> IcmpTimestamps {
+        let unavailable = |reason: String| IcmpTimestamps::Unavailable { reason };
+        if ip.is_ipv6() {
+            return unavailable("ICMPv6 has no timestamp requests".to_string());
+        }
+        if let Some(reason) = self.timestamps_unavailable.get() {
+            return unavailable(reason.clone());
+        }
+
+        let idle = self.timestamp_socket.lock().take();
+        let socket = match idle {
+            Some(socket) => socket,
+            None => match Socket::new(Domain::IPV4, Type::RAW, Some(Protocol::ICMPV4)) {
+                Ok(socket) => socket.into(),
+                Err(e) => {
+                    let reason = format!("Cannot open raw ICMP socket: {}", e);
+                    let _ = self.timestamps_unavailable.set(reason.clone());
+                    return unavailable(reason);
+                }
+            },
+        };
+
+        let timestamps = self.exchange_timestamps(&socket, ip, wait);
+        // Keep one socket open for the next request
+        let mut slot = self.timestamp_socket.lock();
+        if slot.is_none() {
+            *slot = Some(socket);
+        }
+        timestamps
+    }
+
+    /// Send a timestamp request to `ip` on `socket` and wait for the reply
+    fn exchange_timestamps(
+        &self,
+        socket: &UdpSocket,
+        ip: IpAddr,
+        wait: Duration,
+    ) -> IcmpTimestamps {
+        let unavailable = |reason: String| IcmpTimestamps::Unavailable { reason };
+        let sequence = self.next_sequence.fetch_add(1, Ordering::Relaxed);
+        let originate = ms_since_midnight();
+        let packet = encode_timestamp_request(self.identifier, sequence, originate);
+        if let Err(e) = socket.send_to(&packet, SocketAddr::new(ip, 0)) {
+            return unavailable(format!("Send failed: {}", e));
+        }
+
+        let deadline = Instant::now() + wait;
+        let mut buf = [0u8; 2048];
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() || socket.set_read_timeout(Some(remaining)).is_err() {
+                return unavailable("No timestamp reply; the target may ignore them".to_string());
+            }
+            let Ok((len, from)) = socket.recv_from(&mut buf) else {
+                continue;
+            };
+            let returned = ms_since_midnight();
+
+            let Some(reply) = parse_timestamp_reply(&buf[..len]) else {
+                continue;
+            };
+            if from.ip() != ip
+                || reply.identifier != self.identifier
+                || reply.sequence != sequence
+                || reply.originate != originate
+            {
+                continue;
+            }
+            if !reply.is_standard() {
+                return unavailable("The target reports non-standard timestamps".to_string());
+            }
+            return IcmpTimestamps::Available {
+                originate_ms: originate,
+                receive_ms: reply.receive,
+                transmit_ms: reply.transmit,
+                returned_ms: returned,
+                outbound_ms: timestamp_offset_ms(originate, reply.receive),
+                inbound_ms: timestamp_offset_ms(reply.transmit, returned),
+            };
+        }
+    }
+
+    /// Send one echo request to `destination` and wait for its reply
+    ///
+    /// Each request gets a sequence number of its own, so replies can't be
+    /// mistaken for another target's. Probes are counted under the target's
+    /// own address.
+    fn probe(&self, target: &PingTarget, destination: SocketAddr) -> Result<f64, String> {
+        let ip = destination.ip();
+        let v6 = ip.is_ipv6();
+        let mut slot = if v6 { self.v6.lock() } else { self.v4.lock() };
+        if slot.is_none() {
+            *slot = Some(open_socket(v6).map_err(|e| format!("Cannot open ICMP socket: {}", e))?);
+        }
+        let Some(icmp) = slot.as_ref() else {
+            return Err("Cannot open ICMP socket".to_string());
+        };
+
+        let payload_len = target
+            .packet_size
+            .map_or(DEFAULT_PAYLOAD_LEN, |size| size as usize);
+        let sequence = self.next_sequence.fetch_add(1, Ordering::Relaxed);
+        let packet = encode_echo_request(v6, self.identifier, sequence, &vec![0; payload_len]);
+        let timeout = Duration::from_millis(self.timeout_ms.load(Ordering::Relaxed));
+
+        self.accounting
+            .lock()
+            .record_sent(&target.address, ip, sequence, Instant::now());
+        if let Err(e) = icmp.socket.send_to(&packet, destination) {
+            self.accounting.lock().record_timeout(sequence);
+            return Err(format!("Send failed: {}", e));
+        }
+
+        let deadline = Instant::now() + timeout;
+        let mut buf = [0u8; 2048];
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                self.accounting.lock().record_timeout(sequence);
+                return Err("Request timed out".to_string());
+            }
+            icmp.socket
+                .set_read_timeout(Some(remaining))
+                .map_err(|e| e.to_string())?;
+
+            let (len, from) = match icmp.socket.recv_from(&mut buf) {
+                Ok(received) => received,
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                    continue
+                }
+                Err(e) => {
+                    self.accounting.lock().record_timeout(sequence);
+                    return Err(format!("Receive failed: {}", e));
+                }
+            };
+            let now = Instant::now();
+
+            let Some(reply) = parse_echo_reply(v6, &buf[..len]) else {
+                continue;
+            };
+            if icmp.checks_identifier && reply.identifier != self.identifier {
+                continue;
+            }
+
+            // Replies to other probes, including late ones, only update the accounting
+            let outcome = self
+                .accounting
+                .lock()
+                .record_reply(from.ip(), reply.sequence, now);
+            if let ReplyOutcome::OnTime { latency_ms, .. } = outcome {
+                if from.ip() == ip && reply.sequence == sequence {
+                    return Ok(latency_ms);
+                }
+            }
+        }
+    }
+}
+
+impl PingBackend for NativePinger {
+    fn ping(&self, target: &PingTarget, sequence: u32) -> PingResult {
+        if let Some(zoned) = ZonedAddress::parse(&target.address) {
+            let destination = zoned.and_then(|zoned| {
+                let scope_id = zoned.scope_id()?;
+                Ok(SocketAddr::V6(SocketAddrV6::new(zoned.ip, 0, 0, scope_id)))
+            });
+            return match destination {
+                Ok(destination) => self.ping_at(target, destination, sequence),
+                Err(error) => PingResult::failure(target, error, sequence),
+            };
+        }
+        match resolve(&target.address) {
+            Ok(ip) => self.ping_ip(target, ip, sequence),
+            Err(error) => PingResult::failure(target, error, sequence),
+        }
+    }
+
+    fn ping_ip(&self, target: &PingTarget, ip: IpAddr, sequence: u32) -> PingResult {
+        self.ping_at(target, SocketAddr::new(ip, 0), sequence)
+    }
+}
+
+/// Current time in milliseconds since midnight UT, as ICMP timestamps count
+fn ms_since_midnight() -> u32 {
+    Utc::now().timestamp_millis().rem_euclid(86_400_000) as u32
+}
+
+/// How to get permission to open ICMP sockets on this platform
+const ELEVATION_HINT: &str = if cfg!(target_os = "linux") {
+    "Run with CAP_NET_RAW (setcap cap_net_raw+ep on the app binary) or allow \
+     unprivileged ICMP with the net.ipv4.ping_group_range sysctl"
+} else if cfg!(windows) {
+    "Run the app as administrator"
+} else {
+    "Run the app as root"
+};
+
+/// Check whether the native backend can open an ICMP socket
+///
+/// The socket is closed again without sending anything.
+pub fn check_icmp_permission() -> IcmpCapability {
+    capability_from(open_socket(false).map(drop))
+}
+
+/// Interpret the outcome of opening an ICMP socket
+fn capability_from(opened: Result<(), std::io::Error>) -> IcmpCapability {
+    match opened {
+        Ok(()) => IcmpCapability::Available,
+        Err(e) if e.kind() == ErrorKind::PermissionDenied => IcmpCapability::RequiresElevation {
+            hint: ELEVATION_HINT.to_string(),
+        },
+        Err(e) => IcmpCapability::Unsupported {
+            hint: format!("ICMP sockets are not available ({}); use the system backend", e),
+        },
+    }
+}
+
+/// Open an unprivileged datagram ICMP socket, or a raw one if not allowed
+fn open_socket(v6: bool) -> Result<IcmpSocket, std::io::Error> {
+    let (domain, protocol) = if v6 {
+        (Domain::IPV6, Protocol::ICMPV6)
+    } else {
+        (Domain::IPV4, Protocol::ICMPV4)
+    };
+
+    match Socket::new(domain, Type::DGRAM, Some(protocol)) {
+        Ok(socket) => Ok(IcmpSocket {
+            socket: socket.into(),
+            checks_identifier: false,
+        }),
+        Err(_) => Ok(IcmpSocket {
+            socket: Socket::new(domain, Type::RAW, Some(protocol))?.into(),
+            checks_identifier: true,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_icmp_permission_matches_socket_access() {
+        // Whatever the CI host allows, the check agrees with opening a socket
+        let capability = check_icmp_permission();
+        match open_socket(false) {
+            Ok(_) => assert_eq!(capability, IcmpCapability::Available),
+            Err(e) if e.kind() == ErrorKind::PermissionDenied => assert_eq!(
+                capability,
+                IcmpCapability::RequiresElevation {
+                    hint: ELEVATION_HINT.to_string()
+                }
+            ),
+            Err(_) => assert!(matches!(capability, IcmpCapability::Unsupported { .. })),
+        }
+
+        let denied = std::io::Error::from(ErrorKind::PermissionDenied);
+        assert!(matches!(
+            capability_from(Err(denied)),
+            IcmpCapability::RequiresElevation { .. }
+        ));
+        let unsupported = std::io::Error::from(ErrorKind::Unsupported);
+        assert!(matches!(
+            capability_from(Err(unsupported)),
+            IcmpCapability::Unsupported { .. }
+        ));
+    }
+}
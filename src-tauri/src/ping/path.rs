@@ -0,0 +1,310 @@
+use super::Platform;
+use std::io::Read;
+use std::net::IpAddr;
+use std::process::{Child, Command, Stdio};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Most hops a path probe may trace, whatever is configured
+pub const MAX_PATH_HOPS: u8 = 64;
+
+/// Probes sent to each hop per trace
+const DEFAULT_QUERIES: u8 = 3;
+
+/// How often a running trace is checked against its deadline
+const DEADLINE_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Replies from one hop of a single trace
+#[derive(Debug, Clone, PartialEq)]
+pub struct HopSample {
+    /// Distance from this host, starting at 1
+    pub hop: u8,
+    /// First address that answered, `None` if nothing did
+    pub address: Option<String>,
+    /// Round-trip time of each probe, `None` for a timeout
+    pub rtts: Vec<Option<f64>>,
+}
+
+/// MTR-style path probe that traces each hop to a target
+///
+/// Uses the system `traceroute` (`tracert` on Windows), so it needs no
+/// elevated privileges. A trace takes much longer than a ping, so it is run
+/// far less often.
+pub struct PathProbe {
+    timeout_ms: u64,
+    max_hops: u8,
+    queries: u8,
+}
+
+impl PathProbe {
+    pub fn new(timeout_ms: u64) -> Self {
+        Self {
+            timeout_ms,
+            max_hops: 30,
+            queries: DEFAULT_QUERIES,
+        }
+    }
+
+    /// Limit the trace to `max_hops`, capped at [`MAX_PATH_HOPS`]
+    pub fn with_max_hops(mut self, max_hops: u8) -> Self {
+        self.max_hops = max_hops.clamp(1, MAX_PATH_HOPS);
+        self
+    }
+
+    /// Longest a whole trace may take: every hop timing out in turn
+    fn deadline(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms.saturating_mul(u64::from(self.max_hops)))
+    }
+
+    /// Trace the path to an address
+    ///
+    /// A trace still running at its deadline is killed, keeping the hops
+    /// traced so far.
+    pub fn trace(&self, address: &str) -> Result<Vec<HopSample>, String> {
+        let (program, args) = traceroute_command(
+            Platform::current(),
+            address,
+            self.max_hops,
+            self.queries,
+            self.timeout_ms,
+        );
+        let mut child = Command::new(program)
+            .args(&args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to execute {}: {}", program, e))?;
+        let stdout = read_in_background(child.stdout.take());
+        let stderr = read_in_background(child.stderr.take());
+        let timed_out = wait_or_kill(&mut child, self.deadline())?;
+
+        let stdout = stdout.join().unwrap_or_default();
+        let hops = parse_traceroute(&stdout, self.max_hops);
+        if hops.is_empty() {
+            if timed_out {
+                return Err(format!("Trace timed out after {}ms", self.deadline().as_millis()));
+            }
+            let stderr = stderr.join().unwrap_or_default();
+            return Err(format!("Trace failed: {}", stderr.trim()));
+        }
+        Ok(hops)
+    }
+}
+
+/// Read a child's output pipe to the end on a thread of its own, so a full
+/// pipe never blocks the child
+fn read_in_background<R: Read + Send + 'static>(pipe: Option<R>) -> JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut bytes);
+        }
+        String::from_utf8_lossy(&bytes).into_owned()
+    })
+}
+
+/// Wait for `child` to exit, killing it once `limit` has passed
+///
+/// Returns whether the child had to be killed.
+fn wait_or_kill(child: &mut Child, limit: Duration) -> Result<bool, String> {
+    let deadline = Instant::now() + limit;
+    loop {
+        if child.try_wait().map_err(|e| e.to_string())?.is_some() {
+            return Ok(false);
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(true);
+        }
+        std::thread::sleep(DEADLINE_CHECK_INTERVAL);
+    }
+}
+
+/// Program and arguments for a numeric trace of the address
+pub fn traceroute_command(
+    platform: Platform,
+    address: &str,
+    max_hops: u8,
+    queries: u8,
+    timeout_ms: u64,
+) -> (&'static str, Vec<String>) {
+    let max_hops = max_hops.clamp(1, MAX_PATH_HOPS).to_string();
+
+    match platform {
+        // tracert always sends three probes per hop
+        Platform::Windows => (
+            "tracert",
+            vec![
+                "-d".to_string(),
+                "-h".to_string(),
+                max_hops,
+                "-w".to_string(),
+                timeout_ms.to_string(),
+                address.to_string(),
+            ],
+        ),
+        _ => (
+            "traceroute",
+            vec![
+                "-n".to_string(),
+                "-m".to_string(),
+                max_hops,
+                "-q".to_string(),
+                queries.to_string(),
+                "-w".to_string(),
+                (timeout_ms / 1000).max(1).to_string(),
+                address.to_string(),
+            ],
+        ),
+    }
+}
+
+/// Parse `traceroute -n` or `tracert -d` output
+///
+/// Hop lines start with the hop number; other lines, and hops beyond
+/// `max_hops`, are ignored.
+///
+/// Unix: ` 3  10.0.0.1  5.123 ms  *  5.301 ms`
+/// Windows: `  3    12 ms    <1 ms     *     10.0.0.1`
+pub fn parse_traceroute(output: &str, max_hops: u8) -> Vec<HopSample> {
+    let max_hops = max_hops.min(MAX_PATH_HOPS);
+    let mut hops = Vec::new();
+
+    for line in output.lines() {
+        let mut tokens = line.split_whitespace().peekable();
+        let Some(hop) = tokens.next().and_then(|t| t.parse::<u8>().ok()) else {
+            continue;
+        };
+        if hop == 0 || hop > max_hops {
+            continue;
+        }
+
+        let mut sample = HopSample {
+            hop,
+            address: None,
+            rtts: Vec::new(),
+        };
+        while let Some(token) = tokens.next() {
+            if token == "*" {
+                sample.rtts.push(None);
+            } else if let Some(rtt) = parse_rtt(token) {
+                // The unit is usually its own token
+                if tokens.peek() == Some(&"ms") {
+                    tokens.next();
+                }
+                sample.rtts.push(Some(rtt));
+            } else if sample.address.is_none() {
+                let candidate =
+                    token.trim_matches(|c| c == '(' || c == ')' || c == '[' || c == ']');
+                if candidate.parse::<IpAddr>().is_ok() {
+                    sample.address = Some(candidate.to_string());
+                }
+            }
+        }
+        hops.push(sample);
+    }
+
+    hops
+}
+
+/// Round-trip time from a token such as `5.123`, `12ms` or `<1`
+fn parse_rtt(token: &str) -> Option<f64> {
+    let number = token.trim_end_matches("ms").trim_start_matches('<');
+    // Addresses such as 10.0.0.1 do not parse as a float
+    number.parse::<f64>().ok().filter(|rtt| rtt.is_finite())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINUX_OUTPUT: &str = "\
+traceroute to 1.1.1.1 (1.1.1.1), 30 hops max, 60 byte packets
+ 1  192.168.1.1  0.512 ms  0.480 ms  0.455 ms
+ 2  * * *
+ 3  10.20.0.1  8.120 ms *  9.004 ms
+ 4  10.30.0.1  12.5 ms 10.30.0.2  13.1 ms  12.9 ms
+ 5  1.1.1.1  14.002 ms  13.870 ms  14.110 ms
+";
+
+    const WINDOWS_OUTPUT: &str = "\
+Tracing route to 1.1.1.1 over a maximum of 30 hops
+
+  1    <1 ms    <1 ms    <1 ms  192.168.1.1
+  2     *        *        *     Request timed out.
+  3    12 ms    11 ms     *     10.20.0.1
+  4    15 ms    14 ms    15 ms  1.1.1.1
+
+Trace complete.
+";
+
+    #[test]
+    fn test_parse_linux_traceroute() {
+        let hops = parse_traceroute(LINUX_OUTPUT, 30);
+        assert_eq!(hops.len(), 5);
+        assert_eq!(
+            hops[0],
+            HopSample {
+                hop: 1,
+                address: Some("192.168.1.1".to_string()),
+                rtts: vec![Some(0.512), Some(0.48), Some(0.455)],
+            }
+        );
+        assert_eq!(hops[1].address, None);
+        assert_eq!(hops[1].rtts, vec![None, None, None]);
+        assert_eq!(hops[2].rtts, vec![Some(8.12), None, Some(9.004)]);
+        // Load-balanced hops keep the first responder
+        assert_eq!(hops[3].address.as_deref(), Some("10.30.0.1"));
+        assert_eq!(hops[3].rtts.len(), 3);
+        assert_eq!(hops[4].address.as_deref(), Some("1.1.1.1"));
+    }
+
+    #[test]
+    fn test_parse_windows_tracert() {
+        let hops = parse_traceroute(WINDOWS_OUTPUT, 30);
+        assert_eq!(hops.len(), 4);
+        assert_eq!(hops[0].rtts, vec![Some(1.0), Some(1.0), Some(1.0)]);
+        assert_eq!(hops[0].address.as_deref(), Some("192.168.1.1"));
+        assert_eq!(hops[1].address, None);
+        assert_eq!(hops[1].rtts, vec![None, None, None]);
+        assert_eq!(hops[2].rtts, vec![Some(12.0), Some(11.0), None]);
+        assert_eq!(hops[3].address.as_deref(), Some("1.1.1.1"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_process_past_its_deadline_is_killed() {
+        let mut child = Command::new("sleep").arg("10").spawn().unwrap();
+        let started = Instant::now();
+        assert_eq!(wait_or_kill(&mut child, Duration::from_millis(100)), Ok(true));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let mut child = Command::new("true").spawn().unwrap();
+        assert_eq!(wait_or_kill(&mut child, Duration::from_secs(5)), Ok(false));
+        assert_eq!(PathProbe::new(2000).with_max_hops(3).deadline(), Duration::from_secs(6));
+    }
+
+    #[test]
+    fn test_max_hops_guard() {
+        // Hops past the limit are dropped
+        assert_eq!(parse_traceroute(LINUX_OUTPUT, 3).len(), 3);
+
+        let long: String = (1..=100)
+            .map(|hop| format!("{:>2}  * * *\n", hop))
+            .collect();
+        assert_eq!(parse_traceroute(&long, 255).len(), MAX_PATH_HOPS as usize);
+
+        let (program, args) = traceroute_command(Platform::Linux, "1.1.1.1", 200, 3, 2000);
+        assert_eq!(program, "traceroute");
+        assert_eq!(args, ["-n", "-m", "64", "-q", "3", "-w", "2", "1.1.1.1"]);
+        let (program, args) = traceroute_command(Platform::Windows, "1.1.1.1", 0, 3, 2000);
+        assert_eq!(program, "tracert");
+        assert_eq!(args, ["-d", "-h", "1", "-w", "2000", "1.1.1.1"]);
+        assert_eq!(
+            PathProbe::new(1000).with_max_hops(255).max_hops,
+            MAX_PATH_HOPS
+        );
+    }
+}
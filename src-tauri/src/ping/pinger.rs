@@ -1,101 +1,256 @@
-use crate::models::{PingResult, PingTarget};
+use super::{ping_args, Platform};
+use crate::models::{PingError, PingResult, PingTarget};
 use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Delay between a failed attempt and its retry
+const RETRY_DELAY: Duration = Duration::from_millis(200);
+
+/// How often a retry delay checks whether monitoring stopped
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Error reported when a don't-fragment probe exceeds the path MTU
+pub const FRAGMENTATION_NEEDED: &str = "Packet too large: fragmentation needed but DF set";
+
+/// Whether ping output reports that the packet exceeded the path MTU
+///
+/// Linux: "local error: message too long, mtu=1500" or "Frag needed and DF set"
+/// macOS: "sendto: Message too long"
+/// Windows: "Packet needs to be fragmented but DF set."
+fn is_fragmentation_needed(output: &str) -> bool {
+    let output = output.to_lowercase();
+    output.contains("message too long")
+        || output.contains("frag needed")
+        || output.contains("needs to be fragmented")
+}
+
+/// Failure implied by the `ping` exit code, whatever language the output is in
+///
+/// Linux exits with 1 when no reply arrived; macOS with 2 for that, and with
+/// sysexits codes for an unknown host (68) or missing privileges (77).
+/// Windows and other exit codes don't tell the cause apart.
+fn exit_code_failure(platform: Platform, code: i32) -> Option<&'static str> {
+    match (platform, code) {
+        (Platform::Linux, 1) | (Platform::MacOs, 2) => Some("Request timed out"),
+        (Platform::MacOs, 68) => Some("Unknown host"),
+        (Platform::MacOs, 77) => Some("Permission denied"),
+        _ => None,
+    }
+}
 
 /// Cross-platform pinger that uses system ping command
 /// This approach works without root/admin privileges on all platforms
 pub struct Pinger {
     timeout_ms: u64,
+    retries: u32,
+    retry_delay: Duration,
+    /// Once set, failed attempts are no longer retried
+    stop: Option<Arc<AtomicBool>>,
+    /// Run `ping` in the C locale, so its output is English with `.` decimals
+    c_locale: bool,
 }
 
 impl Pinger {
     pub fn new(timeout_ms: u64) -> Self {
-        Self { timeout_ms }
+        Self {
+            timeout_ms,
+            retries: 0,
+            retry_delay: RETRY_DELAY,
+            stop: None,
+            c_locale: true,
+        }
+    }
+
+    /// Give up retrying, and cut the delay before a retry short, once `stop`
+    /// is set
+    pub fn with_stop(mut self, stop: Arc<AtomicBool>) -> Self {
+        self.stop = Some(stop);
+        self
+    }
+
+    /// Set whether `ping` runs in the C locale rather than the user's
+    pub fn with_c_locale(mut self, c_locale: bool) -> Self {
+        self.c_locale = c_locale;
+        self
+    }
+
+    /// Set the default number of retries for targets without their own setting
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
     }
 
     /// Ping a target and return the result
     pub fn ping(&self, target: &PingTarget, sequence: u32) -> PingResult {
-        let result = self.execute_ping(&target.address);
-        
-        match result {
-            Ok(latency) => PingResult::success(target, latency, sequence),
-            Err(error) => PingResult::failure(target, error, sequence),
+        self.ping_with(target, sequence, |target| self.execute_ping(target))
+    }
+
+    /// Ping a target with the given probe, retrying failed attempts
+    fn ping_with<F>(&self, target: &PingTarget, sequence: u32, probe: F) -> PingResult
+    where
+        F: Fn(&PingTarget) -> Result<f64, String>,
+    {
+        let max_attempts = target.retries.unwrap_or(self.retries).saturating_add(1);
+        let mut attempts = 0;
+
+        loop {
+            attempts += 1;
+            match probe(target) {
+                Ok(latency) => {
+                    return PingResult::success(target, latency, sequence).with_attempts(attempts)
+                }
+                Err(error) if attempts >= max_attempts => {
+                    return PingResult::failure(target, error, sequence).with_attempts(attempts)
+                }
+                Err(error) => {
+                    log::debug!(
+                        "Attempt {} to {} failed, retrying: {}",
+                        attempts,
+                        target.address,
+                        error
+                    );
+                    if !self.wait_before_retry() {
+                        return PingResult::failure(target, error, sequence).with_attempts(attempts);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Wait out the retry delay, returning false if stopped meanwhile
+    fn wait_before_retry(&self) -> bool {
+        let stopped = || self.stop.as_ref().is_some_and(|s| s.load(Ordering::SeqCst));
+        let deadline = Instant::now() + self.retry_delay;
+        loop {
+            if stopped() {
+                return false;
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return true;
+            }
+            std::thread::sleep(remaining.min(STOP_CHECK_INTERVAL));
         }
     }
 
+    /// Build the platform-specific ping command
+    fn ping_command(&self, target: &PingTarget) -> Command {
+        let mut command = Command::new("ping");
+        command.args(ping_args(Platform::current(), target, self.timeout_ms));
+        if self.c_locale {
+            command.env("LC_ALL", "C").env("LANG", "C");
+        }
+        command
+    }
+
     /// Execute platform-specific ping command
-    fn execute_ping(&self, address: &str) -> Result<f64, String> {
-        let timeout_secs = (self.timeout_ms / 1000).max(1);
-        
-        #[cfg(target_os = "windows")]
-        let output = Command::new("ping")
-            .args(["-n", "1", "-w", &(self.timeout_ms).to_string(), address])
-            .output();
-
-        #[cfg(target_os = "macos")]
-        let output = Command::new("ping")
-            .args(["-c", "1", "-t", &timeout_secs.to_string(), address])
-            .output();
-
-        #[cfg(target_os = "linux")]
-        let output = Command::new("ping")
-            .args(["-c", "1", "-W", &timeout_secs.to_string(), address])
-            .output();
-
-        #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
-        let output = Command::new("ping")
-            .args(["-c", "1", address])
-            .output();
-
-        match output {
+    fn execute_ping(&self, target: &PingTarget) -> Result<f64, String> {
+        match self.ping_command(target).output() {
             Ok(output) => {
-                if output.status.success() {
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-                    self.parse_latency(&stdout)
-                } else {
-                    let stderr = String::from_utf8_lossy(&output.stderr);
-                    Err(format!("Ping failed: {}", stderr.trim()))
-                }
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let code = output.status.code();
+                self.interpret_output(Platform::current(), code, &stdout, &stderr)
             }
             Err(e) => Err(format!("Failed to execute ping: {}", e)),
         }
     }
 
-    /// Parse latency from ping output
-    fn parse_latency(&self, output: &str) -> Result<f64, String> {
-        // Windows format: "Reply from x.x.x.x: bytes=32 time=12ms TTL=57"
-        // macOS/Linux format: "64 bytes from x.x.x.x: icmp_seq=1 ttl=57 time=12.3 ms"
-        
-        // Try to find "time=" or "time<" pattern
-        if let Some(time_idx) = output.find("time=") {
-            let after_time = &output[time_idx + 5..];
-            return self.extract_number(after_time);
-        }
-        
-        if let Some(time_idx) = output.find("time<") {
-            let after_time = &output[time_idx + 5..];
-            return self.extract_number(after_time);
+    /// Latency or failure reported by a finished `ping`
+    ///
+    /// Windows reports an oversized don't-fragment packet with exit code 0,
+    /// so output saying so is a failure whatever the exit code.
+    fn interpret_output(
+        &self,
+        platform: Platform,
+        code: Option<i32>,
+        stdout: &str,
+        stderr: &str,
+    ) -> Result<f64, String> {
+        if code == Some(0) && !is_fragmentation_needed(stdout) {
+            parse_latency(stdout)
+        } else {
+            Err(self.parse_failure(platform, code, stdout, stderr))
         }
+    }
 
-        // Try to find "time " pattern (some systems use space)
-        if let Some(time_idx) = output.find("time ") {
-            let after_time = &output[time_idx + 5..];
-            return self.extract_number(after_time);
+    /// Describe a failed ping from its output, then its exit code
+    ///
+    /// Linux exits with 1 for "Destination Host Unreachable" as for a
+    /// timeout, so stderr and then each stdout line are classified first.
+    /// When no localized message can be classified, the cause implied by
+    /// the exit code is put in front of stderr.
+    fn parse_failure(
+        &self,
+        platform: Platform,
+        code: Option<i32>,
+        stdout: &str,
+        stderr: &str,
+    ) -> String {
+        if is_fragmentation_needed(stdout) || is_fragmentation_needed(stderr) {
+            return FRAGMENTATION_NEEDED.to_string();
+        }
+        let message = format!("Ping failed: {}", stderr.trim());
+        if PingError::classify(&message) != PingError::Other {
+            return message;
+        }
+        let reported = stdout
+            .lines()
+            .map(str::trim)
+            .find(|line| PingError::classify(line) != PingError::Other);
+        if let Some(line) = reported {
+            return format!("Ping failed: {}", line);
+        }
+        match code.and_then(|code| exit_code_failure(platform, code).map(|kind| (code, kind))) {
+            Some((code, kind)) if stderr.trim().is_empty() => {
+                format!("{} (exit code {})", kind, code)
+            }
+            Some((code, kind)) => format!("{} (exit code {}): {}", kind, code, stderr.trim()),
+            None => message,
         }
+    }
+}
 
-        Err("Could not parse latency from ping output".to_string())
+/// Parse latency from a ping reply
+pub(super) fn parse_latency(output: &str) -> Result<f64, String> {
+    // Windows format: "Reply from x.x.x.x: bytes=32 time=12ms TTL=57"
+    // macOS/Linux format: "64 bytes from x.x.x.x: icmp_seq=1 ttl=57 time=12.3 ms"
+    
+    // Try to find "time=" or "time<" pattern
+    if let Some(time_idx) = output.find("time=") {
+        let after_time = &output[time_idx + 5..];
+        return extract_number(after_time);
+    }
+    
+    // Windows reports sub-millisecond replies as "time<1ms". Only the
+    // bound is known, so record the middle of the range instead of
+    // overstating every fast reply as 1ms.
+    if let Some(time_idx) = output.find("time<") {
+        let after_time = &output[time_idx + 5..];
+        return extract_number(after_time).map(|bound| bound / 2.0);
     }
 
-    /// Extract a floating point number from the beginning of a string
-    fn extract_number(&self, s: &str) -> Result<f64, String> {
-        let num_str: String = s
-            .chars()
-            .take_while(|c| c.is_ascii_digit() || *c == '.')
-            .collect();
-        
-        num_str
-            .parse::<f64>()
-            .map_err(|_| format!("Could not parse number from: {}", s))
+    // Try to find "time " pattern (some systems use space)
+    if let Some(time_idx) = output.find("time ") {
+        let after_time = &output[time_idx + 5..];
+        return extract_number(after_time);
     }
+
+    Err("Could not parse latency from ping output".to_string())
+}
+
+/// Extract a floating point number from the beginning of a string
+fn extract_number(s: &str) -> Result<f64, String> {
+    let num_str: String = s
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    
+    num_str
+        .parse::<f64>()
+        .map_err(|_| format!("Could not parse number from: {}", s))
 }
 
 #[cfg(test)]
@@ -104,22 +259,157 @@ mod tests {
 
     #[test]
     fn test_parse_latency_macos() {
-        let pinger = Pinger::new(5000);
         let output = "64 bytes from 1.1.1.1: icmp_seq=0 ttl=57 time=12.345 ms";
-        assert_eq!(pinger.parse_latency(output).unwrap(), 12.345);
+        assert_eq!(parse_latency(output).unwrap(), 12.345);
     }
 
     #[test]
     fn test_parse_latency_windows() {
-        let pinger = Pinger::new(5000);
         let output = "Reply from 1.1.1.1: bytes=32 time=15ms TTL=57";
-        assert_eq!(pinger.parse_latency(output).unwrap(), 15.0);
+        assert_eq!(parse_latency(output).unwrap(), 15.0);
+    }
+
+    #[test]
+    fn test_parse_latency_below_bound_is_halved() {
+        let output = "Reply from 192.168.1.1: bytes=32 time<1ms TTL=64";
+        assert_eq!(parse_latency(output).unwrap(), 0.5);
     }
 
     #[test]
     fn test_parse_latency_linux() {
-        let pinger = Pinger::new(5000);
         let output = "64 bytes from 1.1.1.1: icmp_seq=1 ttl=57 time=8.92 ms";
-        assert_eq!(pinger.parse_latency(output).unwrap(), 8.92);
+        assert_eq!(parse_latency(output).unwrap(), 8.92);
+    }
+
+    #[test]
+    fn test_parse_fragmentation_needed() {
+        let pinger = Pinger::new(5000);
+
+        let linux = "ping: local error: message too long, mtu=1500";
+        let failure = pinger.parse_failure(Platform::Linux, Some(1), linux, "");
+        assert_eq!(failure, FRAGMENTATION_NEEDED);
+        let macos = "ping: sendto: Message too long";
+        let failure = pinger.parse_failure(Platform::MacOs, Some(2), "", macos);
+        assert_eq!(failure, FRAGMENTATION_NEEDED);
+        let windows = "Packet needs to be fragmented but DF set.";
+        let failure = pinger.parse_failure(Platform::Windows, Some(1), windows, "");
+        assert_eq!(failure, FRAGMENTATION_NEEDED);
+
+        let stderr = "Request timeout for icmp_seq 0";
+        let timeout = pinger.parse_failure(Platform::MacOs, Some(2), "", stderr);
+        assert_eq!(timeout, "Ping failed: Request timeout for icmp_seq 0");
+    }
+
+    #[test]
+    fn test_fragmentation_is_detected_despite_exit_code_zero() {
+        let pinger = Pinger::new(5000);
+        let windows = "Pinging 192.0.2.1 with 1500 bytes of data:\r\n\
+                       Packet needs to be fragmented but DF set.\r\n";
+
+        let failure = pinger.interpret_output(Platform::Windows, Some(0), windows, "");
+        assert_eq!(failure, Err(FRAGMENTATION_NEEDED.to_string()));
+        let reply = "Reply from 192.0.2.1: bytes=32 time=12ms TTL=57";
+        assert_eq!(pinger.interpret_output(Platform::Windows, Some(0), reply, ""), Ok(12.0));
+    }
+
+    #[test]
+    fn test_localized_failure_is_classified_by_exit_code() {
+        let pinger = Pinger::new(5000);
+
+        let stderr = "ping: no se puede resolver ejemplo.invalid: Host desconocido";
+        let failure = pinger.parse_failure(Platform::MacOs, Some(68), "", stderr);
+        assert_eq!(PingError::classify(&failure), PingError::UnknownHost);
+        assert!(failure.ends_with(stderr));
+
+        let stdout = "1 paquetes transmitidos, 0 recibidos, 100% pérdida de paquetes";
+        let failure = pinger.parse_failure(Platform::Linux, Some(1), stdout, "");
+        assert_eq!(failure, "Request timed out (exit code 1)");
+        assert_eq!(PingError::classify(&failure), PingError::Timeout);
+
+        // Output that names the cause wins over the exit code
+        let stdout = "PING 192.0.2.1 (192.0.2.1) 56(84) bytes of data.\n\
+                      From 192.0.2.254 icmp_seq=1 Destination Host Unreachable\n";
+        let failure = pinger.parse_failure(Platform::Linux, Some(1), stdout, "");
+        assert_eq!(
+            failure,
+            "Ping failed: From 192.0.2.254 icmp_seq=1 Destination Host Unreachable"
+        );
+        assert_eq!(PingError::classify(&failure), PingError::Unreachable);
+        let stdout = "92 bytes from 192.0.2.254: Packet filtered\n";
+        let failure = pinger.parse_failure(Platform::MacOs, Some(2), stdout, "");
+        assert_eq!(PingError::classify(&failure), PingError::Unreachable);
+
+        // Exit codes that don't tell the cause apart leave the message as is
+        let failure = pinger.parse_failure(Platform::Linux, Some(2), "", "ping: desconocido");
+        assert_eq!(failure, "Ping failed: ping: desconocido");
+        assert_eq!(PingError::classify(&failure), PingError::Other);
+    }
+
+    #[test]
+    fn test_ping_command_forces_c_locale_unless_disabled() {
+        let target = PingTarget::new("1.1.1.1".to_string(), "Test".to_string());
+        let env = |pinger: &Pinger| -> Vec<(String, String)> {
+            pinger
+                .ping_command(&target)
+                .get_envs()
+                .filter_map(|(key, value)| {
+                    let value = value?.to_string_lossy().into_owned();
+                    Some((key.to_string_lossy().into_owned(), value))
+                })
+                .collect()
+        };
+
+        let forced = env(&Pinger::new(5000));
+        assert!(forced.contains(&("LC_ALL".to_string(), "C".to_string())));
+        assert!(forced.contains(&("LANG".to_string(), "C".to_string())));
+        assert!(env(&Pinger::new(5000).with_c_locale(false)).is_empty());
+    }
+
+    #[test]
+    fn test_retry_after_failed_attempt_records_success() {
+        let mut pinger = Pinger::new(5000).with_retries(2);
+        pinger.retry_delay = Duration::ZERO;
+        let target = PingTarget::new("1.1.1.1".to_string(), "Test".to_string());
+        let calls = std::cell::Cell::new(0);
+
+        let result = pinger.ping_with(&target, 1, |_| {
+            calls.set(calls.get() + 1);
+            if calls.get() == 1 {
+                Err("Request timed out".to_string())
+            } else {
+                Ok(14.2)
+            }
+        });
+
+        assert!(result.success);
+        assert_eq!(result.latency_ms, Some(14.2));
+        assert_eq!(result.attempts, 2);
+        assert_eq!(calls.get(), 2);
+
+        let result = pinger.ping_with(&target, 2, |_| Err("Request timed out".to_string()));
+        assert!(!result.success);
+        assert_eq!(result.attempts, 3);
+    }
+
+    #[test]
+    fn test_stop_ends_retrying_without_waiting_out_the_delay() {
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut pinger = Pinger::new(5000)
+            .with_retries(5)
+            .with_stop(Arc::clone(&stop));
+        pinger.retry_delay = Duration::from_secs(60);
+        let target = PingTarget::new("1.1.1.1".to_string(), "Test".to_string());
+
+        let stopper = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            stop.store(true, Ordering::SeqCst);
+        });
+        let started = Instant::now();
+        let result = pinger.ping_with(&target, 1, |_| Err("Request timed out".to_string()));
+        stopper.join().unwrap();
+
+        assert!(!result.success);
+        assert_eq!(result.attempts, 1);
+        assert!(started.elapsed() < Duration::from_secs(1));
     }
 }
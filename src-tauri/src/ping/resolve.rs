@@ -0,0 +1,291 @@
+use super::{PingBackend, ZonedAddress};
+use crate::models::{PingResult, PingTarget, ResolvedAddress};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::net::{IpAddr, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Looks up the IP address of a hostname
+pub trait Resolver: Send + Sync {
+    fn resolve(&self, host: &str) -> Result<IpAddr, String>;
+}
+
+/// Resolver that asks the operating system
+pub struct SystemResolver;
+
+impl Resolver for SystemResolver {
+    fn resolve(&self, host: &str) -> Result<IpAddr, String> {
+        resolve(host)
+    }
+}
+
+/// Resolve a target address to the IP to probe
+pub(super) fn resolve(address: &str) -> Result<IpAddr, String> {
+    if let Ok(ip) = address.parse() {
+        return Ok(ip);
+    }
+
+    (address, 0)
+        .to_socket_addrs()
+        .map_err(|e| format!("Cannot resolve {}: {}", address, e))?
+        .next()
+        .map(|addr| addr.ip())
+        .ok_or_else(|| format!("Cannot resolve {}", address))
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CachedAddress {
+    ip: IpAddr,
+    resolved: Instant,
+    resolved_at: DateTime<Utc>,
+}
+
+/// Last resolved address of each hostname target
+#[derive(Debug, Default)]
+pub struct ResolverCache {
+    entries: Mutex<HashMap<String, CachedAddress>>,
+}
+
+impl ResolverCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// IP address to probe for `target`
+    ///
+    /// Targets with `resolve_each_probe` bypass the cache and are resolved
+    /// every time, so DNS failures show up as probe failures. Other targets
+    /// reuse their address until it is `max_age` old; if refreshing it fails,
+    /// the old address is kept so the probe still tests the network path.
+    pub fn lookup(
+        &self,
+        target: &PingTarget,
+        resolver: &dyn Resolver,
+        max_age: Duration,
+        now: Instant,
+    ) -> Result<IpAddr, String> {
+        if let Ok(ip) = target.address.parse() {
+            return Ok(ip);
+        }
+        if target.resolve_each_probe {
+            return resolver.resolve(&target.address);
+        }
+
+        let cached = self.entries.lock().get(&target.address).copied();
+        if let Some(cached) = cached {
+            if now.saturating_duration_since(cached.resolved) < max_age {
+                return Ok(cached.ip);
+            }
+        }
+
+        match resolver.resolve(&target.address) {
+            Ok(ip) => {
+                let entry = CachedAddress {
+                    ip,
+                    resolved: now,
+                    resolved_at: Utc::now(),
+                };
+                self.entries.lock().insert(target.address.clone(), entry);
+                Ok(ip)
+            }
+            Err(e) => match cached {
+                Some(cached) => {
+                    log::warn!("{}; keeping {}", e, cached.ip);
+                    Ok(cached.ip)
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    /// Drop the cached address of a hostname
+    pub fn remove_target(&self, address: &str) {
+        self.entries.lock().remove(address);
+    }
+
+    /// Address each hostname last resolved to, and how long ago
+    pub fn entries(&self, now: Instant) -> Vec<ResolvedAddress> {
+        let mut entries: Vec<ResolvedAddress> = self
+            .entries
+            .lock()
+            .iter()
+            .map(|(target, cached)| ResolvedAddress {
+                target: target.clone(),
+                ip: cached.ip,
+                resolved_at: cached.resolved_at,
+                age_ms: now.saturating_duration_since(cached.resolved).as_millis() as u64,
+            })
+            .collect();
+        entries.sort_by(|a, b| a.target.cmp(&b.target));
+        entries
+    }
+}
+
+/// Resolves hostname targets through a [`ResolverCache`] before probing them
+pub struct ResolvingBackend<B> {
+    inner: B,
+    cache: Arc<ResolverCache>,
+    resolver: Arc<dyn Resolver>,
+    max_age: Duration,
+}
+
+impl<B: PingBackend> ResolvingBackend<B> {
+    pub fn new(
+        inner: B,
+        cache: Arc<ResolverCache>,
+        resolver: Arc<dyn Resolver>,
+        max_age: Duration,
+    ) -> Self {
+        Self {
+            inner,
+            cache,
+            resolver,
+            max_age,
+        }
+    }
+}
+
+impl<B: PingBackend> PingBackend for ResolvingBackend<B> {
+    fn ping(&self, target: &PingTarget, sequence: u32) -> PingResult {
+        // Resolving would drop the zone, which the backend needs to pick the
+        // interface
+        if ZonedAddress::parse(&target.address).is_some() {
+            return self.inner.ping(target, sequence);
+        }
+        match self
+            .cache
+            .lookup(target, &*self.resolver, self.max_age, Instant::now())
+        {
+            Ok(ip) => {
+                let mut result = self.inner.ping_ip(target, ip, sequence);
+                if target.address.parse::<IpAddr>().is_err() {
+                    result.resolved_ip = Some(ip);
+                }
+                result
+            }
+            Err(error) => PingResult::failure(target, error, sequence),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ping::MockBackend;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Resolver answering 192.0.2.x, with x counting the lookups
+    #[derive(Default)]
+    struct StubResolver {
+        lookups: AtomicUsize,
+        failing: std::sync::atomic::AtomicBool,
+    }
+
+    impl Resolver for StubResolver {
+        fn resolve(&self, host: &str) -> Result<IpAddr, String> {
+            if self.failing.load(Ordering::SeqCst) {
+                return Err(format!("Cannot resolve {}", host));
+            }
+            let n = self.lookups.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(IpAddr::from([192, 0, 2, n as u8]))
+        }
+    }
+
+    fn host(resolve_each_probe: bool) -> PingTarget {
+        let mut target = PingTarget::new("host.example".to_string(), "Host".to_string());
+        target.resolve_each_probe = resolve_each_probe;
+        target
+    }
+
+    #[test]
+    fn test_resolve_each_probe_sees_every_dns_change_and_failure() {
+        let cache = ResolverCache::new();
+        let resolver = StubResolver::default();
+        let target = host(true);
+        let now = Instant::now();
+        let max_age = Duration::from_secs(300);
+
+        let first = cache.lookup(&target, &resolver, max_age, now).unwrap();
+        let second = cache.lookup(&target, &resolver, max_age, now).unwrap();
+        assert_eq!(resolver.lookups.load(Ordering::SeqCst), 2);
+        assert_ne!(first, second);
+        assert!(cache.entries(now).is_empty());
+
+        resolver.failing.store(true, Ordering::SeqCst);
+        let error = cache.lookup(&target, &resolver, max_age, now).unwrap_err();
+        assert_eq!(error, "Cannot resolve host.example");
+    }
+
+    #[test]
+    fn test_cached_address_is_reused_until_the_interval_passes() {
+        let cache = ResolverCache::new();
+        let resolver = StubResolver::default();
+        let target = host(false);
+        let start = Instant::now();
+        let max_age = Duration::from_secs(300);
+
+        let first = cache.lookup(&target, &resolver, max_age, start).unwrap();
+        let later = start + Duration::from_secs(299);
+        assert_eq!(
+            cache.lookup(&target, &resolver, max_age, later).unwrap(),
+            first
+        );
+        assert_eq!(resolver.lookups.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.entries(later)[0].age_ms, 299_000);
+
+        let refreshed = start + Duration::from_secs(300);
+        let second = cache
+            .lookup(&target, &resolver, max_age, refreshed)
+            .unwrap();
+        assert_ne!(second, first);
+        assert_eq!(cache.entries(refreshed)[0].ip, second);
+
+        // A failed refresh keeps probing the known address
+        resolver.failing.store(true, Ordering::SeqCst);
+        let stale = refreshed + Duration::from_secs(600);
+        assert_eq!(
+            cache.lookup(&target, &resolver, max_age, stale).unwrap(),
+            second
+        );
+
+        // IP targets never touch the resolver or the cache
+        let ip = PingTarget::new("198.51.100.1".to_string(), "IP".to_string());
+        assert!(cache.lookup(&ip, &resolver, max_age, start).is_ok());
+        assert_eq!(cache.entries(start).len(), 1);
+
+        cache.remove_target("host.example");
+        assert!(cache.entries(start).is_empty());
+    }
+
+    #[test]
+    fn test_results_keep_the_hostname() {
+        let resolver = Arc::new(StubResolver::default());
+        resolver.failing.store(true, Ordering::SeqCst);
+        let backend = ResolvingBackend::new(
+            MockBackend::new(vec![Some(10.0)]),
+            Arc::new(ResolverCache::new()),
+            resolver.clone(),
+            Duration::from_secs(300),
+        );
+        let target = host(true);
+
+        let failed = backend.ping(&target, 1);
+        assert!(!failed.success);
+        assert_eq!(backend.inner.calls(), 0);
+
+        resolver.failing.store(false, Ordering::SeqCst);
+        let result = backend.ping(&target, 2);
+        assert!(result.success);
+        assert_eq!(result.target, "host.example");
+        assert_eq!(result.resolved_ip, Some(IpAddr::from([192, 0, 2, 1])));
+
+        // Zoned addresses reach the backend whole, without a lookup
+        let zoned = PingTarget::new("fe80::1%eth0".to_string(), "Router".to_string());
+        let result = backend.ping(&zoned, 3);
+        assert_eq!(result.target, "fe80::1%eth0");
+        assert_eq!(resolver.lookups.load(Ordering::SeqCst), 1);
+        assert_eq!(backend.inner.calls(), 2);
+    }
+}
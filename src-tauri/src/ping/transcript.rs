@@ -0,0 +1,211 @@
+use super::pinger::parse_latency;
+use crate::models::{PingResult, PingTarget};
+use chrono::{DateTime, Duration, Utc};
+
+/// Gap assumed between results whose line carries no timestamp
+const TRANSCRIPT_INTERVAL_SECS: i64 = 1;
+
+/// Parse saved `ping` or `fping` output into results labelled `target_label`
+///
+/// Replies, timeouts and unreachable messages become results; headers and
+/// summaries are skipped. A line may start with a `ping -D` style
+/// `[unix seconds]` or an RFC 3339 timestamp. Results without one are placed
+/// a second apart, before the next timestamped result or ending at `end`.
+pub fn parse_ping_output(
+    text: &str,
+    target_label: &str,
+    end: DateTime<Utc>,
+) -> Result<Vec<PingResult>, String> {
+    let mut address: Option<String> = None;
+    let mut parsed: Vec<(Option<DateTime<Utc>>, PingResult)> = Vec::new();
+
+    for line in text.lines() {
+        let (timestamp, line) = split_timestamp(line.trim());
+        if address.is_none() {
+            address = header_address(line);
+        }
+        let Some(outcome) = parse_line(line) else {
+            continue;
+        };
+        let target = PingTarget::new(
+            address
+                .clone()
+                .or_else(|| reply_address(line))
+                .unwrap_or_default(),
+            target_label.to_string(),
+        );
+        let sequence = sequence_of(line).unwrap_or(parsed.len() as u32);
+        let result = match outcome {
+            Ok(latency) => PingResult::success(&target, latency, sequence),
+            Err(error) => PingResult::failure(&target, error, sequence),
+        };
+        parsed.push((timestamp, result));
+    }
+
+    if parsed.iter().any(|(_, result)| result.target.is_empty()) {
+        return Err("Could not tell which host was pinged".to_string());
+    }
+    if parsed.is_empty() {
+        return Err("No ping results found".to_string());
+    }
+
+    // Count back from the next known timestamp, or from `end`
+    let step = Duration::seconds(TRANSCRIPT_INTERVAL_SECS);
+    let mut next = end + step;
+    for (timestamp, result) in parsed.iter_mut().rev() {
+        result.timestamp = timestamp.unwrap_or(next - step);
+        next = result.timestamp;
+    }
+    Ok(parsed.into_iter().map(|(_, result)| result).collect())
+}
+
+/// Latency of a reply line, or the error of a failure line
+fn parse_line(line: &str) -> Option<Result<f64, String>> {
+    let lower = line.to_lowercase();
+    if lower.contains("unreachable") {
+        return Some(Err(format!("Ping failed: {}", line)));
+    }
+    if lower.contains("timed out") || lower.contains("timeout for") || lower.contains("no answer") {
+        return Some(Err("Request timed out".to_string()));
+    }
+
+    // fping: "1.1.1.1 : [0], 64 bytes, 11.8 ms (11.8 avg, 0% loss)"
+    if line.contains(" : [") {
+        let after = &line[line.find("bytes, ")? + 7..];
+        return after
+            .split(" ms")
+            .next()
+            .and_then(|ms| ms.trim().parse::<f64>().ok())
+            .map(Ok);
+    }
+    if lower.contains("bytes from") || lower.starts_with("reply from") {
+        return parse_latency(line).ok().map(Ok);
+    }
+    None
+}
+
+/// Leading `[1700000000.123]` or RFC 3339 timestamp, and the rest of the line
+fn split_timestamp(line: &str) -> (Option<DateTime<Utc>>, &str) {
+    if let Some(rest) = line.strip_prefix('[') {
+        if let Some((stamp, rest)) = rest.split_once(']') {
+            if let Ok(secs) = stamp.parse::<f64>() {
+                let timestamp = DateTime::from_timestamp(
+                    secs.trunc() as i64,
+                    (secs.fract() * 1e9).round() as u32,
+                );
+                return (timestamp, rest.trim_start());
+            }
+        }
+    }
+    if let Some((stamp, rest)) = line.split_once(' ') {
+        if let Ok(timestamp) = DateTime::parse_from_rfc3339(stamp) {
+            return (Some(timestamp.with_timezone(&Utc)), rest.trim_start());
+        }
+    }
+    (None, line)
+}
+
+/// Host named in a `PING host ...` or `Pinging host ...` header
+fn header_address(line: &str) -> Option<String> {
+    let mut words = line.split_whitespace();
+    match words.next()? {
+        "PING" | "Pinging" => words.next().map(str::to_string),
+        _ => None,
+    }
+}
+
+/// Host a reply came from, for transcripts without a header
+fn reply_address(line: &str) -> Option<String> {
+    if let Some((host, _)) = line.split_once(" : [") {
+        return Some(host.trim().to_string());
+    }
+    let after = &line[line.find("from ")? + 5..];
+    let host = after.split([':', ' ']).next()?;
+    (!host.is_empty()).then(|| host.to_string())
+}
+
+/// `icmp_seq=N`, `icmp_seq N` or fping's `[N]`
+fn sequence_of(line: &str) -> Option<u32> {
+    let after = match line.find("icmp_seq") {
+        Some(index) => &line[index + 9..],
+        None => &line[line.find(" : [")? + 4..],
+    };
+    let digits: String = after.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PingError;
+
+    #[test]
+    fn test_linux_transcript_becomes_results_ending_at_end() {
+        let transcript = "\
+PING 1.1.1.1 (1.1.1.1) 56(84) bytes of data.
+64 bytes from 1.1.1.1: icmp_seq=1 ttl=57 time=11.4 ms
+64 bytes from 1.1.1.1: icmp_seq=2 ttl=57 time=12.6 ms
+no answer yet for icmp_seq=3
+From 192.168.1.1 icmp_seq=4 Destination Host Unreachable
+64 bytes from 1.1.1.1: icmp_seq=5 ttl=57 time=10.9 ms
+
+--- 1.1.1.1 ping statistics ---
+5 packets transmitted, 3 received, 40% packet loss, time 4006ms
+rtt min/avg/max/mdev = 10.900/11.633/12.600/0.713 ms
+";
+        let end = DateTime::<Utc>::from_timestamp(1_700_000_000, 0).unwrap();
+
+        let results = parse_ping_output(transcript, "Cloudflare", end).unwrap();
+
+        assert_eq!(results.len(), 5);
+        assert!(results.iter().all(|r| r.target == "1.1.1.1"));
+        assert!(results.iter().all(|r| r.target_label == "Cloudflare"));
+        let latencies: Vec<Option<f64>> = results.iter().map(|r| r.latency_ms).collect();
+        assert_eq!(
+            latencies,
+            vec![Some(11.4), Some(12.6), None, None, Some(10.9)]
+        );
+        let sequences: Vec<u32> = results.iter().map(|r| r.sequence).collect();
+        assert_eq!(sequences, vec![1, 2, 3, 4, 5]);
+        let kinds: Vec<Option<PingError>> = results
+            .iter()
+            .map(|r| r.error.as_deref().map(PingError::classify))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                None,
+                None,
+                Some(PingError::Timeout),
+                Some(PingError::Unreachable),
+                None
+            ]
+        );
+        assert_eq!(results[4].timestamp, end);
+        assert_eq!(results[0].timestamp, end - Duration::seconds(4));
+    }
+
+    #[test]
+    fn test_timestamped_fping_transcript_keeps_its_timestamps() {
+        let transcript = "\
+[1700000000.250000] 8.8.8.8 : [0], 64 bytes, 14.2 ms (14.2 avg, 0% loss)
+[1700000001.250000] 8.8.8.8 : [1], timed out (14.2 avg, 50% loss)
+8.8.8.8 : [2], 64 bytes, 15.0 ms (14.6 avg, 33% loss)
+";
+        let end = DateTime::<Utc>::from_timestamp(1_800_000_000, 0).unwrap();
+
+        let results = parse_ping_output(transcript, "Google", end).unwrap();
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].target, "8.8.8.8");
+        assert_eq!(results[0].latency_ms, Some(14.2));
+        assert!(!results[1].success);
+        assert_eq!(results[2].sequence, 2);
+        let first = DateTime::<Utc>::from_timestamp(1_700_000_000, 250_000_000).unwrap();
+        assert_eq!(results[0].timestamp, first);
+        assert_eq!(results[1].timestamp, first + Duration::seconds(1));
+        assert_eq!(results[2].timestamp, end);
+
+        assert!(parse_ping_output("nothing to see", "None", end).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use std::net::{IpAddr, Ipv6Addr};
+
+/// Longest zone accepted, the longest interface name Linux allows
+const MAX_ZONE_LEN: usize = 15;
+
+/// An IPv6 address with the zone (interface) it is reached through, as
+/// written `fe80::1%eth0`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZonedAddress {
+    pub ip: Ipv6Addr,
+    pub zone: String,
+}
+
+impl ZonedAddress {
+    /// Parse an address with a `%zone` suffix, `None` if it has none
+    pub fn parse(address: &str) -> Option<Result<Self, String>> {
+        let (ip, zone) = address.split_once('%')?;
+        Some(Self::parse_parts(address, ip, zone))
+    }
+
+    fn parse_parts(address: &str, ip: &str, zone: &str) -> Result<Self, String> {
+        let ip: Ipv6Addr = ip
+            .parse()
+            .map_err(|_| format!("'{}' is not a valid IPv6 address with a zone", address))?;
+        if !is_link_local(&ip) {
+            return Err(format!(
+                "Only link-local IPv6 addresses take a zone, not {}",
+                ip
+            ));
+        }
+        let valid = !zone.is_empty()
+            && zone.len() <= MAX_ZONE_LEN
+            && !zone.starts_with('.')
+            && zone
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid {
+            return Err(format!(
+                "'{}' is not a valid zone; use an interface name or index, e.g. {}%eth0",
+                zone, ip
+            ));
+        }
+        Ok(Self {
+            ip,
+            zone: zone.to_string(),
+        })
+    }
+
+    /// Scope id of the zone: the zone itself if numeric, otherwise the index
+    /// of the interface it names
+    pub fn scope_id(&self) -> Result<u32, String> {
+        if let Ok(index) = self.zone.parse() {
+            return Ok(index);
+        }
+        interface_index(&self.zone)
+    }
+}
+
+/// Whether an IPv6 address is only reachable on its own link, so it needs a
+/// zone: unicast fe80::/10 and link-scoped multicast ff02::/16
+pub fn is_link_local(ip: &Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    first & 0xffc0 == 0xfe80 || first == 0xff02
+}
+
+/// Check that link-local IPv6 addresses carry a valid zone and others none
+///
+/// Addresses that are neither zoned nor IPv6 pass unchecked.
+pub fn check_zone(address: &str) -> Result<(), String> {
+    match ZonedAddress::parse(address) {
+        Some(zoned) => zoned.map(|_| ()),
+        None => match address.parse::<IpAddr>() {
+            Ok(IpAddr::V6(ip)) if is_link_local(&ip) => Err(format!(
+                "Link-local address {} needs a zone naming its interface, e.g. {}%eth0",
+                ip, ip
+            )),
+            _ => Ok(()),
+        },
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn interface_index(name: &str) -> Result<u32, String> {
+    let path = format!("/sys/class/net/{}/ifindex", name);
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|index| index.trim().parse().ok())
+        .ok_or_else(|| format!("No network interface named {}", name))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn interface_index(name: &str) -> Result<u32, String> {
+    Err(format!(
+        "Cannot look up interface {} on this platform; use its numeric index as the zone",
+        name
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_zoned_link_local_addresses() {
+        let zoned = ZonedAddress::parse("fe80::1%eth0").unwrap().unwrap();
+        assert_eq!(zoned.ip, "fe80::1".parse::<Ipv6Addr>().unwrap());
+        assert_eq!(zoned.zone, "eth0");
+
+        let numeric = ZonedAddress::parse("fe80::abcd%12").unwrap().unwrap();
+        assert_eq!(numeric.scope_id(), Ok(12));
+        assert!(ZonedAddress::parse("1.1.1.1").is_none());
+        assert!(ZonedAddress::parse("fe80::1").is_none());
+
+        #[cfg(target_os = "linux")]
+        {
+            let loopback = ZonedAddress::parse("fe80::1%lo").unwrap().unwrap();
+            assert_eq!(loopback.scope_id(), Ok(1));
+            let missing = ZonedAddress::parse("fe80::1%nosuch0").unwrap().unwrap();
+            assert_eq!(
+                missing.scope_id(),
+                Err("No network interface named nosuch0".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_rejects_missing_and_misplaced_zones() {
+        assert_eq!(
+            check_zone("fe80::1"),
+            Err(
+                "Link-local address fe80::1 needs a zone naming its interface, e.g. fe80::1%eth0"
+                    .to_string()
+            )
+        );
+        assert!(check_zone("ff02::1").is_err());
+        assert!(check_zone("fe80::1%").is_err());
+        assert!(check_zone("fe80::1%../eth0").is_err());
+        assert!(check_zone("2606:4700::1111%eth0").is_err());
+        assert!(check_zone("1.1.1.1%eth0").is_err());
+
+        assert!(check_zone("fe80::1%eth0").is_ok());
+        assert!(check_zone("2606:4700::1111").is_ok());
+        assert!(check_zone("1.1.1.1").is_ok());
+        assert!(check_zone("example.com").is_ok());
+    }
+}
@@ -0,0 +1,193 @@
+use crate::models::{PingResult, ResultProcessorConfig};
+use std::net::IpAddr;
+
+/// A step that transforms or enriches each result before it is recorded
+///
+/// Processors run in order at the start of [`crate::state::AppState::add_result`],
+/// so statistics, alarms, logs and events all see the processed result.
+pub trait ResultProcessor: Send + Sync {
+    fn process(&self, result: PingResult) -> PingResult;
+}
+
+/// Rounds latencies to a number of decimal places
+///
+/// `latency_us` is rounded to match, so both fields agree.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoundLatency {
+    pub decimals: u32,
+}
+
+impl ResultProcessor for RoundLatency {
+    fn process(&self, mut result: PingResult) -> PingResult {
+        let scale = 10f64.powi(self.decimals.min(9) as i32);
+        result.latency_ms = result.latency_ms.map(|l| (l * scale).round() / scale);
+        if self.decimals < 3 {
+            let step = 10u64.pow(3 - self.decimals);
+            result.latency_us = result.latency_us.map(|us| (us + step / 2) / step * step);
+        }
+        result
+    }
+}
+
+/// Tags results of IP targets within a subnet
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagSubnet {
+    network: IpAddr,
+    prefix_len: u8,
+    tag: String,
+}
+
+impl TagSubnet {
+    /// Tag results within `subnet`, written like `192.168.0.0/16`
+    pub fn new(subnet: &str, tag: String) -> Result<Self, String> {
+        let invalid = || format!("'{}' is not a subnet like 192.168.0.0/16", subnet);
+        let (network, prefix_len) = subnet.split_once('/').ok_or_else(invalid)?;
+        let network: IpAddr = network.parse().map_err(|_| invalid())?;
+        let prefix_len: u8 = prefix_len.parse().map_err(|_| invalid())?;
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        if prefix_len > max_len {
+            return Err(invalid());
+        }
+        Ok(Self {
+            network,
+            prefix_len,
+            tag,
+        })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        let (network, ip, bits) = match (self.network, ip) {
+            (IpAddr::V4(n), IpAddr::V4(ip)) => (
+                u128::from(u32::from(n)) << 96,
+                u128::from(u32::from(ip)) << 96,
+                self.prefix_len,
+            ),
+            (IpAddr::V6(n), IpAddr::V6(ip)) => (u128::from(n), u128::from(ip), self.prefix_len),
+            _ => return false,
+        };
+        let mask = u128::MAX.checked_shl(128 - u32::from(bits)).unwrap_or(0);
+        network & mask == ip & mask
+    }
+}
+
+impl ResultProcessor for TagSubnet {
+    fn process(&self, mut result: PingResult) -> PingResult {
+        let in_subnet = result.target.parse().is_ok_and(|ip| self.contains(ip));
+        if in_subnet && !result.tags.contains(&self.tag) {
+            result.tags.push(self.tag.clone());
+        }
+        result
+    }
+}
+
+/// Build the built-in processor a config entry describes
+pub fn build_processor(config: &ResultProcessorConfig) -> Result<Box<dyn ResultProcessor>, String> {
+    match config {
+        ResultProcessorConfig::RoundLatency { decimals } => Ok(Box::new(RoundLatency {
+            decimals: *decimals,
+        })),
+        ResultProcessorConfig::TagSubnet { subnet, tag } => {
+            Ok(Box::new(TagSubnet::new(subnet, tag.clone())?))
+        }
+    }
+}
+
+/// Processors every result passes through, in order
+///
+/// Those from the config's `result_processors` run first, then those
+/// registered in code.
+#[derive(Default)]
+pub struct ResultPipeline {
+    configured: Vec<Box<dyn ResultProcessor>>,
+    registered: Vec<Box<dyn ResultProcessor>>,
+}
+
+impl ResultPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the configured processors; invalid entries are skipped
+    pub fn configure(&mut self, configs: &[ResultProcessorConfig]) {
+        self.configured = configs
+            .iter()
+            .filter_map(|config| {
+                build_processor(config)
+                    .map_err(|e| log::error!("Skipping result processor: {}", e))
+                    .ok()
+            })
+            .collect();
+    }
+
+    /// Add a processor after all others
+    pub fn register(&mut self, processor: Box<dyn ResultProcessor>) {
+        self.registered.push(processor);
+    }
+
+    /// Pass a result through every processor
+    pub fn process(&self, result: PingResult) -> PingResult {
+        self.configured
+            .iter()
+            .chain(&self.registered)
+            .fold(result, |result, processor| processor.process(result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PingTarget;
+
+    #[test]
+    fn test_builtin_processors_run_in_order() {
+        let mut pipeline = ResultPipeline::new();
+        pipeline.configure(&[
+            ResultProcessorConfig::RoundLatency { decimals: 1 },
+            ResultProcessorConfig::TagSubnet {
+                subnet: "192.168.0.0/16".to_string(),
+                tag: "lan".to_string(),
+            },
+            ResultProcessorConfig::TagSubnet {
+                subnet: "not a subnet".to_string(),
+                tag: "never".to_string(),
+            },
+        ]);
+        pipeline.register(Box::new(RoundLatency { decimals: 0 }));
+
+        let router = PingTarget::new("192.168.1.1".to_string(), "Router".to_string());
+        let result = pipeline.process(PingResult::success(&router, 1.46, 1));
+        // 1.46 -> 1.5 -> 2, not 1
+        assert_eq!(result.latency_ms, Some(2.0));
+        assert_eq!(result.tags, vec!["lan"]);
+
+        // Microseconds agree with the rounded milliseconds
+        let mut timed = PingResult::success(&router, 1.46, 2);
+        timed.latency_us = Some(1460);
+        let timed = pipeline.process(timed);
+        assert_eq!(timed.latency_us, Some(2000));
+
+        let remote = PingTarget::new("1.1.1.1".to_string(), "Cloudflare".to_string());
+        assert!(pipeline
+            .process(PingResult::success(&remote, 5.0, 1))
+            .tags
+            .is_empty());
+    }
+
+    #[test]
+    fn test_subnet_matching() {
+        let lan = TagSubnet::new("10.0.0.0/8", "lan".to_string()).unwrap();
+        assert!(lan.contains("10.20.30.40".parse().unwrap()));
+        assert!(!lan.contains("11.0.0.1".parse().unwrap()));
+        assert!(!lan.contains("::1".parse().unwrap()));
+
+        let v6 = TagSubnet::new("2001:db8::/32", "doc".to_string()).unwrap();
+        assert!(v6.contains("2001:db8::1".parse().unwrap()));
+        assert!(!v6.contains("2001:db9::1".parse().unwrap()));
+
+        let everything = TagSubnet::new("0.0.0.0/0", "any".to_string()).unwrap();
+        assert!(everything.contains("8.8.8.8".parse().unwrap()));
+
+        assert!(TagSubnet::new("10.0.0.0/33", String::new()).is_err());
+        assert!(TagSubnet::new("10.0.0.0", String::new()).is_err());
+    }
+}
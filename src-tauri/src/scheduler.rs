@@ -0,0 +1,713 @@
+use crate::models::PingTarget;
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, Instant};
+
+/// Per-target probe schedule
+///
+/// Each target fires on its own interval. Targets joining the schedule
+/// together are staggered across their interval, so they don't all fire in
+/// one burst.
+///
+/// In round-robin mode, targets without their own interval instead take
+/// turns in list order, a batch per interval, so each is probed once every
+/// few intervals and large target lists don't burst.
+///
+/// With jitter set, each gap between a target's fires is the interval plus a
+/// uniform random offset of up to the jitter either way.
+///
+/// Fires do not wait for earlier probes to finish. With a cap set, a target
+/// that already has that many probes outstanding skips the fire instead.
+///
+/// With a budget of probes per minute set, every target first gets one probe
+/// a minute, or an even share of the budget if that is less. What is left
+/// tops targets up to their full rate by priority, then list order. Targets
+/// whose share falls short are spaced out to fit it.
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    /// Next fire time per target id
+    next_fire: HashMap<String, Instant>,
+    /// Time left until each target's next fire, captured when paused
+    paused: Option<HashMap<String, Duration>>,
+    /// Probes taken but not yet finished, per target id
+    outstanding: HashMap<String, u32>,
+    /// Most outstanding probes per target; `None` is unlimited
+    max_outstanding: Option<u32>,
+    /// Fires skipped because the target was at the cap
+    skipped: u64,
+    /// Most a gap between fires may differ from the interval
+    jitter: Duration,
+    /// State of the random number generator for jitter; 0 until seeded
+    rng: u64,
+    /// Targets probed per interval in round-robin mode; `None` probes every
+    /// target each interval
+    round_robin: Option<usize>,
+    /// Most probes started in any minute; `None` is unlimited
+    budget: Option<u32>,
+    /// Start times of the probes taken in the last minute
+    recent_fires: VecDeque<Instant>,
+    /// Targets slowed down or dropped by the current budget
+    throttled_targets: HashSet<String>,
+    /// Throttled targets counted once per budget change
+    throttled: u64,
+}
+
+/// Window over which the probe budget applies
+const BUDGET_WINDOW: Duration = Duration::from_secs(60);
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match the schedule to the given targets
+    ///
+    /// Targets no longer present are dropped and new ones are staggered
+    /// across their interval, starting at `now`.
+    pub fn sync(&mut self, targets: &[PingTarget], default_interval: Duration, now: Instant) {
+        let scheduled = self.next_fire.len();
+        self.next_fire
+            .retain(|id, _| targets.iter().any(|t| &t.id == id));
+        self.outstanding
+            .retain(|id, _| targets.iter().any(|t| &t.id == id));
+        let removed = self.next_fire.len() < scheduled;
+
+        let (rotation, others): (Vec<&PingTarget>, Vec<&PingTarget>) =
+            targets.iter().partition(|t| self.in_rotation(t));
+
+        // A changed rotation starts over, with the first batch due now
+        if let Some(batch) = self.round_robin {
+            let joined = rotation.iter().any(|t| !self.next_fire.contains_key(&t.id));
+            if joined || removed {
+                for (i, target) in rotation.iter().enumerate() {
+                    let slot = (i / batch) as u32;
+                    self.next_fire
+                        .insert(target.id.clone(), now + default_interval * slot);
+                }
+            }
+        }
+
+        let new_targets: Vec<&PingTarget> = others
+            .into_iter()
+            .filter(|t| !self.next_fire.contains_key(&t.id))
+            .collect();
+        let count = new_targets.len() as u32;
+        for (i, target) in new_targets.into_iter().enumerate() {
+            let interval = target_interval(target, default_interval);
+            let offset = interval * i as u32 / count;
+            self.next_fire.insert(target.id.clone(), now + offset);
+        }
+    }
+
+    /// Make every target due at `now`, keeping the staggered phase of later fires
+    ///
+    /// Used to probe all targets as soon as monitoring starts rather than
+    /// spreading the first round across an interval.
+    pub fn fire_all_now(
+        &mut self,
+        targets: &[PingTarget],
+        default_interval: Duration,
+        now: Instant,
+    ) {
+        self.sync(targets, default_interval, now);
+        for target in targets {
+            // The rotation already starts with its first batch
+            if self.in_rotation(target) {
+                continue;
+            }
+            if let Some(next_fire) = self.next_fire.get_mut(&target.id) {
+                // Firing adds one interval, which lands back on the staggered phase
+                let interval = target_interval(target, default_interval);
+                *next_fire = next_fire.checked_sub(interval).unwrap_or(now);
+            }
+        }
+    }
+
+    /// Cap the probes per target that may be outstanding at once
+    pub fn set_max_outstanding(&mut self, max: Option<u32>) {
+        self.max_outstanding = max;
+    }
+
+    /// Probe `batch` targets per interval in turn, or every target each
+    /// interval if `None`
+    ///
+    /// Switching modes starts the schedule over.
+    pub fn set_round_robin(&mut self, batch: Option<usize>) {
+        let batch = batch.map(|batch| batch.max(1));
+        if batch != self.round_robin {
+            self.round_robin = batch;
+            self.next_fire.clear();
+        }
+    }
+
+    /// Interval at which a target is probed, taking turns and the budget
+    /// into account
+    pub fn effective_interval(
+        &self,
+        target: &PingTarget,
+        targets: &[PingTarget],
+        default_interval: Duration,
+    ) -> Option<Duration> {
+        self.intervals(targets, default_interval)
+            .get(&target.id)
+            .map(|(interval, _)| *interval)
+    }
+
+    /// Ids of targets the budget probes less often than their interval
+    pub fn stretched_targets(
+        &self,
+        targets: &[PingTarget],
+        default_interval: Duration,
+    ) -> Vec<String> {
+        let intervals = self.intervals(targets, default_interval);
+        targets
+            .iter()
+            .filter(|target| intervals.get(&target.id).is_some_and(|(_, stretched)| *stretched))
+            .map(|target| target.id.clone())
+            .collect()
+    }
+
+    /// Interval of each target by id after sharing out the budget, and
+    /// whether the budget stretched it
+    fn intervals(
+        &self,
+        targets: &[PingTarget],
+        default_interval: Duration,
+    ) -> HashMap<String, (Duration, bool)> {
+        let rounds = self.rounds(targets);
+        let natural: Vec<Duration> = targets
+            .iter()
+            .map(|target| self.interval_of(target, rounds, default_interval))
+            .collect();
+        let Some(budget) = self.budget.filter(|_| !targets.is_empty()) else {
+            return targets
+                .iter()
+                .zip(natural)
+                .map(|(target, interval)| (target.id.clone(), (interval, false)))
+                .collect();
+        };
+
+        let window = BUDGET_WINDOW.as_secs_f64();
+        let wanted: Vec<f64> = natural
+            .iter()
+            .map(|interval| window / interval.as_secs_f64().max(1e-3))
+            .collect();
+        // Everyone gets a little, so no target goes unprobed
+        let share = (budget as f64 / targets.len() as f64).min(1.0);
+        let mut granted: Vec<f64> = wanted.iter().map(|wanted| wanted.min(share)).collect();
+        let mut remaining = budget as f64 - granted.iter().sum::<f64>();
+        // The rest tops targets up by priority; the sort keeps list order
+        let mut order: Vec<usize> = (0..targets.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(targets[i].priority));
+        for i in order {
+            let top_up = (wanted[i] - granted[i]).min(remaining).max(0.0);
+            granted[i] += top_up;
+            remaining -= top_up;
+        }
+
+        targets
+            .iter()
+            .enumerate()
+            .map(|(i, target)| {
+                let budgeted = if granted[i] < wanted[i] {
+                    (Duration::from_secs_f64(window / granted[i]), true)
+                } else {
+                    (natural[i], false)
+                };
+                (target.id.clone(), budgeted)
+            })
+            .collect()
+    }
+
+    /// Whether a target takes turns with others
+    fn in_rotation(&self, target: &PingTarget) -> bool {
+        self.round_robin.is_some() && target.interval_ms.is_none()
+    }
+
+    /// Intervals it takes the rotation to probe every member once
+    fn rounds(&self, targets: &[PingTarget]) -> u32 {
+        let Some(batch) = self.round_robin else {
+            return 1;
+        };
+        let members = targets.iter().filter(|t| self.in_rotation(t)).count();
+        ((members + batch - 1) / batch).max(1) as u32
+    }
+
+    fn interval_of(
+        &self,
+        target: &PingTarget,
+        rounds: u32,
+        default_interval: Duration,
+    ) -> Duration {
+        if self.in_rotation(target) {
+            default_interval * rounds
+        } else {
+            target_interval(target, default_interval)
+        }
+    }
+
+    /// Start at most `budget` probes in any minute, or any number if `None`
+    ///
+    /// A budget of zero is treated as one probe a minute.
+    pub fn set_budget(&mut self, budget: Option<u32>) {
+        let budget = budget.map(|budget| budget.max(1));
+        if budget != self.budget {
+            self.budget = budget;
+            self.throttled_targets.clear();
+        }
+    }
+
+    /// Randomize each gap between fires by up to `jitter` either way
+    pub fn set_jitter(&mut self, jitter: Duration) {
+        self.jitter = jitter;
+    }
+
+    /// Targets due to fire at `now`, each rescheduled one interval later
+    ///
+    /// Each returned target counts as outstanding until [`Self::finish`].
+    pub fn take_due(
+        &mut self,
+        targets: &[PingTarget],
+        default_interval: Duration,
+        now: Instant,
+    ) -> Vec<PingTarget> {
+        while self
+            .recent_fires
+            .front()
+            .is_some_and(|fired| now.saturating_duration_since(*fired) >= BUDGET_WINDOW)
+        {
+            self.recent_fires.pop_front();
+        }
+
+        let intervals = self.intervals(targets, default_interval);
+        let mut due = Vec::new();
+        for target in targets {
+            let Some(&(interval, stretched)) = intervals.get(&target.id) else {
+                continue;
+            };
+            let Some(next_fire) = self.next_fire.get_mut(&target.id) else {
+                continue;
+            };
+            if *next_fire > now {
+                continue;
+            }
+
+            // Keep the original phase unless we fell a whole interval behind
+            let gap = jittered(interval, self.jitter, &mut self.rng);
+            *next_fire += gap;
+            if *next_fire <= now {
+                *next_fire = now + gap;
+            }
+
+            let outstanding = self.outstanding.entry(target.id.clone()).or_insert(0);
+            if self.max_outstanding.is_some_and(|max| *outstanding >= max) {
+                self.skipped += 1;
+                continue;
+            }
+            // Spacing keeps within the budget, but phases may still bunch up
+            let over_budget = self
+                .budget
+                .is_some_and(|budget| self.recent_fires.len() >= budget as usize);
+            if (stretched || over_budget) && self.throttled_targets.insert(target.id.clone()) {
+                self.throttled += 1;
+            }
+            if over_budget {
+                continue;
+            }
+            *outstanding += 1;
+            self.recent_fires.push_back(now);
+            due.push(target.clone());
+        }
+        due
+    }
+
+    /// Mark a probe taken with `take_due` as finished
+    pub fn finish(&mut self, target_id: &str) {
+        if let Some(outstanding) = self.outstanding.get_mut(target_id) {
+            *outstanding = outstanding.saturating_sub(1);
+        }
+    }
+
+    /// Probes outstanding for a target
+    pub fn outstanding(&self, target_id: &str) -> u32 {
+        self.outstanding.get(target_id).copied().unwrap_or(0)
+    }
+
+    /// Fires skipped because the target was at the cap
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    /// Targets slowed down or dropped to stay within the budget, counted
+    /// once per target each time the budget changes
+    pub fn throttled(&self) -> u64 {
+        self.throttled
+    }
+
+    /// Probes started in the minute before `now`
+    pub fn fires_last_minute(&self, now: Instant) -> u32 {
+        self.recent_fires
+            .iter()
+            .filter(|fired| now.saturating_duration_since(**fired) < BUDGET_WINDOW)
+            .count() as u32
+    }
+
+    /// Time until the next target is due, if any are scheduled
+    pub fn time_until_next(&self, now: Instant) -> Option<Duration> {
+        self.next_fire
+            .values()
+            .min()
+            .map(|next| next.saturating_duration_since(now))
+    }
+
+    /// Time until each target's next fire
+    pub fn offsets(&self, now: Instant) -> HashMap<String, Duration> {
+        self.next_fire
+            .iter()
+            .map(|(id, next)| (id.clone(), next.saturating_duration_since(now)))
+            .collect()
+    }
+
+    /// Freeze the schedule, remembering how far off each target's next fire is
+    pub fn pause(&mut self, now: Instant) {
+        if self.paused.is_none() {
+            self.paused = Some(self.offsets(now));
+        }
+    }
+
+    /// Continue a paused schedule from `now`, keeping each target's offset
+    pub fn resume(&mut self, now: Instant) {
+        if let Some(offsets) = self.paused.take() {
+            self.next_fire = offsets
+                .into_iter()
+                .map(|(id, offset)| (id, now + offset))
+                .collect();
+        }
+    }
+
+    /// Number of targets on the schedule
+    pub fn len(&self) -> usize {
+        self.next_fire.len()
+    }
+
+    /// Whether no targets are scheduled
+    pub fn is_empty(&self) -> bool {
+        self.next_fire.is_empty()
+    }
+
+    /// Forget all targets, e.g. when monitoring stops
+    pub fn clear(&mut self) {
+        self.next_fire.clear();
+        self.paused = None;
+        self.outstanding.clear();
+        self.recent_fires.clear();
+    }
+}
+
+/// Interval at which a target is probed
+pub fn target_interval(target: &PingTarget, default_interval: Duration) -> Duration {
+    target
+        .interval_ms
+        .map(Duration::from_millis)
+        .unwrap_or(default_interval)
+}
+
+/// `interval` moved by a uniform random offset within `jitter` either way
+///
+/// The jitter is capped at the interval, so gaps never go negative.
+fn jittered(interval: Duration, jitter: Duration, rng: &mut u64) -> Duration {
+    let jitter = jitter.min(interval);
+    if jitter.is_zero() {
+        return interval;
+    }
+    let span = jitter.as_nanos() as u64 * 2;
+    let offset = next_random(rng) % (span + 1);
+    interval - jitter + Duration::from_nanos(offset)
+}
+
+/// Next value of a xorshift generator, seeded randomly on first use
+fn next_random(state: &mut u64) -> u64 {
+    if *state == 0 {
+        *state = RandomState::new().build_hasher().finish() | 1;
+    }
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn targets(count: usize) -> Vec<PingTarget> {
+        (0..count)
+            .map(|i| PingTarget::new(format!("10.0.0.{}", i), format!("Host {}", i)))
+            .collect()
+    }
+
+    #[test]
+    fn test_new_targets_are_staggered() {
+        let targets = targets(4);
+        let interval = Duration::from_millis(1000);
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new();
+        scheduler.sync(&targets, interval, start);
+
+        let offsets = scheduler.offsets(start);
+        let mut millis: Vec<u128> = offsets.values().map(|d| d.as_millis()).collect();
+        millis.sort();
+        assert_eq!(millis, vec![0, 250, 500, 750]);
+
+        assert_eq!(scheduler.take_due(&targets, interval, start).len(), 1);
+        let due = scheduler.take_due(&targets, interval, start + Duration::from_millis(500));
+        assert_eq!(due.len(), 2);
+    }
+
+    #[test]
+    fn test_resume_keeps_relative_offsets() {
+        let targets = targets(3);
+        let interval = Duration::from_millis(900);
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new();
+        scheduler.sync(&targets, interval, start);
+        scheduler.take_due(&targets, interval, start + Duration::from_millis(400));
+
+        let paused_at = start + Duration::from_millis(450);
+        let before = scheduler.offsets(paused_at);
+        scheduler.pause(paused_at);
+
+        // Resume much later: offsets are relative to the resume time, not "now"
+        let resumed_at = paused_at + Duration::from_secs(60);
+        scheduler.resume(resumed_at);
+        scheduler.sync(&targets, interval, resumed_at);
+        assert_eq!(scheduler.offsets(resumed_at), before);
+
+        let due = scheduler.take_due(&targets, interval, resumed_at);
+        assert!(due.is_empty());
+    }
+
+    #[test]
+    fn test_fire_all_now_keeps_stagger_afterwards() {
+        let targets = targets(4);
+        let interval = Duration::from_millis(1000);
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new();
+        scheduler.fire_all_now(&targets, interval, start);
+
+        assert_eq!(scheduler.take_due(&targets, interval, start).len(), 4);
+        let mut millis: Vec<u128> = scheduler
+            .offsets(start)
+            .values()
+            .map(|d| d.as_millis())
+            .collect();
+        millis.sort();
+        assert_eq!(millis, vec![250, 500, 750, 1000]);
+    }
+
+    #[test]
+    fn test_overlapping_probes_are_capped_per_target() {
+        let targets = targets(1);
+        let id = &targets[0].id;
+        let interval = Duration::from_millis(1000);
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new();
+        scheduler.set_max_outstanding(Some(2));
+        scheduler.sync(&targets, interval, start);
+
+        // A 3s timeout: nothing finishes, yet fires keep their schedule
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        assert_eq!(scheduler.take_due(&targets, interval, at(0)).len(), 1);
+        assert_eq!(scheduler.take_due(&targets, interval, at(1)).len(), 1);
+        assert_eq!(scheduler.outstanding(id), 2);
+
+        // At the cap the fire is skipped, not delayed
+        assert!(scheduler.take_due(&targets, interval, at(2)).is_empty());
+        assert_eq!(scheduler.skipped(), 1);
+        assert_eq!(scheduler.offsets(at(2))[id], Duration::from_secs(1));
+
+        // Once the first probe times out, the next fire goes ahead on time
+        scheduler.finish(id);
+        assert_eq!(scheduler.take_due(&targets, interval, at(3)).len(), 1);
+        assert_eq!(scheduler.outstanding(id), 2);
+    }
+
+    #[test]
+    fn test_jittered_gaps_stay_within_interval_plus_minus_jitter() {
+        let targets = targets(1);
+        let interval = Duration::from_millis(1000);
+        let jitter = Duration::from_millis(200);
+        let mut now = Instant::now();
+        let mut scheduler = Scheduler::new();
+        scheduler.set_jitter(jitter);
+        scheduler.sync(&targets, interval, now);
+
+        let mut gaps = Vec::new();
+        for _ in 0..200 {
+            assert_eq!(scheduler.take_due(&targets, interval, now).len(), 1);
+            scheduler.finish(&targets[0].id);
+            let gap = scheduler.time_until_next(now).unwrap();
+            assert!(
+                gap >= interval - jitter && gap <= interval + jitter,
+                "{:?}",
+                gap
+            );
+            gaps.push(gap);
+            now += gap;
+        }
+
+        // Gaps vary rather than repeating one offset
+        gaps.sort();
+        assert!(gaps[gaps.len() - 1] - gaps[0] > jitter);
+
+        // Without jitter the schedule stays exactly periodic
+        scheduler.set_jitter(Duration::ZERO);
+        scheduler.take_due(&targets, interval, now);
+        assert_eq!(scheduler.time_until_next(now), Some(interval));
+    }
+
+    #[test]
+    fn test_round_robin_visits_each_target_once_per_round() {
+        let mut targets = targets(5);
+        let interval = Duration::from_millis(1000);
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new();
+        scheduler.set_round_robin(Some(2));
+        scheduler.sync(&targets, interval, start);
+
+        // Five targets two at a time take three cycles per round
+        let cycle = |n: u32| start + interval * n;
+        let mut visits: HashMap<String, u32> = HashMap::new();
+        let mut batches = Vec::new();
+        for n in 0..6 {
+            let due = scheduler.take_due(&targets, interval, cycle(n));
+            for target in &due {
+                *visits.entry(target.id.clone()).or_insert(0) += 1;
+                scheduler.finish(&target.id);
+            }
+            batches.push(due.len());
+        }
+        assert_eq!(batches, vec![2, 2, 1, 2, 2, 1]);
+        assert_eq!(visits.len(), 5);
+        assert!(visits.values().all(|&count| count == 2));
+        assert_eq!(
+            scheduler.effective_interval(&targets[0], &targets, interval),
+            Some(interval * 3)
+        );
+
+        // A target with its own interval stays out of the rotation
+        targets[4].interval_ms = Some(500);
+        assert_eq!(
+            scheduler.effective_interval(&targets[0], &targets, interval),
+            Some(interval * 2)
+        );
+        assert_eq!(
+            scheduler.effective_interval(&targets[4], &targets, interval),
+            Some(Duration::from_millis(500))
+        );
+    }
+
+    #[test]
+    fn test_budget_keeps_probes_per_minute_under_cap() {
+        // Five targets every second want 300 probes a minute
+        let targets = targets(5);
+        let interval = Duration::from_millis(1000);
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new();
+        scheduler.set_budget(Some(100));
+        scheduler.sync(&targets, interval, start);
+
+        let mut first_minute: HashMap<String, u32> = HashMap::new();
+        for tick in 0..1200 {
+            let now = start + Duration::from_millis(100) * tick;
+            for target in scheduler.take_due(&targets, interval, now) {
+                if tick < 600 {
+                    *first_minute.entry(target.id.clone()).or_insert(0) += 1;
+                }
+                scheduler.finish(&target.id);
+            }
+            assert!(scheduler.fires_last_minute(now) <= 100);
+        }
+        assert!(first_minute.values().sum::<u32>() <= 100);
+
+        // Every target gets one a minute, then the rest goes in list order:
+        // the first target keeps its rate and the second gets what is left
+        let count = |i: usize| first_minute.get(&targets[i].id).copied().unwrap_or(0);
+        assert_eq!(count(0), 60);
+        assert!((36..=37).contains(&count(1)));
+        assert_eq!(count(4), 1);
+        assert_eq!(
+            scheduler.effective_interval(&targets[4], &targets, interval),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            scheduler.stretched_targets(&targets, interval),
+            targets[1..].iter().map(|target| target.id.clone()).collect::<Vec<_>>()
+        );
+        // Each throttled target counts once, however often it fired
+        assert_eq!(scheduler.throttled(), 4);
+    }
+
+    #[test]
+    fn test_budget_goes_to_higher_priorities_first() {
+        let mut targets = targets(3);
+        targets[2].priority = 1;
+        let interval = Duration::from_millis(1000);
+        let mut scheduler = Scheduler::new();
+        scheduler.set_budget(Some(91));
+
+        // One each, then the high-priority target is topped up to its full
+        // rate and the first target gets the rest
+        let effective = |scheduler: &Scheduler, i: usize| {
+            scheduler.effective_interval(&targets[i], &targets, interval)
+        };
+        assert_eq!(effective(&scheduler, 2), Some(Duration::from_millis(1000)));
+        assert_eq!(effective(&scheduler, 0), Some(Duration::from_millis(2000)));
+        assert_eq!(effective(&scheduler, 1), Some(Duration::from_secs(60)));
+
+        // A new budget counts throttled targets again
+        let start = Instant::now();
+        scheduler.sync(&targets, interval, start);
+        for tick in 0..100 {
+            let now = start + Duration::from_millis(100) * tick;
+            for target in scheduler.take_due(&targets, interval, now) {
+                scheduler.finish(&target.id);
+            }
+        }
+        assert_eq!(scheduler.throttled(), 2);
+        scheduler.set_budget(Some(91));
+        scheduler.set_budget(Some(61));
+        for tick in 100..200 {
+            let now = start + Duration::from_millis(100) * tick;
+            for target in scheduler.take_due(&targets, interval, now) {
+                scheduler.finish(&target.id);
+            }
+        }
+        assert_eq!(scheduler.throttled(), 4);
+    }
+
+    #[test]
+    fn test_zero_budget_allows_one_probe_a_minute() {
+        let targets = targets(3);
+        let interval = Duration::from_millis(1000);
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new();
+        scheduler.set_budget(Some(0));
+        scheduler.sync(&targets, interval, start);
+
+        let mut fired = 0;
+        for tick in 0..600 {
+            let now = start + Duration::from_millis(100) * tick;
+            for target in scheduler.take_due(&targets, interval, now) {
+                fired += 1;
+                scheduler.finish(&target.id);
+            }
+            assert!(scheduler.fires_last_minute(now) <= 1);
+        }
+        assert_eq!(fired, 1);
+        assert!(targets
+            .iter()
+            .all(|target| scheduler.effective_interval(target, &targets, interval).is_some()));
+    }
+}
@@ -0,0 +1,152 @@
+use crate::alerts::{ConnectivityEvent, OutageEscalation, QuorumAlertEvent, SoundAlert};
+use crate::events;
+use crate::logging::LogHealthEvent;
+use crate::metrics::PushHealthEvent;
+use crate::models::{
+    AlarmInfo, AppConfig, AppSnapshot, BackendBenchmark, ConfigIssue, Correlation, CsvExportOptions,
+    DiagnosticReport, HourStat, IcmpCapability, LogCompaction, LoggedSession, MemoryPressure,
+    Outage, PathReport, PeriodComparison, PingResult, PingStatistics, PingTarget, ProbeCounters,
+    ProbeGroup, QualityPoint, ResolvedAddress, ResourceStats, SchedulerStats, SelfTestResult,
+    SessionMarker, SessionSummary, StatField, StatFilter, TargetAdded, TargetError,
+    TargetFirstResult, TargetSummary, TargetUpdate,
+};
+use crate::stats::HistogramBucket;
+use schemars::{schema_for, JsonSchema};
+use serde::Serialize;
+
+/// JSON schema for an event or command payload
+#[derive(Debug, Clone, Serialize)]
+pub struct NamedSchema {
+    pub name: String,
+    pub schema: serde_json::Value,
+}
+
+/// Schemas the frontend can validate against or generate types from
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaCatalog {
+    /// Payload schema per emitted event name
+    pub events: Vec<NamedSchema>,
+    /// Schemas of types passed to and returned from commands
+    pub types: Vec<NamedSchema>,
+}
+
+fn named<T: JsonSchema>(name: &str) -> NamedSchema {
+    NamedSchema {
+        name: name.to_string(),
+        schema: serde_json::to_value(schema_for!(T)).unwrap_or_default(),
+    }
+}
+
+/// Names of the top-level fields of a payload type
+pub fn field_names<T: JsonSchema>() -> Vec<String> {
+    schema_for!(T)
+        .schema
+        .object
+        .map(|object| object.properties.into_keys().collect())
+        .unwrap_or_default()
+}
+
+/// Payload schema for each event in [`events::EVENT_NAMES`]
+pub fn event_schemas() -> Vec<NamedSchema> {
+    vec![
+        named::<PingResult>(events::PING_RESULT),
+        named::<TargetFirstResult>(events::TARGET_FIRST_RESULT),
+        named::<Vec<PingStatistics>>(events::STATS_UPDATE),
+        named::<LogHealthEvent>(events::LOGGING_DEGRADED),
+        named::<LogHealthEvent>(events::LOGGING_RESTORED),
+        named::<ConnectivityEvent>(events::INTERNET_DOWN),
+        named::<ConnectivityEvent>(events::INTERNET_UP),
+        named::<QuorumAlertEvent>(events::QUORUM_ALERT),
+        named::<QuorumAlertEvent>(events::QUORUM_CLEARED),
+        named::<OutageEscalation>(events::OUTAGE_ESCALATED),
+        named::<SoundAlert>(events::PLAY_SOUND),
+        named::<()>(events::MONITORING_DISABLED),
+        named::<SessionMarker>(events::SESSION_MARKER),
+        named::<PushHealthEvent>(events::PUSH_DEGRADED),
+        named::<PushHealthEvent>(events::PUSH_RESTORED),
+        named::<MemoryPressure>(events::MEMORY_PRESSURE),
+    ]
+}
+
+/// Schemas of the main command argument and return types
+pub fn type_schemas() -> Vec<NamedSchema> {
+    vec![
+        named::<AlarmInfo>("AlarmInfo"),
+        named::<AppConfig>("AppConfig"),
+        named::<AppSnapshot>("AppSnapshot"),
+        named::<BackendBenchmark>("BackendBenchmark"),
+        named::<ConfigIssue>("ConfigIssue"),
+        named::<Correlation>("Correlation"),
+        named::<CsvExportOptions>("CsvExportOptions"),
+        named::<DiagnosticReport>("DiagnosticReport"),
+        named::<HistogramBucket>("HistogramBucket"),
+        named::<HourStat>("HourStat"),
+        named::<IcmpCapability>("IcmpCapability"),
+        named::<LogCompaction>("LogCompaction"),
+        named::<LoggedSession>("LoggedSession"),
+        named::<Outage>("Outage"),
+        named::<PathReport>("PathReport"),
+        named::<PeriodComparison>("PeriodComparison"),
+        named::<PingResult>("PingResult"),
+        named::<PingStatistics>("PingStatistics"),
+        named::<PingTarget>("PingTarget"),
+        named::<ProbeCounters>("ProbeCounters"),
+        named::<ProbeGroup>("ProbeGroup"),
+        named::<QualityPoint>("QualityPoint"),
+        named::<ResolvedAddress>("ResolvedAddress"),
+        named::<ResourceStats>("ResourceStats"),
+        named::<SchedulerStats>("SchedulerStats"),
+        named::<SelfTestResult>("SelfTestResult"),
+        named::<SessionSummary>("SessionSummary"),
+        named::<StatField>("StatField"),
+        named::<StatFilter>("StatFilter"),
+        named::<TargetAdded>("TargetAdded"),
+        named::<TargetError>("TargetError"),
+        named::<TargetSummary>("TargetSummary"),
+        named::<TargetUpdate>("TargetUpdate"),
+    ]
+}
+
+/// All schemas
+pub fn schema_catalog() -> SchemaCatalog {
+    SchemaCatalog {
+        events: event_schemas(),
+        types: type_schemas(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sources that emit events
+    const EMITTING_SOURCES: &[&str] = &[
+        include_str!("state.rs"),
+        include_str!("monitor.rs"),
+        include_str!("commands.rs"),
+        include_str!("alerts/connectivity.rs"),
+        include_str!("logging/health.rs"),
+        include_str!("metrics/push.rs"),
+    ];
+
+    #[test]
+    fn test_every_event_has_a_schema() {
+        let schemas = event_schemas();
+        for name in events::EVENT_NAMES {
+            assert!(
+                schemas.iter().any(|s| s.name == *name),
+                "no schema for {}",
+                name
+            );
+        }
+        assert_eq!(schemas.len(), events::EVENT_NAMES.len());
+
+        // Events must be emitted through the named constants, never a literal
+        for source in EMITTING_SOURCES {
+            assert!(!source.contains(&format!("emit({}", '"')));
+        }
+
+        let ping_result = &schemas[0].schema;
+        assert!(ping_result["properties"]["latency_ms"].is_object());
+    }
+}
@@ -0,0 +1,173 @@
+use crate::logging::{create_logger, stream_results};
+use crate::models::{AppConfig, PingResult, PingTarget, SelfTestCheck, SelfTestResult};
+use crate::ping::PingBackend;
+use chrono::Utc;
+use std::path::Path;
+
+/// IPv4 loopback address probed by the self-test
+pub const SELF_TEST_IPV4: &str = "127.0.0.1";
+
+/// IPv6 loopback address probed by the self-test
+pub const SELF_TEST_IPV6: &str = "::1";
+
+/// Check the monitoring pipeline itself against loopback
+///
+/// Pings `127.0.0.1` and `::1` with `backend`, checks the reply was parsed
+/// into a latency, then writes the result with the configured log format
+/// into a scratch directory under `log_dir` and reads it back. Loopback
+/// never leaves the machine, so a failure here points at the app or its
+/// permissions rather than the network. IPv6 loopback is reported but not
+/// required, as some hosts disable IPv6.
+pub fn run_self_test(
+    backend: &dyn PingBackend,
+    config: &AppConfig,
+    log_dir: &Path,
+) -> SelfTestResult {
+    let ipv4 = probe(backend, SELF_TEST_IPV4);
+    let ipv6 = probe(backend, SELF_TEST_IPV6);
+
+    let checks = vec![
+        ping_check("ping_ipv4", &ipv4, true),
+        ping_check("ping_ipv6", &ipv6, false),
+        parse_check(&ipv4),
+        logging_check(&ipv4, config, log_dir),
+    ];
+
+    SelfTestResult {
+        passed: checks.iter().all(|check| check.passed || !check.required),
+        backend: config.backend,
+        checks,
+        ran_at: Utc::now(),
+    }
+}
+
+fn probe(backend: &dyn PingBackend, address: &str) -> PingResult {
+    let target = PingTarget::new(address.to_string(), "Self-test".to_string());
+    backend.ping(&target, 1)
+}
+
+fn ping_check(name: &str, result: &PingResult, required: bool) -> SelfTestCheck {
+    let detail = if result.success {
+        format!("{} replied", result.target)
+    } else {
+        format!(
+            "{} did not reply: {}",
+            result.target,
+            result.error.as_deref().unwrap_or("unknown error")
+        )
+    };
+    SelfTestCheck::new(name, result.success, required, detail)
+}
+
+fn parse_check(result: &PingResult) -> SelfTestCheck {
+    match result.latency_ms {
+        Some(latency) if result.success && latency.is_finite() && latency >= 0.0 => {
+            let detail = format!("Reply parsed as {:.3}ms", latency);
+            SelfTestCheck::new("parse", true, true, detail)
+        }
+        Some(latency) if result.success => {
+            let detail = format!("Reply parsed as an invalid latency {}", latency);
+            SelfTestCheck::new("parse", false, true, detail)
+        }
+        _ if result.success => SelfTestCheck::new("parse", false, true, "Reply had no latency"),
+        _ => SelfTestCheck::new("parse", false, true, "No reply to parse"),
+    }
+}
+
+fn logging_check(result: &PingResult, config: &AppConfig, log_dir: &Path) -> SelfTestCheck {
+    let scratch = log_dir.join(format!("self-test-{}", uuid::Uuid::new_v4()));
+    let outcome = write_and_read_back(result, config, &scratch);
+    if let Err(e) = std::fs::remove_dir_all(&scratch) {
+        log::debug!("Failed to remove {}: {}", scratch.display(), e);
+    }
+
+    match outcome {
+        Ok(true) => SelfTestCheck::new(
+            "logging",
+            true,
+            true,
+            format!(
+                "Wrote and read back a {:?} log in {}",
+                config.log_format,
+                log_dir.display()
+            ),
+        ),
+        Ok(false) => SelfTestCheck::new("logging", false, true, "Logged result was not read back"),
+        Err(e) => SelfTestCheck::new(
+            "logging",
+            false,
+            true,
+            format!("Failed to log in {}: {}", log_dir.display(), e),
+        ),
+    }
+}
+
+/// Log `result` into `dir` and whether it can be read back
+fn write_and_read_back(
+    result: &PingResult,
+    config: &AppConfig,
+    dir: &Path,
+) -> Result<bool, std::io::Error> {
+    {
+        let logger = create_logger(config, dir.to_path_buf())?;
+        logger.log(result)?;
+    }
+
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let found = stream_results(&path)?
+            .any(|logged| logged.target == result.target && logged.sequence == result.sequence);
+        if found {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::LogFormat;
+    use crate::ping::MockBackend;
+
+    fn log_dir() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("ping-self-test-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_self_test_passes_on_a_working_host() {
+        for log_format in [LogFormat::Json, LogFormat::Binary] {
+            let backend = MockBackend::new(vec![Some(0.05)]);
+            let config = AppConfig {
+                log_format,
+                ..AppConfig::default()
+            };
+            let dir = log_dir();
+
+            let result = run_self_test(&backend, &config, &dir);
+
+            assert!(result.passed, "{:?}", result.checks);
+            let names: Vec<&str> = result.checks.iter().map(|c| c.name.as_str()).collect();
+            assert_eq!(names, vec!["ping_ipv4", "ping_ipv6", "parse", "logging"]);
+            assert!(result.checks.iter().all(|check| check.passed));
+            // The scratch log is cleaned up
+            assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_self_test_fails_without_a_loopback_reply() {
+        // IPv4 fails, IPv6 replies
+        let backend = MockBackend::new(vec![None, Some(0.05)]);
+        let dir = log_dir();
+
+        let result = run_self_test(&backend, &AppConfig::default(), &dir);
+
+        assert!(!result.passed);
+        let passed: Vec<bool> = result.checks.iter().map(|c| c.passed).collect();
+        assert_eq!(passed, vec![false, true, false, true]);
+        assert_eq!(result.checks[2].detail, "No reply to parse");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,33 +1,263 @@
-use crate::logging::JsonLogger;
-use crate::models::{AppConfig, PingResult, PingState, PingStatistics, PingTarget};
-use crate::ping::Pinger;
-use crate::stats::StatsCalculator;
-use parking_lot::RwLock;
-use std::collections::VecDeque;
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicU32, Ordering};
-use tokio::sync::broadcast;
+use crate::alerts::{
+    spawn_escalation_action, spawn_failure_command, ConnectivityDetector, Escalations,
+    FailureActions, QuorumAlerts, SoundAlerts,
+};
+use crate::channels::{Broadcaster, Subscriber};
+use crate::events::{self, EventSink, EventSubscriptions};
+use crate::logging::{
+    compact_logs, create_logger, purge_target_logs, read_config, read_lifetime, read_snapshot,
+    read_switches, replace_summary_csv, write_config, write_lifetime, write_snapshot,
+    write_switches, JsonLogger, LogHealth,
+    LogHealthEvent, ResultLogger, SUMMARY_CSV_FILE,
+};
+use crate::metrics::{self, PushHealthEvent};
+use crate::models::{
+    AlarmInfo, AppConfig, AppSnapshot, BackendBenchmark, BackendKind, Baseline, DiagnosticReport,
+    LogCompaction, MemoryPressure, MonitorSwitches, Outage, PathReport, PingError, PingResult,
+    PingState, PingStatistics, PingTarget, ProbeCounters, ProbeGroup, ResolvedAddress,
+    ResourceStats, SchedulerStats, SelfTestResult, SessionBoundary, SessionBoundaryKind,
+    SessionMarker, SessionSummary, StatField, StatFilter, TargetAdded, TargetError,
+    TargetFirstResult, TargetSummary, TargetUpdate, TimeRange,
+};
+use crate::ping::{
+    benchmark_backend, parse_ping_output, DnsProbe, NativePinger, PathProbe, PingBackend, Pinger,
+    ProbeAccounting, ProbeRouter, ResolverCache, ResolvingBackend, SystemResolver,
+    MAX_BENCHMARK_ITERATIONS,
+};
+use crate::processing::{ResultPipeline, ResultProcessor};
+use crate::scheduler::Scheduler;
+use crate::selftest::run_self_test;
+use crate::stats::{
+    capture_baseline, deviation_from_baseline, deviation_from_expected, loss_ratio, query_stats,
+    run_diagnostic,
+    AlarmThresholds, AlarmTracker, ConfidenceThresholds, GapTracker, GroupTracker, HistogramBucket,
+    LatencyBounds, LifetimeTracker, OutageTracker, PathTracker, ResolvedIpTracker, StatsCalculator,
+    MAX_DIAGNOSTIC_SAMPLES, RECENT_LATENCY_WINDOW,
+};
+use crate::validation;
+use chrono::{DateTime, NaiveDate, Utc};
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Percent of `max_memory_mb` at which buffers start to shrink
+const MEMORY_PRESSURE_PERCENT: usize = 90;
+/// Results recorded between checks of `max_memory_mb`
+const MEMORY_CHECK_INTERVAL: u32 = 16;
+/// Fewest recent results kept under memory pressure
+const MIN_HISTORY_UNDER_PRESSURE: usize = 10;
+/// Smallest recent latency window under memory pressure
+const MIN_LATENCY_WINDOW: usize = 5;
+/// Pause between a target's probes in a diagnostic run
+const DIAGNOSTIC_SAMPLE_DELAY: Duration = Duration::from_millis(200);
+/// Pause between probes when capturing a baseline
+const BASELINE_PROBE_DELAY: Duration = Duration::from_millis(200);
+/// Time between saves of lifetime counts while monitoring, so a crash loses
+/// little
+const LIFETIME_SAVE_INTERVAL: Duration = Duration::from_secs(300);
 
 /// Application state shared across the application
 pub struct AppState {
     /// Current configuration
     pub config: RwLock<AppConfig>,
+    /// Processors applied to each result before it is recorded
+    pub pipeline: RwLock<ResultPipeline>,
     /// Statistics calculator
     pub stats: RwLock<StatsCalculator>,
-    /// JSON logger
-    pub logger: RwLock<Option<JsonLogger>>,
+    /// Ping counts kept across resets and restarts
+    pub lifetime: RwLock<LifetimeTracker>,
+    /// Where lifetime counters are saved, once loaded from there
+    pub lifetime_path: RwLock<Option<PathBuf>>,
+    /// Periodic saves of lifetime counts
+    pub lifetime_saves: DueTask,
+    /// Result logger
+    pub logger: RwLock<Option<Box<dyn ResultLogger>>>,
+    /// Health of the result logger, used to back off on write failures
+    pub log_health: Mutex<LogHealth>,
+    /// Detector for all internet targets failing together
+    pub connectivity: Mutex<ConnectivityDetector>,
+    /// When to run each target's failure command
+    pub failure_actions: Mutex<FailureActions>,
+    /// Which quorum alert rules currently hold
+    pub quorum_alerts: Mutex<QuorumAlerts>,
+    /// Escalation tiers reached by each target's ongoing outage
+    pub escalations: Mutex<Escalations>,
+    /// Which sound-enabled targets are down
+    pub sound_alerts: Mutex<SoundAlerts>,
+    /// Destination for frontend events
+    pub event_sink: RwLock<Option<Arc<dyn EventSink>>>,
+    /// Events the frontend listens to; others are not emitted
+    pub subscriptions: RwLock<EventSubscriptions>,
     /// Current ping state
     pub ping_state: RwLock<PingState>,
+    /// Statistics last sent in a `stats-update` event, per target
+    pub emitted_stats: Mutex<HashMap<String, PingStatistics>>,
+    /// Ids of targets whose first result was announced this session
+    pub first_results: Mutex<HashSet<String>>,
+    /// Time between consecutive results of each target
+    pub gaps: Mutex<GapTracker>,
+    /// Most recent outages (for the recent outages panel)
+    pub outages: RwLock<OutageTracker>,
+    /// Targets that are down, flapping, too slow or losing too many pings
+    pub alarms: RwLock<AlarmTracker>,
+    /// Combined reachability of probe groups
+    pub groups: RwLock<GroupTracker>,
+    /// Statistics of hostname targets per resolved IP
+    pub resolved_ips: RwLock<ResolvedIpTracker>,
     /// Recent ping results (for chart display)
     pub recent_results: RwLock<VecDeque<PingResult>>,
+    /// Cap on recent results below `max_history_size`, set under memory pressure
+    pub history_limit: RwLock<Option<usize>>,
+    /// Results recorded since the memory budget was last checked
+    pub memory_checks: AtomicU32,
     /// Sequence counter for pings
     pub sequence: AtomicU32,
     /// Channel to signal stop
-    pub stop_signal: RwLock<Option<broadcast::Sender<()>>>,
+    pub stop_signal: RwLock<Option<Broadcaster<()>>>,
+    /// Set while not running, so probes in flight stop retrying
+    pub probes_stopped: Arc<AtomicBool>,
+    /// Every recorded result, for live subscribers
+    pub results: RwLock<Broadcaster<PingResult>>,
+    /// Per-target probe schedule, kept across pause and resume
+    pub schedule: Mutex<Scheduler>,
+    /// When monitoring was paused, if it is paused
+    pub paused_at: Mutex<Option<DateTime<Utc>>>,
+    /// Identifies the current ping loop; older loops exit when it changes
+    pub loop_generation: AtomicU64,
+    /// Probes started but not yet finished
+    pub inflight: AtomicU32,
+    /// Pinger for the native backend, kept open to catch late replies
+    pub native: Arc<NativePinger>,
+    /// Resolved IPs of hostname targets, kept across backends
+    pub resolver_cache: Arc<ResolverCache>,
+    /// Sent/received bookkeeping shared with the native pinger
+    pub probe_accounting: Arc<Mutex<ProbeAccounting>>,
+    /// Target whose results alone are emitted live, if any
+    pub focused_target: RwLock<Option<String>>,
+    /// Master switch; no probes are sent while off
+    pub monitoring_enabled: AtomicBool,
+    /// Where the master switch and pause state are saved, once loaded
+    pub switches_path: RwLock<Option<PathBuf>>,
+    /// Where the configuration is saved, once loaded
+    pub config_path: RwLock<Option<PathBuf>>,
+    /// Set if the previous run exited paused, until `auto_start` takes it
+    pub launch_paused: AtomicBool,
+    /// Set once the app has shut down
+    pub shut_down: AtomicBool,
+    /// Per-hop statistics from path traces
+    pub paths: RwLock<PathTracker>,
+    /// Periodic path traces
+    pub path_probes: DueTask,
+    /// Periodic pushes to the Pushgateway
+    pub pushes: DueTask,
+    /// Health of the Pushgateway, used to back off while pushes fail
+    pub push_health: Mutex<LogHealth>,
+    /// Periodic rewrites of the summary CSV
+    pub summaries: DueTask,
+    /// Id of the current monitoring run, from start until stop
+    pub session_id: RwLock<Option<String>>,
+    /// When the current monitoring run started, or its statistics were reset
+    pub session_started_at: RwLock<Option<DateTime<Utc>>>,
+    /// End of the current run's startup grace, if it has one
+    pub startup_grace_until: RwLock<Option<DateTime<Utc>>>,
+    /// Label of the current session, e.g. "before ISP call"
+    pub session_label: RwLock<Option<String>>,
+    /// Label changes during the current session
+    pub session_markers: RwLock<Vec<SessionMarker>>,
+}
+
+/// Background task run every so often, one run at a time
+#[derive(Debug, Default)]
+pub struct DueTask {
+    last_started: Mutex<Option<Instant>>,
+    running: Arc<AtomicBool>,
+}
+
+impl DueTask {
+    /// Start a run if none is running and `interval` passed since the last
+    /// one started
+    pub fn take_due(&self, interval: Duration, now: Instant) -> Option<DueRun> {
+        let mut last_started = self.last_started.lock();
+        let due = last_started.map_or(true, |last| now.duration_since(last) >= interval);
+        if !due || self.running.swap(true, Ordering::SeqCst) {
+            return None;
+        }
+        *last_started = Some(now);
+        Some(DueRun(Arc::clone(&self.running)))
+    }
+}
+
+/// A started run of a [`DueTask`], finished when dropped, even if the run
+/// panicked
+#[derive(Debug)]
+pub struct DueRun(Arc<AtomicBool>);
+
+impl Drop for DueRun {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Counts a probe as in flight until dropped
+struct InflightGuard<'a>(&'a AtomicU32);
+
+impl<'a> InflightGuard<'a> {
+    fn new(counter: &'a AtomicU32) -> Self {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Self(counter)
+    }
+}
+
+impl Drop for InflightGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// The loop generation and session a cycle of probes was dispatched in
+///
+/// Results that come back after either changed belong to a run that is
+/// over, e.g. after a stop and restart, and are dropped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dispatch {
+    generation: u64,
+    session: Option<String>,
+}
+
+/// Releases a dispatched cycle's outstanding probes in the schedule when
+/// dropped, even if probing panicked
+struct OutstandingGuard<'a> {
+    state: &'a AppState,
+    targets: &'a [PingTarget],
+    session: &'a Option<String>,
+}
+
+impl Drop for OutstandingGuard<'_> {
+    fn drop(&mut self) {
+        // A new session has a fresh schedule that doesn't count these probes
+        if *self.state.session_id.read() == *self.session {
+            self.state.finish_targets(self.targets);
+        }
+    }
 }
 
 impl AppState {
     pub fn new() -> Self {
+        // Initialize logger
+        let logger = create_logger(&AppConfig::default(), JsonLogger::default_log_dir())
+            .map_err(|e| log::error!("Failed to create logger: {}", e))
+            .ok();
+
+        Self::with_logger(logger)
+    }
+
+    /// Create state that writes results to the given logger
+    pub fn with_logger(logger: Option<Box<dyn ResultLogger>>) -> Self {
         let config = AppConfig::default();
         let mut stats = StatsCalculator::new();
         
@@ -35,21 +265,128 @@ impl AppState {
         for target in &config.targets {
             stats.init_target(target);
         }
-        
-        // Initialize logger
-        let logger = JsonLogger::new(JsonLogger::default_log_dir())
-            .map_err(|e| log::error!("Failed to create logger: {}", e))
-            .ok();
-        
+
+        let sequence_start = config.sequence_start;
+        let broadcast_capacity = config.broadcast_capacity;
+        let probe_accounting = Arc::new(Mutex::new(ProbeAccounting::new()));
+        let native = Arc::new(NativePinger::new(config.timeout_ms, Arc::clone(&probe_accounting)));
+        let mut schedule = Scheduler::new();
+        Self::configure_schedule(&mut schedule, &config);
+
         Self {
             config: RwLock::new(config),
+            pipeline: RwLock::new(ResultPipeline::new()),
             stats: RwLock::new(stats),
+            lifetime: RwLock::new(LifetimeTracker::new()),
+            lifetime_path: RwLock::new(None),
+            lifetime_saves: DueTask::default(),
             logger: RwLock::new(logger),
+            log_health: Mutex::new(LogHealth::default()),
+            connectivity: Mutex::new(ConnectivityDetector::new()),
+            failure_actions: Mutex::new(FailureActions::new()),
+            quorum_alerts: Mutex::new(QuorumAlerts::new()),
+            escalations: Mutex::new(Escalations::new()),
+            sound_alerts: Mutex::new(SoundAlerts::new()),
+            event_sink: RwLock::new(None),
+            subscriptions: RwLock::new(EventSubscriptions::default()),
             ping_state: RwLock::new(PingState::Stopped),
+            emitted_stats: Mutex::new(HashMap::new()),
+            first_results: Mutex::new(HashSet::new()),
+            gaps: Mutex::new(GapTracker::new()),
+            outages: RwLock::new(OutageTracker::default()),
+            alarms: RwLock::new(AlarmTracker::new()),
+            groups: RwLock::new(GroupTracker::new()),
+            resolved_ips: RwLock::new(ResolvedIpTracker::new()),
             recent_results: RwLock::new(VecDeque::new()),
-            sequence: AtomicU32::new(0),
+            history_limit: RwLock::new(None),
+            memory_checks: AtomicU32::new(0),
+            sequence: AtomicU32::new(sequence_start),
             stop_signal: RwLock::new(None),
+            probes_stopped: Arc::new(AtomicBool::new(true)),
+            results: RwLock::new(Broadcaster::new("ping results", broadcast_capacity)),
+            schedule: Mutex::new(schedule),
+            paused_at: Mutex::new(None),
+            loop_generation: AtomicU64::new(0),
+            inflight: AtomicU32::new(0),
+            native,
+            resolver_cache: Arc::new(ResolverCache::new()),
+            probe_accounting,
+            focused_target: RwLock::new(None),
+            monitoring_enabled: AtomicBool::new(true),
+            switches_path: RwLock::new(None),
+            config_path: RwLock::new(None),
+            launch_paused: AtomicBool::new(false),
+            shut_down: AtomicBool::new(false),
+            paths: RwLock::new(PathTracker::new()),
+            path_probes: DueTask::default(),
+            pushes: DueTask::default(),
+            push_health: Mutex::new(LogHealth::default()),
+            summaries: DueTask::default(),
+            session_id: RwLock::new(None),
+            session_started_at: RwLock::new(None),
+            startup_grace_until: RwLock::new(None),
+            session_label: RwLock::new(None),
+            session_markers: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Set the destination for frontend events
+    pub fn set_event_sink(&self, sink: Arc<dyn EventSink>) {
+        *self.event_sink.write() = Some(sink);
+    }
+
+    /// Emit an event to the frontend, if a sink is attached
+    pub fn emit<S: Serialize + ?Sized>(&self, event: &str, payload: &S) {
+        if !self.is_subscribed(event) {
+            return;
+        }
+        let sink = self.event_sink.read().clone();
+        let Some(sink) = sink else {
+            return;
+        };
+
+        let result = serde_json::to_value(payload)
+            .map_err(|e| e.to_string())
+            .and_then(|payload| sink.emit_value(event, payload));
+        match result {
+            Ok(_) => log::debug!("Emitted {} event", event),
+            Err(e) => log::error!("Failed to emit {} event: {}", event, e),
+        }
+    }
+
+    /// Emit an event carrying a result, keeping only `result_emit_fields`
+    ///
+    /// The result is the payload itself, or the payload's field `nested`.
+    pub fn emit_projected<S: Serialize>(&self, event: &str, payload: &S, nested: Option<&str>) {
+        let fields = self.config.read().result_emit_fields.clone();
+        let Some(fields) = fields else {
+            return self.emit(event, payload);
+        };
+        if !self.is_subscribed(event) {
+            return;
+        }
+        let mut value = match serde_json::to_value(payload) {
+            Ok(value) => value,
+            Err(e) => return log::error!("Failed to emit {} event: {}", event, e),
+        };
+        let result = match nested {
+            Some(field) => value.get_mut(field),
+            None => Some(&mut value),
+        };
+        if let Some(result) = result {
+            events::project_fields(result, &fields);
         }
+        self.emit(event, &value);
+    }
+
+    /// Emit only these events from now on
+    pub fn set_event_subscriptions(&self, names: Vec<String>) -> Result<(), String> {
+        self.subscriptions.write().set(names)
+    }
+
+    /// Whether the frontend listens to `event`, so its payload is worth building
+    pub fn is_subscribed(&self, event: &str) -> bool {
+        self.subscriptions.read().contains(event)
     }
 
     /// Get the next sequence number
@@ -57,195 +394,3555 @@ impl AppState {
         self.sequence.fetch_add(1, Ordering::SeqCst)
     }
 
-    /// Reset sequence counter
+    /// Reset sequence counter to the configured starting value
     pub fn reset_sequence(&self) {
-        self.sequence.store(0, Ordering::SeqCst);
+        let start = self.config.read().sequence_start;
+        self.sequence.store(start, Ordering::SeqCst);
     }
 
-    /// Add a ping result
-    pub fn add_result(&self, result: PingResult) {
-        // Update statistics
-        {
+    /// Run every result after those already registered through `processor`
+    pub fn add_result_processor(&self, processor: Box<dyn ResultProcessor>) {
+        self.pipeline.write().register(processor);
+    }
+
+    /// Add a ping result, returning it as recorded
+    pub fn add_result(&self, result: PingResult) -> PingResult {
+        let dispatched = result.timestamp;
+        self.add_dispatched_result(result, dispatched)
+    }
+
+    /// Add the result of a probe dispatched at `dispatched`, which gaps
+    /// between probes are measured from
+    fn add_dispatched_result(&self, result: PingResult, dispatched: DateTime<Utc>) -> PingResult {
+        let mut result = self.pipeline.read().process(result);
+
+        // Grade the latency for gauges
+        let (bounds, embed_label, thresholds, log_enabled) = {
+            let config = self.config.read();
+            let log_enabled = !config
+                .targets
+                .iter()
+                .any(|t| t.address == result.target && !t.log_enabled);
+            (
+                LatencyBounds::new(config.latency_warn_ms, config.latency_crit_ms),
+                config.embed_session_label,
+                AlarmThresholds {
+                    down_after: config.failure_action_cycles,
+                    latency_crit_ms: config.latency_crit_ms,
+                    loss_percent: config.loss_alarm_percent,
+                },
+                log_enabled,
+            )
+        };
+        result.latency_normalized = Some(bounds.normalize(result.latency_ms));
+        if embed_label {
+            result.session_label = self.session_label.read().clone();
+        }
+        result.session_id = self.session_id.read().clone();
+        result.gap_since_previous_ms = self.gaps.lock().observe(&result, dispatched);
+        result.startup_grace = !result.success
+            && self
+                .startup_grace_until
+                .read()
+                .is_some_and(|until| result.timestamp < until);
+        if result.startup_grace {
+            log::debug!("{} failed during the startup grace", result.target);
+        }
+
+        // Update statistics and the chart buffer together, so snapshots see
+        // both or neither. Startup-grace failures are charted but not counted.
+        let max_size = self.history_limit();
+        let smoothed_loss = {
+            let mut recent = self.recent_results.write();
             let mut stats = self.stats.write();
-            stats.update(&result);
+            if !result.startup_grace {
+                stats.update(&result);
+            }
+            recent.push_back(result.clone());
+            while recent.len() > max_size {
+                recent.pop_front();
+            }
+            stats.smoothed_loss(&result.target)
+        };
+        if !result.startup_grace {
+            self.alarms.write().observe(&result, smoothed_loss, &thresholds);
+            self.lifetime.write().record(&result);
+            self.outages.write().update(&result);
+            let config = self.config.read();
+            self.groups.write().observe(&config.groups, &config.targets, &result);
+            if config.track_per_resolved_ip {
+                self.resolved_ips.write().observe(&result);
+            }
         }
         
         // Log the result
-        {
-            let logger = self.logger.read();
-            if let Some(ref logger) = *logger {
-                if let Err(e) = logger.log(&result) {
+        if log_enabled {
+            self.log_result(&result);
+        }
+
+        self.results.read().send(result.clone());
+        self.enforce_memory_budget();
+
+        result
+    }
+
+    /// Results the chart buffer may hold
+    fn history_limit(&self) -> usize {
+        let max_history = self.config.read().max_history_size;
+        self.history_limit.read().map_or(max_history, |limit| limit.min(max_history))
+    }
+
+    /// Shrink the buffers once their estimated size nears `max_memory_mb`
+    ///
+    /// Checked every `MEMORY_CHECK_INTERVAL` results. The chart buffer and
+    /// the recent latency windows are halved until the estimate is back
+    /// under the threshold or they reach their floors, and a
+    /// `memory-pressure` event reports their new sizes. They stay lowered
+    /// until the budget changes.
+    fn enforce_memory_budget(&self) {
+        let Some(budget_mb) = self.config.read().max_memory_mb else {
+            return;
+        };
+        if self.memory_checks.fetch_add(1, Ordering::Relaxed) % MEMORY_CHECK_INTERVAL != 0 {
+            return;
+        }
+        let budget_bytes = (budget_mb as usize).saturating_mul(1024 * 1024);
+        let threshold = budget_bytes / 100 * MEMORY_PRESSURE_PERCENT;
+        let used_bytes = self.get_resource_stats().approx_total_bytes;
+        if used_bytes < threshold {
+            return;
+        }
+
+        let (history_limit, latency_window) = loop {
+            let (history_limit, latency_window, shrunk) = self.shrink_buffers();
+            // Once at the floors, the rest is histograms and logs
+            if !shrunk || self.get_resource_stats().approx_total_bytes < threshold {
+                break (history_limit, latency_window);
+            }
+        };
+
+        log::warn!(
+            "Approaching memory budget ({} of {} bytes), keeping {} results and {} latencies",
+            used_bytes,
+            budget_bytes,
+            history_limit,
+            latency_window
+        );
+        self.emit(
+            events::MEMORY_PRESSURE,
+            &MemoryPressure {
+                budget_bytes,
+                used_bytes,
+                history_limit,
+                latency_window,
+            },
+        );
+    }
+
+    /// Halve the chart buffer and the recent latency windows, down to floors
+    ///
+    /// Returns their new sizes and whether either got smaller.
+    fn shrink_buffers(&self) -> (usize, usize, bool) {
+        let (history_limit, trimmed) = {
+            let mut recent = self.recent_results.write();
+            let limit = (recent.len() / 2).max(MIN_HISTORY_UNDER_PRESSURE);
+            let excess = recent.len().saturating_sub(limit);
+            recent.drain(..excess);
+            recent.shrink_to_fit();
+            *self.history_limit.write() = Some(limit);
+            (limit, excess > 0)
+        };
+
+        let mut stats = self.stats.write();
+        let current = stats.recent_window();
+        let window = (current / 2).max(MIN_LATENCY_WINDOW);
+        if window < current {
+            stats.set_recent_window(window);
+            self.groups.write().set_recent_window(window);
+            self.resolved_ips.write().set_recent_window(window);
+        }
+        (history_limit, window, trimmed || window < current)
+    }
+
+    /// Write a result to the log, backing off while writes keep failing
+    fn log_result(&self, result: &PingResult) {
+        let now = Instant::now();
+        let mut health = self.log_health.lock();
+        if !health.should_attempt(now) {
+            return;
+        }
+
+        let outcome = match *self.logger.read() {
+            Some(ref logger) => logger.log(result),
+            None => return,
+        };
+
+        let event = match outcome {
+            Ok(()) => health.record_success(),
+            Err(e) => {
+                if health.is_degraded() {
+                    log::debug!("Log write retry failed: {}", e);
+                } else {
                     log::error!("Failed to log ping result: {}", e);
                 }
+                health.record_failure(now, &e.to_string())
             }
-        }
-        
-        // Add to recent results
-        {
-            let config = self.config.read();
-            let max_size = config.max_history_size;
-            drop(config);
-            
-            let mut recent = self.recent_results.write();
-            recent.push_back(result);
-            while recent.len() > max_size {
-                recent.pop_front();
+        };
+        drop(health);
+
+        if let Some(event) = event {
+            match event {
+                LogHealthEvent::Degraded { ref error } => {
+                    log::warn!("Logging degraded, retrying periodically: {}", error)
+                }
+                LogHealthEvent::Restored => log::info!("Logging restored"),
             }
+            self.emit(event.event_name(), &event);
         }
     }
 
-    /// Get recent ping results
-    pub fn get_recent_results(&self, count: Option<usize>) -> Vec<PingResult> {
-        let recent = self.recent_results.read();
-        let count = count.unwrap_or(recent.len());
-        recent.iter().rev().take(count).cloned().collect()
+    /// Evaluate overall internet reachability after a cycle of pings
+    ///
+    /// Targets marked as local are ignored, so a reachable gateway does not
+    /// mask an internet outage.
+    pub fn observe_cycle(&self, results: &[PingResult]) {
+        let (successes, threshold) = {
+            let config = self.config.read();
+            let successes: Vec<bool> = results
+                .iter()
+                .filter(|result| {
+                    !config
+                        .targets
+                        .iter()
+                        .any(|t| t.address == result.target && t.local)
+                })
+                .map(|result| result.success)
+                .collect();
+            (successes, config.internet_down_cycles)
+        };
+
+        let event = self
+            .connectivity
+            .lock()
+            .observe_cycle(&successes, threshold, Utc::now());
+        if let Some(event) = event {
+            log::warn!("Connectivity changed: {:?}", event);
+            self.emit(event.event_name(), &event);
+        }
     }
 
-    /// Get all statistics
-    pub fn get_all_stats(&self) -> Vec<PingStatistics> {
-        self.stats.read().get_all_stats()
+    /// Run failure commands for targets that just crossed the failure threshold
+    ///
+    /// Commands run on their own threads so a slow script never delays
+    /// probing.
+    pub fn run_failure_actions(&self, targets: &[PingTarget], results: &[PingResult]) {
+        let (default_command, threshold, cooldown) = {
+            let config = self.config.read();
+            (
+                config.on_failure_command.clone(),
+                config.failure_action_cycles,
+                Duration::from_secs(config.alert_cooldown_secs),
+            )
+        };
+
+        let mut actions = self.failure_actions.lock();
+        for (target, result) in targets.iter().zip(results) {
+            let Some(command) = target.on_failure_command.as_ref().or(default_command.as_ref())
+            else {
+                continue;
+            };
+            let Some(failures) = actions.observe(result, threshold, cooldown) else {
+                continue;
+            };
+
+            log::warn!(
+                "{} failed {} times in a row, running failure command",
+                target.address,
+                failures
+            );
+            if let Err(e) = spawn_failure_command(command, result, failures) {
+                log::error!("Failed to start failure command for {}: {}", target.address, e);
+            }
+        }
     }
 
-    /// Get statistics for a specific target
-    pub fn get_stats_for_target(&self, target: &str) -> Option<PingStatistics> {
-        self.stats.read().get_stats(target)
+    /// Run every alert check on a cycle's results, given in target order
+    fn observe_alerts(&self, targets: &[PingTarget], results: &[PingResult]) {
+        self.observe_cycle(results);
+        self.run_failure_actions(targets, results);
+        self.observe_quorum_alerts(results);
+        self.observe_escalations(results);
+        self.observe_sound_alerts(targets, results);
     }
 
-    /// Get current configuration
-    pub fn get_config(&self) -> AppConfig {
-        self.config.read().clone()
+    /// Evaluate the quorum alert rules after a batch of pings
+    pub fn observe_quorum_alerts(&self, results: &[PingResult]) {
+        let events = {
+            let config = self.config.read();
+            if config.quorum_alerts.is_empty() {
+                return;
+            }
+            self.quorum_alerts.lock().observe(
+                results,
+                &config.quorum_alerts,
+                &config.targets,
+                config.failure_action_cycles,
+                Duration::from_secs(config.alert_cooldown_secs),
+            )
+        };
+
+        for event in events {
+            log::warn!("Quorum alert changed: {:?}", event);
+            self.emit(event.event_name(), &event);
+        }
     }
 
-    /// Update configuration
-    pub fn update_config(&self, config: AppConfig) {
-        // Update stats calculator with new targets
+    /// Escalate ongoing outages that reached another tier
+    ///
+    /// Each escalation is emitted as `outage-escalated`; webhooks and
+    /// commands run on their own threads.
+    pub fn observe_escalations(&self, results: &[PingResult]) {
+        let (tiers, timeout) = {
+            let config = self.config.read();
+            if config.escalation_tiers.is_empty() {
+                return;
+            }
+            (
+                config.escalation_tiers.clone(),
+                Duration::from_millis(config.timeout_ms),
+            )
+        };
+
+        let mut escalations = Vec::new();
         {
-            let mut stats = self.stats.write();
-            for target in &config.targets {
-                stats.init_target(target);
+            let outages = self.outages.read();
+            let mut tracker = self.escalations.lock();
+            for result in results {
+                let down_since = outages.ongoing_since(&result.target);
+                for escalation in tracker.observe(result, down_since, &tiers) {
+                    escalations.push((escalation, result));
+                }
+            }
+        }
+
+        for (escalation, result) in escalations {
+            log::warn!(
+                "{} down for {}s, escalating to tier {}",
+                escalation.target,
+                escalation.down_for_secs,
+                escalation.tier
+            );
+            self.emit(events::OUTAGE_ESCALATED, &escalation);
+            if let Err(e) = spawn_escalation_action(&escalation, result, timeout) {
+                log::error!("Failed to escalate outage of {}: {}", escalation.target, e);
             }
         }
-        
-        *self.config.write() = config;
     }
 
-    /// Get all targets
-    pub fn get_targets(&self) -> Vec<PingTarget> {
-        self.config.read().targets.clone()
+    /// Ask the frontend to sound for sound-enabled targets that just went down
+    pub fn observe_sound_alerts(&self, targets: &[PingTarget], results: &[PingResult]) {
+        let (threshold, cooldown) = {
+            let config = self.config.read();
+            (
+                config.failure_action_cycles,
+                Duration::from_secs(config.alert_cooldown_secs),
+            )
+        };
+
+        let alerts = self
+            .sound_alerts
+            .lock()
+            .observe(targets, results, threshold, cooldown);
+        for alert in alerts {
+            log::warn!("{} is down, playing alert sound", alert.target);
+            self.emit(events::PLAY_SOUND, &alert);
+        }
     }
 
-    /// Get enabled targets
-    pub fn get_enabled_targets(&self) -> Vec<PingTarget> {
-        self.config.read()
-            .targets
-            .iter()
-            .filter(|t| t.enabled)
-            .cloned()
-            .collect()
+    /// Stop the ping loop and save what should survive a restart
+    ///
+    /// Only the first call does anything, so both an explicit quit and the
+    /// final exit event can call it.
+    pub fn shutdown(&self, snapshot_path: &Path) {
+        if self.shut_down.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        log::info!("Shutting down");
+
+        self.set_ping_state(PingState::Stopped);
+        if let Some(stop) = self.stop_signal.read().as_ref() {
+            stop.send(());
+        }
+        self.end_session();
+
+        if let Err(e) = self.save_recent_snapshot(snapshot_path) {
+            log::error!("Failed to save recent results: {}", e);
+        }
+        if let Err(e) = self.save_lifetime_stats() {
+            log::error!("Failed to save lifetime statistics: {}", e);
+        }
     }
 
-    /// Add a new target
-    pub fn add_target(&self, target: PingTarget) -> PingTarget {
-        let mut config = self.config.write();
-        let target_clone = target.clone();
-        config.targets.push(target);
-        
-        // Initialize stats for the new target
-        self.stats.write().init_target(&target_clone);
-        
-        target_clone
+    /// Continue lifetime counts saved at `path`, and save them there from now on
+    ///
+    /// Returns the number of targets with saved counts.
+    pub fn load_lifetime_stats(&self, path: &Path) -> Result<usize, std::io::Error> {
+        *self.lifetime_path.write() = Some(path.to_path_buf());
+        if !path.exists() {
+            return Ok(0);
+        }
+
+        let counters = read_lifetime(path)?;
+        let count = counters.len();
+        *self.lifetime.write() = LifetimeTracker::from_counters(counters);
+        Ok(count)
     }
 
-    /// Remove a target by ID
-    pub fn remove_target(&self, id: &str) -> bool {
-        let mut config = self.config.write();
-        let initial_len = config.targets.len();
-        
-        // Find the target address before removing
-        let target_address = config.targets
-            .iter()
-            .find(|t| t.id == id)
-            .map(|t| t.address.clone());
-        
-        config.targets.retain(|t| t.id != id);
-        
-        // Remove from stats
-        if let Some(address) = target_address {
-            self.stats.write().remove_target(&address);
+    /// Save lifetime counts where they were loaded from
+    ///
+    /// Returns whether they were written.
+    pub fn save_lifetime_stats(&self) -> Result<bool, std::io::Error> {
+        let Some(path) = self.lifetime_path.read().clone() else {
+            return Ok(false);
+        };
+        write_lifetime(&path, self.lifetime.read().counters())?;
+        Ok(true)
+    }
+
+    /// The started save if lifetime counts are due to be saved again
+    pub fn take_due_lifetime_save(&self) -> Option<DueRun> {
+        self.lifetime_path.read().as_ref()?;
+        self.lifetime_saves
+            .take_due(LIFETIME_SAVE_INTERVAL, Instant::now())
+    }
+
+    /// Restore the master switch and pause state saved at `path`, and save
+    /// them there from now on
+    pub fn load_monitor_switches(&self, path: &Path) -> Result<MonitorSwitches, std::io::Error> {
+        *self.switches_path.write() = Some(path.to_path_buf());
+        if !path.exists() {
+            return Ok(MonitorSwitches::default());
         }
-        
-        config.targets.len() < initial_len
+
+        let switches = read_switches(path)?;
+        self.monitoring_enabled
+            .store(switches.monitoring_enabled, Ordering::SeqCst);
+        self.launch_paused.store(switches.paused, Ordering::SeqCst);
+        Ok(switches)
     }
 
-    /// Toggle a target's enabled state
-    pub fn toggle_target(&self, id: &str) -> Option<bool> {
-        let mut config = self.config.write();
-        if let Some(target) = config.targets.iter_mut().find(|t| t.id == id) {
-            target.enabled = !target.enabled;
-            Some(target.enabled)
-        } else {
-            None
+    /// Load the configuration saved at `path`, saving future changes there
+    ///
+    /// Returns whether a saved configuration was found.
+    pub fn load_config(&self, path: &Path) -> Result<bool, std::io::Error> {
+        *self.config_path.write() = Some(path.to_path_buf());
+        if !path.exists() {
+            return Ok(false);
         }
+
+        let config = read_config(path)?;
+        let mut current = self.config.write();
+        self.apply_config(&mut current, config);
+        Ok(true)
     }
 
-    /// Update a target
-    pub fn update_target(&self, id: &str, address: String, label: String) -> Option<PingTarget> {
-        let mut config = self.config.write();
-        if let Some(target) = config.targets.iter_mut().find(|t| t.id == id) {
-            target.address = address;
-            target.label = label;
-            Some(target.clone())
-        } else {
-            None
+    /// Save `config` where the configuration was loaded from
+    ///
+    /// Called with the config lock held, so saves land in the order the
+    /// changes were made.
+    fn persist_config(&self, config: &AppConfig) {
+        let Some(path) = self.config_path.read().clone() else {
+            return;
+        };
+        if let Err(e) = write_config(&path, config) {
+            log::error!("Failed to save config: {}", e);
         }
     }
 
-    /// Get ping state
-    pub fn get_ping_state(&self) -> PingState {
-        *self.ping_state.read()
+    /// Whether the previous run exited paused, only once
+    pub fn take_launch_paused(&self) -> bool {
+        self.launch_paused.swap(false, Ordering::SeqCst)
     }
 
-    /// Set ping state
-    pub fn set_ping_state(&self, state: PingState) {
-        *self.ping_state.write() = state;
+    /// Save the master switch and pause state where they were loaded from
+    fn save_monitor_switches(&self) {
+        let Some(path) = self.switches_path.read().clone() else {
+            return;
+        };
+        let switches = MonitorSwitches {
+            monitoring_enabled: self.is_monitoring_enabled(),
+            paused: self.get_ping_state() == PingState::Paused,
+        };
+        if let Err(e) = write_switches(&path, &switches) {
+            log::error!("Failed to save monitoring switches: {}", e);
+        }
     }
 
-    /// Get log directory path
-    pub fn get_log_path(&self) -> PathBuf {
-        let logger = self.logger.read();
-        if let Some(ref logger) = *logger {
-            logger.log_dir().clone()
+    /// Reset lifetime counts too, saving the reset right away
+    pub fn reset_lifetime_stats(&self) -> Result<(), std::io::Error> {
+        self.lifetime.write().clear();
+        self.save_lifetime_stats().map(drop)
+    }
+
+    /// Save the recent results buffer if `persist_recent_results` is on
+    ///
+    /// Returns whether a snapshot was written.
+    pub fn save_recent_snapshot(&self, path: &Path) -> Result<bool, std::io::Error> {
+        if !self.config.read().persist_recent_results {
+            return Ok(false);
+        }
+
+        let recent: Vec<PingResult> = self.recent_results.read().iter().cloned().collect();
+        write_snapshot(path, &recent)?;
+        Ok(true)
+    }
+
+    /// Load results saved by a previous run into the recent results buffer
+    ///
+    /// The snapshot is removed once read, so it is only restored once.
+    /// Returns the number of results restored.
+    pub fn restore_recent_snapshot(&self, path: &Path) -> Result<usize, std::io::Error> {
+        if !path.exists() {
+            return Ok(0);
+        }
+
+        let max_size = self.config.read().max_history_size;
+        let restored = read_snapshot(path, max_size)?;
+        std::fs::remove_file(path)?;
+
+        let count = restored.len();
+        *self.recent_results.write() = restored.into();
+        Ok(count)
+    }
+
+    /// Receive every result recorded from now on
+    ///
+    /// The subscription ends if `broadcast_capacity` changes; subscribe again
+    /// to continue.
+    pub fn subscribe_results(&self) -> Subscriber<PingResult> {
+        self.results.read().subscribe()
+    }
+
+    /// Get the latest `count` ping results, or all buffered results if `None`
+    ///
+    /// A count larger than the buffer returns everything buffered. Results are
+    /// newest first when `newest_first` is set, otherwise in the order they
+    /// were recorded.
+    pub fn get_recent_results(&self, count: Option<usize>, newest_first: bool) -> Vec<PingResult> {
+        let recent = self.recent_results.read();
+        let count = count.unwrap_or(recent.len()).min(recent.len());
+        let latest = recent.iter().skip(recent.len() - count);
+
+        if newest_first {
+            latest.rev().cloned().collect()
         } else {
-            JsonLogger::default_log_dir()
+            latest.cloned().collect()
         }
     }
 
-    /// Reset all statistics
-    pub fn reset_stats(&self) {
-        self.stats.write().reset_all();
-        self.recent_results.write().clear();
-        self.reset_sequence();
+    /// Targets currently down, flapping, too slow or losing too many pings,
+    /// once per reason, in config order. Disabled targets are left out.
+    pub fn get_alarming_targets(&self) -> Vec<AlarmInfo> {
+        self.alarms.read().alarms(&self.get_enabled_targets())
     }
 
-    /// Create a pinger with current timeout settings
-    pub fn create_pinger(&self) -> Pinger {
-        let config = self.config.read();
-        Pinger::new(config.timeout_ms)
+    /// Get the most recent outages, newest first
+    pub fn get_recent_outages(&self, count: usize) -> Vec<Outage> {
+        self.outages.read().recent(count)
     }
 
-    /// Get ping interval
-    pub fn get_ping_interval(&self) -> u64 {
-        self.config.read().ping_interval_ms
+    /// Get all statistics, in the order targets appear in the config
+    ///
+    /// Statistics of targets no longer configured, e.g. loaded from a log,
+    /// follow sorted by label and then address.
+    pub fn get_all_stats(&self) -> Vec<PingStatistics> {
+        let stats = self.stats.read().get_all_stats();
+        self.arrange_stats(stats, &self.get_targets())
     }
 
-    /// Set ping interval
-    pub fn set_ping_interval(&self, interval_ms: u64) {
-        self.config.write().ping_interval_ms = interval_ms;
+    /// Statistics of the targets passing `filter`, sorted by `sort_by`
+    pub fn query_statistics(
+        &self,
+        sort_by: StatField,
+        descending: bool,
+        filter: Option<StatFilter>,
+    ) -> Vec<PingStatistics> {
+        let alarms = match filter {
+            Some(StatFilter::Down | StatFilter::Alarming) => self.get_alarming_targets(),
+            _ => Vec::new(),
+        };
+        query_stats(self.get_all_stats(), sort_by, descending, filter, &alarms)
     }
-}
 
-impl Default for AppState {
-    fn default() -> Self {
-        Self::new()
+    /// Put statistics in the order of `targets` and add baselines
+    fn arrange_stats(
+        &self,
+        mut stats: Vec<PingStatistics>,
+        targets: &[PingTarget],
+    ) -> Vec<PingStatistics> {
+        let order: HashMap<&str, usize> = targets
+            .iter()
+            .enumerate()
+            .map(|(i, target)| (target.address.as_str(), i))
+            .collect();
+        // Stable, so the fallback order is kept among unconfigured targets
+        stats.sort_by_key(|s| order.get(s.target.as_str()).copied().unwrap_or(usize::MAX));
+        self.apply_baselines(&mut stats);
+        stats
+    }
+
+    /// Ping state, statistics, recent results and config in one consistent
+    /// view, e.g. for the dashboard instead of several separate queries
+    ///
+    /// Results change statistics and the recent results together under the
+    /// buffer's lock, so the statistics count exactly the buffered results
+    /// plus those the buffer has already dropped, startup-grace failures
+    /// aside. The config and ping state are read under the same locks, so
+    /// none can change in between.
+    pub fn get_snapshot(&self) -> AppSnapshot {
+        let (config, ping_state, stats, recent_results) = {
+            // Config changes lock statistics while holding the config
+            let config = self.config.read();
+            let ping_state = self.ping_state.read();
+            let recent = self.recent_results.read();
+            let stats = self.stats.read().get_all_stats();
+            let recent_results = recent.iter().rev().cloned().collect();
+            (config.redacted(), *ping_state, stats, recent_results)
+        };
+        AppSnapshot {
+            ping_state,
+            statistics: self.arrange_stats(stats, &config.targets),
+            recent_results,
+            config,
+            taken_at: Utc::now(),
+        }
+    }
+
+    /// Statistics of targets that changed since the last call
+    ///
+    /// Used for `stats-update` events, so that with many targets only the
+    /// ones just probed are sent. `get_all_stats` gives the full snapshot.
+    pub fn take_changed_stats(&self) -> Vec<PingStatistics> {
+        let mut emitted = self.emitted_stats.lock();
+        let changed: Vec<PingStatistics> = self
+            .get_all_stats()
+            .into_iter()
+            .filter(|stats| emitted.get(&stats.target) != Some(stats))
+            .collect();
+        for stats in &changed {
+            emitted.insert(stats.target.clone(), stats.clone());
+        }
+        changed
+    }
+
+    /// Get statistics for a specific target
+    pub fn get_stats_for_target(&self, target: &str) -> Option<PingStatistics> {
+        let mut stats = self.stats.read().get_stats(target)?;
+        self.apply_baselines(std::slice::from_mut(&mut stats));
+        Some(stats)
+    }
+
+    /// Latency histogram buckets for a target, empty before its first success
+    pub fn get_latency_histogram(&self, id: &str) -> Option<Vec<HistogramBucket>> {
+        let target = self.get_targets().into_iter().find(|t| t.id == id)?;
+        Some(
+            self.stats
+                .read()
+                .latency_histogram(&target.address)
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Distinct recent errors of a target, most recently seen first
+    pub fn get_target_errors(&self, id: &str) -> Option<Vec<TargetError>> {
+        let target = self.get_targets().into_iter().find(|t| t.id == id)?;
+        Some(self.stats.read().target_errors(&target.address).unwrap_or_default())
+    }
+
+    /// Failures of a target this session, counted by classified error kind
+    pub fn get_failure_breakdown(&self, id: &str) -> Option<HashMap<PingError, u64>> {
+        let target = self.get_targets().into_iter().find(|t| t.id == id)?;
+        Some(self.stats.read().failure_breakdown(&target.address).unwrap_or_default())
+    }
+
+    /// Measure a target's latency afresh from now, keeping its ping and loss counts
+    ///
+    /// Returns false if no target has that id.
+    pub fn reset_target_latency(&self, id: &str) -> bool {
+        let Some(target) = self.get_targets().into_iter().find(|t| t.id == id) else {
+            return false;
+        };
+        self.stats.write().reset_latency(&target.address);
+        true
+    }
+
+    /// Summarize a target's latest result and statistics in one call
+    pub fn get_target_summary(&self, id: &str) -> Option<TargetSummary> {
+        let target = self.get_targets().into_iter().find(|t| t.id == id)?;
+        let stats = self
+            .get_stats_for_target(&target.address)
+            .unwrap_or_else(|| PingStatistics::new(&target));
+        let p95_latency_ms = self.stats.read().latency_percentile(&target.address, 0.95);
+        let current_latency_ms = self
+            .recent_results
+            .read()
+            .iter()
+            .rev()
+            .find(|r| r.target == target.address)
+            .and_then(|r| r.latency_ms);
+
+        Some(TargetSummary {
+            display_label: target.display_label.unwrap_or_else(|| target.label.clone()),
+            id: target.id,
+            target: target.address,
+            target_label: target.label,
+            current_latency_ms,
+            min_latency_ms: stats.min_latency_ms,
+            avg_latency_ms: stats.avg_latency_ms,
+            p95_latency_ms,
+            max_latency_ms: stats.max_latency_ms,
+            packet_loss_percent: stats.packet_loss_percent,
+            jitter_ms: stats.jitter_ms,
+            last_ping: stats.last_ping,
+        })
+    }
+
+    /// Statistics of a hostname target per IP it resolved to, with
+    /// `track_per_resolved_ip` set
+    pub fn get_resolved_ip_stats(&self, address: &str) -> Vec<PingStatistics> {
+        self.resolved_ips.read().breakdown(address)
+    }
+
+    /// Get combined statistics for each probe group
+    pub fn get_group_stats(&self) -> Vec<PingStatistics> {
+        self.groups.read().get_all_stats()
+    }
+
+    /// Add a probe group
+    pub fn add_group(&self, group: ProbeGroup) -> ProbeGroup {
+        let mut config = self.config.write();
+        config.groups.push(group.clone());
+        self.persist_config(&config);
+        group
+    }
+
+    /// Remove a probe group by ID
+    pub fn remove_group(&self, id: &str) -> bool {
+        let mut config = self.config.write();
+        let initial_len = config.groups.len();
+        config.groups.retain(|g| g.id != id);
+        self.persist_config(&config);
+        self.groups.write().remove_group(id);
+        config.groups.len() < initial_len
+    }
+
+    /// Fill in each target's deviation from its baseline and expected latency,
+    /// its lifetime counts and the monitor's session start
+    fn apply_baselines(&self, stats: &mut [PingStatistics]) {
+        let config = self.config.read();
+        let lifetime = self.lifetime.read();
+        let session_started_at = *self.session_started_at.read();
+        let labels = config.display_labels();
+        for stat in stats {
+            stat.lifetime = lifetime.get(&stat.target).cloned();
+            stat.monitor_session_start = session_started_at;
+            if config.shared_session_start && session_started_at.is_some() {
+                stat.session_start = session_started_at;
+            }
+            let position = config.targets.iter().position(|t| t.address == stat.target);
+            if let Some(i) = position {
+                stat.display_label = labels[i].clone();
+            }
+            let target = position.map(|i| &config.targets[i]);
+            stat.deviation_from_baseline = target
+                .and_then(|t| t.baseline.as_ref())
+                .map(|baseline| deviation_from_baseline(stat, baseline));
+            stat.latency_vs_expected = target
+                .and_then(|t| t.expected_latency_ms)
+                .and_then(|expected| deviation_from_expected(stat, expected));
+        }
+    }
+
+    /// Measure a target's normal performance with `samples` probes and store
+    /// it as the target's baseline
+    pub fn capture_baseline(&self, id: &str, samples: u32) -> Result<PingTarget, String> {
+        self.ensure_monitoring_enabled()?;
+        let target = self
+            .get_targets()
+            .into_iter()
+            .find(|t| t.id == id)
+            .ok_or_else(|| "Target not found".to_string())?;
+        let baseline =
+            capture_baseline(&self.create_pinger(), &target, samples, BASELINE_PROBE_DELAY);
+        self.set_target_baseline(id, baseline)
+            .ok_or_else(|| "Target not found".to_string())
+    }
+
+    /// Store a captured baseline on a target, saving it with the config
+    pub fn set_target_baseline(&self, id: &str, baseline: Baseline) -> Option<PingTarget> {
+        let mut config = self.config.write();
+        let target = config.targets.iter_mut().find(|t| t.id == id)?;
+        target.baseline = Some(baseline);
+        let target = target.clone();
+        self.persist_config(&config);
+        Some(target)
+    }
+
+    /// Get current configuration
+    pub fn get_config(&self) -> AppConfig {
+        self.config.read().clone()
+    }
+
+    /// Update configuration
+    pub fn update_config(&self, config: AppConfig) {
+        let mut current = self.config.write();
+        self.apply_config(&mut current, config);
+        self.persist_config(&current);
+    }
+
+    /// Change the config with `change` under the config lock, returning the
+    /// updated config
+    pub fn update_config_with<F: FnOnce(&mut AppConfig)>(&self, change: F) -> AppConfig {
+        let mut current = self.config.write();
+        let mut config = current.clone();
+        change(&mut config);
+        self.apply_config(&mut current, config.clone());
+        self.persist_config(&current);
+        config
+    }
+
+    /// Change only the config fields present in `patch`, a JSON object
+    ///
+    /// The merge happens under the config lock, so concurrent patches to
+    /// different fields all take effect. A merged config that fails
+    /// validation is not applied. Returns the updated config.
+    pub fn patch_config(&self, patch: &serde_json::Value) -> Result<AppConfig, String> {
+        let fields = patch
+            .as_object()
+            .ok_or_else(|| "Config patch must be an object".to_string())?;
+
+        let log_dir = self.get_log_path();
+        let mut current = self.config.write();
+        let mut merged = serde_json::to_value(&*current).map_err(|e| e.to_string())?;
+        if let Some(merged) = merged.as_object_mut() {
+            for (key, value) in fields {
+                if !merged.contains_key(key) {
+                    return Err(format!("Unknown config field: {}", key));
+                }
+                merged.insert(key.clone(), value.clone());
+            }
+        }
+        let config: AppConfig =
+            serde_json::from_value(merged).map_err(|e| format!("Invalid config patch: {}", e))?;
+        validation::check_config(&config, &log_dir)?;
+
+        self.apply_config(&mut current, config.clone());
+        self.persist_config(&current);
+        Ok(config)
+    }
+
+    /// Replace the config held in `current`, updating dependent state
+    fn apply_config(&self, current: &mut AppConfig, config: AppConfig) {
+        // Switch loggers when the log format changes
+        if config.log_format != current.log_format || config.pretty_logs != current.pretty_logs {
+            match create_logger(&config, self.get_log_path()) {
+                Ok(logger) => *self.logger.write() = Some(logger),
+                Err(e) => log::error!("Failed to create {:?} logger: {}", config.log_format, e),
+            }
+        }
+
+        // Rebuild the result channel when its capacity changes
+        if config.broadcast_capacity != current.broadcast_capacity {
+            *self.results.write() = Broadcaster::new("ping results", config.broadcast_capacity);
+        }
+
+        // A new budget starts again from the configured buffer sizes
+        if config.max_memory_mb != current.max_memory_mb {
+            *self.history_limit.write() = None;
+            self.stats.write().set_recent_window(RECENT_LATENCY_WINDOW);
+            self.groups.write().set_recent_window(RECENT_LATENCY_WINDOW);
+            self.resolved_ips.write().set_recent_window(RECENT_LATENCY_WINDOW);
+        }
+
+        if !config.track_per_resolved_ip {
+            self.resolved_ips.write().clear();
+        }
+
+        // Update stats calculator with new targets
+        {
+            let mut stats = self.stats.write();
+            let bounds = LatencyBounds::new(config.latency_warn_ms, config.latency_crit_ms);
+            stats.set_latency_bounds(bounds);
+            stats.set_loss_decimals(config.loss_display_decimals);
+            stats.set_loss_alpha(config.loss_smoothing_alpha);
+            let confidence = ConfidenceThresholds::new(
+                config.confidence_low_samples,
+                config.confidence_high_samples,
+            );
+            stats.set_confidence_thresholds(confidence);
+            stats.set_error_capacity(config.error_history_size);
+            self.outages.write().set_capacity(config.outage_history_size);
+            let mut groups = self.groups.write();
+            groups.set_latency_bounds(bounds);
+            groups.set_loss_decimals(config.loss_display_decimals);
+            groups.set_loss_alpha(config.loss_smoothing_alpha);
+            groups.set_confidence_thresholds(confidence);
+            let mut resolved_ips = self.resolved_ips.write();
+            resolved_ips.set_latency_bounds(bounds);
+            resolved_ips.set_loss_decimals(config.loss_display_decimals);
+            resolved_ips.set_loss_alpha(config.loss_smoothing_alpha);
+            resolved_ips.set_confidence_thresholds(confidence);
+            for target in &config.targets {
+                stats.init_target(target);
+            }
+        }
+        self.pipeline.write().configure(&config.result_processors);
+        Self::configure_schedule(&mut self.schedule.lock(), &config);
+        
+        *current = config;
+    }
+
+    /// Get all targets
+    pub fn get_targets(&self) -> Vec<PingTarget> {
+        let config = self.config.read();
+        config
+            .targets
+            .iter()
+            .zip(config.display_labels())
+            .map(|(target, label)| PingTarget {
+                display_label: Some(label),
+                ..target.clone()
+            })
+            .collect()
+    }
+
+    /// Get enabled targets
+    pub fn get_enabled_targets(&self) -> Vec<PingTarget> {
+        self.config.read()
+            .targets
+            .iter()
+            .filter(|t| t.enabled)
+            .cloned()
+            .collect()
+    }
+
+    /// Add a new target
+    pub fn add_target(&self, target: PingTarget) -> TargetAdded {
+        let mut config = self.config.write();
+        let label_warning = validation::duplicate_label_warning(&config.targets, &target);
+        if let Some(ref warning) = label_warning {
+            log::warn!("{}", warning);
+        }
+        let target_clone = target.clone();
+        config.targets.push(target);
+        self.persist_config(&config);
+        
+        // Initialize stats for the new target
+        self.stats.write().init_target(&target_clone);
+        
+        TargetAdded {
+            target: target_clone,
+            label_warning,
+        }
+    }
+
+    /// Remove a target by ID
+    pub fn remove_target(&self, id: &str) -> bool {
+        let (removed, target_address, address_in_use) = {
+            let mut config = self.config.write();
+            let initial_len = config.targets.len();
+
+            // Find the target address before removing
+            let target_address = config
+                .targets
+                .iter()
+                .find(|t| t.id == id)
+                .map(|t| t.address.clone());
+
+            config.targets.retain(|t| t.id != id);
+            self.persist_config(&config);
+            let address_in_use = target_address
+                .as_ref()
+                .is_some_and(|address| config.targets.iter().any(|t| &t.address == address));
+            (config.targets.len() < initial_len, target_address, address_in_use)
+        };
+
+        // Remove from stats, unless another target still probes the address
+        if let Some(address) = target_address.filter(|_| !address_in_use) {
+            self.forget_address(&address);
+        }
+        self.first_results.lock().remove(id);
+
+        // Go back to emitting every target if the focused one is gone
+        let mut focused = self.focused_target.write();
+        if focused.as_deref() == Some(id) {
+            *focused = None;
+        }
+
+        removed
+    }
+
+    /// Drop everything tracked for a target address
+    fn forget_address(&self, address: &str) {
+        self.stats.write().remove_target(address);
+        self.lifetime.write().remove_target(address);
+        self.outages.write().remove_target(address);
+        self.alarms.write().remove_target(address);
+        self.resolved_ips.write().remove_target(address);
+        self.failure_actions.lock().remove_target(address);
+        self.quorum_alerts.lock().remove_target(address);
+        self.escalations.lock().remove_target(address);
+        self.sound_alerts.lock().remove_target(address);
+        self.emitted_stats.lock().remove(address);
+        self.gaps.lock().remove_target(address);
+        self.paths.write().remove_target(address);
+        self.resolver_cache.remove_target(address);
+    }
+
+    /// Toggle a target's enabled state
+    pub fn toggle_target(&self, id: &str) -> Option<bool> {
+        let mut config = self.config.write();
+        if let Some(target) = config.targets.iter_mut().find(|t| t.id == id) {
+            target.enabled = !target.enabled;
+            let enabled = target.enabled;
+            self.persist_config(&config);
+            Some(enabled)
+        } else {
+            None
+        }
+    }
+
+    /// Update a target
+    ///
+    /// A new address is a different host, so the target's statistics and
+    /// history start afresh; the result says whether that happened.
+    pub fn update_target(&self, id: &str, address: String, label: String) -> Option<TargetUpdate> {
+        let (target, previous_address, previous_in_use, label_warning) = {
+            let mut config = self.config.write();
+            let target = config.targets.iter_mut().find(|t| t.id == id)?;
+            let previous_address = (target.address != address).then(|| target.address.clone());
+            target.address = address;
+            target.label = label;
+            let target = target.clone();
+            let previous_in_use = previous_address
+                .as_ref()
+                .is_some_and(|previous| config.targets.iter().any(|t| &t.address == previous));
+            let label_warning = validation::duplicate_label_warning(&config.targets, &target);
+            self.persist_config(&config);
+            (target, previous_address, previous_in_use, label_warning)
+        };
+
+        if let Some(ref previous) = previous_address {
+            log::info!(
+                "{} changed address from {} to {}, resetting its statistics",
+                target.label,
+                previous,
+                target.address
+            );
+            // Another target may still probe the old address
+            if !previous_in_use {
+                self.forget_address(previous);
+            }
+            self.stats.write().init_target(&target);
+            self.first_results.lock().remove(&target.id);
+        }
+        if let Some(ref warning) = label_warning {
+            log::warn!("{}", warning);
+        }
+        Some(TargetUpdate {
+            target,
+            stats_reset: previous_address.is_some(),
+            previous_address,
+            label_warning,
+        })
+    }
+
+    /// Get ping state
+    pub fn get_ping_state(&self) -> PingState {
+        *self.ping_state.read()
+    }
+
+    /// Set ping state
+    pub fn set_ping_state(&self, state: PingState) {
+        *self.ping_state.write() = state;
+        self.probes_stopped.store(state != PingState::Running, Ordering::SeqCst);
+        // Stopping on exit must not forget that monitoring was paused
+        if !self.shut_down.load(Ordering::SeqCst) {
+            self.save_monitor_switches();
+        }
+    }
+
+    /// Get log directory path
+    pub fn get_log_path(&self) -> PathBuf {
+        let logger = self.logger.read();
+        if let Some(ref logger) = *logger {
+            logger.log_dir().clone()
+        } else {
+            JsonLogger::default_log_dir()
+        }
+    }
+
+    /// Remove a target's logged results within `range` from disk
+    ///
+    /// `target` is a target id or address, so targets that were already
+    /// removed can still be purged. Logging waits while files are rewritten,
+    /// then the logger reopens today's file so it appends to the new copy.
+    pub fn purge_target_logs(&self, target: &str, range: &TimeRange) -> Result<usize, String> {
+        let address = self
+            .get_targets()
+            .into_iter()
+            .find(|t| t.id == target)
+            .map_or_else(|| target.to_string(), |t| t.address);
+        let config = self.config.read().clone();
+        let log_dir = self.get_log_path();
+
+        let mut logger = self.logger.write();
+        let removed = purge_target_logs(&log_dir, &address, range)
+            .map_err(|e| format!("Failed to purge logs: {}", e))?;
+        if removed > 0 && logger.is_some() {
+            match create_logger(&config, log_dir) {
+                Ok(reopened) => *logger = Some(reopened),
+                Err(e) => log::error!("Failed to reopen logger after purge: {}", e),
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Merge a date's log fragments into one sorted file
+    ///
+    /// Logging waits while the files are merged, then the logger reopens
+    /// today's file so it appends to the merged copy.
+    pub fn compact_logs(&self, date: NaiveDate) -> Result<LogCompaction, String> {
+        let config = self.config.read().clone();
+        let log_dir = self.get_log_path();
+
+        let mut logger = self.logger.write();
+        let compaction = compact_logs(&log_dir, date)
+            .map_err(|e| format!("Failed to compact logs: {}", e))?;
+        if compaction.fragments > 0 && logger.is_some() {
+            match create_logger(&config, log_dir) {
+                Ok(reopened) => *logger = Some(reopened),
+                Err(e) => log::error!("Failed to reopen logger after compaction: {}", e),
+            }
+        }
+        Ok(compaction)
+    }
+
+    /// Reset the session statistics, keeping lifetime counts
+    pub fn reset_stats(&self) {
+        {
+            let mut recent = self.recent_results.write();
+            self.stats.write().reset_all();
+            recent.clear();
+        }
+        if self.session_id.read().is_some() {
+            *self.session_started_at.write() = Some(Utc::now());
+        }
+        self.outages.write().clear();
+        self.alarms.write().clear();
+        self.groups.write().clear();
+        self.resolved_ips.write().clear();
+        *self.paused_at.lock() = None;
+        self.probe_accounting.lock().clear();
+        self.connectivity.lock().reset();
+        self.failure_actions.lock().clear();
+        self.quorum_alerts.lock().clear();
+        self.escalations.lock().clear();
+        self.sound_alerts.lock().clear();
+        self.emitted_stats.lock().clear();
+        self.first_results.lock().clear();
+        self.gaps.lock().clear();
+        self.paths.write().clear();
+        self.session_markers.write().clear();
+        self.reset_sequence();
+    }
+
+    /// Start a new monitoring run, logging its start marker
+    ///
+    /// Results recorded until [`Self::end_session`] carry the returned id.
+    pub fn start_session(&self) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+        let started_at = Utc::now();
+        let grace_ms = self.config.read().startup_grace_ms;
+        *self.session_id.write() = Some(id.clone());
+        *self.session_started_at.write() = Some(started_at);
+        let grace = chrono::Duration::from_std(Duration::from_millis(grace_ms)).ok();
+        *self.startup_grace_until.write() = grace
+            .filter(|_| grace_ms > 0)
+            .and_then(|grace| started_at.checked_add_signed(grace));
+        self.first_results.lock().clear();
+        // Time spent stopped is no stall
+        self.gaps.lock().forget_previous();
+        self.log_boundary(&SessionBoundary::new(&id, SessionBoundaryKind::Start));
+        id
+    }
+
+    /// End the current monitoring run, if any, logging its end marker
+    pub fn end_session(&self) {
+        if let Some(id) = self.session_id.write().take() {
+            self.log_boundary(&SessionBoundary::new(&id, SessionBoundaryKind::End));
+        }
+    }
+
+    /// Backfill the logs from saved `ping` or `fping` output
+    ///
+    /// The results are logged as a session of their own, leaving live
+    /// statistics, lifetime counts and alarms alone. Lines without a
+    /// timestamp are spaced a second apart, ending when the file was last
+    /// modified. Returns the number of results imported.
+    pub fn import_ping_output(&self, path: &Path, target_label: &str) -> Result<usize, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let end = std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .map(DateTime::<Utc>::from)
+            .unwrap_or_else(|_| Utc::now());
+        let results = parse_ping_output(&text, target_label, end)?;
+        if self.logger.read().is_none() {
+            return Err("Logging is unavailable, so there is nowhere to import to".to_string());
+        }
+        Ok(self.import_results(results))
+    }
+
+    /// Log historical results as their own session
+    fn import_results(&self, mut results: Vec<PingResult>) -> usize {
+        let (Some(first), Some(last)) = (results.first(), results.last()) else {
+            return 0;
+        };
+        let id = uuid::Uuid::new_v4().to_string();
+        let mut start = SessionBoundary::new(&id, SessionBoundaryKind::Start);
+        start.timestamp = first.timestamp;
+        let mut end = SessionBoundary::new(&id, SessionBoundaryKind::End);
+        end.timestamp = last.timestamp;
+        let bounds = {
+            let config = self.config.read();
+            LatencyBounds::new(config.latency_warn_ms, config.latency_crit_ms)
+        };
+
+        self.log_boundary(&start);
+        for result in &mut results {
+            result.latency_normalized = Some(bounds.normalize(result.latency_ms));
+            result.session_id = Some(id.clone());
+            self.log_result(result);
+        }
+        self.log_boundary(&end);
+        log::info!("Imported {} historical results as session {}", results.len(), id);
+        results.len()
+    }
+
+    fn log_boundary(&self, boundary: &SessionBoundary) {
+        if let Some(ref logger) = *self.logger.read() {
+            if let Err(e) = logger.log_boundary(boundary) {
+                log::error!("Failed to log session marker: {}", e);
+            }
+        }
+    }
+
+    /// Create a pinger with current timeout settings
+    pub fn create_pinger(&self) -> Pinger {
+        let config = self.config.read();
+        Pinger::new(config.timeout_ms)
+            .with_retries(config.retries)
+            .with_c_locale(config.ping_c_locale)
+    }
+
+    /// Create the backend selected in the config
+    ///
+    /// Targets with a DNS query are timed by a DNS probe whatever the backend.
+    pub fn create_backend(&self) -> Box<dyn PingBackend> {
+        let (backend, timeout_ms, retries, resolve_interval, icmp_timestamps) = {
+            let config = self.config.read();
+            (
+                config.backend,
+                config.timeout_ms,
+                config.retries,
+                config.resolve_interval_secs,
+                config.icmp_timestamps,
+            )
+        };
+
+        let icmp: Box<dyn PingBackend> = match backend {
+            BackendKind::System => {
+                Box::new(self.create_pinger().with_stop(Arc::clone(&self.probes_stopped)))
+            }
+            BackendKind::Native => {
+                self.native.set_timeout(timeout_ms);
+                self.native.set_retries(retries);
+                self.native.set_timestamps(icmp_timestamps);
+                Box::new(Arc::clone(&self.native))
+            }
+        };
+        let icmp = ResolvingBackend::new(
+            icmp,
+            Arc::clone(&self.resolver_cache),
+            Arc::new(SystemResolver),
+            Duration::from_secs(resolve_interval),
+        );
+        Box::new(ProbeRouter::new(icmp, DnsProbe::new(timeout_ms)))
+    }
+
+    /// Addresses the current hostname targets last resolved to, and their age
+    pub fn get_resolved_addresses(&self) -> Vec<ResolvedAddress> {
+        let targets = self.get_targets();
+        self.resolver_cache
+            .entries(Instant::now())
+            .into_iter()
+            .filter(|entry| targets.iter().any(|t| t.address == entry.target))
+            .collect()
+    }
+
+    /// Time a backend's per-probe overhead against localhost
+    ///
+    /// Uses a backend of its own, so the monitor's schedule, statistics and
+    /// probe counters are untouched.
+    pub fn benchmark_backend(
+        &self,
+        kind: BackendKind,
+        iterations: u32,
+    ) -> Result<BackendBenchmark, String> {
+        self.ensure_monitoring_enabled()?;
+        if iterations == 0 || iterations > MAX_BENCHMARK_ITERATIONS {
+            return Err(format!(
+                "Iterations must be between 1 and {}",
+                MAX_BENCHMARK_ITERATIONS
+            ));
+        }
+
+        let timeout_ms = self.config.read().timeout_ms;
+        let backend: Box<dyn PingBackend> = match kind {
+            BackendKind::System => Box::new(Pinger::new(timeout_ms)),
+            BackendKind::Native => Box::new(NativePinger::new(
+                timeout_ms,
+                Arc::new(Mutex::new(ProbeAccounting::new())),
+            )),
+        };
+        Ok(benchmark_backend(&backend, kind, iterations))
+    }
+
+    /// Probe the given targets, or all enabled ones, `samples` times each
+    ///
+    /// Like `benchmark_backend`, this uses a backend of its own and records
+    /// nothing, so monitoring carries on undisturbed.
+    pub fn run_diagnostic(
+        &self,
+        ids: Option<Vec<String>>,
+        samples: u32,
+    ) -> Result<DiagnosticReport, String> {
+        self.ensure_monitoring_enabled()?;
+        if samples == 0 || samples > MAX_DIAGNOSTIC_SAMPLES {
+            return Err(format!(
+                "Samples must be between 1 and {}",
+                MAX_DIAGNOSTIC_SAMPLES
+            ));
+        }
+        let targets = match ids {
+            Some(ids) => {
+                let all = self.get_targets();
+                ids.iter()
+                    .map(|id| {
+                        all.iter()
+                            .find(|t| &t.id == id)
+                            .cloned()
+                            .ok_or_else(|| format!("Target not found: {}", id))
+                    })
+                    .collect::<Result<Vec<_>, String>>()?
+            }
+            None => self.get_enabled_targets(),
+        };
+
+        let (timeout_ms, concurrency, bounds) = {
+            let config = self.config.read();
+            (
+                config.timeout_ms,
+                config.max_concurrent_probes,
+                LatencyBounds::new(config.latency_warn_ms, config.latency_crit_ms),
+            )
+        };
+        let backend = ProbeRouter::new(self.standalone_backend(), DnsProbe::new(timeout_ms));
+        Ok(run_diagnostic(
+            &backend,
+            &targets,
+            samples,
+            DIAGNOSTIC_SAMPLE_DELAY,
+            concurrency,
+            bounds,
+        ))
+    }
+
+    /// Check the ping backend, reply parsing and logging against loopback
+    ///
+    /// Uses a backend of its own and a scratch directory in the log
+    /// directory, so the monitor's statistics and logs are untouched.
+    pub fn run_self_test(&self) -> Result<SelfTestResult, String> {
+        self.ensure_monitoring_enabled()?;
+        let config = self.get_config();
+        Ok(run_self_test(&*self.standalone_backend(), &config, &self.get_log_path()))
+    }
+
+    /// ICMP backend of the configured kind, apart from the monitor's probe
+    /// counters and resolver cache
+    fn standalone_backend(&self) -> Box<dyn PingBackend> {
+        let (kind, timeout_ms) = {
+            let config = self.config.read();
+            (config.backend, config.timeout_ms)
+        };
+        match kind {
+            BackendKind::System => Box::new(self.create_pinger()),
+            BackendKind::Native => Box::new(NativePinger::new(
+                timeout_ms,
+                Arc::new(Mutex::new(ProbeAccounting::new())),
+            )),
+        }
+    }
+
+    /// Exact sent/received counts for probes sent by the native backend
+    pub fn get_probe_counters(&self) -> Vec<ProbeCounters> {
+        self.probe_accounting.lock().counters()
+    }
+
+    /// Register a new ping loop, returning its generation
+    pub fn begin_loop(&self) -> u64 {
+        self.loop_generation.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Whether the loop with the given generation is still the current one
+    pub fn is_current_loop(&self, generation: u64) -> bool {
+        self.loop_generation.load(Ordering::SeqCst) == generation
+    }
+
+    /// Enabled targets that are due to be pinged now
+    pub fn take_due_targets(&self) -> Vec<PingTarget> {
+        let targets = self.get_enabled_targets();
+        let interval = Duration::from_millis(self.get_ping_interval());
+        let now = Instant::now();
+
+        let mut schedule = self.schedule.lock();
+        schedule.sync(&targets, interval, now);
+        schedule.take_due(&targets, interval, now)
+    }
+
+    /// Start a fresh schedule, with every target due now if `probe_on_start` is set
+    pub fn restart_schedule(&self) {
+        let targets = self.get_enabled_targets();
+        let interval = Duration::from_millis(self.get_ping_interval());
+        let probe_on_start = self.config.read().probe_on_start;
+
+        let mut schedule = self.schedule.lock();
+        schedule.clear();
+        if probe_on_start {
+            schedule.fire_all_now(&targets, interval, Instant::now());
+        }
+    }
+
+    /// Apply the config's overlap cap, jitter and scheduling mode
+    fn configure_schedule(schedule: &mut Scheduler, config: &AppConfig) {
+        schedule.set_max_outstanding(Some(config.max_outstanding_probes.max(1)));
+        schedule.set_jitter(Duration::from_millis(config.interval_jitter_ms));
+        schedule.set_round_robin(config.round_robin_batch());
+        schedule.set_budget(config.max_pings_per_minute);
+    }
+
+    /// Mark probes taken with `take_due_targets` as finished
+    pub fn finish_targets(&self, targets: &[PingTarget]) {
+        let mut schedule = self.schedule.lock();
+        for target in targets {
+            schedule.finish(&target.id);
+        }
+    }
+
+    /// Time until the next target is due, if any are scheduled
+    pub fn time_until_next_probe(&self) -> Option<Duration> {
+        self.schedule.lock().time_until_next(Instant::now())
+    }
+
+    /// Record the loop generation and session of a cycle about to be
+    /// dispatched by the loop with `generation`
+    pub fn dispatch(&self, generation: u64) -> Dispatch {
+        Dispatch {
+            generation,
+            session: self.session_id.read().clone(),
+        }
+    }
+
+    /// Whether results of a cycle dispatched as `dispatch` may still be recorded
+    pub fn is_current_dispatch(&self, dispatch: &Dispatch) -> bool {
+        self.is_current_loop(dispatch.generation) && *self.session_id.read() == dispatch.session
+    }
+
+    /// Probe targets taken with `take_due_targets` by the ping loop
+    ///
+    /// The targets are marked finished in the schedule however probing ends.
+    /// Results that arrive once the dispatch is stale are dropped.
+    pub fn run_dispatched_cycle(
+        &self,
+        backend: &dyn PingBackend,
+        targets: &[PingTarget],
+        dispatch: &Dispatch,
+    ) -> Vec<PingResult> {
+        let _outstanding = OutstandingGuard {
+            state: self,
+            targets,
+            session: &dispatch.session,
+        };
+        self.run_cycle_while(backend, targets, || self.is_current_dispatch(dispatch))
+    }
+
+    /// Probe the given targets once and record the results
+    ///
+    /// Each result is emitted as `ping-result` unless another target is
+    /// focused, then the cycle is checked for an internet-wide outage. A
+    /// target's first result of the session is also emitted as
+    /// `target-first-result`.
+    pub fn run_cycle(&self, backend: &dyn PingBackend, targets: &[PingTarget]) -> Vec<PingResult> {
+        self.run_cycle_while(backend, targets, || true)
+    }
+
+    /// Like `run_cycle`, but stops recording once `current` turns false
+    fn run_cycle_while<F>(
+        &self,
+        backend: &dyn PingBackend,
+        targets: &[PingTarget],
+        current: F,
+    ) -> Vec<PingResult>
+    where
+        F: Fn() -> bool,
+    {
+        let sequence = self.next_sequence();
+        let focused = self.focused_target.read().clone();
+        let mut results = Vec::with_capacity(targets.len());
+
+        for target in targets {
+            let dispatched = Utc::now();
+            let result = {
+                let _inflight = InflightGuard::new(&self.inflight);
+                backend.ping(target, sequence)
+            };
+            log::debug!("Ping result for {}: {:?}", target.address, result);
+            if !current() {
+                log::debug!("Dropping results of a cycle from a finished run");
+                break;
+            }
+
+            let result = self.add_dispatched_result(result, dispatched);
+            if self.first_results.lock().insert(target.id.clone()) {
+                let first = TargetFirstResult {
+                    target_id: target.id.clone(),
+                    result: result.clone(),
+                };
+                self.emit_projected(events::TARGET_FIRST_RESULT, &first, Some("result"));
+            }
+            if focused.as_ref().map_or(true, |id| *id == target.id) {
+                self.emit_projected(events::PING_RESULT, &result, None);
+            }
+            results.push(result);
+        }
+        let targets = &targets[..results.len()];
+
+        if results.iter().any(|result| result.startup_grace) {
+            // Failures during the startup grace raise no alerts
+            let (targets, counted): (Vec<PingTarget>, Vec<PingResult>) = targets
+                .iter()
+                .cloned()
+                .zip(results.iter().cloned())
+                .filter(|(_, result)| !result.startup_grace)
+                .unzip();
+            self.observe_alerts(&targets, &counted);
+        } else {
+            self.observe_alerts(targets, &results);
+        }
+
+        // Replies that beat their timeout after all un-lose those pings
+        let late = self.probe_accounting.lock().take_late_replies();
+        if !late.is_empty() {
+            let mut stats = self.stats.write();
+            let mut lifetime = self.lifetime.write();
+            for (target, latency_ms, received_at) in late {
+                stats.record_late_reply(&target, latency_ms, received_at);
+                lifetime.record_late_reply(&target);
+            }
+        }
+
+        results
+    }
+
+    /// Create a path probe with current timeout and hop settings
+    pub fn create_path_probe(&self) -> PathProbe {
+        let config = self.config.read();
+        PathProbe::new(config.timeout_ms).with_max_hops(config.path_max_hops)
+    }
+
+    /// Targets whose paths are due to be traced, and the started round
+    ///
+    /// `None` while a previous round of traces is still running.
+    pub fn take_due_path_targets(&self) -> Option<(Vec<PingTarget>, DueRun)> {
+        let interval = self.config.read().path_probe_interval_secs;
+        if interval == 0 {
+            return None;
+        }
+
+        let targets: Vec<PingTarget> = self
+            .get_enabled_targets()
+            .into_iter()
+            .filter(|t| t.trace_path)
+            .collect();
+        if targets.is_empty() {
+            return None;
+        }
+        let run = self
+            .path_probes
+            .take_due(Duration::from_secs(interval), Instant::now())?;
+        Some((targets, run))
+    }
+
+    /// Trace the paths to targets taken with `take_due_path_targets`
+    pub fn run_path_probes(&self, targets: &[PingTarget]) {
+        let probe = self.create_path_probe();
+        for target in targets {
+            if let Err(e) = self.trace_path(&probe, target) {
+                log::warn!("Path trace to {} failed: {}", target.address, e);
+            }
+        }
+    }
+
+    /// Trace the path to one target and record it
+    pub fn trace_path(&self, probe: &PathProbe, target: &PingTarget) -> Result<PathReport, String> {
+        self.ensure_monitoring_enabled()?;
+        let hops = probe.trace(&target.address)?;
+        let mut paths = self.paths.write();
+        paths.record(&target.address, &hops, Utc::now());
+        paths
+            .report(&target.address)
+            .ok_or_else(|| "Path not recorded".to_string())
+    }
+
+    /// Pushgateway URL and job if a push is due, and the started push
+    ///
+    /// `None` while pushing is off, a push is running, or the gateway is
+    /// being backed off after repeated failures.
+    pub fn take_due_push(&self) -> Option<(String, String, DueRun)> {
+        let (url, job, interval) = {
+            let config = self.config.read();
+            (
+                config.pushgateway_url.clone()?,
+                config.pushgateway_job.clone(),
+                config.push_interval_secs,
+            )
+        };
+
+        let now = Instant::now();
+        if !self.push_health.lock().should_attempt(now) {
+            return None;
+        }
+        let run = self.pushes.take_due(Duration::from_secs(interval), now)?;
+        Some((url, job, run))
+    }
+
+    /// Push current statistics to the Pushgateway taken with `take_due_push`
+    ///
+    /// Emits `push-degraded` once pushes keep failing and `push-restored`
+    /// when one succeeds again.
+    pub fn push_metrics(&self, url: &str, job: &str) -> Result<(), String> {
+        let body = metrics::format_metrics(&self.get_all_stats());
+        let timeout = Duration::from_millis(self.config.read().timeout_ms);
+        let outcome = metrics::push_metrics(url, job, &body, timeout);
+
+        let now = Instant::now();
+        let event = match outcome {
+            Ok(()) => self.push_health.lock().record_success(),
+            Err(ref e) => self.push_health.lock().record_failure(now, e),
+        };
+
+        if let Some(event) = event {
+            let job = job.to_string();
+            let event = match event {
+                LogHealthEvent::Degraded { error } => {
+                    log::warn!("Pushing metrics degraded, retrying with backoff: {}", error);
+                    PushHealthEvent::Degraded { job, error }
+                }
+                LogHealthEvent::Restored => {
+                    log::info!("Pushing metrics restored");
+                    PushHealthEvent::Restored { job }
+                }
+            };
+            self.emit(event.event_name(), &event);
+        }
+        outcome
+    }
+
+    /// The started write if the summary CSV is due to be rewritten
+    pub fn take_due_summary(&self) -> Option<DueRun> {
+        let interval = self.config.read().summary_csv_interval_secs;
+        if interval == 0 {
+            return None;
+        }
+        self.summaries
+            .take_due(Duration::from_secs(interval), Instant::now())
+    }
+
+    /// Rewrite `summary.csv` in the log directory with current statistics
+    pub fn write_summary(&self) -> Result<PathBuf, String> {
+        let path = self.get_log_path().join(SUMMARY_CSV_FILE);
+        replace_summary_csv(&path, &self.get_all_stats())
+            .map(|()| path)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Per-hop statistics of a target's traced path
+    pub fn get_path_report(&self, id: &str) -> Option<PathReport> {
+        let target = self.get_targets().into_iter().find(|t| t.id == id)?;
+        Some(self.paths.read().report(&target.address).unwrap_or(PathReport {
+            target: target.address,
+            runs: 0,
+            last_run: None,
+            hops: Vec::new(),
+        }))
+    }
+
+    /// Label the session, starting a sub-session if the label changes
+    ///
+    /// Emits a `session-marker` event and returns the marker on a change.
+    pub fn set_session_label(&self, label: Option<String>) -> Option<SessionMarker> {
+        let label = label
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty());
+        {
+            let mut current = self.session_label.write();
+            if *current == label {
+                return None;
+            }
+            *current = label.clone();
+        }
+
+        let marker = SessionMarker {
+            label,
+            at: Utc::now(),
+        };
+        self.session_markers.write().push(marker.clone());
+        self.emit(events::SESSION_MARKER, &marker);
+        Some(marker)
+    }
+
+    /// Overview of the current session
+    pub fn get_session_summary(&self) -> SessionSummary {
+        let stats = self.stats.read().get_all_stats();
+        let total_pings = stats.iter().map(|s| s.total_pings).sum();
+        let failed_pings = stats.iter().map(|s| s.failed_pings).sum();
+
+        SessionSummary {
+            label: self.session_label.read().clone(),
+            started_at: stats.iter().filter_map(|s| s.session_start).min(),
+            total_pings,
+            failed_pings,
+            packet_loss_percent: loss_ratio(failed_pings, total_pings) * 100.0,
+            outages: self.outages.read().len(),
+            markers: self.session_markers.read().clone(),
+        }
+    }
+
+    /// One-line overview for menu bars and scripts, e.g.
+    /// `Running | 3/4 up | avg 18ms | loss 0.4% | 2 alarms`
+    ///
+    /// Covers the enabled targets; a target is up when its latest result
+    /// succeeded.
+    pub fn get_status_line(&self) -> String {
+        let status = if !self.is_monitoring_enabled() {
+            "Disabled"
+        } else {
+            match self.get_ping_state() {
+                PingState::Running => "Running",
+                PingState::Paused => "Paused",
+                PingState::Stopped => "Stopped",
+            }
+        };
+
+        let config = self.config.read();
+        let addresses = || {
+            config
+                .targets
+                .iter()
+                .filter(|t| t.enabled)
+                .map(|t| t.address.as_str())
+        };
+        let (up, alarms) = {
+            let tracker = self.alarms.read();
+            let up = addresses().filter(|a| tracker.is_up(a) == Some(true)).count();
+            (up, tracker.alarm_count(addresses()))
+        };
+        let (total, failed, avg_latency) = self.stats.read().totals(addresses());
+
+        let mut line = String::with_capacity(64);
+        let _ = write!(line, "{} | {}/{} up", status, up, addresses().count());
+        let _ = match avg_latency {
+            Some(avg) => write!(line, " | avg {:.0}ms", avg),
+            None => write!(line, " | avg -"),
+        };
+        let _ = write!(
+            line,
+            " | loss {:.1}% | {} {}",
+            loss_ratio(failed, total) * 100.0,
+            alarms,
+            if alarms == 1 { "alarm" } else { "alarms" }
+        );
+        line
+    }
+
+    /// Turn all probing on or off without stopping the ping loop
+    pub fn set_monitoring_enabled(&self, enabled: bool) {
+        self.monitoring_enabled.store(enabled, Ordering::SeqCst);
+        self.save_monitor_switches();
+    }
+
+    /// Whether probes may be sent
+    pub fn is_monitoring_enabled(&self) -> bool {
+        self.monitoring_enabled.load(Ordering::SeqCst)
+    }
+
+    /// Refuse on-demand probing (baselines, traces, diagnostics, benchmarks
+    /// and the self-test) while the kill switch is off
+    pub fn ensure_monitoring_enabled(&self) -> Result<(), String> {
+        if self.is_monitoring_enabled() {
+            Ok(())
+        } else {
+            Err("Monitoring is disabled".to_string())
+        }
+    }
+
+    /// Emit live results for only this target, or for all targets if `None`
+    ///
+    /// Results of every target still feed statistics and logs.
+    pub fn focus_target(&self, id: Option<String>) {
+        *self.focused_target.write() = id;
+    }
+
+    /// Number of probes currently in flight
+    pub fn get_inflight_count(&self) -> u32 {
+        self.inflight.load(Ordering::SeqCst)
+    }
+
+    /// Approximate memory used by buffers and statistics, and open handles
+    pub fn get_resource_stats(&self) -> ResourceStats {
+        let (recent_results, recent_results_bytes) = {
+            let recent = self.recent_results.read();
+            let bytes = recent.iter().map(PingResult::approx_size).sum::<usize>()
+                + (recent.capacity() - recent.len()) * std::mem::size_of::<PingResult>();
+            (recent.len(), bytes)
+        };
+        let (targets, samples, bytes) = self.stats.read().usage();
+        let (groups, group_samples, group_bytes) = self.groups.read().usage();
+        let (ips, ip_samples, ip_bytes) = self.resolved_ips.read().usage();
+        let latency_samples = samples + group_samples + ip_samples;
+        let latency_buffers_bytes = bytes + group_bytes + ip_bytes;
+        let log_files = self.logger.read().as_ref().map_or(0, |l| l.open_files());
+
+        ResourceStats {
+            recent_results,
+            recent_results_bytes,
+            stats_targets: targets + groups + ips,
+            latency_samples,
+            latency_buffers_bytes,
+            samples_retained: recent_results as u64 + latency_samples,
+            approx_total_bytes: recent_results_bytes + latency_buffers_bytes,
+            open_files: log_files + self.native.open_sockets(),
+            history_limit: self.history_limit(),
+            latency_window: self.stats.read().recent_window(),
+        }
+    }
+
+    /// Snapshot of the probe scheduler
+    pub fn get_scheduler_stats(&self) -> SchedulerStats {
+        let targets = self.get_enabled_targets();
+        let interval = Duration::from_millis(self.get_ping_interval());
+        let max_pings_per_minute = self.config.read().max_pings_per_minute;
+        // Config changes lock the schedule while holding the config
+        let schedule = self.schedule.lock();
+        let mut effective_interval_ms = HashMap::new();
+        for target in &targets {
+            if let Some(effective) = schedule.effective_interval(target, &targets, interval) {
+                effective_interval_ms.insert(target.id.clone(), effective.as_millis() as u64);
+            }
+        }
+        let throttled_targets = schedule.stretched_targets(&targets, interval);
+        let now = Instant::now();
+        let gaps = self.gaps.lock();
+
+        SchedulerStats {
+            scheduled_targets: schedule.len(),
+            next_probe_in_ms: schedule
+                .time_until_next(now)
+                .map(|wait| wait.as_millis() as u64),
+            inflight_probes: self.get_inflight_count(),
+            skipped_probes: schedule.skipped(),
+            effective_interval_ms,
+            max_pings_per_minute,
+            pings_last_minute: schedule.fires_last_minute(now),
+            throttled_probes: schedule.throttled(),
+            throttled_targets,
+            max_gap_ms: gaps.max_ms(),
+            avg_gap_ms: gaps.avg_ms(),
+        }
+    }
+
+    /// Freeze the probe schedule while paused
+    pub fn pause_schedule(&self) {
+        self.schedule.lock().pause(Instant::now());
+        self.paused_at.lock().get_or_insert_with(Utc::now);
+        // Failed cycles before the pause must not add up with those after it
+        self.connectivity.lock().reset();
+    }
+
+    /// Continue the probe schedule where it was paused
+    pub fn resume_schedule(&self) {
+        self.schedule.lock().resume(Instant::now());
+        self.gaps.lock().forget_previous();
+        if let Some(paused_at) = self.paused_at.lock().take() {
+            self.apply_pause_gap(paused_at, Utc::now());
+        }
+    }
+
+    /// Account for a pause from `from` to `to` in the outage history
+    ///
+    /// With `pause_counts_as_gap` every enabled target is recorded as down for
+    /// the pause; otherwise the pause is left out of ongoing outages.
+    pub fn apply_pause_gap(&self, from: DateTime<Utc>, to: DateTime<Utc>) {
+        let counts_as_gap = self.config.read().pause_counts_as_gap;
+        let mut outages = self.outages.write();
+        if counts_as_gap {
+            outages.record_gap(&self.get_enabled_targets(), from, to);
+        } else {
+            outages.exclude_gap(from, to);
+        }
+    }
+
+    /// Get ping interval
+    pub fn get_ping_interval(&self) -> u64 {
+        self.config.read().ping_interval_ms
+    }
+
+    /// Set ping interval
+    pub fn set_ping_interval(&self, interval_ms: u64) {
+        let mut config = self.config.write();
+        config.ping_interval_ms = interval_ms;
+        self.persist_config(&config);
+    }
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::RecordingSink;
+    use crate::models::{
+        AlarmReason, EscalationAction, EscalationTier, ResultProcessorConfig, SchedulingMode,
+    };
+    use crate::ping::{MockBackend, Resolver, ResolverCache, ResolvingBackend};
+    use std::time::Duration;
+
+    /// Logger whose writes fail while `failing` is set
+    struct FlakyLogger {
+        failing: Arc<AtomicBool>,
+        written: Arc<AtomicU32>,
+        dir: PathBuf,
+    }
+
+    impl ResultLogger for FlakyLogger {
+        fn log(&self, _result: &PingResult) -> Result<(), std::io::Error> {
+            if self.failing.load(Ordering::SeqCst) {
+                Err(std::io::Error::new(std::io::ErrorKind::Other, "disk full"))
+            } else {
+                self.written.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        }
+
+        fn log_dir(&self) -> &PathBuf {
+            &self.dir
+        }
+    }
+
+    #[test]
+    fn test_logging_degrades_and_restores_while_stats_continue() {
+        let failing = Arc::new(AtomicBool::new(true));
+        let written = Arc::new(AtomicU32::new(0));
+        let logger = FlakyLogger {
+            failing: Arc::clone(&failing),
+            written: Arc::clone(&written),
+            dir: PathBuf::from("."),
+        };
+        let state = AppState::with_logger(Some(Box::new(logger)));
+        *state.log_health.lock() = LogHealth::new(3, Duration::ZERO, Duration::ZERO);
+        let sink = Arc::new(RecordingSink::default());
+        state.set_event_sink(sink.clone());
+
+        let target = state.get_targets()[0].clone();
+        for seq in 0..10 {
+            state.add_result(PingResult::success(&target, 10.0, seq));
+        }
+
+        // One degraded event despite ten failed writes, and stats kept updating
+        assert_eq!(sink.count("logging-degraded"), 1);
+        assert_eq!(sink.count("logging-restored"), 0);
+        assert_eq!(sink.payloads("logging-degraded")[0]["error"], "disk full");
+        assert!(state.log_health.lock().is_degraded());
+        assert_eq!(state.get_stats_for_target(&target.address).unwrap().total_pings, 10);
+
+        failing.store(false, Ordering::SeqCst);
+        state.add_result(PingResult::success(&target, 10.0, 10));
+        state.add_result(PingResult::success(&target, 10.0, 11));
+
+        assert_eq!(sink.names(), vec!["logging-degraded", "logging-restored"]);
+        assert_eq!(written.load(Ordering::SeqCst), 2);
+        assert_eq!(state.get_stats_for_target(&target.address).unwrap().total_pings, 12);
+    }
+
+    #[test]
+    fn test_log_disabled_target_updates_stats_without_logging() {
+        let written = Arc::new(AtomicU32::new(0));
+        let logger = FlakyLogger {
+            failing: Arc::new(AtomicBool::new(false)),
+            written: Arc::clone(&written),
+            dir: PathBuf::from("."),
+        };
+        let state = AppState::with_logger(Some(Box::new(logger)));
+        let mut config = state.get_config();
+        config.targets[1].log_enabled = false;
+        state.update_config(config);
+        let targets = state.get_targets();
+
+        state.add_result(PingResult::success(&targets[0], 10.0, 1));
+        state.add_result(PingResult::success(&targets[1], 20.0, 1));
+        state.add_result(PingResult::failure(&targets[1], "Timeout".to_string(), 2));
+
+        assert_eq!(written.load(Ordering::SeqCst), 1);
+        let quiet = state.get_stats_for_target(&targets[1].address).unwrap();
+        assert_eq!(quiet.total_pings, 2);
+        assert_eq!(quiet.failed_pings, 1);
+        assert_eq!(state.get_recent_results(None, false).len(), 3);
+    }
+
+    #[test]
+    fn test_due_task_runs_once_at_a_time_and_survives_a_panic() {
+        let task = DueTask::default();
+        let start = Instant::now();
+        let interval = Duration::from_secs(60);
+
+        let run = task.take_due(interval, start).unwrap();
+        let later = start + interval;
+        assert!(task.take_due(interval, later).is_none());
+
+        // A run that panics still finishes
+        let panicking = std::thread::spawn(move || {
+            let _run = run;
+            panic!("task failed");
+        });
+        assert!(panicking.join().is_err());
+        assert!(task.take_due(interval, later - Duration::from_secs(1)).is_none());
+        assert!(task.take_due(interval, later).is_some());
+    }
+
+    #[test]
+    fn test_summary_csv_matches_current_stats() {
+        let dir = std::env::temp_dir().join(format!("ping-summary-test-{}", uuid::Uuid::new_v4()));
+        let state = AppState::with_logger(Some(Box::new(JsonLogger::new(dir.clone()).unwrap())));
+        let mut config = state.get_config();
+        config.summary_csv_interval_secs = 60;
+        state.update_config(config);
+        let targets = state.get_targets();
+        state.add_result(PingResult::success(&targets[0], 10.0, 1));
+        state.add_result(PingResult::success(&targets[0], 20.0, 2));
+        state.add_result(PingResult::failure(&targets[1], "Timeout".to_string(), 1));
+
+        let run = state.take_due_summary().unwrap();
+        // Not due again within the interval, nor while the write is running
+        assert!(state.take_due_summary().is_none());
+        let path = state.write_summary().unwrap();
+        drop(run);
+        assert_eq!(path, dir.join(SUMMARY_CSV_FILE));
+        assert!(state.take_due_summary().is_none());
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let rows: Vec<Vec<&str>> = contents.lines().map(|l| l.split(',').collect()).collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0][..3], ["target", "target_label", "total_pings"]);
+        for (row, stats) in rows[1..].iter().zip(state.get_all_stats()) {
+            assert_eq!(row[0], stats.target);
+            assert_eq!(row[1], stats.target_label);
+            assert_eq!(row[2], stats.total_pings.to_string());
+            assert_eq!(row[5], stats.packet_loss_percent.to_string());
+            let avg = stats.avg_latency_ms.map(|l| l.to_string()).unwrap_or_default();
+            assert_eq!(row[7], avg);
+        }
+        assert_eq!(rows[1][7], "15");
+        assert_eq!(rows[2][5], "100");
+        assert!(!dir.join("summary.csv.tmp").exists());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_target_added_later_shares_monitor_session_start() {
+        let state = AppState::with_logger(None);
+        let first = state.get_targets()[0].clone();
+        state.start_session();
+        let started = state.session_started_at.read().unwrap();
+        state.add_result(PingResult::failure(&first, "Timeout".to_string(), 0));
+
+        let later = state
+            .add_target(PingTarget::new("192.0.2.7".to_string(), "Later".to_string()))
+            .target;
+        let mut result = PingResult::success(&later, 10.0, 0);
+        result.timestamp = started + chrono::Duration::minutes(5);
+        state.add_result(result.clone());
+
+        let first_stats = state.get_stats_for_target(&first.address).unwrap();
+        let later_stats = state.get_stats_for_target(&later.address).unwrap();
+        assert_eq!(first_stats.monitor_session_start, Some(started));
+        assert_eq!(later_stats.monitor_session_start, Some(started));
+        assert_eq!(later_stats.first_seen, Some(result.timestamp));
+        assert_ne!(later_stats.first_seen, first_stats.first_seen);
+        assert_eq!(later_stats.session_start, later_stats.first_seen);
+
+        let mut config = state.get_config();
+        config.shared_session_start = true;
+        state.update_config(config);
+        let later_stats = state.get_stats_for_target(&later.address).unwrap();
+        assert_eq!(later_stats.session_start, Some(started));
+        assert_eq!(later_stats.first_seen, Some(result.timestamp));
+    }
+
+    #[test]
+    fn test_alarming_targets_lists_only_targets_in_trouble() {
+        let state = AppState::with_logger(None);
+        state.add_target(PingTarget::new("192.0.2.1".to_string(), "Slow".to_string()));
+        state.add_target(PingTarget::new("192.0.2.2".to_string(), "Flaky".to_string()));
+        let targets = state.get_targets();
+        let (healthy, down, slow, flapping) = (&targets[0], &targets[1], &targets[2], &targets[3]);
+        for sequence in 0..10 {
+            state.add_result(PingResult::success(healthy, 12.0, sequence));
+            state.add_result(PingResult::success(slow, 250.0, sequence));
+            state.add_result(if sequence % 2 == 0 {
+                PingResult::success(flapping, 12.0, sequence)
+            } else {
+                PingResult::failure(flapping, "Request timed out".to_string(), sequence)
+            });
+        }
+        let mut first_failure = None;
+        for sequence in 0..3 {
+            let result = state.add_result(PingResult::failure(
+                down,
+                "Request timed out".to_string(),
+                sequence,
+            ));
+            first_failure.get_or_insert(result.timestamp);
+        }
+
+        let alarms = state.get_alarming_targets();
+        let reasons: Vec<(&str, AlarmReason)> =
+            alarms.iter().map(|a| (a.target.as_str(), a.reason)).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (down.address.as_str(), AlarmReason::Down),
+                (down.address.as_str(), AlarmReason::HighLoss),
+                (slow.address.as_str(), AlarmReason::HighLatency),
+                (flapping.address.as_str(), AlarmReason::Flapping),
+                (flapping.address.as_str(), AlarmReason::HighLoss),
+            ]
+        );
+        assert_eq!(alarms[0].id, down.id);
+        assert_eq!(alarms[0].since, first_failure.unwrap());
+        assert_eq!(alarms[0].detail, "3 failed pings in a row");
+
+        // Recovery clears the alarms
+        for sequence in 3..30 {
+            state.add_result(PingResult::success(down, 12.0, sequence));
+        }
+        assert!(state.get_alarming_targets().iter().all(|a| a.target != down.address));
+        state.reset_stats();
+        assert!(state.get_alarming_targets().is_empty());
+    }
+
+    #[test]
+    fn test_update_target_reports_stats_reset_on_address_change() {
+        let state = AppState::with_logger(None);
+        let target = state.get_targets()[0].clone();
+        state.add_result(PingResult::success(&target, 10.0, 0));
+
+        // A new label keeps the statistics
+        let update = state
+            .update_target(&target.id, target.address.clone(), "Renamed".to_string())
+            .unwrap();
+        assert!(!update.stats_reset);
+        assert_eq!(update.previous_address, None);
+        assert_eq!(update.target.label, "Renamed");
+        assert_eq!(state.get_stats_for_target(&target.address).unwrap().total_pings, 1);
+
+        // A new address starts them afresh
+        let update = state
+            .update_target(&target.id, "192.0.2.9".to_string(), "Renamed".to_string())
+            .unwrap();
+        assert!(update.stats_reset);
+        assert_eq!(update.previous_address.as_deref(), Some(target.address.as_str()));
+        assert_eq!(state.get_stats_for_target(&target.address), None);
+        assert_eq!(state.get_stats_for_target("192.0.2.9").unwrap().total_pings, 0);
+
+        // An address another target still uses keeps its statistics
+        let other = state.get_targets()[1].clone();
+        state.add_result(PingResult::success(&other, 10.0, 0));
+        let twin = state
+            .add_target(PingTarget::new(other.address.clone(), "Twin".to_string()))
+            .target;
+        let update = state
+            .update_target(&twin.id, "192.0.2.10".to_string(), "Twin".to_string())
+            .unwrap();
+        assert!(update.stats_reset);
+        assert_eq!(state.get_stats_for_target(&other.address).unwrap().total_pings, 1);
+
+        // Removing a twin keeps them too, removing the last user drops them
+        let twin = state
+            .add_target(PingTarget::new(other.address.clone(), "Twin".to_string()))
+            .target;
+        assert!(state.remove_target(&twin.id));
+        assert_eq!(state.get_stats_for_target(&other.address).unwrap().total_pings, 1);
+        assert!(state.remove_target(&other.id));
+        assert_eq!(state.get_stats_for_target(&other.address), None);
+
+        assert!(state.update_target("missing", String::new(), String::new()).is_none());
+    }
+
+    #[test]
+    fn test_reset_zeroes_session_stats_but_keeps_lifetime() {
+        let path = std::env::temp_dir().join(format!("lifetime-{}.json", uuid::Uuid::new_v4()));
+        let state = AppState::with_logger(None);
+        assert_eq!(state.load_lifetime_stats(&path).unwrap(), 0);
+        let target = state.get_targets()[0].clone();
+        for sequence in 0..3 {
+            state.add_result(PingResult::success(&target, 10.0, sequence));
+        }
+        state.add_result(PingResult::failure(&target, "Timeout".to_string(), 3));
+
+        state.reset_stats();
+        state.add_result(PingResult::success(&target, 10.0, 4));
+        let stats = state.get_stats_for_target(&target.address).unwrap();
+        assert_eq!(stats.total_pings, 1);
+        assert_eq!(stats.packet_loss_percent, 0.0);
+        let lifetime = stats.lifetime.unwrap();
+        assert_eq!(lifetime.total_pings, 5);
+        assert_eq!(lifetime.failed_pings, 1);
+        assert_eq!(lifetime.packet_loss_percent, 20.0);
+
+        // Lifetime counts carry over to the next run, session counts do not
+        let snapshot_dir =
+            std::env::temp_dir().join(format!("ping-lifetime-test-{}", uuid::Uuid::new_v4()));
+        state.shutdown(&snapshot_dir.join("recent.json"));
+        let restarted = AppState::with_logger(None);
+        assert_eq!(restarted.load_lifetime_stats(&path).unwrap(), 1);
+        restarted.add_result(PingResult::success(&target, 10.0, 1));
+        let stats = restarted.get_stats_for_target(&target.address).unwrap();
+        assert_eq!(stats.total_pings, 1);
+        assert_eq!(stats.lifetime.as_ref().unwrap().total_pings, 6);
+        assert_eq!(stats.lifetime.unwrap().since, lifetime.since);
+
+        // Resetting lifetime counts too starts both from zero
+        restarted.reset_stats();
+        restarted.reset_lifetime_stats().unwrap();
+        let stats = restarted.get_stats_for_target(&target.address).unwrap();
+        assert_eq!(stats.total_pings, 0);
+        assert_eq!(stats.lifetime, None);
+        assert!(read_lifetime(&path).unwrap().is_empty());
+
+        // Counts are also saved now and then while running
+        restarted.add_result(PingResult::success(&target, 10.0, 2));
+        let run = restarted.take_due_lifetime_save().unwrap();
+        assert!(restarted.take_due_lifetime_save().is_none());
+        assert!(restarted.save_lifetime_stats().unwrap());
+        drop(run);
+        assert_eq!(read_lifetime(&path).unwrap().len(), 1);
+        assert!(AppState::with_logger(None).take_due_lifetime_save().is_none());
+
+        let _ = std::fs::remove_file(path);
+        let _ = std::fs::remove_dir_all(snapshot_dir);
+    }
+
+    #[test]
+    fn test_imported_output_is_logged_but_not_counted_live() {
+        let dir = std::env::temp_dir().join(format!("ping-import-test-{}", uuid::Uuid::new_v4()));
+        let state = AppState::with_logger(Some(Box::new(JsonLogger::new(dir.clone()).unwrap())));
+        let target = state.get_targets()[0].clone();
+        let transcript = dir.join("transcript.txt");
+        std::fs::write(
+            &transcript,
+            format!(
+                "PING {0} ({0}) 56(84) bytes of data.\n\
+                 64 bytes from {0}: icmp_seq=1 ttl=57 time=11.4 ms\n\
+                 no answer yet for icmp_seq=2\n",
+                target.address
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(state.import_ping_output(&transcript, "Imported").unwrap(), 2);
+
+        let stats = state.get_stats_for_target(&target.address).unwrap();
+        assert_eq!(stats.total_pings, 0);
+        assert_eq!(stats.lifetime, None);
+        assert!(state.get_alarming_targets().is_empty());
+        let range = TimeRange::new(Utc::now() - chrono::Duration::hours(1), Utc::now());
+        let path = crate::logging::log_files_in_range(&dir, &range)
+            .unwrap()
+            .remove(0);
+        let logged: Vec<PingResult> = crate::logging::stream_results(&path).unwrap().collect();
+        assert_eq!(logged.len(), 2);
+        assert!(logged.iter().all(|r| r.session_id.is_some()));
+
+        // Without a logger there is nothing to import into
+        let unlogged = AppState::with_logger(None);
+        assert!(unlogged.import_ping_output(&transcript, "Imported").is_err());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_session_markers_bracket_the_results_of_a_run() {
+        let dir = std::env::temp_dir().join(format!("ping-session-test-{}", uuid::Uuid::new_v4()));
+        let state = AppState::with_logger(Some(Box::new(JsonLogger::new(dir.clone()).unwrap())));
+        let target = state.get_targets()[0].clone();
+
+        state.add_result(PingResult::success(&target, 10.0, 1));
+        let first = state.start_session();
+        state.add_result(PingResult::success(&target, 11.0, 2));
+        state.add_result(PingResult::failure(&target, "Timeout".to_string(), 3));
+        state.end_session();
+        let second = state.start_session();
+        state.add_result(PingResult::success(&target, 12.0, 4));
+        assert_ne!(first, second);
+
+        let path = crate::logging::log_files_in_range(
+            &dir,
+            &TimeRange::new(Utc::now() - chrono::Duration::hours(1), Utc::now()),
+        )
+        .unwrap()
+        .remove(0);
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let shape: Vec<(Option<&str>, Option<&str>)> = lines
+            .iter()
+            .map(|line| (line["boundary"].as_str(), line["session_id"].as_str()))
+            .collect();
+        assert_eq!(
+            shape,
+            vec![
+                (None, None),
+                (Some("start"), Some(first.as_str())),
+                (None, Some(first.as_str())),
+                (None, Some(first.as_str())),
+                (Some("end"), Some(first.as_str())),
+                (Some("start"), Some(second.as_str())),
+                (None, Some(second.as_str())),
+            ]
+        );
+
+        // Markers are invisible to result readers but list the sessions
+        let range = TimeRange::new(Utc::now() - chrono::Duration::hours(1), Utc::now());
+        assert_eq!(crate::logging::stream_results(&path).unwrap().count(), 4);
+        let sessions = crate::logging::list_sessions(&dir, range).unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].id, first);
+        assert!(sessions[0].ended_at.is_some());
+        assert_eq!(sessions[1].ended_at, None);
+        let sequences: Vec<u32> = crate::logging::query_logs(&dir, None, Some(&first), range)
+            .unwrap()
+            .map(|r| r.sequence)
+            .collect();
+        assert_eq!(sequences, vec![2, 3]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_failing_pushes_degrade_and_back_off() {
+        // Nothing listens on a port that was just released
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let state = AppState::with_logger(None);
+        let mut config = state.get_config();
+        config.pushgateway_url = Some(format!("http://127.0.0.1:{}", port));
+        config.push_interval_secs = 0;
+        state.update_config(config);
+        *state.push_health.lock() =
+            LogHealth::new(2, Duration::from_secs(60), Duration::from_secs(60));
+        let sink = Arc::new(RecordingSink::default());
+        state.set_event_sink(sink.clone());
+
+        for _ in 0..2 {
+            let (url, job, _run) = state.take_due_push().unwrap();
+            assert_eq!(job, "ping_connectivity");
+            assert!(state.push_metrics(&url, &job).is_err());
+        }
+
+        assert_eq!(sink.names(), vec!["push-degraded"]);
+        let degraded = &sink.payloads(events::PUSH_DEGRADED)[0];
+        assert_eq!(degraded["status"], "degraded");
+        assert_eq!(degraded["job"], "ping_connectivity");
+        // Backing off: no push is due despite the zero interval
+        assert!(state.take_due_push().is_none());
+    }
+
+    #[test]
+    fn test_resource_stats_count_retained_samples() {
+        let state = AppState::with_logger(None);
+        let max_history = state.get_config().max_history_size;
+        let target = PingTarget::new("192.0.2.1".to_string(), "Test".to_string());
+        let empty = state.get_resource_stats();
+        assert_eq!(empty.recent_results, 0);
+        assert_eq!(empty.latency_samples, 0);
+        assert_eq!(empty.open_files, 0);
+
+        for sequence in 0..40 {
+            state.add_result(PingResult::success(&target, 10.0, sequence));
+        }
+        let stats = state.get_resource_stats();
+        assert_eq!(stats.recent_results, 40);
+        // Each latency is in the histogram and the 30-sample recent window
+        assert_eq!(stats.latency_samples, 40 + 30);
+        assert_eq!(stats.samples_retained, 40 + 40 + 30);
+        assert!(stats.recent_results_bytes >= 40 * std::mem::size_of::<PingResult>());
+        assert!(stats.latency_buffers_bytes > 0);
+
+        // The chart buffer stops at max_history_size
+        for sequence in 0..max_history as u32 {
+            state.add_result(PingResult::failure(&target, "Timeout".to_string(), sequence));
+        }
+        assert_eq!(state.get_resource_stats().recent_results, max_history);
+    }
+
+    #[test]
+    fn test_exceeding_memory_budget_trims_buffers() {
+        let state = AppState::with_logger(None);
+        let sink = Arc::new(RecordingSink::default());
+        state.set_event_sink(sink.clone());
+        let mut config = state.get_config();
+        config.max_history_size = 10_000;
+        config.max_memory_mb = Some(1);
+        state.update_config(config);
+
+        // Each result carries a 4KiB error, so a few hundred pass the budget
+        let target = PingTarget::new("192.0.2.1".to_string(), "Test".to_string());
+        let error = "x".repeat(4096);
+        for sequence in 0..400 {
+            state.add_result(PingResult::success(&target, 10.0, sequence));
+            state.add_result(PingResult::failure(&target, error.clone(), sequence));
+        }
+
+        let pressure = sink.payloads(events::MEMORY_PRESSURE);
+        assert!(!pressure.is_empty());
+        let first: MemoryPressure = serde_json::from_value(pressure[0].clone()).unwrap();
+        assert_eq!(first.budget_bytes, 1024 * 1024);
+        assert!(first.used_bytes >= first.budget_bytes / 100 * MEMORY_PRESSURE_PERCENT);
+        assert!(first.latency_window <= RECENT_LATENCY_WINDOW / 2);
+
+        let stats = state.get_resource_stats();
+        assert!(stats.approx_total_bytes < 1024 * 1024);
+        assert!(stats.recent_results < 800);
+        assert!(stats.recent_results <= stats.history_limit);
+        assert!(stats.latency_window < RECENT_LATENCY_WINDOW);
+
+        // Lifting the budget restores the configured sizes
+        let mut config = state.get_config();
+        config.max_memory_mb = None;
+        state.update_config(config);
+        let stats = state.get_resource_stats();
+        assert_eq!(stats.history_limit, 10_000);
+        assert_eq!(stats.latency_window, RECENT_LATENCY_WINDOW);
+    }
+
+    #[test]
+    fn test_memory_pressure_is_reported_at_the_floors() {
+        let state = AppState::with_logger(None);
+        let sink = Arc::new(RecordingSink::default());
+        state.set_event_sink(sink.clone());
+        let mut config = state.get_config();
+        config.max_memory_mb = Some(0);
+        state.update_config(config);
+
+        // The budget is checked on the 1st, 17th and 33rd results, and each
+        // check reports even once nothing is left to shrink
+        let target = PingTarget::new("192.0.2.1".to_string(), "Test".to_string());
+        for sequence in 0..40 {
+            state.add_result(PingResult::success(&target, 10.0, sequence));
+        }
+
+        let pressure = sink.payloads(events::MEMORY_PRESSURE);
+        assert_eq!(pressure.len(), 3);
+        for payload in pressure {
+            let pressure: MemoryPressure = serde_json::from_value(payload).unwrap();
+            assert_eq!(pressure.history_limit, MIN_HISTORY_UNDER_PRESSURE);
+            assert_eq!(pressure.latency_window, MIN_LATENCY_WINDOW);
+        }
+    }
+
+    #[test]
+    fn test_stats_compare_latency_with_each_targets_expectation() {
+        let state = AppState::with_logger(None);
+        let mut gateway = PingTarget::new("192.168.1.1".to_string(), "Gateway".to_string());
+        gateway.expected_latency_ms = Some(1.0);
+        let mut remote = PingTarget::new("192.0.2.1".to_string(), "Remote".to_string());
+        remote.expected_latency_ms = Some(80.0);
+        let plain = PingTarget::new("192.0.2.2".to_string(), "Plain".to_string());
+        for target in [&gateway, &remote, &plain] {
+            state.add_target(target.clone());
+            state.add_result(PingResult::success(target, 40.0, 1));
+            state.add_result(PingResult::success(target, 60.0, 2));
+        }
+
+        // The same 50ms is far too slow for the gateway but fine for the remote host
+        let gateway = state.get_stats_for_target("192.168.1.1").unwrap();
+        let deviation = gateway.latency_vs_expected.unwrap();
+        assert_eq!(deviation.expected_latency_ms, 1.0);
+        assert_eq!(deviation.difference_ms, 49.0);
+        assert_eq!(deviation.ratio, 50.0);
+
+        let remote = state.get_stats_for_target("192.0.2.1").unwrap();
+        let deviation = remote.latency_vs_expected.unwrap();
+        assert_eq!(deviation.difference_ms, -30.0);
+        assert_eq!(deviation.ratio, 0.625);
+
+        assert_eq!(state.get_stats_for_target("192.0.2.2").unwrap().latency_vs_expected, None);
+    }
+
+    #[test]
+    fn test_all_stats_follow_config_order() {
+        let state = AppState::with_logger(None);
+        for (address, label) in [("192.0.2.9", "Alpha"), ("192.0.2.1", "Zulu")] {
+            state.add_target(PingTarget::new(address.to_string(), label.to_string()));
+        }
+        let targets = state.get_targets();
+        state.run_cycle(&MockBackend::new(vec![Some(10.0)]), &targets);
+        // Results for a target that is not configured sort after the rest
+        state.add_result(PingResult::success(
+            &PingTarget::new("192.0.2.5".to_string(), "Old".to_string()),
+            5.0,
+            1,
+        ));
+
+        let expected: Vec<String> = targets
+            .iter()
+            .map(|t| t.address.clone())
+            .chain(["192.0.2.5".to_string()])
+            .collect();
+        for _ in 0..10 {
+            let order: Vec<String> = state.get_all_stats().into_iter().map(|s| s.target).collect();
+            assert_eq!(order, expected);
+        }
+    }
+
+    #[test]
+    fn test_gap_is_measured_from_the_targets_own_previous_result() {
+        let state = AppState::with_logger(None);
+        let targets = state.get_targets();
+        let start = Utc::now();
+        let at = |target: &PingTarget, ms: i64| {
+            let mut result = PingResult::success(target, 10.0, 1);
+            result.timestamp = start + chrono::Duration::milliseconds(ms);
+            state.add_result(result).gap_since_previous_ms
+        };
+
+        assert_eq!(at(&targets[0], 0), None);
+        assert_eq!(at(&targets[1], 100), None);
+        // Not 900ms since the other target's result
+        assert_eq!(at(&targets[0], 1000), Some(1000.0));
+        assert_eq!(at(&targets[1], 1100), Some(1000.0));
+        // A stall
+        assert_eq!(at(&targets[0], 5000), Some(4000.0));
+
+        let scheduler = state.get_scheduler_stats();
+        assert_eq!(scheduler.max_gap_ms, Some(4000.0));
+        assert_eq!(scheduler.avg_gap_ms, Some(2000.0));
+
+        // Time spent paused is not a gap
+        state.resume_schedule();
+        assert_eq!(at(&targets[0], 60_000), None);
+        assert_eq!(state.get_scheduler_stats().max_gap_ms, Some(4000.0));
+    }
+
+    #[test]
+    fn test_gap_is_measured_between_dispatches_not_replies() {
+        let state = AppState::with_logger(None);
+        let target = state.get_targets().remove(0);
+        let start = Utc::now();
+        let probe = |sent_ms: i64, reply_ms: i64| {
+            let mut result = PingResult::success(&target, reply_ms as f64, 1);
+            result.timestamp = start + chrono::Duration::milliseconds(sent_ms + reply_ms);
+            let sent = start + chrono::Duration::milliseconds(sent_ms);
+            state.add_dispatched_result(result, sent).gap_since_previous_ms
+        };
+
+        // A slow reply followed by a fast one is not a stall
+        assert_eq!(probe(0, 900), None);
+        assert_eq!(probe(1000, 5), Some(1000.0));
+        assert_eq!(probe(2000, 900), Some(1000.0));
+        assert_eq!(state.get_scheduler_stats().max_gap_ms, Some(1000.0));
+    }
+
+    #[test]
+    fn test_config_changes_reach_the_schedule_and_reading_stats_changes_nothing() {
+        let state = AppState::with_logger(None);
+        let targets = state.get_enabled_targets();
+        state.restart_schedule();
+        assert_eq!(state.get_scheduler_stats().scheduled_targets, 2);
+
+        // Taking turns one at a time doubles each target's interval at once
+        let mut config = state.get_config();
+        config.scheduling_mode = SchedulingMode::RoundRobin;
+        config.round_robin_batch_size = 1;
+        state.update_config(config);
+        let first = state.get_scheduler_stats();
+        assert_eq!(first.scheduled_targets, 0);
+        assert_eq!(first.effective_interval_ms[&targets[0].id], 2000);
+
+        let again = state.get_scheduler_stats();
+        assert_eq!(again.scheduled_targets, 0);
+        assert_eq!(again.effective_interval_ms, first.effective_interval_ms);
+    }
+
+    #[test]
+    fn test_status_line_summarizes_seeded_state() {
+        let state = AppState::with_logger(None);
+        assert_eq!(
+            state.get_status_line(),
+            "Stopped | 0/2 up | avg - | loss 0.0% | 0 alarms"
+        );
+
+        state.add_target(PingTarget::new("192.0.2.1".to_string(), "Third".to_string()));
+        state.add_target(PingTarget::new("192.0.2.2".to_string(), "Fourth".to_string()));
+        let targets = state.get_targets();
+        for (target, latency) in targets.iter().zip([10.0, 20.0, 30.0]) {
+            state.add_result(PingResult::success(target, latency, 1));
+        }
+        // Down and losing every ping
+        for sequence in 1..=3 {
+            state.add_result(PingResult::failure(&targets[3], "Timeout".to_string(), sequence));
+        }
+        state.set_ping_state(PingState::Running);
+
+        assert_eq!(
+            state.get_status_line(),
+            "Running | 3/4 up | avg 20ms | loss 50.0% | 2 alarms"
+        );
+    }
+
+    #[test]
+    fn test_snapshot_masks_commands_and_webhooks() {
+        let state = AppState::with_logger(None);
+        let mut config = state.get_config();
+        config.on_failure_command = Some("notify --token secret".to_string());
+        config.targets[0].on_failure_command = Some("/home/me/alert.sh".to_string());
+        config.escalation_tiers = vec![
+            EscalationTier {
+                after_secs: 60,
+                action: EscalationAction::Webhook {
+                    url: "https://hooks.example.com/T0/secret".to_string(),
+                },
+            },
+            EscalationTier {
+                after_secs: 600,
+                action: EscalationAction::Command {
+                    command: "page-oncall --key secret".to_string(),
+                },
+            },
+        ];
+        state.update_config(config);
+
+        let snapshot = serde_json::to_string(&state.get_snapshot()).unwrap();
+        assert!(!snapshot.contains("secret"));
+        assert!(!snapshot.contains("alert.sh"));
+        assert!(snapshot.contains(crate::models::REDACTED));
+        // The live config keeps the real values
+        let config = state.get_config();
+        assert_eq!(config.on_failure_command.as_deref(), Some("notify --token secret"));
+    }
+
+    #[test]
+    fn test_snapshot_stats_match_its_recent_results() {
+        let state = Arc::new(AppState::with_logger(None));
+        let config = AppConfig {
+            max_history_size: 10_000,
+            ..state.get_config()
+        };
+        state.update_config(config);
+        let targets = state.get_targets();
+
+        let writer = {
+            let state = Arc::clone(&state);
+            let targets = targets.clone();
+            std::thread::spawn(move || {
+                let backend = MockBackend::new(vec![Some(10.0), None]);
+                for _ in 0..300 {
+                    state.run_cycle(&backend, &targets);
+                }
+            })
+        };
+        let check = |snapshot: &AppSnapshot| {
+            assert_eq!(snapshot.statistics.len(), targets.len());
+            for (stats, target) in snapshot.statistics.iter().zip(&targets) {
+                assert_eq!(stats.target, target.address);
+                let buffered: Vec<&PingResult> = snapshot
+                    .recent_results
+                    .iter()
+                    .filter(|r| r.target == target.address)
+                    .collect();
+                let failed = buffered.iter().filter(|r| !r.success).count();
+                assert_eq!(stats.total_pings, buffered.len() as u64);
+                assert_eq!(stats.failed_pings, failed as u64);
+            }
+        };
+        while !writer.is_finished() {
+            check(&state.get_snapshot());
+        }
+        writer.join().unwrap();
+
+        let snapshot = state.get_snapshot();
+        check(&snapshot);
+        assert_eq!(snapshot.recent_results.len(), 600);
+        assert_eq!(snapshot.config.max_history_size, 10_000);
+        assert_eq!(snapshot.ping_state, PingState::Stopped);
+        // Newest first
+        assert!(snapshot.recent_results[0].sequence > snapshot.recent_results[599].sequence);
+    }
+
+    #[test]
+    fn test_outage_escalates_once_per_tier() {
+        let state = AppState::with_logger(None);
+        let sink = Arc::new(RecordingSink::default());
+        state.set_event_sink(sink.clone());
+        state.update_config(AppConfig {
+            escalation_tiers: vec![
+                EscalationTier {
+                    after_secs: 0,
+                    action: EscalationAction::Notify,
+                },
+                EscalationTier {
+                    after_secs: 3600,
+                    action: EscalationAction::Notify,
+                },
+            ],
+            ..state.get_config()
+        });
+        let targets = state.get_targets();
+        let backend = MockBackend::new(vec![None]);
+
+        state.run_cycle(&backend, &targets);
+        state.run_cycle(&backend, &targets);
+
+        // Only the first tier is due, once for each target
+        let escalated = sink.payloads(events::OUTAGE_ESCALATED);
+        assert_eq!(escalated.len(), targets.len());
+        assert_eq!(escalated[0]["target"], targets[0].address.as_str());
+        assert_eq!(escalated[0]["tier"], 0);
+        assert_eq!(escalated[0]["action"]["kind"], "notify");
+    }
+
+    #[test]
+    fn test_sound_plays_on_down_transition_of_sound_targets_only() {
+        let state = AppState::with_logger(None);
+        let sink = Arc::new(RecordingSink::default());
+        state.set_event_sink(sink.clone());
+        let mut config = state.get_config();
+        config.targets[1].sound_alert = true;
+        config.failure_action_cycles = 2;
+        config.alert_cooldown_secs = 0;
+        state.update_config(config);
+        let targets = state.get_targets();
+        let down = MockBackend::new(vec![None]);
+        let up = MockBackend::new(vec![Some(10.0)]);
+
+        state.run_cycle(&down, &targets);
+        assert_eq!(sink.count(events::PLAY_SOUND), 0);
+        state.run_cycle(&down, &targets);
+        state.run_cycle(&down, &targets);
+        let sounds = sink.payloads(events::PLAY_SOUND);
+        assert_eq!(sounds.len(), 1);
+        assert_eq!(sounds[0]["target_id"], targets[1].id.as_str());
+        assert_eq!(sounds[0]["failures"], 2);
+
+        // Going down again after recovering sounds again
+        state.run_cycle(&up, &targets);
+        state.run_cycle(&down, &targets);
+        state.run_cycle(&down, &targets);
+        assert_eq!(sink.count(events::PLAY_SOUND), 2);
+
+        // Matched by address when only some targets were probed
+        state.run_cycle(&up, &targets);
+        let failure = PingResult::failure(&targets[1], "Timeout".to_string(), 9);
+        state.observe_sound_alerts(&targets, std::slice::from_ref(&failure));
+        state.observe_sound_alerts(&targets, std::slice::from_ref(&failure));
+        assert_eq!(sink.count(events::PLAY_SOUND), 3);
+    }
+
+    #[test]
+    fn test_failures_during_startup_grace_are_logged_but_not_counted() {
+        let written = Arc::new(AtomicU32::new(0));
+        let logger = FlakyLogger {
+            failing: Arc::new(AtomicBool::new(false)),
+            written: Arc::clone(&written),
+            dir: PathBuf::from("."),
+        };
+        let state = AppState::with_logger(Some(Box::new(logger)));
+        let sink = Arc::new(RecordingSink::default());
+        state.set_event_sink(sink.clone());
+        let mut config = state.get_config();
+        config.startup_grace_ms = 60_000;
+        config.failure_action_cycles = 1;
+        config.alert_cooldown_secs = 0;
+        config.targets[0].sound_alert = true;
+        state.update_config(config);
+        state.start_session();
+        let targets = state.get_targets();
+        let down = MockBackend::new(vec![None]);
+
+        for _ in 0..3 {
+            let results = state.run_cycle(&down, &targets);
+            assert!(results.iter().all(|r| r.startup_grace));
+        }
+        assert_eq!(written.load(Ordering::SeqCst), 6);
+        let stats = state.get_stats_for_target(&targets[0].address).unwrap();
+        assert_eq!((stats.total_pings, stats.failed_pings), (0, 0));
+        // Charted with the flag, but not counted
+        let recent = state.get_recent_results(None, false);
+        assert_eq!(recent.len(), 6);
+        assert!(recent.iter().all(|r| r.startup_grace));
+        assert!(stats.lifetime.is_none());
+        assert!(state.get_recent_outages(10).is_empty());
+        assert_eq!(sink.count(events::PLAY_SOUND), 0);
+
+        // Once the grace is over, failures count and alert
+        *state.startup_grace_until.write() = Some(Utc::now());
+        let results = state.run_cycle(&down, &targets);
+        assert!(results.iter().all(|r| !r.startup_grace));
+        assert_eq!(written.load(Ordering::SeqCst), 8);
+        let stats = state.get_stats_for_target(&targets[0].address).unwrap();
+        assert_eq!((stats.total_pings, stats.failed_pings), (1, 1));
+        assert_eq!(sink.count(events::PLAY_SOUND), 1);
+    }
+
+    /// Resolver alternating between two addresses, like a CDN hostname
+    #[derive(Default)]
+    struct AlternatingResolver {
+        lookups: AtomicU32,
+    }
+
+    impl Resolver for AlternatingResolver {
+        fn resolve(&self, _host: &str) -> Result<std::net::IpAddr, String> {
+            let n = self.lookups.fetch_add(1, Ordering::SeqCst);
+            Ok(std::net::IpAddr::from([203, 0, 113, 1 + (n % 2) as u8]))
+        }
+    }
+
+    #[test]
+    fn test_changing_resolved_ips_are_tracked_only_when_enabled() {
+        let mut cdn = PingTarget::new("cdn.example".to_string(), "CDN".to_string());
+        cdn.resolve_each_probe = true;
+
+        for track in [false, true] {
+            let backend = ResolvingBackend::new(
+                MockBackend::new(vec![Some(10.0), Some(30.0), None, Some(20.0)]),
+                Arc::new(ResolverCache::new()),
+                Arc::new(AlternatingResolver::default()),
+                Duration::from_secs(300),
+            );
+            let state = AppState::with_logger(None);
+            let mut config = state.get_config();
+            config.targets = vec![cdn.clone()];
+            config.track_per_resolved_ip = track;
+            state.update_config(config);
+            for _ in 0..4 {
+                state.run_cycle(&backend, &[cdn.clone()]);
+            }
+
+            // The hostname aggregates every IP either way
+            let stats = state.get_stats_for_target("cdn.example").unwrap();
+            assert_eq!((stats.total_pings, stats.failed_pings), (4, 1));
+            let per_ip = state.get_resolved_ip_stats("cdn.example");
+            if !track {
+                assert!(per_ip.is_empty());
+                continue;
+            }
+
+            let ips: Vec<_> = per_ip.iter().map(|s| s.target.as_str()).collect();
+            assert_eq!(ips, ["203.0.113.1", "203.0.113.2"]);
+            assert_eq!((per_ip[0].total_pings, per_ip[0].failed_pings), (2, 1));
+            assert_eq!(per_ip[0].avg_latency_ms, Some(10.0));
+            assert_eq!((per_ip[1].total_pings, per_ip[1].failed_pings), (2, 0));
+            assert_eq!(per_ip[1].avg_latency_ms, Some(25.0));
+            assert_eq!(per_ip[1].target_label, "CDN");
+        }
+    }
+
+    #[test]
+    fn test_colliding_labels_get_the_address_appended() {
+        let state = AppState::with_logger(None);
+        let mut config = state.get_config();
+        config.targets = vec![
+            PingTarget::new("1.1.1.1".to_string(), "DNS".to_string()),
+            PingTarget::new("8.8.8.8".to_string(), "Google".to_string()),
+            PingTarget::new("9.9.9.9".to_string(), "dns ".to_string()),
+        ];
+        state.update_config(config);
+
+        let expected = ["DNS (1.1.1.1)", "Google", "dns  (9.9.9.9)"];
+        let labels: Vec<_> = state
+            .get_targets()
+            .into_iter()
+            .map(|t| t.display_label.unwrap())
+            .collect();
+        assert_eq!(labels, expected);
+        let labels: Vec<_> = state
+            .get_all_stats()
+            .into_iter()
+            .map(|s| s.display_label)
+            .collect();
+        assert_eq!(labels, expected);
+
+        // Renaming one apart resolves the collision and warns no more
+        let id = state.get_targets()[2].id.clone();
+        let update = state
+            .update_target(&id, "9.9.9.9".to_string(), "Quad9".to_string())
+            .unwrap();
+        assert_eq!(update.label_warning, None);
+        assert_eq!(state.get_targets()[0].display_label.as_deref(), Some("DNS"));
+        let update = state
+            .update_target(&id, "9.9.9.9".to_string(), "Google".to_string())
+            .unwrap();
+        assert!(update.label_warning.unwrap().contains("8.8.8.8"));
+        let added = state.add_target(PingTarget::new("4.4.4.4".to_string(), "dns".to_string()));
+        assert!(added.label_warning.unwrap().contains("1.1.1.1"));
+        let summary = state.get_target_summary(&added.target.id).unwrap();
+        assert_eq!(summary.display_label, "dns (4.4.4.4)");
+
+        state.update_config(AppConfig {
+            disambiguate_labels: false,
+            ..state.get_config()
+        });
+        assert_eq!(state.get_all_stats()[2].display_label, "Google");
+    }
+
+    #[test]
+    fn test_projected_result_events_omit_other_fields() {
+        let state = AppState::with_logger(None);
+        let sink = Arc::new(RecordingSink::default());
+        state.set_event_sink(sink.clone());
+        let mut config = state.get_config();
+        config.result_emit_fields = Some(vec!["target".to_string(), "latency_ms".to_string()]);
+        state.update_config(config);
+        let targets = state.get_targets();
+        let backend = MockBackend::new(vec![Some(10.0)]);
+
+        state.run_cycle(&backend, &targets);
+
+        let keys = |payload: &serde_json::Value| {
+            let mut keys: Vec<String> = payload.as_object().unwrap().keys().cloned().collect();
+            keys.sort();
+            keys
+        };
+        let emitted = sink.payloads(events::PING_RESULT);
+        assert_eq!(emitted.len(), targets.len());
+        for payload in &emitted {
+            assert_eq!(keys(payload), vec!["latency_ms", "target"]);
+        }
+        let first = &sink.payloads(events::TARGET_FIRST_RESULT)[0];
+        assert_eq!(first["target_id"], targets[0].id.as_str());
+        assert_eq!(keys(&first["result"]), vec!["latency_ms", "target"]);
+        // The recorded result stays complete
+        assert_eq!(state.get_recent_results(Some(1), true)[0].sequence, 1);
+    }
+
+    #[test]
+    fn test_first_result_is_emitted_once_per_target() {
+        let state = AppState::with_logger(None);
+        let sink = Arc::new(RecordingSink::default());
+        state.set_event_sink(sink.clone());
+        let targets = state.get_targets();
+        let backend = MockBackend::new(vec![Some(10.0), None]);
+
+        state.run_cycle(&backend, &targets);
+        state.run_cycle(&backend, &targets);
+        let first = sink.payloads(events::TARGET_FIRST_RESULT);
+        assert_eq!(first.len(), targets.len());
+        for target in &targets {
+            let count = first
+                .iter()
+                .filter(|payload| payload["target_id"] == target.id.as_str())
+                .count();
+            assert_eq!(count, 1);
+        }
+        assert_eq!(first[0]["result"]["sequence"], 1);
+
+        // Resetting the statistics starts over
+        state.reset_stats();
+        state.run_cycle(&backend, &targets);
+        assert_eq!(sink.count(events::TARGET_FIRST_RESULT), targets.len() * 2);
+    }
+
+    #[test]
+    fn test_unsubscribed_events_are_not_emitted() {
+        let state = AppState::with_logger(None);
+        let sink = Arc::new(RecordingSink::default());
+        state.set_event_sink(sink.clone());
+        let targets = state.get_targets();
+        let backend = MockBackend::new(vec![Some(10.0)]);
+
+        state.run_cycle(&backend, &targets);
+        assert_eq!(sink.count(events::PING_RESULT), targets.len());
+
+        let names = vec![events::STATS_UPDATE.to_string()];
+        state.set_event_subscriptions(names).unwrap();
+        assert!(!state.is_subscribed(events::PING_RESULT));
+        state.run_cycle(&backend, &targets);
+        state.emit(events::STATS_UPDATE, &state.get_all_stats());
+        assert_eq!(sink.count(events::PING_RESULT), targets.len());
+        assert_eq!(sink.count(events::STATS_UPDATE), 1);
+
+        let unknown = vec!["ping-reslt".to_string()];
+        assert_eq!(
+            state.set_event_subscriptions(unknown),
+            Err("Unknown event: ping-reslt".to_string())
+        );
+        assert!(state.is_subscribed(events::STATS_UPDATE));
+    }
+
+    #[test]
+    fn test_internet_down_fires_once_when_all_external_targets_fail() {
+        let state = AppState::with_logger(None);
+        let sink = Arc::new(RecordingSink::default());
+        state.set_event_sink(sink.clone());
+        let mut gateway = PingTarget::new("192.168.1.1".to_string(), "Gateway".to_string());
+        gateway.local = true;
+        state.add_target(gateway.clone());
+        let external = state
+            .get_targets()
+            .into_iter()
+            .filter(|t| !t.local)
+            .collect::<Vec<_>>();
+        assert_eq!(state.get_config().internet_down_cycles, 3);
+
+        for seq in 0..6 {
+            // The gateway keeps answering while every internet target fails
+            let mut cycle = vec![PingResult::success(&gateway, 1.0, seq)];
+            cycle.extend(
+                external
+                    .iter()
+                    .map(|t| PingResult::failure(t, "Timeout".to_string(), seq)),
+            );
+            state.observe_cycle(&cycle);
+            assert_eq!(sink.count("internet-down"), usize::from(seq >= 2));
+        }
+        assert!(state.connectivity.lock().is_down());
+
+        let cycle = vec![
+            PingResult::success(&external[0], 12.0, 6),
+            PingResult::failure(&external[1], "Timeout".to_string(), 6),
+        ];
+        state.observe_cycle(&cycle);
+        assert_eq!(sink.names(), vec!["internet-down", "internet-up"]);
+    }
+
+    #[test]
+    fn test_pause_resets_failed_cycles_towards_internet_down() {
+        let state = AppState::with_logger(None);
+        let sink = Arc::new(RecordingSink::default());
+        state.set_event_sink(sink.clone());
+        let targets = state.get_targets();
+        let failed_cycle = |seq| {
+            targets
+                .iter()
+                .map(|t| PingResult::failure(t, "Timeout".to_string(), seq))
+                .collect::<Vec<_>>()
+        };
+
+        state.observe_cycle(&failed_cycle(0));
+        state.observe_cycle(&failed_cycle(1));
+        state.pause_schedule();
+        state.resume_schedule();
+        state.observe_cycle(&failed_cycle(2));
+        state.observe_cycle(&failed_cycle(3));
+        assert_eq!(sink.count("internet-down"), 0);
+
+        state.observe_cycle(&failed_cycle(4));
+        assert_eq!(sink.count("internet-down"), 1);
+    }
+
+    #[test]
+    fn test_on_demand_probing_is_refused_while_monitoring_is_disabled() {
+        let state = AppState::with_logger(None);
+        let target = state.get_targets()[0].clone();
+        state.set_monitoring_enabled(false);
+        let disabled = Err("Monitoring is disabled".to_string());
+
+        assert_eq!(state.capture_baseline(&target.id, 1).map(|_| ()), disabled);
+        let probe = state.create_path_probe();
+        assert_eq!(state.trace_path(&probe, &target).map(|_| ()), disabled);
+        assert_eq!(state.run_diagnostic(None, 1).map(|_| ()), disabled);
+        let benchmark = state.benchmark_backend(BackendKind::System, 1);
+        assert_eq!(benchmark.map(|_| ()), disabled);
+        assert_eq!(state.run_self_test().map(|_| ()), disabled);
+        assert!(state.get_targets()[0].baseline.is_none());
+    }
+
+    /// Backend whose probes panic
+    struct PanickingBackend;
+
+    impl PingBackend for PanickingBackend {
+        fn ping(&self, _target: &PingTarget, _sequence: u32) -> PingResult {
+            panic!("probe failed")
+        }
+    }
+
+    #[test]
+    fn test_dispatched_cycle_releases_its_probes_even_on_panic() {
+        let state = AppState::with_logger(None);
+        state.restart_schedule();
+        let dispatch = state.dispatch(state.begin_loop());
+        let targets = state.take_due_targets();
+        assert_eq!(targets.len(), 2);
+        assert_eq!(state.schedule.lock().outstanding(&targets[0].id), 1);
+
+        let cycle = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            state.run_dispatched_cycle(&PanickingBackend, &targets, &dispatch)
+        }));
+        assert!(cycle.is_err());
+        assert!(targets
+            .iter()
+            .all(|t| state.schedule.lock().outstanding(&t.id) == 0));
+        assert_eq!(state.get_inflight_count(), 0);
+    }
+
+    #[test]
+    fn test_results_of_a_stale_dispatch_are_dropped() {
+        let state = AppState::with_logger(None);
+        let targets = state.get_targets();
+        let backend = MockBackend::new(vec![Some(10.0)]);
+
+        let dispatch = state.dispatch(state.begin_loop());
+        assert_eq!(state.run_dispatched_cycle(&backend, &targets, &dispatch).len(), 2);
+
+        // A new session, as after a stop and start
+        state.start_session();
+        assert!(!state.is_current_dispatch(&dispatch));
+        assert!(state
+            .run_dispatched_cycle(&backend, &targets, &dispatch)
+            .is_empty());
+        let dispatch = state.dispatch(state.loop_generation.load(Ordering::SeqCst));
+        state.begin_loop();
+        assert!(state
+            .run_dispatched_cycle(&backend, &targets, &dispatch)
+            .is_empty());
+        assert!(state.get_all_stats().iter().all(|s| s.total_pings == 1));
+    }
+
+    #[test]
+    fn test_recorded_results_carry_normalized_latency() {
+        let state = AppState::with_logger(None);
+        let target = state.get_targets()[0].clone();
+
+        let result = state.add_result(PingResult::success(&target, 25.0, 1));
+        assert_eq!(result.latency_normalized, Some(0.25));
+        let result = state.add_result(PingResult::failure(&target, "Timeout".to_string(), 2));
+        assert_eq!(result.latency_normalized, Some(1.0));
+
+        let stats = state.get_stats_for_target(&target.address).unwrap();
+        assert_eq!(stats.latency_normalized, Some(0.25));
+    }
+
+    #[test]
+    fn test_sequence_starts_at_configured_base() {
+        let state = AppState::with_logger(None);
+        let backend = MockBackend::new(vec![Some(10.0)]);
+        let targets = state.get_targets();
+        let sequences = |results: Vec<PingResult>| -> Vec<u32> {
+            results.iter().map(|r| r.sequence).collect()
+        };
+
+        assert_eq!(sequences(state.run_cycle(&backend, &targets)), [1, 1]);
+        assert_eq!(sequences(state.run_cycle(&backend, &targets)), [2, 2]);
+
+        let mut config = state.get_config();
+        config.sequence_start = 0;
+        state.update_config(config);
+        state.reset_sequence();
+        assert_eq!(sequences(state.run_cycle(&backend, &targets)), [0, 0]);
+    }
+
+    #[test]
+    fn test_inflight_count_returns_to_zero_after_run() {
+        let state = AppState::with_logger(None);
+        let sink = Arc::new(RecordingSink::default());
+        state.set_event_sink(sink.clone());
+        let backend = MockBackend::new(vec![Some(10.0), None]);
+        let targets = state.get_enabled_targets();
+
+        let results = state.run_cycle(&backend, &targets);
+
+        assert_eq!(results.len(), targets.len());
+        assert_eq!(backend.calls(), targets.len());
+        assert_eq!(sink.count("ping-result"), targets.len());
+        assert_eq!(state.get_inflight_count(), 0);
+        assert_eq!(state.get_scheduler_stats().inflight_probes, 0);
+    }
+
+    fn state_with_recent(sequences: std::ops::Range<u32>) -> AppState {
+        let state = AppState::with_logger(None);
+        let target = PingTarget::new("1.1.1.1".to_string(), "Test".to_string());
+        for sequence in sequences {
+            state.add_result(PingResult::success(&target, 10.0, sequence));
+        }
+        state
+    }
+
+    fn sequences(results: &[PingResult]) -> Vec<u32> {
+        results.iter().map(|r| r.sequence).collect()
+    }
+
+    #[test]
+    fn test_recent_results_newest_first() {
+        let state = state_with_recent(1..6);
+        assert_eq!(sequences(&state.get_recent_results(Some(3), true)), vec![5, 4, 3]);
+        assert_eq!(sequences(&state.get_recent_results(None, true)), vec![5, 4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn test_recent_results_oldest_first() {
+        let state = state_with_recent(1..6);
+        assert_eq!(sequences(&state.get_recent_results(Some(3), false)), vec![3, 4, 5]);
+        assert_eq!(sequences(&state.get_recent_results(None, false)), vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_recent_results_oversized_count_returns_all() {
+        let state = state_with_recent(1..4);
+        assert_eq!(sequences(&state.get_recent_results(Some(100), true)), vec![3, 2, 1]);
+        assert_eq!(sequences(&state.get_recent_results(Some(100), false)), vec![1, 2, 3]);
+        assert!(state.get_recent_results(Some(0), true).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_result_subscribers_see_recorded_results() {
+        let state = AppState::with_logger(None);
+        let mut subscriber = state.subscribe_results();
+        let target = PingTarget::new("1.1.1.1".to_string(), "Test".to_string());
+
+        state.add_result(PingResult::success(&target, 10.0, 7));
+
+        assert_eq!(subscriber.recv().await.map(|r| r.sequence), Some(7));
+    }
+
+    #[test]
+    fn test_target_summary_from_seeded_state() {
+        let state = AppState::with_logger(None);
+        let target = state.get_targets().remove(0);
+        for (sequence, latency) in [10.0, 20.0, 30.0, 40.0].into_iter().enumerate() {
+            state.add_result(PingResult::success(&target, latency, sequence as u32));
+        }
+        state.add_result(PingResult::failure(&target, "Timeout".to_string(), 4));
+        state.add_result(PingResult::success(&target, 50.0, 5));
+
+        let summary = state.get_target_summary(&target.id).unwrap();
+
+        assert_eq!(summary.target, target.address);
+        assert_eq!(summary.current_latency_ms, Some(50.0));
+        assert_eq!(summary.min_latency_ms, Some(10.0));
+        assert_eq!(summary.avg_latency_ms, Some(30.0));
+        assert_eq!(summary.p95_latency_ms, Some(50.0));
+        assert_eq!(summary.max_latency_ms, Some(50.0));
+        assert!((summary.packet_loss_percent - 100.0 / 6.0).abs() < 1e-9);
+        assert!(summary.jitter_ms.unwrap() > 0.0);
+        assert!(state.get_target_summary("missing").is_none());
+    }
+
+    #[test]
+    fn test_recent_results_snapshot_round_trip() {
+        let path = std::env::temp_dir().join(format!("recent-{}.json", uuid::Uuid::new_v4()));
+        let before = state_with_recent(1..6);
+
+        // Nothing is written unless persistence is enabled
+        assert!(!before.save_recent_snapshot(&path).unwrap());
+        let mut config = before.get_config();
+        config.persist_recent_results = true;
+        before.update_config(config);
+        assert!(before.save_recent_snapshot(&path).unwrap());
+
+        let after = AppState::with_logger(None);
+        let mut config = after.get_config();
+        config.max_history_size = 3;
+        after.update_config(config);
+        assert_eq!(after.restore_recent_snapshot(&path).unwrap(), 3);
+
+        let restored = after.get_recent_results(None, false);
+        assert_eq!(sequences(&restored), vec![3, 4, 5]);
+        assert!(restored.iter().all(|r| r.restored));
+        assert!(!path.exists());
+        assert_eq!(after.restore_recent_snapshot(&path).unwrap(), 0);
+    }
+
+    /// Pause for ten minutes while `down` is in an outage, then report both
+    /// targets up again
+    fn outages_after_pause(counts_as_gap: bool) -> Vec<Outage> {
+        let state = AppState::with_logger(None);
+        let mut config = state.get_config();
+        config.pause_counts_as_gap = counts_as_gap;
+        state.update_config(config);
+        let targets = state.get_enabled_targets();
+        let (down, up) = (&targets[0], &targets[1]);
+        let start = Utc::now() - chrono::Duration::minutes(20);
+        let at = |mut result: PingResult, secs: i64| {
+            result.timestamp = start + chrono::Duration::seconds(secs);
+            result
+        };
+
+        state.add_result(at(PingResult::success(up, 10.0, 1), 0));
+        state.add_result(at(PingResult::failure(down, "Timeout".to_string(), 1), 0));
+        state.apply_pause_gap(
+            start + chrono::Duration::seconds(5),
+            start + chrono::Duration::seconds(605),
+        );
+        state.add_result(at(PingResult::success(up, 10.0, 2), 606));
+        state.add_result(at(PingResult::success(down, 10.0, 2), 606));
+
+        state.get_recent_outages(10)
+    }
+
+    #[test]
+    fn test_pause_is_not_counted_as_gap() {
+        let outages = outages_after_pause(false);
+
+        // Only the real failure is an outage, without the paused time
+        assert_eq!(outages.len(), 1);
+        assert_eq!(outages[0].duration_ms, 6_000);
+    }
+
+    #[test]
+    fn test_pause_counts_as_gap() {
+        let outages = outages_after_pause(true);
+
+        // The healthy target is down for the pause; the failing one stays down
+        assert_eq!(outages.len(), 2);
+        let down = outages.iter().find(|o| o.target == "1.1.1.1").unwrap();
+        let up = outages.iter().find(|o| o.target == "8.8.8.8").unwrap();
+        assert_eq!(up.duration_ms, 600_000);
+        assert_eq!(down.duration_ms, 606_000);
+    }
+
+    #[test]
+    fn test_only_focused_target_results_are_emitted() {
+        let state = AppState::with_logger(None);
+        let sink = Arc::new(RecordingSink::default());
+        state.set_event_sink(sink.clone());
+        let backend = MockBackend::new(vec![Some(10.0)]);
+        let targets = state.get_enabled_targets();
+
+        state.focus_target(Some(targets[1].id.clone()));
+        state.run_cycle(&backend, &targets);
+        state.run_cycle(&backend, &targets);
+
+        let emitted = sink.payloads(events::PING_RESULT);
+        assert_eq!(emitted.len(), 2);
+        assert!(emitted.iter().all(|p| p["target"] == targets[1].address.as_str()));
+        for target in &targets {
+            assert_eq!(state.get_stats_for_target(&target.address).unwrap().total_pings, 2);
+        }
+
+        state.focus_target(None);
+        state.run_cycle(&backend, &targets);
+        assert_eq!(sink.count(events::PING_RESULT), 2 + targets.len());
+    }
+
+    #[test]
+    fn test_baseline_survives_a_relaunch() {
+        let path = std::env::temp_dir().join(format!("config-{}.json", uuid::Uuid::new_v4()));
+        let state = AppState::with_logger(None);
+        state.load_config(&path).unwrap();
+        let target = state.get_targets()[0].clone();
+        let baseline = Baseline {
+            avg_latency_ms: Some(12.5),
+            p95_latency_ms: Some(20.0),
+            packet_loss_percent: 0.0,
+            samples: 10,
+            captured_at: Utc::now(),
+        };
+        assert!(state.set_target_baseline(&target.id, baseline.clone()).is_some());
+
+        let state = AppState::with_logger(None);
+        assert!(state.load_config(&path).unwrap());
+        assert_eq!(state.get_targets()[0].baseline, Some(baseline));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_preset_survives_a_relaunch() {
+        let path = std::env::temp_dir().join(format!("config-{}.json", uuid::Uuid::new_v4()));
+        let state = AppState::with_logger(None);
+        state.load_config(&path).unwrap();
+        let applied = state.update_config_with(AppConfig::apply_low_resource_preset);
+
+        let state = AppState::with_logger(None);
+        assert!(state.load_config(&path).unwrap());
+        let config = state.get_config();
+        assert_eq!(config.ping_interval_ms, 5000);
+        assert_eq!(config.max_outstanding_probes, 1);
+        assert_eq!(serde_json::to_value(config).unwrap(), serde_json::to_value(applied).unwrap());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_patches_both_apply() {
+        let state = Arc::new(AppState::new());
+        let patches = [
+            serde_json::json!({ "timeout_ms": 1234 }),
+            serde_json::json!({ "max_history_size": 42 }),
+        ];
+
+        let handles: Vec<_> = patches
+            .into_iter()
+            .map(|patch| {
+                let state = Arc::clone(&state);
+                std::thread::spawn(move || state.patch_config(&patch).unwrap())
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let config = state.get_config();
+        assert_eq!(config.timeout_ms, 1234);
+        assert_eq!(config.max_history_size, 42);
+        assert_eq!(config.ping_interval_ms, AppConfig::default().ping_interval_ms);
+
+        assert!(state.patch_config(&serde_json::json!({ "no_such": 1 })).is_err());
+        assert!(state.patch_config(&serde_json::json!({ "timeout_ms": "x" })).is_err());
+        assert!(state.patch_config(&serde_json::json!([1])).is_err());
+        let invalid = state.patch_config(&serde_json::json!({ "max_pings_per_minute": 0 }));
+        assert!(invalid.unwrap_err().contains("max_pings_per_minute"));
+        assert_eq!(state.get_config().max_pings_per_minute, None);
+        assert_eq!(state.get_config().timeout_ms, 1234);
+    }
+
+    #[test]
+    fn test_changed_stats_omit_unchanged_targets() {
+        let state = AppState::with_logger(None);
+        let targets = state.get_enabled_targets();
+        assert!(targets.len() >= 2);
+        let backend = MockBackend::new(vec![Some(10.0)]);
+
+        // Everything is new at first
+        state.run_cycle(&backend, &targets);
+        assert_eq!(state.take_changed_stats().len(), targets.len());
+        assert!(state.take_changed_stats().is_empty());
+
+        // Only the probed target is sent
+        state.run_cycle(&backend, &targets[..1]);
+        let changed = state.take_changed_stats();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].target, targets[0].address);
+        assert_eq!(changed[0].total_pings, 2);
+
+        // A reset sends everything again
+        state.reset_stats();
+        assert_eq!(state.take_changed_stats().len(), targets.len());
+    }
+
+    #[test]
+    fn test_shutdown_stops_loop_once() {
+        let state = AppState::with_logger(None);
+        state.set_ping_state(PingState::Running);
+        let stop = Broadcaster::new("stop signal", 1);
+        let mut rx = stop.subscribe();
+        *state.stop_signal.write() = Some(stop);
+
+        let path = std::env::temp_dir().join("shutdown-snapshot-unused.json");
+        state.shutdown(&path);
+        assert_eq!(state.get_ping_state(), PingState::Stopped);
+        assert!(rx.try_recv().is_some());
+
+        state.shutdown(&path);
+        assert!(rx.try_recv().is_none());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_session_label_in_summary_and_markers() {
+        let state = AppState::with_logger(None);
+        let sink = Arc::new(RecordingSink::default());
+        state.set_event_sink(sink.clone());
+        let mut config = state.get_config();
+        config.embed_session_label = true;
+        state.update_config(config);
+        let target = state.get_targets()[0].clone();
+
+        let marker = state.set_session_label(Some("before ISP call".to_string()));
+        assert_eq!(marker.unwrap().label.as_deref(), Some("before ISP call"));
+        // Setting the same label again is not a new sub-session
+        assert!(state
+            .set_session_label(Some(" before ISP call ".to_string()))
+            .is_none());
+
+        let result = state.add_result(PingResult::failure(&target, "Timeout".to_string(), 1));
+        assert_eq!(result.session_label.as_deref(), Some("before ISP call"));
+        assert_eq!(
+            state.get_recent_outages(1)[0].session_label.as_deref(),
+            Some("before ISP call")
+        );
+
+        state.set_session_label(Some("after router swap".to_string()));
+        let summary = state.get_session_summary();
+        assert_eq!(summary.label.as_deref(), Some("after router swap"));
+        assert_eq!(summary.total_pings, 1);
+        assert_eq!(summary.outages, 1);
+        let labels: Vec<_> = summary.markers.iter().map(|m| m.label.clone()).collect();
+        assert_eq!(
+            labels,
+            [
+                Some("before ISP call".to_string()),
+                Some("after router swap".to_string())
+            ]
+        );
+        assert_eq!(sink.count(events::SESSION_MARKER), 2);
+    }
+
+    #[test]
+    fn test_result_processors_apply_before_stats() {
+        struct AddOverhead(f64);
+        impl ResultProcessor for AddOverhead {
+            fn process(&self, mut result: PingResult) -> PingResult {
+                result.latency_ms = result.latency_ms.map(|l| l + self.0);
+                result
+            }
+        }
+
+        let state = AppState::with_logger(None);
+        state.update_config(AppConfig {
+            result_processors: vec![ResultProcessorConfig::RoundLatency { decimals: 0 }],
+            ..state.get_config()
+        });
+        state.add_result_processor(Box::new(AddOverhead(100.0)));
+        let target = state.get_targets()[0].clone();
+
+        let recorded = state.add_result(PingResult::success(&target, 10.4, 1));
+        // Rounded from config first, then the registered processor
+        assert_eq!(recorded.latency_ms, Some(110.0));
+        assert_eq!(state.get_recent_results(Some(1), true)[0].latency_ms, Some(110.0));
+        let stats = state.get_stats_for_target(&target.address).unwrap();
+        assert_eq!(stats.avg_latency_ms, Some(110.0));
+        assert_eq!(stats.max_latency_ms, Some(110.0));
     }
 }
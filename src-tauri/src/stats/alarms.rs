@@ -0,0 +1,357 @@
+use crate::models::{AlarmInfo, AlarmReason, PingResult, PingTarget};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, VecDeque};
+
+/// Latest results per target checked for flapping
+pub const FLAP_WINDOW: usize = 20;
+
+/// Changes between success and failure within the window that count as flapping
+pub const FLAP_CHANGES: usize = 6;
+
+/// Successful pings in a row at or above `latency_crit_ms` that raise an alarm
+pub const SLOW_PINGS: u32 = 3;
+
+/// When a target counts as alarming
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlarmThresholds {
+    /// Failures in a row after which a target is down
+    pub down_after: u32,
+    pub latency_crit_ms: f64,
+    /// Smoothed packet loss, 0-100, at which loss is too high
+    pub loss_percent: f64,
+}
+
+/// An alarm that currently holds
+#[derive(Debug, Clone)]
+struct ActiveAlarm {
+    reason: AlarmReason,
+    since: DateTime<Utc>,
+    detail: String,
+}
+
+/// Whether a target is up or down, and since which result
+#[derive(Debug, Clone, Copy)]
+struct Status {
+    up: bool,
+    /// Number of the result that started the status, counting from 1
+    from: u64,
+    since: DateTime<Utc>,
+}
+
+/// Recent behaviour of one target
+#[derive(Debug, Clone, Default)]
+struct TargetAlarms {
+    /// Results observed
+    results: u64,
+    status: Option<Status>,
+    /// Numbers of the results that changed the status, within the flap window
+    transitions: VecDeque<u64>,
+    slow: u32,
+    slow_since: Option<DateTime<Utc>>,
+    active: Vec<ActiveAlarm>,
+}
+
+impl TargetAlarms {
+    /// Count a result, noting a change between up and down
+    fn record_status(&mut self, up: bool, at: DateTime<Utc>) {
+        self.results += 1;
+        match self.status {
+            Some(status) if status.up == up => {}
+            previous => {
+                if previous.is_some() {
+                    self.transitions.push_back(self.results);
+                }
+                self.status = Some(Status {
+                    up,
+                    from: self.results,
+                    since: at,
+                });
+            }
+        }
+        // Changes between the latest FLAP_WINDOW results
+        while self
+            .transitions
+            .front()
+            .is_some_and(|&n| n + FLAP_WINDOW as u64 <= self.results + 1)
+        {
+            self.transitions.pop_front();
+        }
+    }
+
+    /// Failed results in a row, and since when
+    fn down_streak(&self) -> Option<(u64, DateTime<Utc>)> {
+        self.status
+            .filter(|status| !status.up)
+            .map(|status| (self.results - status.from + 1, status.since))
+    }
+
+    /// Raise, refresh or clear the alarm for `reason`
+    ///
+    /// A raised alarm keeps its original `since` while it holds.
+    fn set(&mut self, reason: AlarmReason, alarm: Option<(DateTime<Utc>, String)>) {
+        let existing = self.active.iter().position(|a| a.reason == reason);
+        match (existing, alarm) {
+            (Some(index), Some((_, detail))) => self.active[index].detail = detail,
+            (None, Some((since, detail))) => {
+                self.active.push(ActiveAlarm {
+                    reason,
+                    since,
+                    detail,
+                });
+                self.active.sort_by_key(|a| a.reason);
+            }
+            (Some(index), None) => {
+                self.active.remove(index);
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+/// Tracks which targets are down, flapping, too slow or losing too many pings
+#[derive(Debug, Clone, Default)]
+pub struct AlarmTracker {
+    targets: HashMap<String, TargetAlarms>,
+}
+
+impl AlarmTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Re-evaluate a target's alarms after a result
+    ///
+    /// Down and flapping alarms follow the target's changes between up and
+    /// down: it is down once it has failed `down_after` times since it went
+    /// down, and flapping once it changed `FLAP_CHANGES` times within the
+    /// latest `FLAP_WINDOW` results. `smoothed_loss` is the target's smoothed
+    /// packet loss after the result.
+    pub fn observe(
+        &mut self,
+        result: &PingResult,
+        smoothed_loss: Option<f64>,
+        thresholds: &AlarmThresholds,
+    ) {
+        let target = self.targets.entry(result.target.clone()).or_default();
+        let at = result.timestamp;
+        target.record_status(result.success, at);
+
+        if result.success {
+            // Sanitized latencies only; failures neither extend nor end a run
+            match result.latency_ms.filter(|l| l.is_finite() && *l >= 0.0) {
+                Some(latency) if latency >= thresholds.latency_crit_ms => {
+                    target.slow_since.get_or_insert(at);
+                    target.slow += 1;
+                }
+                Some(_) => {
+                    target.slow = 0;
+                    target.slow_since = None;
+                }
+                None => {}
+            }
+        }
+
+        let down = target
+            .down_streak()
+            .filter(|(failures, _)| *failures >= thresholds.down_after.max(1) as u64)
+            .map(|(failures, since)| (since, format!("{} failed pings in a row", failures)));
+        target.set(AlarmReason::Down, down);
+
+        let changes = target.transitions.len();
+        let flapping = (changes >= FLAP_CHANGES).then(|| {
+            (
+                at,
+                format!(
+                    "{} changes between up and down in the last {} pings",
+                    changes,
+                    target.results.min(FLAP_WINDOW as u64)
+                ),
+            )
+        });
+        target.set(AlarmReason::Flapping, flapping);
+
+        let slow = (target.slow >= SLOW_PINGS).then(|| {
+            (
+                target.slow_since.unwrap_or(at),
+                format!(
+                    "{} pings in a row at or above {} ms",
+                    target.slow, thresholds.latency_crit_ms
+                ),
+            )
+        });
+        target.set(AlarmReason::HighLatency, slow);
+
+        let loss = smoothed_loss
+            .filter(|loss| *loss >= thresholds.loss_percent)
+            .map(|loss| {
+                (
+                    at,
+                    format!(
+                        "{:.1}% packet loss, at or above {}%",
+                        loss, thresholds.loss_percent
+                    ),
+                )
+            });
+        target.set(AlarmReason::HighLoss, loss);
+    }
+
+    /// Alarms of the given targets, in their order
+    pub fn alarms(&self, targets: &[PingTarget]) -> Vec<AlarmInfo> {
+        targets
+            .iter()
+            .filter_map(|t| self.targets.get(&t.address).map(|alarms| (t, alarms)))
+            .flat_map(|(target, alarms)| {
+                alarms.active.iter().map(move |alarm| AlarmInfo {
+                    id: target.id.clone(),
+                    target: target.address.clone(),
+                    target_label: target.label.clone(),
+                    reason: alarm.reason,
+                    since: alarm.since,
+                    detail: alarm.detail.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Whether a target's latest result succeeded, if it has any
+    pub fn is_up(&self, target_address: &str) -> Option<bool> {
+        Some(self.targets.get(target_address)?.status?.up)
+    }
+
+    /// Number of alarms of the given targets
+    pub fn alarm_count<'a, I>(&self, target_addresses: I) -> usize
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        target_addresses
+            .into_iter()
+            .filter_map(|a| self.targets.get(a))
+            .map(|alarms| alarms.active.len())
+            .sum()
+    }
+
+    /// Forget a target, e.g. when it is removed
+    pub fn remove_target(&mut self, target_address: &str) {
+        self.targets.remove(target_address);
+    }
+
+    /// Forget every target
+    pub fn clear(&mut self) {
+        self.targets.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLDS: AlarmThresholds = AlarmThresholds {
+        down_after: 3,
+        latency_crit_ms: 100.0,
+        loss_percent: 20.0,
+    };
+
+    fn target() -> PingTarget {
+        PingTarget::new("192.0.2.1".to_string(), "Test".to_string())
+    }
+
+    /// Result `seconds` after a fixed start, failed if `latency` is `None`
+    fn result(target: &PingTarget, seconds: i64, latency: Option<f64>) -> PingResult {
+        let mut result = match latency {
+            Some(latency) => PingResult::success(target, latency, seconds as u32),
+            None => PingResult::failure(target, "Timeout".to_string(), seconds as u32),
+        };
+        result.timestamp = DateTime::<Utc>::from_timestamp(1_700_000_000 + seconds, 0).unwrap();
+        result
+    }
+
+    fn reasons(tracker: &AlarmTracker, target: &PingTarget) -> Vec<AlarmReason> {
+        let targets = std::slice::from_ref(target);
+        tracker.alarms(targets).iter().map(|a| a.reason).collect()
+    }
+
+    #[test]
+    fn test_down_since_the_target_went_down() {
+        let target = target();
+        let mut tracker = AlarmTracker::new();
+        tracker.observe(&result(&target, 0, Some(10.0)), None, &THRESHOLDS);
+        for second in 1..=2 {
+            tracker.observe(&result(&target, second, None), None, &THRESHOLDS);
+        }
+        assert!(reasons(&tracker, &target).is_empty());
+        assert_eq!(tracker.is_up(&target.address), Some(false));
+
+        tracker.observe(&result(&target, 3, None), None, &THRESHOLDS);
+        let alarms = tracker.alarms(std::slice::from_ref(&target));
+        assert_eq!(alarms.len(), 1);
+        assert_eq!(alarms[0].reason, AlarmReason::Down);
+        assert_eq!(alarms[0].since, result(&target, 1, None).timestamp);
+        assert_eq!(alarms[0].detail, "3 failed pings in a row");
+
+        tracker.observe(&result(&target, 4, Some(10.0)), None, &THRESHOLDS);
+        assert!(reasons(&tracker, &target).is_empty());
+        assert_eq!(tracker.is_up(&target.address), Some(true));
+    }
+
+    #[test]
+    fn test_flapping_counts_changes_within_the_window() {
+        let target = target();
+        let mut tracker = AlarmTracker::new();
+        // Up, down, up, ... : the sixth change raises the alarm
+        for second in 0..FLAP_CHANGES as i64 {
+            let latency = (second % 2 == 0).then_some(10.0);
+            tracker.observe(&result(&target, second, latency), None, &THRESHOLDS);
+        }
+        assert!(reasons(&tracker, &target).is_empty());
+        let next = FLAP_CHANGES as i64;
+        tracker.observe(&result(&target, next, Some(10.0)), None, &THRESHOLDS);
+        assert_eq!(reasons(&tracker, &target), vec![AlarmReason::Flapping]);
+
+        // Steady results push the first change out of the window once the
+        // result before it is no longer among the latest
+        for second in next + 1..next + FLAP_WINDOW as i64 {
+            tracker.observe(&result(&target, second, Some(10.0)), None, &THRESHOLDS);
+            let expected = second < FLAP_WINDOW as i64;
+            assert_eq!(
+                reasons(&tracker, &target).contains(&AlarmReason::Flapping),
+                expected,
+                "after result {}",
+                second
+            );
+        }
+        assert!(reasons(&tracker, &target).is_empty());
+    }
+
+    #[test]
+    fn test_slow_and_lossy_targets() {
+        let target = target();
+        let mut tracker = AlarmTracker::new();
+        tracker.observe(&result(&target, 0, Some(150.0)), None, &THRESHOLDS);
+        tracker.observe(&result(&target, 1, Some(150.0)), None, &THRESHOLDS);
+        // A failure neither ends nor extends the slow run
+        tracker.observe(&result(&target, 2, None), None, &THRESHOLDS);
+        tracker.observe(&result(&target, 3, Some(150.0)), Some(25.0), &THRESHOLDS);
+
+        let alarms = tracker.alarms(std::slice::from_ref(&target));
+        let reasons: Vec<AlarmReason> = alarms.iter().map(|a| a.reason).collect();
+        assert_eq!(reasons, vec![AlarmReason::HighLatency, AlarmReason::HighLoss]);
+        assert_eq!(alarms[0].since, result(&target, 0, None).timestamp);
+
+        tracker.observe(&result(&target, 4, Some(10.0)), Some(5.0), &THRESHOLDS);
+        assert!(tracker.alarms(std::slice::from_ref(&target)).is_empty());
+    }
+
+    #[test]
+    fn test_removed_targets_have_no_alarms() {
+        let target = target();
+        let mut tracker = AlarmTracker::new();
+        for second in 0..3 {
+            tracker.observe(&result(&target, second, None), None, &THRESHOLDS);
+        }
+        assert_eq!(tracker.alarm_count([target.address.as_str()]), 1);
+
+        tracker.remove_target(&target.address);
+        assert_eq!(tracker.alarm_count([target.address.as_str()]), 0);
+        assert_eq!(tracker.is_up(&target.address), None);
+    }
+}
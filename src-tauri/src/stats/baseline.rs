@@ -0,0 +1,102 @@
+use super::percentile;
+use crate::models::{
+    Baseline, BaselineDeviation, ExpectedLatencyDeviation, PingStatistics, PingTarget,
+};
+use crate::ping::PingBackend;
+use chrono::Utc;
+use std::time::Duration;
+
+/// Measure a baseline for a target with a burst of `samples` probes
+pub fn capture_baseline(
+    backend: &dyn PingBackend,
+    target: &PingTarget,
+    samples: u32,
+    delay: Duration,
+) -> Baseline {
+    let samples = samples.max(1);
+    let mut latencies = Vec::with_capacity(samples as usize);
+
+    for sequence in 0..samples {
+        if sequence > 0 && !delay.is_zero() {
+            std::thread::sleep(delay);
+        }
+        if let Some(latency) = backend.ping(target, sequence).latency_ms {
+            latencies.push(latency);
+        }
+    }
+
+    latencies.sort_by(|a, b| a.total_cmp(b));
+    let failed = samples as usize - latencies.len();
+    let avg_latency_ms = if latencies.is_empty() {
+        None
+    } else {
+        Some(latencies.iter().sum::<f64>() / latencies.len() as f64)
+    };
+
+    Baseline {
+        avg_latency_ms,
+        p95_latency_ms: percentile(&latencies, 0.95),
+        packet_loss_percent: failed as f64 / samples as f64 * 100.0,
+        samples,
+        captured_at: Utc::now(),
+    }
+}
+
+/// How current statistics compare with a baseline
+pub fn deviation_from_baseline(stats: &PingStatistics, baseline: &Baseline) -> BaselineDeviation {
+    let latency_ratio = match (stats.avg_latency_ms, baseline.avg_latency_ms) {
+        (Some(current), Some(base)) if base > 0.0 => Some(current / base),
+        _ => None,
+    };
+
+    BaselineDeviation {
+        latency_ratio,
+        loss_change_points: stats.packet_loss_percent - baseline.packet_loss_percent,
+    }
+}
+
+/// How the current average latency compares with an expected latency
+///
+/// `None` until a ping succeeds, or if the expected latency isn't positive.
+pub fn deviation_from_expected(
+    stats: &PingStatistics,
+    expected_latency_ms: f64,
+) -> Option<ExpectedLatencyDeviation> {
+    let current = stats.avg_latency_ms?;
+    if expected_latency_ms <= 0.0 {
+        return None;
+    }
+
+    Some(ExpectedLatencyDeviation {
+        expected_latency_ms,
+        difference_ms: current - expected_latency_ms,
+        ratio: current / expected_latency_ms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ping::MockBackend;
+
+    #[test]
+    fn test_capture_baseline_and_deviation() {
+        let backend = MockBackend::new(vec![Some(10.0), Some(12.0), None, Some(14.0)]);
+        let target = PingTarget::new("1.1.1.1".to_string(), "Test".to_string());
+
+        let baseline = capture_baseline(&backend, &target, 4, Duration::ZERO);
+
+        assert_eq!(backend.calls(), 4);
+        assert_eq!(baseline.samples, 4);
+        assert_eq!(baseline.avg_latency_ms, Some(12.0));
+        assert!((baseline.p95_latency_ms.unwrap() - 13.8).abs() < 1e-9);
+        assert_eq!(baseline.packet_loss_percent, 25.0);
+
+        let mut stats = PingStatistics::new(&target);
+        stats.avg_latency_ms = Some(36.0);
+        stats.packet_loss_percent = 30.0;
+        let deviation = deviation_from_baseline(&stats, &baseline);
+        assert_eq!(deviation.latency_ratio, Some(3.0));
+        assert_eq!(deviation.loss_change_points, 5.0);
+    }
+}
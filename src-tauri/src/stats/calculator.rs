@@ -1,11 +1,33 @@
-use crate::models::{PingResult, PingStatistics, PingTarget};
+use super::{
+    loss_ratio, round_loss_percent, smooth_loss, BoundedRing, ConfidenceThresholds, HistogramBucket,
+    LatencyBounds, LatencyHistogram, DEFAULT_LOSS_ALPHA, DEFAULT_LOSS_DECIMALS,
+};
+use crate::models::{PingError, PingResult, PingStatistics, PingTarget, TargetError};
 use chrono::Utc;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+
+/// Successful pings kept for the recent-window latency figures
+pub const RECENT_LATENCY_WINDOW: usize = 30;
+
+/// Distinct errors kept per target
+pub const ERROR_HISTORY_CAPACITY: usize = 8;
 
 /// Statistics calculator for ping results
 pub struct StatsCalculator {
     /// Statistics per target (keyed by target address)
     stats: HashMap<String, TargetStats>,
+    /// Thresholds used to grade latency
+    latency_bounds: LatencyBounds,
+    /// Decimal places of the displayed packet loss
+    loss_decimals: u32,
+    /// Weight of each new result in the smoothed packet loss
+    loss_alpha: f64,
+    /// Successful pings kept per target for the recent-window figures
+    recent_window: usize,
+    /// Distinct errors kept per target
+    error_capacity: usize,
+    /// Sample counts at which latency figures become trustworthy
+    confidence: ConfidenceThresholds,
 }
 
 /// Internal statistics tracking for a single target
@@ -15,95 +37,231 @@ struct TargetStats {
     total_pings: u64,
     successful_pings: u64,
     failed_pings: u64,
-    latencies: Vec<f64>,
-    session_start: Option<chrono::DateTime<Utc>>,
+    latencies: LatencyHistogram,
+    /// Latest successful latencies, oldest first
+    recent_latencies: VecDeque<f64>,
+    /// Distinct recent errors, least recently seen first
+    errors: BoundedRing<TargetError>,
+    /// Failures per classified error kind
+    failure_kinds: HashMap<PingError, u64>,
+    /// Exponentially weighted packet loss, 0-100
+    smoothed_loss: Option<f64>,
+    /// Successes whose latency was negative, e.g. after a clock step
+    clock_anomalies: u64,
+    /// Timestamp of the first result since start or reset
+    first_seen: Option<chrono::DateTime<Utc>>,
+    /// Highest latency and when it was measured
+    slowest_ping: Option<(chrono::DateTime<Utc>, f64)>,
+    /// Lowest latency and when it was measured
+    fastest_ping: Option<(chrono::DateTime<Utc>, f64)>,
     last_ping: Option<chrono::DateTime<Utc>>,
 }
 
 impl TargetStats {
-    fn new(target: &PingTarget) -> Self {
+    fn new(target: &PingTarget, recent_window: usize, error_capacity: usize) -> Self {
         Self {
             target: target.address.clone(),
             target_label: target.label.clone(),
             total_pings: 0,
             successful_pings: 0,
             failed_pings: 0,
-            latencies: Vec::new(),
-            session_start: None,
+            latencies: LatencyHistogram::new(),
+            recent_latencies: VecDeque::with_capacity(recent_window),
+            errors: BoundedRing::new(error_capacity),
+            failure_kinds: HashMap::new(),
+            smoothed_loss: None,
+            clock_anomalies: 0,
+            first_seen: None,
+            slowest_ping: None,
+            fastest_ping: None,
             last_ping: None,
         }
     }
 
-    fn update(&mut self, result: &PingResult) {
+    fn update(&mut self, result: &PingResult, loss_alpha: f64, recent_window: usize) {
         self.total_pings += 1;
+        self.smoothed_loss = Some(smooth_loss(self.smoothed_loss, result.success, loss_alpha));
         self.last_ping = Some(result.timestamp);
         
-        if self.session_start.is_none() {
-            self.session_start = Some(result.timestamp);
+        if self.first_seen.is_none() {
+            self.first_seen = Some(result.timestamp);
         }
         
         if result.success {
             self.successful_pings += 1;
-            if let Some(latency) = result.latency_ms {
-                self.latencies.push(latency);
+            // A single NaN would poison every derived figure, and a negative
+            // latency from a clock step would drag down min and average
+            if let Some(latency) = result.latency_ms.filter(|l| *l < 0.0) {
+                log::warn!(
+                    "Dropping negative latency {} for {}, was the clock adjusted?",
+                    latency,
+                    result.target
+                );
+                self.clock_anomalies += 1;
+            } else if let Some(latency) = result.latency_ms.filter(|l| l.is_finite()) {
+                self.record_latency(latency, result.timestamp, recent_window);
             }
         } else {
             self.failed_pings += 1;
+            let kind = result
+                .error
+                .as_deref()
+                .map_or(PingError::Other, PingError::classify);
+            *self.failure_kinds.entry(kind).or_insert(0) += 1;
+            if let Some(error) = &result.error {
+                self.record_error(error, result.timestamp);
+            }
+        }
+    }
+
+    /// Move an error to the back of the ring, counting repeats of the same message
+    /// Add a valid latency measured at `at` to every latency figure
+    fn record_latency(&mut self, latency: f64, at: chrono::DateTime<Utc>, recent_window: usize) {
+        self.latencies.record(latency);
+        if self.slowest_ping.map_or(true, |(_, ms)| latency > ms) {
+            self.slowest_ping = Some((at, latency));
         }
+        if self.fastest_ping.map_or(true, |(_, ms)| latency < ms) {
+            self.fastest_ping = Some((at, latency));
+        }
+        while self.recent_latencies.len() >= recent_window.max(1) {
+            self.recent_latencies.pop_front();
+        }
+        self.recent_latencies.push_back(latency);
+    }
+
+    fn record_error(&mut self, error: &str, at: chrono::DateTime<Utc>) {
+        let existing = self.errors.iter().position(|e| e.error == error);
+        let entry = match existing.and_then(|index| self.errors.remove(index)) {
+            Some(mut entry) => {
+                entry.count += 1;
+                entry.last_seen = at;
+                entry
+            }
+            None => TargetError {
+                error: error.to_string(),
+                kind: PingError::classify(error),
+                count: 1,
+                first_seen: at,
+                last_seen: at,
+            },
+        };
+        self.errors.push(entry);
     }
 
-    fn to_statistics(&self) -> PingStatistics {
-        let packet_loss_percent = if self.total_pings > 0 {
-            (self.failed_pings as f64 / self.total_pings as f64) * 100.0
+    fn to_statistics(
+        &self,
+        bounds: &LatencyBounds,
+        loss_decimals: u32,
+        confidence: &ConfidenceThresholds,
+    ) -> PingStatistics {
+        let loss_ratio = loss_ratio(self.failed_pings, self.total_pings);
+        let packet_loss_percent = loss_ratio * 100.0;
+
+        // Jitter is the standard deviation of latency
+        let min_latency_ms = self.latencies.min();
+        let max_latency_ms = self.latencies.max();
+        let avg_latency_ms = self.latencies.mean();
+        let jitter_ms = self.latencies.std_dev();
+
+        // Best recent RTT approximates the unloaded path; the recent average
+        // sitting above it is queueing delay
+        let (recent_min_latency_ms, congestion_ms) = if self.recent_latencies.is_empty() {
+            (None, None)
         } else {
-            0.0
+            let min = self.recent_latencies.iter().cloned().fold(f64::INFINITY, f64::min);
+            let avg =
+                self.recent_latencies.iter().sum::<f64>() / self.recent_latencies.len() as f64;
+            (Some(min), Some(avg - min))
         };
 
-        let (min_latency_ms, max_latency_ms, avg_latency_ms, jitter_ms) = 
-            if !self.latencies.is_empty() {
-                let min = self.latencies.iter().cloned().fold(f64::INFINITY, f64::min);
-                let max = self.latencies.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-                let sum: f64 = self.latencies.iter().sum();
-                let avg = sum / self.latencies.len() as f64;
-                
-                // Calculate jitter (average deviation from mean)
-                let jitter = if self.latencies.len() > 1 {
-                    let variance: f64 = self.latencies
-                        .iter()
-                        .map(|&x| (x - avg).powi(2))
-                        .sum::<f64>() / (self.latencies.len() - 1) as f64;
-                    variance.sqrt()
-                } else {
-                    0.0
-                };
-                
-                (Some(min), Some(max), Some(avg), Some(jitter))
-            } else {
-                (None, None, None, None)
-            };
+        let latency_normalized = if self.total_pings > 0 {
+            Some(bounds.normalize(avg_latency_ms))
+        } else {
+            None
+        };
 
         PingStatistics {
             target: self.target.clone(),
             target_label: self.target_label.clone(),
+            display_label: self.target_label.clone(),
             total_pings: self.total_pings,
             successful_pings: self.successful_pings,
             failed_pings: self.failed_pings,
             packet_loss_percent,
+            packet_loss_display: round_loss_percent(packet_loss_percent, loss_decimals),
+            loss_ratio,
+            smoothed_loss_percent: self.smoothed_loss,
+            clock_anomalies: self.clock_anomalies,
             min_latency_ms,
             max_latency_ms,
             avg_latency_ms,
             jitter_ms,
-            session_start: self.session_start,
+            sample_confidence: confidence.grade(self.latencies.len()),
+            recent_min_latency_ms,
+            congestion_ms,
+            latency_normalized,
+            deviation_from_baseline: None,
+            latency_vs_expected: None,
+            lifetime: None,
+            session_start: self.first_seen,
+            monitor_session_start: None,
+            first_seen: self.first_seen,
+            slowest_ping: self.slowest_ping,
+            fastest_ping: self.fastest_ping,
             last_ping: self.last_ping,
+            last_error: self.errors.back().map(|e| e.error.clone()),
+            last_error_kind: self.errors.back().map(|e| e.kind),
         }
     }
 
+    /// Latencies held and approximate bytes used
+    fn usage(&self) -> (u64, usize) {
+        let samples = self.latencies.len() + self.recent_latencies.len() as u64;
+        let errors: usize = self
+            .errors
+            .iter()
+            .map(|e| std::mem::size_of::<TargetError>() + e.error.capacity())
+            .sum();
+        let bytes = std::mem::size_of::<Self>()
+            + self.target.capacity()
+            + self.target_label.capacity()
+            + self.latencies.approx_size()
+            + self.recent_latencies.capacity() * std::mem::size_of::<f64>()
+            + errors;
+        (samples, bytes)
+    }
+
+    /// Drop the oldest recent latencies beyond `window` and release their memory
+    fn shrink_recent(&mut self, window: usize) {
+        while self.recent_latencies.len() > window {
+            self.recent_latencies.pop_front();
+        }
+        self.recent_latencies.shrink_to_fit();
+    }
+
+    /// Start the latency distribution and smoothed loss afresh, keeping the counts
+    fn reset_latency(&mut self) {
+        self.latencies.clear();
+        self.recent_latencies.clear();
+        self.smoothed_loss = None;
+        self.slowest_ping = None;
+        self.fastest_ping = None;
+    }
+
     fn reset(&mut self) {
         self.total_pings = 0;
         self.successful_pings = 0;
         self.failed_pings = 0;
         self.latencies.clear();
-        self.session_start = None;
+        self.recent_latencies.clear();
+        self.errors.clear();
+        self.failure_kinds.clear();
+        self.smoothed_loss = None;
+        self.clock_anomalies = 0;
+        self.first_seen = None;
+        self.slowest_ping = None;
+        self.fastest_ping = None;
         self.last_ping = None;
     }
 }
@@ -112,13 +270,75 @@ impl StatsCalculator {
     pub fn new() -> Self {
         Self {
             stats: HashMap::new(),
+            latency_bounds: LatencyBounds::default(),
+            loss_decimals: DEFAULT_LOSS_DECIMALS,
+            loss_alpha: DEFAULT_LOSS_ALPHA,
+            recent_window: RECENT_LATENCY_WINDOW,
+            error_capacity: ERROR_HISTORY_CAPACITY,
+            confidence: ConfidenceThresholds::default(),
+        }
+    }
+
+    /// Set the thresholds used to grade latency
+    pub fn set_latency_bounds(&mut self, bounds: LatencyBounds) {
+        self.latency_bounds = bounds;
+    }
+
+    /// Set the decimal places of `packet_loss_display`
+    pub fn set_loss_decimals(&mut self, decimals: u32) {
+        self.loss_decimals = decimals;
+    }
+
+    /// Set the weight of each new result in `smoothed_loss_percent`
+    pub fn set_loss_alpha(&mut self, alpha: f64) {
+        self.loss_alpha = alpha;
+    }
+
+    /// Set the sample counts that grade `sample_confidence`
+    pub fn set_confidence_thresholds(&mut self, thresholds: ConfidenceThresholds) {
+        self.confidence = thresholds;
+    }
+
+    /// Set the successful pings kept per target for the recent-window figures
+    ///
+    /// Shrinking drops each target's oldest recent latencies right away.
+    pub fn set_recent_window(&mut self, window: usize) {
+        self.recent_window = window;
+        for stats in self.stats.values_mut() {
+            stats.shrink_recent(window);
         }
     }
 
+    /// Set the distinct errors kept per target, dropping the oldest right away
+    pub fn set_error_capacity(&mut self, capacity: usize) {
+        self.error_capacity = capacity;
+        for stats in self.stats.values_mut() {
+            stats.errors.set_capacity(capacity);
+        }
+    }
+
+    /// Successful pings kept per target for the recent-window figures
+    pub fn recent_window(&self) -> usize {
+        self.recent_window
+    }
+
+    /// Build statistics from a sequence of results, e.g. streamed from a log file
+    pub fn from_results<I>(results: I) -> Self
+    where
+        I: IntoIterator<Item = PingResult>,
+    {
+        let mut calculator = Self::new();
+        for result in results {
+            calculator.update(&result);
+        }
+        calculator
+    }
+
     /// Initialize statistics for a target
     pub fn init_target(&mut self, target: &PingTarget) {
         if !self.stats.contains_key(&target.address) {
-            self.stats.insert(target.address.clone(), TargetStats::new(target));
+            let stats = TargetStats::new(target, self.recent_window, self.error_capacity);
+            self.stats.insert(target.address.clone(), stats);
         }
     }
 
@@ -127,22 +347,108 @@ impl StatsCalculator {
         // If the target doesn't exist in stats, create it
         if !self.stats.contains_key(&result.target) {
             let target = PingTarget::new(result.target.clone(), result.target_label.clone());
-            self.stats.insert(result.target.clone(), TargetStats::new(&target));
+            let stats = TargetStats::new(&target, self.recent_window, self.error_capacity);
+            self.stats.insert(result.target.clone(), stats);
         }
         
         if let Some(stats) = self.stats.get_mut(&result.target) {
-            stats.update(result);
+            stats.update(result, self.loss_alpha, self.recent_window);
         }
     }
 
     /// Get statistics for a specific target
     pub fn get_stats(&self, target_address: &str) -> Option<PingStatistics> {
-        self.stats.get(target_address).map(|s| s.to_statistics())
+        self.stats
+            .get(target_address)
+            .map(|s| s.to_statistics(&self.latency_bounds, self.loss_decimals, &self.confidence))
+    }
+
+    /// Count a ping recorded as failed as answered after `latency_ms` after
+    /// all, by a reply received at `received_at`
+    pub fn record_late_reply(
+        &mut self,
+        target_address: &str,
+        latency_ms: f64,
+        received_at: chrono::DateTime<Utc>,
+    ) {
+        if let Some(stats) = self.stats.get_mut(target_address) {
+            if stats.failed_pings > 0 {
+                stats.failed_pings -= 1;
+                stats.successful_pings += 1;
+                if latency_ms.is_finite() && latency_ms >= 0.0 {
+                    stats.record_latency(latency_ms, received_at, self.recent_window);
+                }
+            }
+        }
+    }
+
+    /// Latency percentile for a target, `q` in 0-1
+    ///
+    /// Accurate to within [`super::HISTOGRAM_PRECISION`].
+    pub fn latency_percentile(&self, target_address: &str, q: f64) -> Option<f64> {
+        self.stats.get(target_address)?.latencies.percentile(q)
+    }
+
+    /// Non-empty latency histogram buckets for a target
+    pub fn latency_histogram(&self, target_address: &str) -> Option<Vec<HistogramBucket>> {
+        Some(self.stats.get(target_address)?.latencies.buckets())
+    }
+
+    /// Distinct recent errors of a target, most recently seen first
+    pub fn target_errors(&self, target_address: &str) -> Option<Vec<TargetError>> {
+        Some(self.stats.get(target_address)?.errors.iter().rev().cloned().collect())
+    }
+
+    /// Failures of a target counted by classified error kind
+    pub fn failure_breakdown(&self, target_address: &str) -> Option<HashMap<PingError, u64>> {
+        Some(self.stats.get(target_address)?.failure_kinds.clone())
+    }
+
+    /// Smoothed packet loss of a target, 0-100
+    pub fn smoothed_loss(&self, target_address: &str) -> Option<f64> {
+        self.stats.get(target_address)?.smoothed_loss
+    }
+
+    /// Number of targets, latencies held and approximate bytes used
+    pub fn usage(&self) -> (usize, u64, usize) {
+        self.stats
+            .values()
+            .map(TargetStats::usage)
+            .fold((self.stats.len(), 0, 0), |(targets, samples, bytes), (s, b)| {
+                (targets, samples + s, bytes + b)
+            })
+    }
+
+    /// Total and failed pings of the given targets, and the mean of all
+    /// their recorded latencies
+    pub fn totals<'a, I>(&self, target_addresses: I) -> (u64, u64, Option<f64>)
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let (mut total, mut failed, mut samples, mut latency_sum) = (0, 0, 0, 0.0);
+        for stats in target_addresses.into_iter().filter_map(|a| self.stats.get(a)) {
+            total += stats.total_pings;
+            failed += stats.failed_pings;
+            if let Some(mean) = stats.latencies.mean() {
+                samples += stats.latencies.len();
+                latency_sum += mean * stats.latencies.len() as f64;
+            }
+        }
+        (total, failed, (samples > 0).then(|| latency_sum / samples as f64))
     }
 
-    /// Get statistics for all targets
+    /// Get statistics for all targets, sorted by label and then address
     pub fn get_all_stats(&self) -> Vec<PingStatistics> {
-        self.stats.values().map(|s| s.to_statistics()).collect()
+        let mut stats: Vec<PingStatistics> = self
+            .stats
+            .values()
+            .map(|s| s.to_statistics(&self.latency_bounds, self.loss_decimals, &self.confidence))
+            .collect();
+        stats.sort_by(|a, b| {
+            (a.target_label.as_str(), a.target.as_str())
+                .cmp(&(b.target_label.as_str(), b.target.as_str()))
+        });
+        stats
     }
 
     /// Reset statistics for a specific target
@@ -152,6 +458,13 @@ impl StatsCalculator {
         }
     }
 
+    /// Reset a target's latency figures and smoothed loss, keeping its counters
+    pub fn reset_latency(&mut self, target_address: &str) {
+        if let Some(stats) = self.stats.get_mut(target_address) {
+            stats.reset_latency();
+        }
+    }
+
     /// Reset all statistics
     pub fn reset_all(&mut self) {
         for stats in self.stats.values_mut() {
@@ -159,6 +472,11 @@ impl StatsCalculator {
         }
     }
 
+    /// Forget every target, keeping the grading and display settings
+    pub fn clear(&mut self) {
+        self.stats.clear();
+    }
+
     /// Remove a target from statistics
     pub fn remove_target(&mut self, target_address: &str) {
         self.stats.remove(target_address);
@@ -200,4 +518,284 @@ mod tests {
         assert_eq!(stats.max_latency_ms, Some(20.0));
         assert_eq!(stats.avg_latency_ms, Some(15.0));
     }
+
+    #[test]
+    fn test_late_reply_counts_as_success_with_its_latency() {
+        let mut calc = StatsCalculator::new();
+        let target = PingTarget::new("1.1.1.1".to_string(), "Test".to_string());
+        calc.update(&PingResult::success(&target, 10.0, 1));
+        calc.update(&PingResult::failure(&target, "Timeout".to_string(), 2));
+
+        let received_at = chrono::DateTime::<Utc>::from_timestamp(1_700_000_000, 0).unwrap();
+        calc.record_late_reply("1.1.1.1", 5100.0, received_at);
+        let stats = calc.get_stats("1.1.1.1").unwrap();
+        assert_eq!((stats.successful_pings, stats.failed_pings), (2, 0));
+        assert_eq!(stats.max_latency_ms, Some(5100.0));
+        assert_eq!(stats.avg_latency_ms, Some(2555.0));
+        assert_eq!(stats.slowest_ping, Some((received_at, 5100.0)));
+
+        // Nothing is left to un-lose
+        calc.record_late_reply("1.1.1.1", 6000.0, received_at);
+        assert_eq!(calc.get_stats("1.1.1.1").unwrap().max_latency_ms, Some(5100.0));
+
+        // The only reply so far is the fastest and the recent window's minimum
+        let mut calc = StatsCalculator::new();
+        calc.update(&PingResult::failure(&target, "Timeout".to_string(), 1));
+        calc.record_late_reply("1.1.1.1", 1200.0, received_at);
+        let stats = calc.get_stats("1.1.1.1").unwrap();
+        assert_eq!(stats.fastest_ping, Some((received_at, 1200.0)));
+        assert_eq!(stats.recent_min_latency_ms, Some(1200.0));
+    }
+
+    #[test]
+    fn test_non_finite_latencies_are_sanitized() {
+        let mut calc = StatsCalculator::new();
+        let target = PingTarget::new("1.1.1.1".to_string(), "Test".to_string());
+
+        for latency in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let result = PingResult::success(&target, latency, 1);
+            assert_eq!(result.latency_ms, None);
+            assert!(serde_json::to_string(&result).is_ok());
+            calc.update(&result);
+        }
+
+        // Results built by hand, e.g. decoded from a log, are skipped too
+        let mut result = PingResult::success(&target, 10.0, 2);
+        calc.update(&result);
+        result.latency_ms = Some(f64::NAN);
+        calc.update(&result);
+        calc.update(&PingResult::success(&target, 20.0, 3));
+
+        let stats = calc.get_stats("1.1.1.1").unwrap();
+        assert_eq!(stats.successful_pings, 6);
+        assert_eq!(stats.min_latency_ms, Some(10.0));
+        assert_eq!(stats.max_latency_ms, Some(20.0));
+        assert_eq!(stats.avg_latency_ms, Some(15.0));
+        assert!(stats.jitter_ms.unwrap().is_finite());
+        assert!(stats.congestion_ms.unwrap().is_finite());
+    }
+
+    #[test]
+    fn test_negative_latencies_are_dropped_and_counted() {
+        let mut calc = StatsCalculator::new();
+        let target = PingTarget::new("1.1.1.1".to_string(), "Test".to_string());
+
+        calc.update(&PingResult::success(&target, 12.0, 1));
+        calc.update(&PingResult::success(&target, -950.0, 2));
+        calc.update(&PingResult::success(&target, 18.0, 3));
+
+        let stats = calc.get_stats("1.1.1.1").unwrap();
+        assert_eq!(stats.clock_anomalies, 1);
+        assert_eq!(stats.successful_pings, 3);
+        assert_eq!(stats.min_latency_ms, Some(12.0));
+        assert_eq!(stats.avg_latency_ms, Some(15.0));
+        assert_eq!(stats.fastest_ping.map(|(_, ms)| ms), Some(12.0));
+        assert_eq!(stats.recent_min_latency_ms, Some(12.0));
+
+        calc.reset_all();
+        assert_eq!(calc.get_stats("1.1.1.1").unwrap().clock_anomalies, 0);
+    }
+
+    #[test]
+    fn test_error_history_keeps_distinct_errors() {
+        let mut calc = StatsCalculator::new();
+        let target = PingTarget::new("192.0.2.1".to_string(), "Test".to_string());
+        let unreachable = "Ping failed: Destination Host Unreachable";
+
+        calc.update(&PingResult::failure(&target, "Request timed out".to_string(), 1));
+        calc.update(&PingResult::failure(&target, unreachable.to_string(), 2));
+        calc.update(&PingResult::failure(&target, "Request timed out".to_string(), 3));
+        calc.update(&PingResult::success(&target, 12.0, 4));
+
+        // The last error survives a later success
+        let stats = calc.get_stats("192.0.2.1").unwrap();
+        assert_eq!(stats.last_error.as_deref(), Some("Request timed out"));
+        assert_eq!(stats.last_error_kind, Some(PingError::Timeout));
+
+        let errors = calc.target_errors("192.0.2.1").unwrap();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].kind, PingError::Timeout);
+        assert_eq!(errors[0].count, 2);
+        assert_eq!(errors[1].error, unreachable);
+        assert_eq!(errors[1].kind, PingError::Unreachable);
+
+        // Only the most recent distinct errors are kept
+        for i in 0..ERROR_HISTORY_CAPACITY {
+            let error = format!("Send failed: error {}", i);
+            calc.update(&PingResult::failure(&target, error, 5));
+        }
+        let errors = calc.target_errors("192.0.2.1").unwrap();
+        assert_eq!(errors.len(), ERROR_HISTORY_CAPACITY);
+        assert!(errors.iter().all(|e| e.kind == PingError::Other));
+        assert_eq!(calc.get_stats("192.0.2.1").unwrap().last_error_kind, Some(PingError::Other));
+    }
+
+    #[test]
+    fn test_failure_breakdown_counts_each_kind() {
+        let mut calc = StatsCalculator::new();
+        let target = PingTarget::new("192.0.2.1".to_string(), "Test".to_string());
+        let errors = [
+            "Request timed out",
+            "Request timed out",
+            "Request timed out",
+            "Request timed out",
+            "Ping failed: Destination Host Unreachable",
+            "Cannot resolve host.example",
+            "Send failed: no buffer space",
+        ];
+        for (sequence, error) in errors.iter().enumerate() {
+            calc.update(&PingResult::failure(&target, error.to_string(), sequence as u32));
+        }
+        let mut unexplained = PingResult::failure(&target, String::new(), 7);
+        unexplained.error = None;
+        calc.update(&unexplained);
+        calc.update(&PingResult::success(&target, 10.0, 8));
+
+        let breakdown = calc.failure_breakdown("192.0.2.1").unwrap();
+        assert_eq!(
+            breakdown,
+            HashMap::from([
+                (PingError::Timeout, 4),
+                (PingError::Unreachable, 1),
+                (PingError::UnknownHost, 1),
+                (PingError::Other, 2),
+            ])
+        );
+        let failed = calc.get_stats("192.0.2.1").unwrap().failed_pings;
+        assert_eq!(breakdown.values().sum::<u64>(), failed);
+
+        calc.reset_all();
+        assert!(calc.failure_breakdown("192.0.2.1").unwrap().is_empty());
+        assert_eq!(calc.failure_breakdown("192.0.2.9"), None);
+    }
+
+    #[test]
+    fn test_congestion_spike_against_recent_min() {
+        let mut calc = StatsCalculator::new();
+        let target = PingTarget::new("1.1.1.1".to_string(), "Test".to_string());
+
+        for sequence in 0..20 {
+            calc.update(&PingResult::success(&target, 20.0, sequence));
+        }
+        let stats = calc.get_stats("1.1.1.1").unwrap();
+        assert_eq!(stats.recent_min_latency_ms, Some(20.0));
+        assert_eq!(stats.congestion_ms, Some(0.0));
+
+        // Queues fill up: latency climbs while the best recent RTT holds
+        for sequence in 20..30 {
+            calc.update(&PingResult::success(&target, 80.0, sequence));
+        }
+        let stats = calc.get_stats("1.1.1.1").unwrap();
+        assert_eq!(stats.recent_min_latency_ms, Some(20.0));
+        assert_eq!(stats.congestion_ms, Some(20.0));
+
+        // The spike ages out of the window once the link is quiet again
+        for sequence in 30..60 {
+            calc.update(&PingResult::success(&target, 20.0, sequence));
+        }
+        assert_eq!(calc.get_stats("1.1.1.1").unwrap().congestion_ms, Some(0.0));
+    }
+
+    #[test]
+    fn test_slowest_and_fastest_pings_keep_their_timestamps() {
+        let mut calc = StatsCalculator::new();
+        let target = PingTarget::new("1.1.1.1".to_string(), "Test".to_string());
+        let start = chrono::DateTime::<Utc>::from_timestamp(1_700_000_000, 0).unwrap();
+        let at = |secs: i64| start + chrono::Duration::seconds(secs);
+        for (secs, latency) in [(0, 20.0), (1, 8.0), (2, 95.0), (3, 8.0), (4, 40.0)] {
+            let mut result = PingResult::success(&target, latency, secs as u32);
+            result.timestamp = at(secs);
+            calc.update(&result);
+        }
+        let mut failure = PingResult::failure(&target, "Timeout".to_string(), 5);
+        failure.timestamp = at(5);
+        calc.update(&failure);
+
+        let stats = calc.get_stats("1.1.1.1").unwrap();
+        assert_eq!(stats.slowest_ping, Some((at(2), 95.0)));
+        // The first of equal latencies is kept
+        assert_eq!(stats.fastest_ping, Some((at(1), 8.0)));
+
+        calc.reset_all();
+        let stats = calc.get_stats("1.1.1.1").unwrap();
+        assert_eq!(stats.slowest_ping, None);
+        assert_eq!(stats.fastest_ping, None);
+    }
+
+    #[test]
+    fn test_reset_latency_keeps_counters() {
+        let mut calc = StatsCalculator::new();
+        let target = PingTarget::new("1.1.1.1".to_string(), "Test".to_string());
+        for sequence in 0..8 {
+            calc.update(&PingResult::success(&target, 80.0, sequence));
+        }
+        calc.update(&PingResult::failure(&target, "Request timed out".to_string(), 8));
+        calc.update(&PingResult::failure(&target, "Request timed out".to_string(), 9));
+
+        calc.reset_latency("1.1.1.1");
+        let stats = calc.get_stats("1.1.1.1").unwrap();
+        assert_eq!(stats.avg_latency_ms, None);
+        assert_eq!(stats.jitter_ms, None);
+        assert_eq!(stats.recent_min_latency_ms, None);
+        assert_eq!(stats.smoothed_loss_percent, None);
+        assert_eq!(calc.latency_percentile("1.1.1.1", 0.95), None);
+        assert_eq!(stats.total_pings, 10);
+        assert_eq!(stats.successful_pings, 8);
+        assert_eq!(stats.packet_loss_percent, 20.0);
+
+        // Only latencies from after the reset count towards the new regime
+        calc.update(&PingResult::success(&target, 5.0, 10));
+        let stats = calc.get_stats("1.1.1.1").unwrap();
+        assert_eq!(stats.avg_latency_ms, Some(5.0));
+        assert_eq!(stats.max_latency_ms, Some(5.0));
+        assert_eq!(stats.total_pings, 11);
+    }
+
+    #[test]
+    fn test_shrinking_recent_window_drops_oldest_latencies() {
+        let mut calc = StatsCalculator::new();
+        let target = PingTarget::new("1.1.1.1".to_string(), "Test".to_string());
+        for sequence in 0..30 {
+            let latency = if sequence < 25 { 10.0 } else { 40.0 };
+            calc.update(&PingResult::success(&target, latency, sequence));
+        }
+        assert_eq!(calc.get_stats("1.1.1.1").unwrap().recent_min_latency_ms, Some(10.0));
+
+        calc.set_recent_window(5);
+        assert_eq!(calc.usage().1, 30 + 5);
+        assert_eq!(calc.get_stats("1.1.1.1").unwrap().recent_min_latency_ms, Some(40.0));
+
+        // New results stay within the smaller window
+        calc.update(&PingResult::success(&target, 50.0, 30));
+        assert_eq!(calc.usage().1, 31 + 5);
+    }
+
+    #[test]
+    fn test_incremental_figures_match_batch_computation() {
+        let mut calc = StatsCalculator::new();
+        let target = PingTarget::new("1.1.1.1".to_string(), "Test".to_string());
+        let latencies: Vec<f64> = (0..10_000u32)
+            .map(|i| 20.0 + f64::from(i % 37) * 0.7 + f64::from(i % 101).sqrt())
+            .collect();
+        for (sequence, latency) in latencies.iter().enumerate() {
+            calc.update(&PingResult::success(&target, *latency, sequence as u32));
+        }
+        let stats = calc.get_stats("1.1.1.1").unwrap();
+
+        let n = latencies.len() as f64;
+        let mean = latencies.iter().sum::<f64>() / n;
+        let variance = latencies.iter().map(|l| (l - mean).powi(2)).sum::<f64>() / (n - 1.0);
+        assert!((stats.avg_latency_ms.unwrap() - mean).abs() < 1e-9);
+        assert!((stats.jitter_ms.unwrap() - variance.sqrt()).abs() < 1e-9);
+        let min = latencies.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = latencies.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        assert_eq!(stats.min_latency_ms, Some(min));
+        assert_eq!(stats.max_latency_ms, Some(max));
+
+        let recent = &latencies[latencies.len() - RECENT_LATENCY_WINDOW..];
+        let recent_min = recent.iter().cloned().fold(f64::INFINITY, f64::min);
+        let recent_avg = recent.iter().sum::<f64>() / recent.len() as f64;
+        assert_eq!(stats.recent_min_latency_ms, Some(recent_min));
+        assert!((stats.congestion_ms.unwrap() - (recent_avg - recent_min)).abs() < 1e-9);
+    }
 }
@@ -0,0 +1,119 @@
+use super::StatsCalculator;
+use crate::logging::query_logs;
+use crate::models::{PeriodComparison, PeriodSummary, PingStatistics, TimeRange};
+use std::path::Path;
+
+/// Statistics for a target computed from results logged within `range`
+pub fn stats_for_range(
+    log_dir: &Path,
+    target: &str,
+    range: TimeRange,
+) -> Result<Option<PingStatistics>, std::io::Error> {
+    let results = query_logs(log_dir, Some(target), None, range)?;
+    Ok(StatsCalculator::from_results(results).get_stats(target))
+}
+
+/// Compare a target's logged results over two periods
+pub fn compare_periods(
+    log_dir: &Path,
+    target: &str,
+    period_a: TimeRange,
+    period_b: TimeRange,
+) -> Result<PeriodComparison, std::io::Error> {
+    let stats_a = stats_for_range(log_dir, target, period_a)?;
+    let stats_b = stats_for_range(log_dir, target, period_b)?;
+
+    let metric = |f: fn(&PingStatistics) -> Option<f64>| {
+        let a = stats_a.as_ref().and_then(f)?;
+        let b = stats_b.as_ref().and_then(f)?;
+        Some((a, b))
+    };
+
+    let latency = metric(|s| s.avg_latency_ms);
+    let latency_change_percent = latency.and_then(|(a, b)| {
+        if a > 0.0 {
+            Some((b - a) / a * 100.0)
+        } else {
+            None
+        }
+    });
+
+    Ok(PeriodComparison {
+        target: target.to_string(),
+        latency_change_ms: latency.map(|(a, b)| b - a),
+        latency_change_percent,
+        loss_change_points: metric(|s| Some(s.packet_loss_percent)).map(|(a, b)| b - a),
+        jitter_change_ms: metric(|s| s.jitter_ms).map(|(a, b)| b - a),
+        period_a: PeriodSummary {
+            range: period_a,
+            stats: stats_a,
+        },
+        period_b: PeriodSummary {
+            range: period_b,
+            stats: stats_b,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{PingResult, PingTarget};
+    use chrono::{Duration, TimeZone, Utc};
+    use std::fs;
+    use std::io::Write;
+
+    #[test]
+    fn test_compare_periods_of_differing_quality() {
+        let dir = std::env::temp_dir().join(format!("ping-compare-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let target = PingTarget::new("1.1.1.1".to_string(), "Cloudflare".to_string());
+        let night_a = Utc.with_ymd_and_hms(2025, 3, 1, 22, 0, 0).unwrap();
+        let night_b = Utc.with_ymd_and_hms(2025, 3, 2, 22, 0, 0).unwrap();
+
+        let mut lines = Vec::new();
+        for i in 0..4 {
+            // First night: steady 10ms, no loss
+            let mut result = PingResult::success(&target, 10.0, i);
+            result.timestamp = night_a + Duration::minutes(i as i64);
+            lines.push(result);
+
+            // Second night: 20ms with one failure in four
+            let mut result = if i == 3 {
+                PingResult::failure(&target, "Timeout".to_string(), i)
+            } else {
+                PingResult::success(&target, 20.0, i)
+            };
+            result.timestamp = night_b + Duration::minutes(i as i64);
+            lines.push(result);
+        }
+        for result in lines {
+            let name = format!("ping-{}.jsonl", result.timestamp.format("%Y-%m-%d"));
+            let mut file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(dir.join(name))
+                .unwrap();
+            writeln!(file, "{}", serde_json::to_string(&result).unwrap()).unwrap();
+        }
+
+        let period_a = TimeRange::new(night_a, night_a + Duration::hours(1));
+        let period_b = TimeRange::new(night_b, night_b + Duration::hours(1));
+        let comparison = compare_periods(&dir, "1.1.1.1", period_a, period_b).unwrap();
+
+        assert_eq!(comparison.period_a.stats.as_ref().unwrap().total_pings, 4);
+        assert_eq!(comparison.period_b.stats.as_ref().unwrap().total_pings, 4);
+        assert_eq!(comparison.latency_change_ms, Some(10.0));
+        assert_eq!(comparison.latency_change_percent, Some(100.0));
+        assert_eq!(comparison.loss_change_points, Some(25.0));
+
+        // A period without data yields no stats and no deltas
+        let empty = TimeRange::new(night_a - Duration::days(10), night_a - Duration::days(9));
+        let comparison = compare_periods(&dir, "1.1.1.1", empty, period_b).unwrap();
+        assert!(comparison.period_a.stats.is_none());
+        assert_eq!(comparison.latency_change_ms, None);
+        assert_eq!(comparison.loss_change_points, None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
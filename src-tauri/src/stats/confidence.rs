@@ -0,0 +1,73 @@
+use crate::models::Confidence;
+
+/// Latency samples below which statistics are too few to mean anything
+pub const DEFAULT_CONFIDENCE_LOW_SAMPLES: u64 = 10;
+
+/// Latency samples from which statistics are reliable
+pub const DEFAULT_CONFIDENCE_HIGH_SAMPLES: u64 = 100;
+
+/// Sample counts at which statistics become trustworthy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfidenceThresholds {
+    /// Samples needed for `Low` confidence
+    pub low: u64,
+    /// Samples needed for `High` confidence
+    pub high: u64,
+}
+
+impl ConfidenceThresholds {
+    pub fn new(low: u64, high: u64) -> Self {
+        Self { low, high }
+    }
+
+    /// Confidence in figures computed from `samples` values
+    pub fn grade(&self, samples: u64) -> Confidence {
+        if samples >= self.high.max(self.low) {
+            Confidence::High
+        } else if samples >= self.low {
+            Confidence::Low
+        } else {
+            Confidence::Insufficient
+        }
+    }
+}
+
+impl Default for ConfidenceThresholds {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_CONFIDENCE_LOW_SAMPLES,
+            DEFAULT_CONFIDENCE_HIGH_SAMPLES,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_counts_map_to_confidence() {
+        let thresholds = ConfidenceThresholds::default();
+        let grades: Vec<Confidence> = [0, 1, 9, 10, 99, 100, 5000]
+            .into_iter()
+            .map(|samples| thresholds.grade(samples))
+            .collect();
+        assert_eq!(
+            grades,
+            vec![
+                Confidence::Insufficient,
+                Confidence::Insufficient,
+                Confidence::Insufficient,
+                Confidence::Low,
+                Confidence::Low,
+                Confidence::High,
+                Confidence::High,
+            ]
+        );
+
+        let custom = ConfidenceThresholds::new(3, 20);
+        assert_eq!(custom.grade(2), Confidence::Insufficient);
+        assert_eq!(custom.grade(3), Confidence::Low);
+        assert_eq!(custom.grade(20), Confidence::High);
+    }
+}
@@ -0,0 +1,195 @@
+use crate::logging::query_logs;
+use crate::models::{Correlation, PingResult, TimeRange};
+use chrono::Duration;
+use std::path::Path;
+
+/// Correlate two targets' results
+///
+/// Both slices must be ordered by time. Each result is paired with at most
+/// one result of the other target taken within `max_skew` of it.
+pub fn correlate(
+    target_a: &str,
+    target_b: &str,
+    results_a: &[PingResult],
+    results_b: &[PingResult],
+    max_skew: Duration,
+) -> Correlation {
+    let pairs = pair_by_time(results_a, results_b, max_skew);
+
+    let (latencies_a, latencies_b): (Vec<f64>, Vec<f64>) = pairs
+        .iter()
+        .filter_map(|(a, b)| Some((a.latency_ms?, b.latency_ms?)))
+        .unzip();
+
+    let any_failed = pairs
+        .iter()
+        .filter(|(a, b)| !a.success || !b.success)
+        .count();
+    let both_failed = pairs
+        .iter()
+        .filter(|(a, b)| !a.success && !b.success)
+        .count();
+    let co_failure_rate = (any_failed > 0).then(|| both_failed as f64 / any_failed as f64);
+
+    Correlation {
+        target_a: target_a.to_string(),
+        target_b: target_b.to_string(),
+        samples: pairs.len() as u32,
+        latency_correlation: pearson(&latencies_a, &latencies_b),
+        co_failure_rate,
+    }
+}
+
+/// Correlate two targets over results logged within `range`
+pub fn correlate_from_logs(
+    log_dir: &Path,
+    target_a: &str,
+    target_b: &str,
+    range: TimeRange,
+    max_skew: Duration,
+) -> Result<Correlation, std::io::Error> {
+    let mut results_a: Vec<PingResult> =
+        query_logs(log_dir, Some(target_a), None, range)?.collect();
+    let mut results_b: Vec<PingResult> =
+        query_logs(log_dir, Some(target_b), None, range)?.collect();
+    results_a.sort_by_key(|r| r.timestamp);
+    results_b.sort_by_key(|r| r.timestamp);
+
+    Ok(correlate(
+        target_a, target_b, &results_a, &results_b, max_skew,
+    ))
+}
+
+/// Pair results taken within `max_skew` of each other, walking both in order
+fn pair_by_time<'a>(
+    results_a: &'a [PingResult],
+    results_b: &'a [PingResult],
+    max_skew: Duration,
+) -> Vec<(&'a PingResult, &'a PingResult)> {
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+
+    while i < results_a.len() && j < results_b.len() {
+        let (a, b) = (&results_a[i], &results_b[j]);
+        let skew = a.timestamp - b.timestamp;
+        if skew.abs() <= max_skew {
+            pairs.push((a, b));
+            i += 1;
+            j += 1;
+        } else if skew < Duration::zero() {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    pairs
+}
+
+/// Pearson correlation coefficient, `None` without variation to compare
+fn pearson(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    let n = xs.len().min(ys.len());
+    if n < 2 {
+        return None;
+    }
+
+    let mean_x = xs[..n].iter().sum::<f64>() / n as f64;
+    let mean_y = ys[..n].iter().sum::<f64>() / n as f64;
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in xs[..n].iter().zip(&ys[..n]) {
+        let (dx, dy) = (x - mean_x, y - mean_y);
+        cov += dx * dy;
+        var_x += dx * dx;
+        var_y += dy * dy;
+    }
+
+    if var_x == 0.0 || var_y == 0.0 {
+        return None;
+    }
+    Some(cov / (var_x * var_y).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PingTarget;
+    use chrono::Utc;
+
+    /// Results one second apart; `None` latencies are failures
+    fn series(target: &PingTarget, latencies: &[Option<f64>], offset_ms: i64) -> Vec<PingResult> {
+        let start = Utc::now() - Duration::hours(1);
+        latencies
+            .iter()
+            .enumerate()
+            .map(|(i, latency)| {
+                let mut result = match latency {
+                    Some(latency) => PingResult::success(target, *latency, i as u32),
+                    None => PingResult::failure(target, "Timeout".to_string(), i as u32),
+                };
+                result.timestamp =
+                    start + Duration::seconds(i as i64) + Duration::milliseconds(offset_ms);
+                result
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_correlated_and_independent_series() {
+        let a = PingTarget::new("192.0.2.1".to_string(), "A".to_string());
+        let b = PingTarget::new("192.0.2.2".to_string(), "B".to_string());
+        let skew = Duration::milliseconds(500);
+
+        // Shared upstream: same failures, latencies moving together
+        let mut seed: u64 = 7;
+        let mut next = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as f64 / (1u64 << 31) as f64
+        };
+        let base: Vec<Option<f64>> = (0..500)
+            .map(|i| (i % 10 != 0).then(|| 10.0 + 40.0 * next()))
+            .collect();
+        let scaled: Vec<Option<f64>> = base.iter().map(|l| l.map(|l| 2.0 * l + 5.0)).collect();
+        let corr = correlate(
+            &a.address,
+            &b.address,
+            &series(&a, &base, 0),
+            &series(&b, &scaled, 200),
+            skew,
+        );
+        assert_eq!(corr.samples, 500);
+        assert!((corr.latency_correlation.unwrap() - 1.0).abs() < 1e-9);
+        assert_eq!(corr.co_failure_rate, Some(1.0));
+
+        // Separate paths: unrelated latencies and failures
+        let first: Vec<Option<f64>> = (0..500)
+            .map(|i| (i % 10 != 0).then(|| 10.0 + 40.0 * next()))
+            .collect();
+        let second: Vec<Option<f64>> = (0..500)
+            .map(|i| (i % 10 != 5).then(|| 10.0 + 40.0 * next()))
+            .collect();
+        let corr = correlate(
+            &a.address,
+            &b.address,
+            &series(&a, &first, 0),
+            &series(&b, &second, -200),
+            skew,
+        );
+        assert_eq!(corr.samples, 500);
+        assert!(corr.latency_correlation.unwrap().abs() < 0.15);
+        assert_eq!(corr.co_failure_rate, Some(0.0));
+
+        // Results too far apart are not paired
+        let corr = correlate(
+            &a.address,
+            &b.address,
+            &series(&a, &first[..3], 0),
+            &series(&b, &second[..3], 700),
+            Duration::milliseconds(100),
+        );
+        assert_eq!(corr.samples, 0);
+        assert_eq!(corr.latency_correlation, None);
+        assert_eq!(corr.co_failure_rate, None);
+    }
+}
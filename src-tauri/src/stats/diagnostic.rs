@@ -0,0 +1,346 @@
+use super::{loss_ratio, percentile, LatencyBounds};
+use crate::models::{DiagnosticReport, HealthVerdict, PingError, PingTarget, TargetDiagnostic};
+use crate::ping::PingBackend;
+use chrono::Utc;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Most probes per target in one diagnostic run
+pub const MAX_DIAGNOSTIC_SAMPLES: u32 = 100;
+
+/// Probe each target `samples` times and judge how healthy it looks
+///
+/// Up to `concurrency` targets are probed at once; each target's samples are
+/// sent one after another, `delay` apart. A target whose probing panics is
+/// reported down with the panic as its error. Nothing is recorded in
+/// statistics or logs.
+pub fn run_diagnostic(
+    backend: &dyn PingBackend,
+    targets: &[PingTarget],
+    samples: u32,
+    delay: Duration,
+    concurrency: usize,
+    bounds: LatencyBounds,
+) -> DiagnosticReport {
+    let started_at = Utc::now();
+    let start = Instant::now();
+    let samples = samples.max(1);
+    let next = AtomicUsize::new(0);
+
+    let mut diagnosed: Vec<(usize, TargetDiagnostic)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..concurrency.clamp(1, targets.len().max(1)))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::SeqCst);
+                        let Some(target) = targets.get(i) else {
+                            return done;
+                        };
+                        let diagnosed = catch_unwind(AssertUnwindSafe(|| {
+                            diagnose_target(backend, target, samples, delay, bounds)
+                        }))
+                        .unwrap_or_else(|panic| panicked_target(target, panic));
+                        done.push((i, diagnosed));
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("diagnostic worker panics are caught"))
+            .collect()
+    });
+    diagnosed.sort_by_key(|(i, _)| *i);
+    let targets: Vec<TargetDiagnostic> = diagnosed.into_iter().map(|(_, d)| d).collect();
+
+    let local = overall(targets.iter().filter(|t| t.local));
+    let internet = overall(targets.iter().filter(|t| !t.local));
+    let summary = [("local network", local), ("internet", internet)]
+        .iter()
+        .filter_map(|(name, verdict)| verdict.map(|v| format!("{} {}", name, verdict_name(v))))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    DiagnosticReport {
+        started_at,
+        duration_ms: start.elapsed().as_millis() as u64,
+        samples,
+        targets,
+        local,
+        internet,
+        summary: if summary.is_empty() {
+            "no targets".to_string()
+        } else {
+            summary
+        },
+    }
+}
+
+/// Probe one target `samples` times
+fn diagnose_target(
+    backend: &dyn PingBackend,
+    target: &PingTarget,
+    samples: u32,
+    delay: Duration,
+    bounds: LatencyBounds,
+) -> TargetDiagnostic {
+    let mut latencies = Vec::with_capacity(samples as usize);
+    let mut failures: Vec<(PingError, u32)> = Vec::new();
+
+    for sequence in 0..samples {
+        if sequence > 0 && !delay.is_zero() {
+            std::thread::sleep(delay);
+        }
+        let result = backend.ping(target, sequence);
+        match result.latency_ms.filter(|_| result.success) {
+            Some(latency) => latencies.push(latency),
+            None => {
+                let kind = result
+                    .error
+                    .as_deref()
+                    .map_or(PingError::Other, PingError::classify);
+                match failures.iter_mut().find(|(k, _)| *k == kind) {
+                    Some((_, count)) => *count += 1,
+                    None => failures.push((kind, 1)),
+                }
+            }
+        }
+    }
+
+    latencies.sort_by(|a, b| a.total_cmp(b));
+    let received = latencies.len() as u32;
+    let avg_latency_ms = if latencies.is_empty() {
+        None
+    } else {
+        Some(latencies.iter().sum::<f64>() / latencies.len() as f64)
+    };
+    let packet_loss_percent = loss_ratio((samples - received) as u64, samples as u64) * 100.0;
+
+    let verdict = if received == 0 {
+        HealthVerdict::Down
+    } else if received < samples || avg_latency_ms.is_some_and(|avg| avg >= bounds.crit_ms) {
+        HealthVerdict::Degraded
+    } else {
+        HealthVerdict::Fine
+    };
+    // The most frequent failure, the earliest seen among equals
+    let failure_kind = failures
+        .iter()
+        .rev()
+        .max_by_key(|(_, count)| *count)
+        .map(|(kind, _)| *kind);
+
+    TargetDiagnostic {
+        id: target.id.clone(),
+        target: target.address.clone(),
+        target_label: target.label.clone(),
+        local: target.local,
+        sent: samples,
+        received,
+        packet_loss_percent,
+        min_latency_ms: latencies.first().copied(),
+        avg_latency_ms,
+        p50_latency_ms: percentile(&latencies, 0.5),
+        p95_latency_ms: percentile(&latencies, 0.95),
+        max_latency_ms: latencies.last().copied(),
+        verdict,
+        failure_kind,
+        error: None,
+    }
+}
+
+/// Entry for a target whose probing panicked
+fn panicked_target(target: &PingTarget, panic: Box<dyn std::any::Any + Send>) -> TargetDiagnostic {
+    let message = panic
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown cause".to_string());
+    log::error!("Diagnosing {} panicked: {}", target.address, message);
+
+    TargetDiagnostic {
+        id: target.id.clone(),
+        target: target.address.clone(),
+        target_label: target.label.clone(),
+        local: target.local,
+        sent: 0,
+        received: 0,
+        packet_loss_percent: 100.0,
+        min_latency_ms: None,
+        avg_latency_ms: None,
+        p50_latency_ms: None,
+        p95_latency_ms: None,
+        max_latency_ms: None,
+        verdict: HealthVerdict::Down,
+        failure_kind: Some(PingError::Other),
+        error: Some(format!("Probing panicked: {}", message)),
+    }
+}
+
+/// A verdict as it is serialized, e.g. `degraded`
+fn verdict_name(verdict: HealthVerdict) -> String {
+    match serde_json::to_value(verdict) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => format!("{:?}", verdict),
+    }
+}
+
+/// Verdict for a group of targets: down if all are, fine if all are
+fn overall<'a, I>(targets: I) -> Option<HealthVerdict>
+where
+    I: Iterator<Item = &'a TargetDiagnostic>,
+{
+    targets
+        .map(|t| t.verdict)
+        .reduce(|a, b| if a == b { a } else { HealthVerdict::Degraded })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PingResult;
+    use crate::ping::MockBackend;
+
+    #[test]
+    fn test_diagnostic_report_judges_each_target() {
+        // Probed in order with one worker: 4 samples per target
+        let backend = MockBackend::new(vec![
+            Some(1.0),
+            Some(2.0),
+            Some(1.0),
+            Some(2.0),
+            Some(20.0),
+            None,
+            Some(30.0),
+            Some(40.0),
+            None,
+            None,
+            None,
+            None,
+        ]);
+        let mut gateway = PingTarget::new("192.168.1.1".to_string(), "Gateway".to_string());
+        gateway.local = true;
+        let targets = vec![
+            gateway,
+            PingTarget::new("1.1.1.1".to_string(), "Cloudflare".to_string()),
+            PingTarget::new("192.0.2.1".to_string(), "Unused".to_string()),
+        ];
+
+        let report = run_diagnostic(
+            &backend,
+            &targets,
+            4,
+            Duration::ZERO,
+            1,
+            LatencyBounds::default(),
+        );
+
+        assert_eq!(backend.calls(), 12);
+        assert_eq!(report.samples, 4);
+        let gateway = &report.targets[0];
+        assert_eq!(gateway.id, targets[0].id);
+        assert_eq!((gateway.sent, gateway.received), (4, 4));
+        assert_eq!(gateway.packet_loss_percent, 0.0);
+        assert_eq!(gateway.min_latency_ms, Some(1.0));
+        assert_eq!(gateway.avg_latency_ms, Some(1.5));
+        assert_eq!(gateway.p50_latency_ms, Some(1.5));
+        assert_eq!(gateway.verdict, HealthVerdict::Fine);
+        assert_eq!(gateway.failure_kind, None);
+
+        let internet = &report.targets[1];
+        assert_eq!(internet.received, 3);
+        assert_eq!(internet.packet_loss_percent, 25.0);
+        assert_eq!(internet.max_latency_ms, Some(40.0));
+        assert_eq!(internet.verdict, HealthVerdict::Degraded);
+        assert_eq!(internet.failure_kind, Some(PingError::Timeout));
+
+        assert_eq!(report.targets[2].verdict, HealthVerdict::Down);
+        assert_eq!(report.targets[2].p95_latency_ms, None);
+        assert_eq!(report.local, Some(HealthVerdict::Fine));
+        assert_eq!(report.internet, Some(HealthVerdict::Degraded));
+        assert_eq!(report.summary, "local network fine, internet degraded");
+    }
+
+    /// Backend that tracks how many probes run at once
+    struct SlowBackend {
+        running: AtomicUsize,
+        most: AtomicUsize,
+    }
+
+    impl PingBackend for SlowBackend {
+        fn ping(&self, target: &PingTarget, sequence: u32) -> PingResult {
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.most.fetch_max(running, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(20));
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            PingResult::success(target, 5.0, sequence)
+        }
+    }
+
+    #[test]
+    fn test_diagnostic_probes_targets_concurrently_up_to_the_cap() {
+        let backend = SlowBackend {
+            running: AtomicUsize::new(0),
+            most: AtomicUsize::new(0),
+        };
+        let targets: Vec<PingTarget> = (0..6)
+            .map(|i| PingTarget::new(format!("192.0.2.{}", i), format!("Host {}", i)))
+            .collect();
+
+        let report = run_diagnostic(
+            &backend,
+            &targets,
+            2,
+            Duration::ZERO,
+            3,
+            LatencyBounds::default(),
+        );
+
+        assert_eq!(backend.most.load(Ordering::SeqCst), 3);
+        let addresses: Vec<&str> = report.targets.iter().map(|t| t.target.as_str()).collect();
+        let expected: Vec<&str> = targets.iter().map(|t| t.address.as_str()).collect();
+        assert_eq!(addresses, expected);
+        assert_eq!(report.local, None);
+        assert_eq!(report.summary, "internet fine");
+    }
+
+    /// Backend that panics when probing one address
+    struct PanickingBackend(&'static str);
+
+    impl PingBackend for PanickingBackend {
+        fn ping(&self, target: &PingTarget, sequence: u32) -> PingResult {
+            if target.address == self.0 {
+                panic!("socket exploded");
+            }
+            PingResult::success(target, 5.0, sequence)
+        }
+    }
+
+    #[test]
+    fn test_panicking_probe_is_reported_as_a_failed_entry() {
+        let targets: Vec<PingTarget> = (0..3)
+            .map(|i| PingTarget::new(format!("192.0.2.{}", i), format!("Host {}", i)))
+            .collect();
+
+        let report = run_diagnostic(
+            &PanickingBackend("192.0.2.1"),
+            &targets,
+            2,
+            Duration::ZERO,
+            1,
+            LatencyBounds::default(),
+        );
+
+        assert_eq!(report.targets.len(), 3);
+        let failed = &report.targets[1];
+        assert_eq!(failed.target, "192.0.2.1");
+        assert_eq!(failed.verdict, HealthVerdict::Down);
+        assert_eq!(failed.error.as_deref(), Some("Probing panicked: socket exploded"));
+        // The same worker goes on to the next target
+        assert_eq!(report.targets[2].verdict, HealthVerdict::Fine);
+        assert_eq!(report.targets[2].error, None);
+        assert_eq!(report.summary, "internet degraded");
+    }
+}
@@ -0,0 +1,69 @@
+use crate::models::PingResult;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// Time between consecutive probes of each target, from dispatch to
+/// dispatch, so a slow reply doesn't count as a stall
+///
+/// A gap well above the target's interval means the monitor itself stalled,
+/// e.g. because the host was overloaded or asleep, rather than the network.
+#[derive(Debug, Clone, Default)]
+pub struct GapTracker {
+    /// Dispatch time of each target's latest probe, by address
+    previous: HashMap<String, DateTime<Utc>>,
+    max_ms: Option<f64>,
+    total_ms: f64,
+    count: u64,
+}
+
+impl GapTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a result of a probe dispatched at `dispatched`, returning the
+    /// time since its target's previous probe was dispatched
+    ///
+    /// Probes dispatched before the target's latest, e.g. overlapping ones
+    /// that finished late, have no gap.
+    pub fn observe(&mut self, result: &PingResult, dispatched: DateTime<Utc>) -> Option<f64> {
+        let previous = self.previous.get(&result.target).copied();
+        if previous.is_some_and(|previous| dispatched < previous) {
+            return None;
+        }
+        self.previous.insert(result.target.clone(), dispatched);
+
+        let gap_us = (dispatched - previous?).num_microseconds()?;
+        let gap_ms = gap_us as f64 / 1000.0;
+        self.max_ms = Some(self.max_ms.map_or(gap_ms, |max| max.max(gap_ms)));
+        self.total_ms += gap_ms;
+        self.count += 1;
+        Some(gap_ms)
+    }
+
+    /// Longest gap seen
+    pub fn max_ms(&self) -> Option<f64> {
+        self.max_ms
+    }
+
+    /// Average gap
+    pub fn avg_ms(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.total_ms / self.count as f64)
+    }
+
+    /// Start each target's next gap afresh, e.g. after a pause, so the
+    /// deliberate break isn't taken for a stall
+    pub fn forget_previous(&mut self) {
+        self.previous.clear();
+    }
+
+    /// Forget a target, e.g. when it is removed
+    pub fn remove_target(&mut self, target_address: &str) {
+        self.previous.remove(target_address);
+    }
+
+    /// Forget every target and the gaps seen
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
@@ -0,0 +1,199 @@
+use super::{ConfidenceThresholds, LatencyBounds, StatsCalculator};
+use crate::models::{GroupPolicy, PingResult, PingStatistics, PingTarget, ProbeGroup};
+use std::collections::HashMap;
+
+/// Whether a group is up given how many of its members responded
+pub fn group_is_up(policy: GroupPolicy, up: usize, total: usize) -> bool {
+    if total == 0 {
+        return false;
+    }
+
+    match policy {
+        GroupPolicy::Any => up > 0,
+        GroupPolicy::Majority => up * 2 > total,
+        GroupPolicy::All => up == total,
+    }
+}
+
+/// Combined reachability of probe groups
+///
+/// Each time a member reports, the group is judged on the latest result of
+/// every member and recorded as one synthetic ping, so a group's packet loss
+/// is the share of observations in which it was down.
+#[derive(Default)]
+pub struct GroupTracker {
+    /// Latest result per target address
+    latest: HashMap<String, PingResult>,
+    /// Synthetic statistics keyed by group id
+    stats: StatsCalculator,
+}
+
+impl GroupTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the thresholds used to grade group latency
+    pub fn set_latency_bounds(&mut self, bounds: LatencyBounds) {
+        self.stats.set_latency_bounds(bounds);
+    }
+
+    /// Set the decimal places of group `packet_loss_display`
+    pub fn set_loss_decimals(&mut self, decimals: u32) {
+        self.stats.set_loss_decimals(decimals);
+    }
+
+    /// Set the weight of each new observation in group `smoothed_loss_percent`
+    pub fn set_loss_alpha(&mut self, alpha: f64) {
+        self.stats.set_loss_alpha(alpha);
+    }
+
+    /// Set the sample counts that grade group `sample_confidence`
+    pub fn set_confidence_thresholds(&mut self, thresholds: ConfidenceThresholds) {
+        self.stats.set_confidence_thresholds(thresholds);
+    }
+
+    /// Set the observations kept per group for the recent-window figures
+    pub fn set_recent_window(&mut self, window: usize) {
+        self.stats.set_recent_window(window);
+    }
+
+    /// Record a result and update every group the target belongs to
+    pub fn observe(&mut self, groups: &[ProbeGroup], targets: &[PingTarget], result: &PingResult) {
+        self.latest.insert(result.target.clone(), result.clone());
+
+        for group in groups {
+            let members: Vec<&PingTarget> = targets
+                .iter()
+                .filter(|t| group.members.contains(&t.id))
+                .collect();
+            if !members.iter().any(|t| t.address == result.target) {
+                continue;
+            }
+
+            // Wait until every member has reported at least once
+            let Some(latest) = members
+                .iter()
+                .map(|t| self.latest.get(&t.address))
+                .collect::<Option<Vec<_>>>()
+            else {
+                continue;
+            };
+
+            let up = latest.iter().filter(|r| r.success).count();
+            let is_up = group_is_up(group.policy, up, members.len());
+            let best_latency = latest
+                .iter()
+                .filter_map(|r| r.latency_ms)
+                .min_by(|a, b| a.total_cmp(b));
+
+            let synthetic = PingResult {
+                timestamp: result.timestamp,
+                target: group.id.clone(),
+                target_label: group.name.clone(),
+                latency_ms: best_latency.filter(|_| is_up),
+                success: is_up,
+                sequence: result.sequence,
+                error: (!is_up).then(|| format!("{} of {} members up", up, members.len())),
+                ..Default::default()
+            };
+            self.stats.update(&synthetic);
+        }
+    }
+
+    /// Statistics for each group that has been observed
+    pub fn get_all_stats(&self) -> Vec<PingStatistics> {
+        self.stats.get_all_stats()
+    }
+
+    /// Statistics for one group
+    pub fn get_stats(&self, group_id: &str) -> Option<PingStatistics> {
+        self.stats.get_stats(group_id)
+    }
+
+    /// Number of groups, latencies held and approximate bytes used
+    pub fn usage(&self) -> (usize, u64, usize) {
+        let (groups, samples, bytes) = self.stats.usage();
+        let latest: usize = self.latest.values().map(PingResult::approx_size).sum();
+        (groups, samples, bytes + latest)
+    }
+
+    /// Forget a group's statistics
+    pub fn remove_group(&mut self, group_id: &str) {
+        self.stats.remove_target(group_id);
+    }
+
+    /// Forget all results and statistics
+    pub fn clear(&mut self) {
+        self.latest.clear();
+        self.stats.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_up_policies() {
+        // (members up, members total) -> expected for Any, Majority, All
+        let cases = [
+            (0, 3, [false, false, false]),
+            (1, 3, [true, false, false]),
+            (2, 3, [true, true, false]),
+            (3, 3, [true, true, true]),
+            (1, 2, [true, false, false]),
+            (2, 2, [true, true, true]),
+            (0, 0, [false, false, false]),
+        ];
+
+        for (up, total, expected) in cases {
+            let policies = [GroupPolicy::Any, GroupPolicy::Majority, GroupPolicy::All];
+            for (policy, expected) in policies.into_iter().zip(expected) {
+                assert_eq!(
+                    group_is_up(policy, up, total),
+                    expected,
+                    "{:?} with {}/{} up",
+                    policy,
+                    up,
+                    total
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_group_statistics_follow_policy() {
+        let targets = vec![
+            PingTarget::new("10.0.0.1".to_string(), "NTP 1".to_string()),
+            PingTarget::new("10.0.0.2".to_string(), "NTP 2".to_string()),
+            PingTarget::new("10.0.0.3".to_string(), "NTP 3".to_string()),
+        ];
+        let members: Vec<String> = targets.iter().map(|t| t.id.clone()).collect();
+        let any = ProbeGroup::new("NTP any".to_string(), members.clone(), GroupPolicy::Any);
+        let all = ProbeGroup::new("NTP all".to_string(), members, GroupPolicy::All);
+        let groups = vec![any.clone(), all.clone()];
+        let mut tracker = GroupTracker::new();
+
+        // One member hiccups; the others answer
+        tracker.observe(
+            &groups,
+            &targets,
+            &PingResult::success(&targets[0], 12.0, 1),
+        );
+        tracker.observe(&groups, &targets, &PingResult::success(&targets[1], 8.0, 1));
+        assert!(tracker.get_stats(&any.id).is_none());
+        let timeout = PingResult::failure(&targets[2], "Timeout".to_string(), 1);
+        tracker.observe(&groups, &targets, &timeout);
+
+        let any_stats = tracker.get_stats(&any.id).unwrap();
+        assert_eq!(any_stats.target_label, "NTP any");
+        assert_eq!(any_stats.total_pings, 1);
+        assert_eq!(any_stats.packet_loss_percent, 0.0);
+        assert_eq!(any_stats.avg_latency_ms, Some(8.0));
+
+        let all_stats = tracker.get_stats(&all.id).unwrap();
+        assert_eq!(all_stats.failed_pings, 1);
+        assert_eq!(all_stats.avg_latency_ms, None);
+    }
+}
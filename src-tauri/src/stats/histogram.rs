@@ -0,0 +1,249 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Values below this many microseconds get a bucket each
+const SUB_BUCKET_COUNT: u64 = 128;
+const HALF_SUB_BUCKET_COUNT: u64 = SUB_BUCKET_COUNT / 2;
+/// log2 of `HALF_SUB_BUCKET_COUNT`
+const HALF_SUB_BUCKET_BITS: u32 = 6;
+
+/// Largest relative error of a recorded value above 128µs
+pub const HISTOGRAM_PRECISION: f64 = 1.0 / HALF_SUB_BUCKET_COUNT as f64;
+
+/// Non-empty histogram bucket
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct HistogramBucket {
+    /// Inclusive lower bound
+    pub lower_ms: f64,
+    /// Exclusive upper bound
+    pub upper_ms: f64,
+    pub count: u64,
+}
+
+/// Latency histogram with fixed relative precision and bounded memory
+///
+/// Latencies are recorded in whole microseconds. Values below 128µs are
+/// exact; above that, each power of two is split into 64 buckets, so any
+/// value is known to within [`HISTOGRAM_PRECISION`]. Covering 1µs to an hour
+/// takes under 2,000 counters, however long the session runs.
+///
+/// Min, max, mean and standard deviation are tracked exactly alongside.
+#[derive(Debug, Clone, Default)]
+pub struct LatencyHistogram {
+    counts: Vec<u64>,
+    total: u64,
+    min_ms: f64,
+    max_ms: f64,
+    mean_ms: f64,
+    /// Sum of squared deviations from the mean (Welford)
+    m2: f64,
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Approximate bytes held, including the bucket counters
+    pub fn approx_size(&self) -> usize {
+        std::mem::size_of::<Self>() + self.counts.capacity() * std::mem::size_of::<u64>()
+    }
+
+    /// Record one latency
+    pub fn record(&mut self, latency_ms: f64) {
+        let latency_ms = latency_ms.max(0.0);
+        let index = bucket_index(to_micros(latency_ms));
+        if index >= self.counts.len() {
+            self.counts.resize(index + 1, 0);
+        }
+        self.counts[index] += 1;
+
+        if self.total == 0 {
+            self.min_ms = latency_ms;
+            self.max_ms = latency_ms;
+        } else {
+            self.min_ms = self.min_ms.min(latency_ms);
+            self.max_ms = self.max_ms.max(latency_ms);
+        }
+        self.total += 1;
+        let delta = latency_ms - self.mean_ms;
+        self.mean_ms += delta / self.total as f64;
+        self.m2 += delta * (latency_ms - self.mean_ms);
+    }
+
+    /// Number of recorded latencies
+    pub fn len(&self) -> u64 {
+        self.total
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        (!self.is_empty()).then_some(self.min_ms)
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        (!self.is_empty()).then_some(self.max_ms)
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (!self.is_empty()).then_some(self.mean_ms)
+    }
+
+    /// Sample standard deviation; 0 for a single value
+    pub fn std_dev(&self) -> Option<f64> {
+        match self.total {
+            0 => None,
+            1 => Some(0.0),
+            n => Some((self.m2 / (n - 1) as f64).sqrt()),
+        }
+    }
+
+    /// Latency at or below which a fraction `q` (0-1) of values fall
+    ///
+    /// Returns the upper end of the bucket holding that rank, clamped to the
+    /// recorded min and max.
+    pub fn percentile(&self, q: f64) -> Option<f64> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let rank = ((q.clamp(0.0, 1.0) * self.total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, &count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let (_, upper) = bucket_range(index);
+                let value = (upper - 1) as f64 / 1000.0;
+                return Some(value.clamp(self.min_ms, self.max_ms));
+            }
+        }
+        Some(self.max_ms)
+    }
+
+    /// Counts of every non-empty bucket, lowest first
+    pub fn buckets(&self) -> Vec<HistogramBucket> {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0)
+            .map(|(index, &count)| {
+                let (lower, upper) = bucket_range(index);
+                HistogramBucket {
+                    lower_ms: lower as f64 / 1000.0,
+                    upper_ms: upper as f64 / 1000.0,
+                    count,
+                }
+            })
+            .collect()
+    }
+
+    /// Forget every recorded value
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+fn to_micros(latency_ms: f64) -> u64 {
+    (latency_ms * 1000.0).round() as u64
+}
+
+/// Bucket holding a value in microseconds
+fn bucket_index(micros: u64) -> usize {
+    if micros < SUB_BUCKET_COUNT {
+        return micros as usize;
+    }
+
+    // Shift so the value lands in [64, 128)
+    let shift = (63 - micros.leading_zeros()) - HALF_SUB_BUCKET_BITS;
+    let sub_bucket = (micros >> shift) - HALF_SUB_BUCKET_COUNT;
+    (SUB_BUCKET_COUNT + (shift as u64 - 1) * HALF_SUB_BUCKET_COUNT + sub_bucket) as usize
+}
+
+/// Microsecond range `[lower, upper)` covered by a bucket
+fn bucket_range(index: usize) -> (u64, u64) {
+    let index = index as u64;
+    if index < SUB_BUCKET_COUNT {
+        return (index, index + 1);
+    }
+
+    let offset = index - SUB_BUCKET_COUNT;
+    let shift = offset / HALF_SUB_BUCKET_COUNT + 1;
+    let sub_bucket = offset % HALF_SUB_BUCKET_COUNT + HALF_SUB_BUCKET_COUNT;
+    (sub_bucket << shift, (sub_bucket + 1) << shift)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Exact nearest-rank percentile
+    fn exact_percentile(sorted: &[f64], q: f64) -> f64 {
+        let rank = ((q * sorted.len() as f64).ceil() as usize).max(1);
+        sorted[rank - 1]
+    }
+
+    #[test]
+    fn test_percentiles_within_precision_bound() {
+        // Log-uniform latencies from 50µs to 5s, from a fixed-seed LCG
+        let mut seed: u64 = 42;
+        let mut values: Vec<f64> = (0..20_000)
+            .map(|_| {
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                let unit = (seed >> 11) as f64 / (1u64 << 53) as f64;
+                0.05 * 100_000f64.powf(unit)
+            })
+            .collect();
+
+        let mut histogram = LatencyHistogram::new();
+        for &value in &values {
+            histogram.record(value);
+        }
+        values.sort_by(|a, b| a.total_cmp(b));
+
+        for q in [0.0, 0.5, 0.9, 0.99, 0.999, 1.0] {
+            let exact = exact_percentile(&values, q);
+            let estimate = histogram.percentile(q).unwrap();
+            // Whole-microsecond rounding adds up to 1µs on top of the bound
+            let bound = exact * HISTOGRAM_PRECISION + 0.001;
+            assert!(
+                (estimate - exact).abs() <= bound,
+                "p{}: {} vs exact {}",
+                q * 100.0,
+                estimate,
+                exact
+            );
+        }
+
+        assert_eq!(histogram.len(), 20_000);
+        assert_eq!(histogram.min(), values.first().copied());
+        assert_eq!(histogram.max(), values.last().copied());
+        // Memory stays small however many values are recorded
+        assert!(histogram.counts.len() < 2_000);
+    }
+
+    #[test]
+    fn test_bucket_ranges_tile_the_value_space() {
+        for micros in [0, 1, 127, 128, 129, 255, 256, 1_000, 123_456, 3_600_000_000] {
+            let (lower, upper) = bucket_range(bucket_index(micros));
+            assert!(lower <= micros && micros < upper, "{}", micros);
+        }
+        for index in 0..1_000 {
+            assert_eq!(bucket_range(index).1, bucket_range(index + 1).0);
+        }
+
+        let mut histogram = LatencyHistogram::new();
+        histogram.record(10.0);
+        histogram.record(10.0);
+        histogram.record(20.0);
+        let buckets = histogram.buckets();
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(buckets[0].count, 2);
+        assert!(buckets[0].lower_ms <= 10.0 && 10.0 < buckets[0].upper_ms);
+        assert_eq!(histogram.mean(), Some(40.0 / 3.0));
+    }
+}
@@ -0,0 +1,82 @@
+use super::loss_ratio;
+use crate::models::{LifetimeCounters, PingResult};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// Per-target ping counts that outlive session resets and restarts
+#[derive(Debug, Default)]
+pub struct LifetimeTracker {
+    /// Counters per target address
+    counters: HashMap<String, LifetimeCounters>,
+}
+
+impl LifetimeTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Continue counting from counters saved by an earlier run
+    pub fn from_counters(counters: HashMap<String, LifetimeCounters>) -> Self {
+        Self { counters }
+    }
+
+    /// Count a ping result
+    pub fn record(&mut self, result: &PingResult) {
+        let counters = self
+            .counters
+            .entry(result.target.clone())
+            .or_insert_with(|| empty_counters(result.timestamp));
+        counters.total_pings += 1;
+        if result.success {
+            counters.successful_pings += 1;
+        } else {
+            counters.failed_pings += 1;
+        }
+        update_loss(counters);
+    }
+
+    /// Count a ping recorded as failed as answered after all
+    pub fn record_late_reply(&mut self, target_address: &str) {
+        if let Some(counters) = self.counters.get_mut(target_address) {
+            if counters.failed_pings > 0 {
+                counters.failed_pings -= 1;
+                counters.successful_pings += 1;
+                update_loss(counters);
+            }
+        }
+    }
+
+    /// Counters of a target, if it was ever pinged
+    pub fn get(&self, target_address: &str) -> Option<&LifetimeCounters> {
+        self.counters.get(target_address)
+    }
+
+    /// Counters of every target, keyed by address
+    pub fn counters(&self) -> &HashMap<String, LifetimeCounters> {
+        &self.counters
+    }
+
+    /// Forget a removed target
+    pub fn remove_target(&mut self, target_address: &str) {
+        self.counters.remove(target_address);
+    }
+
+    /// Start counting from zero for every target
+    pub fn clear(&mut self) {
+        self.counters.clear();
+    }
+}
+
+fn empty_counters(since: DateTime<Utc>) -> LifetimeCounters {
+    LifetimeCounters {
+        total_pings: 0,
+        successful_pings: 0,
+        failed_pings: 0,
+        packet_loss_percent: 0.0,
+        since,
+    }
+}
+
+fn update_loss(counters: &mut LifetimeCounters) {
+    counters.packet_loss_percent = loss_ratio(counters.failed_pings, counters.total_pings) * 100.0;
+}
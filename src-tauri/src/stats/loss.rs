@@ -0,0 +1,96 @@
+/// Decimal places of `packet_loss_display` unless configured otherwise
+pub const DEFAULT_LOSS_DECIMALS: u32 = 1;
+
+/// Weight of each new result in `smoothed_loss_percent` unless configured otherwise
+pub const DEFAULT_LOSS_ALPHA: f64 = 0.1;
+
+/// Most decimal places a loss figure is rounded to
+const MAX_LOSS_DECIMALS: u32 = 6;
+
+/// Fraction of pings lost, 0-1, and exactly 0 when none were sent
+pub fn loss_ratio(failed: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    (failed as f64 / total as f64).clamp(0.0, 1.0)
+}
+
+/// Round a loss percentage for display, e.g. 33.333… to 33.3
+pub fn round_loss_percent(percent: f64, decimals: u32) -> f64 {
+    let scale = 10f64.powi(decimals.min(MAX_LOSS_DECIMALS) as i32);
+    ((percent * scale).round() / scale).clamp(0.0, 100.0)
+}
+
+/// Move a smoothed loss percentage toward 0 on a success or 100 on a failure
+///
+/// The first result sets the value outright. `alpha` in (0, 1] weighs the new
+/// result; smaller values give a steadier, slower indicator.
+pub fn smooth_loss(previous: Option<f64>, success: bool, alpha: f64) -> f64 {
+    let sample = if success { 0.0 } else { 100.0 };
+    match previous {
+        Some(previous) => previous + alpha.clamp(0.0, 1.0) * (sample - previous),
+        None => sample,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_representative_loss_fractions() {
+        let cases = [
+            (0, 0, 0.0, 0.0),
+            (0, 10, 0.0, 0.0),
+            (1, 3, 1.0 / 3.0, 33.3),
+            (2, 3, 2.0 / 3.0, 66.7),
+            (1, 8, 0.125, 12.5),
+            (1, 1000, 0.001, 0.1),
+            (1, 100_000, 0.00001, 0.0),
+            (7, 7, 1.0, 100.0),
+        ];
+        for (failed, total, ratio, display) in cases {
+            let actual = loss_ratio(failed, total);
+            assert!((actual - ratio).abs() < 1e-12, "{}/{}", failed, total);
+            assert_eq!(
+                round_loss_percent(actual * 100.0, DEFAULT_LOSS_DECIMALS),
+                display,
+                "{}/{}",
+                failed,
+                total
+            );
+        }
+
+        // More failures than pings cannot exceed 100%
+        assert_eq!(loss_ratio(5, 4), 1.0);
+        assert_eq!(round_loss_percent(100.04, 1), 100.0);
+
+        assert_eq!(round_loss_percent(100.0 / 3.0, 0), 33.0);
+        assert_eq!(round_loss_percent(100.0 / 3.0, 2), 33.33);
+        assert_eq!(round_loss_percent(100.0 / 3.0, 99), 33.333333);
+    }
+
+    #[test]
+    fn test_smoothed_loss_converges_after_a_step() {
+        let mut smoothed = smooth_loss(None, true, 0.2);
+        for _ in 0..10 {
+            smoothed = smooth_loss(Some(smoothed), true, 0.2);
+        }
+        assert_eq!(smoothed, 0.0);
+
+        // Loss steps to 100%: the value climbs steadily without jumping
+        let mut trace = Vec::new();
+        for _ in 0..30 {
+            smoothed = smooth_loss(Some(smoothed), false, 0.2);
+            trace.push(smoothed);
+        }
+        assert!((trace[0] - 20.0).abs() < 1e-9);
+        assert!((trace[1] - 36.0).abs() < 1e-9);
+        assert!(trace.windows(2).all(|w| w[1] > w[0] && w[1] - w[0] <= 20.0));
+        assert!(trace[29] > 99.0 && trace[29] < 100.0);
+
+        // And recovers the same way
+        let recovered = smooth_loss(Some(trace[29]), true, 0.2);
+        assert!(recovered < trace[29] && recovered > 75.0);
+    }
+}
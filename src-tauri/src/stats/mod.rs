@@ -1,3 +1,50 @@
+mod alarms;
+mod baseline;
 mod calculator;
+mod comparison;
+mod confidence;
+mod correlation;
+mod diagnostic;
+mod gaps;
+mod groups;
+mod histogram;
+mod lifetime;
+mod loss;
+mod normalize;
+mod outages;
+mod path;
+mod percentile;
+mod profile;
+mod quality;
+mod query;
+mod resolved;
+mod ring;
 
-pub use calculator::StatsCalculator;
+pub use alarms::{AlarmThresholds, AlarmTracker};
+pub use baseline::{capture_baseline, deviation_from_baseline, deviation_from_expected};
+pub use calculator::{StatsCalculator, ERROR_HISTORY_CAPACITY, RECENT_LATENCY_WINDOW};
+pub use comparison::{compare_periods, stats_for_range};
+pub use confidence::{
+    ConfidenceThresholds, DEFAULT_CONFIDENCE_HIGH_SAMPLES, DEFAULT_CONFIDENCE_LOW_SAMPLES,
+};
+pub use correlation::{correlate, correlate_from_logs};
+pub use diagnostic::{run_diagnostic, MAX_DIAGNOSTIC_SAMPLES};
+pub use gaps::GapTracker;
+pub use groups::{group_is_up, GroupTracker};
+pub use histogram::{HistogramBucket, LatencyHistogram, HISTOGRAM_PRECISION};
+pub use lifetime::LifetimeTracker;
+pub use loss::{
+    loss_ratio, round_loss_percent, smooth_loss, DEFAULT_LOSS_ALPHA, DEFAULT_LOSS_DECIMALS,
+};
+pub use normalize::LatencyBounds;
+pub use outages::{OutageTracker, DEFAULT_OUTAGE_CAPACITY};
+pub use path::PathTracker;
+pub use profile::{hourly_profile, hourly_profile_from_logs};
+pub use quality::{
+    quality_rating, quality_score, quality_timeseries, quality_timeseries_from_logs,
+    MAX_QUALITY_BUCKETS,
+};
+pub use query::{matches_filter, query_stats, sort_stats};
+pub use percentile::{percentile, percentile_with, PercentileMethod, PERCENTILE_METHOD};
+pub use resolved::ResolvedIpTracker;
+pub use ring::BoundedRing;
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+/// Latency thresholds used to grade latency for display
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LatencyBounds {
+    pub warn_ms: f64,
+    pub crit_ms: f64,
+}
+
+impl LatencyBounds {
+    pub fn new(warn_ms: f64, crit_ms: f64) -> Self {
+        Self { warn_ms, crit_ms }
+    }
+
+    /// Map a latency onto 0-1 for gauges
+    ///
+    /// 0 to `warn_ms` maps to 0-0.5, `warn_ms` to `crit_ms` maps to 0.5-1,
+    /// and anything slower (or a failed ping, `None`) is 1.
+    pub fn normalize(&self, latency_ms: Option<f64>) -> f32 {
+        let Some(latency) = latency_ms else {
+            return 1.0;
+        };
+        let warn = self.warn_ms.max(f64::EPSILON);
+        let crit = self.crit_ms.max(warn);
+
+        let value = if latency <= warn {
+            latency / warn * 0.5
+        } else if latency < crit {
+            0.5 + (latency - warn) / (crit - warn) * 0.5
+        } else {
+            1.0
+        };
+        value.clamp(0.0, 1.0) as f32
+    }
+}
+
+impl Default for LatencyBounds {
+    fn default() -> Self {
+        Self::new(50.0, 100.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_at_and_beyond_bounds() {
+        let bounds = LatencyBounds::new(50.0, 150.0);
+
+        assert_eq!(bounds.normalize(Some(0.0)), 0.0);
+        assert_eq!(bounds.normalize(Some(25.0)), 0.25);
+        assert_eq!(bounds.normalize(Some(50.0)), 0.5);
+        assert_eq!(bounds.normalize(Some(100.0)), 0.75);
+        assert_eq!(bounds.normalize(Some(150.0)), 1.0);
+        assert_eq!(bounds.normalize(Some(5000.0)), 1.0);
+        assert_eq!(bounds.normalize(Some(-3.0)), 0.0);
+        assert_eq!(bounds.normalize(None), 1.0);
+    }
+}
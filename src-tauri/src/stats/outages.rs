@@ -0,0 +1,234 @@
+use super::BoundedRing;
+use crate::models::{Outage, PingResult, PingTarget};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// Number of outages kept for the recent outages panel
+pub const DEFAULT_OUTAGE_CAPACITY: usize = 10;
+
+/// Keeps the most recent outages across all targets
+///
+/// An outage starts with a target's first failed ping and ends with its next
+/// successful one.
+pub struct OutageTracker {
+    outages: BoundedRing<Outage>,
+    /// Start of the ongoing outage per target address
+    open: HashMap<String, DateTime<Utc>>,
+}
+
+impl OutageTracker {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            outages: BoundedRing::new(capacity),
+            open: HashMap::new(),
+        }
+    }
+
+    /// Update outages with a new ping result
+    pub fn update(&mut self, result: &PingResult) {
+        if result.success {
+            let Some(started_at) = self.open.remove(&result.target) else {
+                return;
+            };
+            // The entry may already have been evicted by newer outages
+            if let Some(outage) = self
+                .outages
+                .iter_mut()
+                .rev()
+                .find(|o| o.target == result.target && o.started_at == started_at)
+            {
+                outage.ended_at = Some(result.timestamp);
+                outage.duration_ms = duration_ms(started_at, result.timestamp);
+            }
+        } else if !self.open.contains_key(&result.target) {
+            self.open.insert(result.target.clone(), result.timestamp);
+            self.outages.push(Outage {
+                target: result.target.clone(),
+                target_label: result.target_label.clone(),
+                started_at: result.timestamp,
+                ended_at: None,
+                duration_ms: 0,
+                session_label: result.session_label.clone(),
+            });
+        }
+    }
+
+    /// Start of a target's ongoing outage, if it is down
+    pub fn ongoing_since(&self, target_address: &str) -> Option<DateTime<Utc>> {
+        self.open.get(target_address).copied()
+    }
+
+    /// Most recent outages, newest first
+    ///
+    /// Ongoing outages have no end and report their duration so far.
+    pub fn recent(&self, count: usize) -> Vec<Outage> {
+        let now = Utc::now();
+        self.outages
+            .iter()
+            .rev()
+            .take(count)
+            .map(|outage| {
+                let mut outage = outage.clone();
+                if outage.ended_at.is_none() {
+                    outage.duration_ms = duration_ms(outage.started_at, now);
+                }
+                outage
+            })
+            .collect()
+    }
+
+    /// Leave a pause out of ongoing outages
+    ///
+    /// Each ongoing outage starts later by the length of the pause, so it
+    /// only counts time the target was actually being probed.
+    pub fn exclude_gap(&mut self, from: DateTime<Utc>, to: DateTime<Utc>) {
+        let gap = (to - from).max(chrono::Duration::zero());
+        for (target, started_at) in self.open.iter_mut() {
+            let shifted = *started_at + gap;
+            if let Some(outage) = self
+                .outages
+                .iter_mut()
+                .rev()
+                .find(|o| &o.target == target && o.started_at == *started_at)
+            {
+                outage.started_at = shifted;
+            }
+            *started_at = shifted;
+        }
+    }
+
+    /// Record a pause as downtime for each of the given targets
+    ///
+    /// Targets already in an ongoing outage simply stay down through the gap.
+    pub fn record_gap(&mut self, targets: &[PingTarget], from: DateTime<Utc>, to: DateTime<Utc>) {
+        for target in targets {
+            if self.open.contains_key(&target.address) {
+                continue;
+            }
+            self.outages.push(Outage {
+                target: target.address.clone(),
+                target_label: target.label.clone(),
+                started_at: from,
+                ended_at: Some(to),
+                duration_ms: duration_ms(from, to),
+                session_label: None,
+            });
+        }
+    }
+
+    /// Change how many outages are kept, dropping the oldest right away
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.outages.set_capacity(capacity);
+    }
+
+    /// Number of outages kept
+    pub fn len(&self) -> usize {
+        self.outages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outages.is_empty()
+    }
+
+    /// Forget a target's ongoing outage, e.g. when it is removed
+    ///
+    /// Its entry is dropped too, as it would otherwise never end. Finished
+    /// outages stay in the history.
+    pub fn remove_target(&mut self, target_address: &str) {
+        let Some(started_at) = self.open.remove(target_address) else {
+            return;
+        };
+        if let Some(index) = self
+            .outages
+            .iter()
+            .rposition(|o| o.target == target_address && o.started_at == started_at)
+        {
+            self.outages.remove(index);
+        }
+    }
+
+    /// Clear all outages
+    pub fn clear(&mut self) {
+        self.outages.clear();
+        self.open.clear();
+    }
+}
+
+impl Default for OutageTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_OUTAGE_CAPACITY)
+    }
+}
+
+fn duration_ms(from: DateTime<Utc>, to: DateTime<Utc>) -> u64 {
+    (to - from).num_milliseconds().max(0) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn at(mut result: PingResult, start: DateTime<Utc>, secs: i64) -> PingResult {
+        result.timestamp = start + Duration::seconds(secs);
+        result
+    }
+
+    #[test]
+    fn test_ring_evicts_oldest_and_keeps_ongoing_outage() {
+        let mut tracker = OutageTracker::new(3);
+        let target = PingTarget::new("1.1.1.1".to_string(), "Test".to_string());
+        let start = Utc::now() - Duration::minutes(10);
+
+        // Four complete outages of 2s each; only the last three are kept
+        for i in 0..4 {
+            let t = i * 10;
+            tracker.update(&at(
+                PingResult::failure(&target, "Timeout".to_string(), 0),
+                start,
+                t,
+            ));
+            tracker.update(&at(
+                PingResult::failure(&target, "Timeout".to_string(), 0),
+                start,
+                t + 1,
+            ));
+            tracker.update(&at(PingResult::success(&target, 10.0, 0), start, t + 2));
+        }
+        let outages = tracker.recent(10);
+        assert_eq!(outages.len(), 3);
+        assert_eq!(outages[0].started_at, start + Duration::seconds(30));
+        assert_eq!(outages[2].started_at, start + Duration::seconds(10));
+        assert!(outages.iter().all(|o| o.duration_ms == 2000));
+
+        // An ongoing outage is listed first with no end
+        tracker.update(&at(
+            PingResult::failure(&target, "Timeout".to_string(), 0),
+            start,
+            50,
+        ));
+        let outages = tracker.recent(2);
+        assert_eq!(outages.len(), 2);
+        assert_eq!(outages[0].ended_at, None);
+        assert!(outages[0].duration_ms > 0);
+        assert_eq!(outages[1].started_at, start + Duration::seconds(30));
+    }
+
+    #[test]
+    fn test_removed_target_leaves_no_endless_outage() {
+        let mut tracker = OutageTracker::new(10);
+        let target = PingTarget::new("1.1.1.1".to_string(), "Test".to_string());
+        let start = Utc::now() - Duration::minutes(10);
+        let timeout = || PingResult::failure(&target, "Timeout".to_string(), 0);
+
+        tracker.update(&at(timeout(), start, 0));
+        tracker.update(&at(PingResult::success(&target, 10.0, 0), start, 2));
+        tracker.update(&at(timeout(), start, 10));
+        tracker.remove_target(&target.address);
+
+        let outages = tracker.recent(10);
+        assert_eq!(outages.len(), 1);
+        assert_eq!(outages[0].ended_at, Some(start + Duration::seconds(2)));
+        assert_eq!(tracker.ongoing_since(&target.address), None);
+    }
+}
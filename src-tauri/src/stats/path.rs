@@ -0,0 +1,152 @@
+use crate::models::{PathHop, PathReport};
+use crate::ping::HopSample;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+
+/// Accumulated replies from one hop
+#[derive(Debug, Clone, Default)]
+struct HopStats {
+    address: Option<String>,
+    sent: u64,
+    received: u64,
+    total_ms: f64,
+    min_ms: Option<f64>,
+    max_ms: Option<f64>,
+    last_ms: Option<f64>,
+}
+
+impl HopStats {
+    fn update(&mut self, sample: &HopSample) {
+        if sample.address.is_some() {
+            self.address = sample.address.clone();
+        }
+        for rtt in &sample.rtts {
+            self.sent += 1;
+            if let Some(rtt) = *rtt {
+                self.received += 1;
+                self.total_ms += rtt;
+                self.min_ms = Some(self.min_ms.map_or(rtt, |min| min.min(rtt)));
+                self.max_ms = Some(self.max_ms.map_or(rtt, |max| max.max(rtt)));
+                self.last_ms = Some(rtt);
+            }
+        }
+    }
+
+    fn to_hop(&self, hop: u8) -> PathHop {
+        let packet_loss_percent = if self.sent > 0 {
+            (self.sent - self.received) as f64 / self.sent as f64 * 100.0
+        } else {
+            0.0
+        };
+
+        PathHop {
+            hop,
+            address: self.address.clone(),
+            sent: self.sent,
+            received: self.received,
+            packet_loss_percent,
+            avg_latency_ms: (self.received > 0).then(|| self.total_ms / self.received as f64),
+            min_latency_ms: self.min_ms,
+            max_latency_ms: self.max_ms,
+            last_latency_ms: self.last_ms,
+        }
+    }
+}
+
+/// Hops of one target's path across traces
+#[derive(Debug, Clone, Default)]
+struct TargetPath {
+    hops: BTreeMap<u8, HopStats>,
+    runs: u32,
+    last_run: Option<DateTime<Utc>>,
+}
+
+/// Per-hop statistics of each traced target, like MTR's report
+#[derive(Debug, Clone, Default)]
+pub struct PathTracker {
+    targets: HashMap<String, TargetPath>,
+}
+
+impl PathTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add one trace of a target's path
+    pub fn record(&mut self, target_address: &str, samples: &[HopSample], at: DateTime<Utc>) {
+        let path = self.targets.entry(target_address.to_string()).or_default();
+        for sample in samples {
+            path.hops.entry(sample.hop).or_default().update(sample);
+        }
+        path.runs += 1;
+        path.last_run = Some(at);
+    }
+
+    /// Per-hop statistics of a target, nearest hop first
+    pub fn report(&self, target_address: &str) -> Option<PathReport> {
+        let path = self.targets.get(target_address)?;
+        Some(PathReport {
+            target: target_address.to_string(),
+            runs: path.runs,
+            last_run: path.last_run,
+            hops: path
+                .hops
+                .iter()
+                .map(|(&hop, stats)| stats.to_hop(hop))
+                .collect(),
+        })
+    }
+
+    /// Forget a target's path
+    pub fn remove_target(&mut self, target_address: &str) {
+        self.targets.remove(target_address);
+    }
+
+    /// Forget all paths
+    pub fn clear(&mut self) {
+        self.targets.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hop_loss_accumulates_across_traces() {
+        let mut tracker = PathTracker::new();
+        let hop = |hop, address: Option<&str>, rtts: Vec<Option<f64>>| HopSample {
+            hop,
+            address: address.map(str::to_string),
+            rtts,
+        };
+
+        tracker.record(
+            "1.1.1.1",
+            &[
+                hop(1, Some("192.168.1.1"), vec![Some(1.0), Some(2.0)]),
+                hop(2, Some("10.0.0.1"), vec![Some(10.0), None]),
+            ],
+            Utc::now(),
+        );
+        tracker.record(
+            "1.1.1.1",
+            &[
+                hop(1, Some("192.168.1.1"), vec![Some(3.0), Some(2.0)]),
+                hop(2, None, vec![None, None]),
+            ],
+            Utc::now(),
+        );
+
+        let report = tracker.report("1.1.1.1").unwrap();
+        assert_eq!(report.runs, 2);
+        assert_eq!(report.hops.len(), 2);
+        assert_eq!(report.hops[0].packet_loss_percent, 0.0);
+        assert_eq!(report.hops[0].avg_latency_ms, Some(2.0));
+        assert_eq!(report.hops[0].max_latency_ms, Some(3.0));
+        // The hop that drops probes stands out, and keeps its last address
+        assert_eq!(report.hops[1].packet_loss_percent, 75.0);
+        assert_eq!(report.hops[1].address.as_deref(), Some("10.0.0.1"));
+        assert!(tracker.report("8.8.8.8").is_none());
+    }
+}
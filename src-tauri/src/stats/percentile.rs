@@ -0,0 +1,101 @@
+/// How a percentile is taken from sorted values
+///
+/// Tools disagree here, so figures compared against `ping` summaries, `mtr`
+/// or a spreadsheet may differ slightly depending on the method they use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PercentileMethod {
+    /// Linear interpolation between the two closest ranks (Hyndman & Fan
+    /// type 7), as in NumPy, R and spreadsheet `PERCENTILE`
+    Linear,
+    /// Smallest value with at least a fraction `q` of values at or below it,
+    /// always one of the values
+    NearestRank,
+}
+
+/// Method used for every percentile in reports
+pub const PERCENTILE_METHOD: PercentileMethod = PercentileMethod::Linear;
+
+/// Percentile of sorted values using [`PERCENTILE_METHOD`]
+///
+/// `q` is in 0-1. Returns `None` for an empty slice.
+pub fn percentile(sorted: &[f64], q: f64) -> Option<f64> {
+    percentile_with(sorted, q, PERCENTILE_METHOD)
+}
+
+/// Percentile of sorted values using `method`
+///
+/// `q` is clamped to 0-1. Returns `None` for an empty slice. Values should be
+/// sorted with `f64::total_cmp`, so equal inputs always give equal results.
+pub fn percentile_with(sorted: &[f64], q: f64, method: PercentileMethod) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let q = q.clamp(0.0, 1.0);
+
+    match method {
+        PercentileMethod::Linear => {
+            let rank = q * (sorted.len() - 1) as f64;
+            let lower = rank.floor() as usize;
+            let upper = rank.ceil() as usize;
+            let weight = rank - lower as f64;
+            Some(sorted[lower] + (sorted[upper] - sorted[lower]) * weight)
+        }
+        PercentileMethod::NearestRank => {
+            let rank = (q * sorted.len() as f64).ceil() as usize;
+            Some(sorted[rank.clamp(1, sorted.len()) - 1])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.unwrap();
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn test_linear_matches_type_7() {
+        // Same as numpy.percentile and R's quantile(type = 7)
+        let values = [1.0, 2.0, 3.0, 4.0];
+        assert_close(percentile(&values, 0.25), 1.75);
+        assert_close(percentile(&values, 0.5), 2.5);
+        assert_close(percentile(&values, 0.9), 3.7);
+
+        let values = [15.0, 20.0, 35.0, 40.0, 50.0];
+        assert_close(percentile(&values, 0.0), 15.0);
+        assert_close(percentile(&values, 0.4), 29.0);
+        assert_close(percentile(&values, 0.95), 48.0);
+        assert_close(percentile(&values, 1.0), 50.0);
+
+        assert_close(percentile(&[7.5], 0.95), 7.5);
+        assert_eq!(percentile(&[], 0.5), None);
+    }
+
+    #[test]
+    fn test_nearest_rank_picks_a_value() {
+        let values = [15.0, 20.0, 35.0, 40.0, 50.0];
+        let nearest = |q| percentile_with(&values, q, PercentileMethod::NearestRank);
+        assert_close(nearest(0.0), 15.0);
+        assert_close(nearest(0.3), 20.0);
+        assert_close(nearest(0.4), 20.0);
+        assert_close(nearest(0.5), 35.0);
+        assert_close(nearest(1.0), 50.0);
+    }
+
+    #[test]
+    fn test_ties_and_out_of_range_quantiles() {
+        let values = [5.0, 5.0, 5.0, 9.0];
+        assert_close(percentile(&values, 0.5), 5.0);
+        assert_close(percentile(&values, 0.75), 6.0);
+        assert_close(percentile(&values, -1.0), 5.0);
+        assert_close(percentile(&values, 2.0), 9.0);
+    }
+}
@@ -0,0 +1,101 @@
+use crate::logging::query_logs;
+use crate::models::{HourStat, PingResult, TimeRange};
+use chrono::{DateTime, Duration, Local, Timelike, Utc};
+use std::path::Path;
+
+/// Average latency and loss per local hour of day
+pub fn hourly_profile<I>(results: I) -> [HourStat; 24]
+where
+    I: IntoIterator<Item = PingResult>,
+{
+    let mut latency_sums = [0.0; 24];
+    let mut profile: [HourStat; 24] = std::array::from_fn(|hour| HourStat::new(hour as u8));
+
+    for result in results {
+        let hour = result.timestamp.with_timezone(&Local).hour() as usize;
+        let stat = &mut profile[hour];
+        stat.total_pings += 1;
+        if result.success {
+            stat.successful_pings += 1;
+            if let Some(latency) = result.latency_ms {
+                latency_sums[hour] += latency;
+                stat.latency_samples += 1;
+            }
+        }
+    }
+
+    for (stat, sum) in profile.iter_mut().zip(latency_sums) {
+        if stat.latency_samples > 0 {
+            stat.avg_latency_ms = Some(sum / stat.latency_samples as f64);
+        }
+        if stat.total_pings > 0 {
+            let failed = stat.total_pings - stat.successful_pings;
+            stat.packet_loss_percent = failed as f64 / stat.total_pings as f64 * 100.0;
+        }
+    }
+
+    profile
+}
+
+/// Hour-of-day profile for a target over the `days` before `now`, from logs
+pub fn hourly_profile_from_logs(
+    log_dir: &Path,
+    target: &str,
+    days: u32,
+    now: DateTime<Utc>,
+) -> Result<[HourStat; 24], std::io::Error> {
+    let range = TimeRange::new(now - Duration::days(i64::from(days)), now);
+    Ok(hourly_profile(query_logs(log_dir, Some(target), None, range)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PingTarget;
+    use chrono::TimeZone;
+    use std::fs;
+    use std::io::Write;
+
+    #[test]
+    fn test_profile_shows_evening_congestion() {
+        let dir = std::env::temp_dir().join(format!("ping-profile-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let target = PingTarget::new("1.1.1.1".to_string(), "Cloudflare".to_string());
+        let now = Local.with_ymd_and_hms(2025, 3, 10, 0, 0, 0).unwrap();
+
+        // Three days of hourly samples, slow between 19:00 and 21:59
+        for day in 1..=3 {
+            let midnight = now - Duration::days(day);
+            let mut file =
+                fs::File::create(dir.join(format!("ping-{}.jsonl", midnight.format("%Y-%m-%d"))))
+                    .unwrap();
+            for hour in 0..24 {
+                let latency = if (19..22).contains(&hour) { 60.0 } else { 10.0 };
+                let mut result = PingResult::success(&target, latency, hour);
+                result.timestamp = (midnight + Duration::hours(hour as i64)).with_timezone(&Utc);
+                writeln!(file, "{}", serde_json::to_string(&result).unwrap()).unwrap();
+            }
+        }
+        // Older data outside the window is ignored
+        let mut old = PingResult::success(&target, 500.0, 0);
+        old.timestamp = (now - Duration::days(9)).with_timezone(&Utc);
+        let name = format!(
+            "ping-{}.jsonl",
+            (now - Duration::days(9)).format("%Y-%m-%d")
+        );
+        fs::write(dir.join(name), serde_json::to_string(&old).unwrap()).unwrap();
+
+        let profile =
+            hourly_profile_from_logs(&dir, "1.1.1.1", 3, now.with_timezone(&Utc)).unwrap();
+
+        assert_eq!(profile[0].total_pings, 3);
+        assert_eq!(profile[8].avg_latency_ms, Some(10.0));
+        assert_eq!(profile[20].avg_latency_ms, Some(60.0));
+        assert_eq!(profile[20].packet_loss_percent, 0.0);
+        let evening = profile[19..22].iter().filter_map(|h| h.avg_latency_ms);
+        let morning = profile[6..9].iter().filter_map(|h| h.avg_latency_ms);
+        assert!(evening.sum::<f64>() > morning.sum::<f64>() * 3.0);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
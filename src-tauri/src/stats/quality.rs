@@ -0,0 +1,201 @@
+use super::{LatencyBounds, StatsCalculator};
+use crate::logging::query_logs;
+use crate::models::{PingResult, PingStatistics, QualityPoint, QualityRating, TimeRange};
+use chrono::Duration;
+use std::path::Path;
+
+/// Most buckets one quality series may have
+pub const MAX_QUALITY_BUCKETS: i64 = 10_000;
+
+/// Connection quality from 0 (unusable) to 100 (perfect), `None` without pings
+///
+/// Average latency and jitter are graded onto 0-1 against the latency
+/// bounds and cost up to 40 and 20 points; packet loss then scales what is
+/// left, so 50% loss halves the score.
+pub fn quality_score(stats: &PingStatistics, bounds: &LatencyBounds) -> Option<f64> {
+    if stats.total_pings == 0 {
+        return None;
+    }
+    let latency = f64::from(bounds.normalize(stats.avg_latency_ms));
+    let jitter = stats
+        .jitter_ms
+        .map_or(0.0, |jitter| f64::from(bounds.normalize(Some(jitter))));
+    Some((100.0 - 40.0 * latency - 20.0 * jitter) * (1.0 - stats.loss_ratio))
+}
+
+/// Rating of a quality score, for people who don't read milliseconds
+pub fn quality_rating(score: f64) -> QualityRating {
+    if score >= 90.0 {
+        QualityRating::Excellent
+    } else if score >= 70.0 {
+        QualityRating::Good
+    } else if score >= 50.0 {
+        QualityRating::Fair
+    } else {
+        QualityRating::Poor
+    }
+}
+
+/// Quality of each `bucket` of `range`, oldest first
+///
+/// Buckets without results have no score, so charts show a gap rather than
+/// a dip.
+pub fn quality_timeseries<I>(
+    results: I,
+    range: TimeRange,
+    bucket: Duration,
+    bounds: &LatencyBounds,
+) -> Result<Vec<QualityPoint>, String>
+where
+    I: IntoIterator<Item = PingResult>,
+{
+    let bucket_ms = bucket.num_milliseconds();
+    if bucket_ms <= 0 {
+        return Err("Bucket must be positive".to_string());
+    }
+    let span_ms = (range.to - range.from).num_milliseconds().max(0);
+    let count = (span_ms + bucket_ms - 1) / bucket_ms;
+    if count > MAX_QUALITY_BUCKETS {
+        return Err(format!(
+            "Range holds {} buckets, at most {} are allowed",
+            count, MAX_QUALITY_BUCKETS
+        ));
+    }
+
+    let mut buckets: Vec<Option<StatsCalculator>> = (0..count).map(|_| None).collect();
+    for result in results {
+        if !range.contains(result.timestamp) {
+            continue;
+        }
+        let index = (result.timestamp - range.from).num_milliseconds() / bucket_ms;
+        buckets[index as usize]
+            .get_or_insert_with(StatsCalculator::new)
+            .update(&result);
+    }
+
+    Ok(buckets
+        .into_iter()
+        .enumerate()
+        .map(|(index, calculator)| {
+            let start = range.from + Duration::milliseconds(bucket_ms * index as i64);
+            let stats = calculator.and_then(|c| c.get_all_stats().into_iter().next());
+            let score = stats.as_ref().and_then(|s| quality_score(s, bounds));
+            QualityPoint {
+                start,
+                total_pings: stats.as_ref().map_or(0, |s| s.total_pings),
+                score,
+                rating: score.map(quality_rating),
+                avg_latency_ms: stats.as_ref().and_then(|s| s.avg_latency_ms),
+                jitter_ms: stats.as_ref().and_then(|s| s.jitter_ms),
+                packet_loss_percent: stats.as_ref().map(|s| s.packet_loss_percent),
+            }
+        })
+        .collect())
+}
+
+/// Quality series for a target over `range`, from logs
+pub fn quality_timeseries_from_logs(
+    log_dir: &Path,
+    target: &str,
+    range: TimeRange,
+    bucket: Duration,
+    bounds: &LatencyBounds,
+) -> Result<Vec<QualityPoint>, String> {
+    let results = query_logs(log_dir, Some(target), None, range)
+        .map_err(|e| format!("Failed to read logs: {}", e))?;
+    quality_timeseries(results, range, bucket, bounds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PingTarget;
+    use chrono::{TimeZone, Utc};
+    use std::fs;
+    use std::io::Write;
+
+    #[test]
+    fn test_degraded_period_dips_in_the_series() {
+        let dir = std::env::temp_dir().join(format!("ping-quality-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let target = PingTarget::new("1.1.1.1".to_string(), "Cloudflare".to_string());
+        let from = Utc.with_ymd_and_hms(2025, 3, 10, 12, 0, 0).unwrap();
+
+        // One ping every 10s for an hour; minutes 20-29 are slow and lossy
+        let mut file = fs::File::create(dir.join("ping-2025-03-10.jsonl")).unwrap();
+        for i in 0..360 {
+            let minute = i / 6;
+            let mut result = if (20..30).contains(&minute) {
+                if i % 3 == 0 {
+                    PingResult::failure(&target, "Timeout".to_string(), i)
+                } else {
+                    PingResult::success(&target, 120.0 + f64::from(i % 2) * 60.0, i)
+                }
+            } else {
+                PingResult::success(&target, 12.0 + f64::from(i % 2), i)
+            };
+            result.timestamp = from + Duration::seconds(i64::from(i) * 10);
+            writeln!(file, "{}", serde_json::to_string(&result).unwrap()).unwrap();
+        }
+
+        // An extra quarter hour with no data at the end
+        let range = TimeRange::new(from, from + Duration::minutes(75));
+        let bounds = LatencyBounds::new(50.0, 100.0);
+        let series =
+            quality_timeseries_from_logs(&dir, "1.1.1.1", range, Duration::minutes(5), &bounds)
+                .unwrap();
+
+        assert_eq!(series.len(), 15);
+        assert_eq!(series[1].start, from + Duration::minutes(5));
+        assert_eq!(series[0].total_pings, 30);
+        let ratings: Vec<Option<QualityRating>> = series.iter().map(|p| p.rating).collect();
+        assert_eq!(ratings[0], Some(QualityRating::Excellent));
+        assert_eq!(ratings[4], Some(QualityRating::Poor));
+        assert_eq!(ratings[5], Some(QualityRating::Poor));
+        assert_eq!(ratings[6], Some(QualityRating::Excellent));
+        assert!(series[12..].iter().all(|p| p.score.is_none()));
+
+        let score = |i: usize| series[i].score.unwrap();
+        assert!(score(4) < score(3) / 2.0);
+        assert!((score(0) - score(11)).abs() < 1.0);
+
+        assert!(quality_timeseries(Vec::new(), range, Duration::zero(), &bounds).is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_score_spans_perfect_to_unusable() {
+        let bounds = LatencyBounds::new(50.0, 100.0);
+        let stats = |total: u64, failed: u64, avg: Option<f64>, jitter: Option<f64>| {
+            let mut calculator = StatsCalculator::new();
+            let target = PingTarget::new("1.1.1.1".to_string(), String::new());
+            for i in 0..total {
+                let result = if i < failed {
+                    PingResult::failure(&target, "Timeout".to_string(), 0)
+                } else {
+                    PingResult::success(&target, avg.unwrap(), 0)
+                };
+                calculator.update(&result);
+            }
+            let mut stats = calculator.get_all_stats().remove(0);
+            stats.jitter_ms = jitter;
+            stats
+        };
+
+        assert_eq!(
+            quality_score(&stats(10, 0, Some(0.0), Some(0.0)), &bounds),
+            Some(100.0)
+        );
+        assert_eq!(
+            quality_score(&stats(10, 0, Some(100.0), Some(0.0)), &bounds),
+            Some(60.0)
+        );
+        assert_eq!(
+            quality_score(&stats(10, 5, Some(0.0), Some(0.0)), &bounds),
+            Some(50.0)
+        );
+        assert_eq!(quality_score(&stats(4, 4, None, None), &bounds), Some(0.0));
+        assert_eq!(quality_rating(89.9), QualityRating::Good);
+    }
+}
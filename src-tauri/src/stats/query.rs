@@ -0,0 +1,162 @@
+use crate::models::{AlarmInfo, AlarmReason, PingStatistics, StatField, StatFilter};
+use std::cmp::Ordering;
+
+/// Whether a target's statistics pass a filter, given the current alarms
+pub fn matches_filter(stats: &PingStatistics, filter: StatFilter, alarms: &[AlarmInfo]) -> bool {
+    let alarmed = |reason: Option<AlarmReason>| {
+        alarms
+            .iter()
+            .any(|a| a.target == stats.target && reason.map_or(true, |r| a.reason == r))
+    };
+
+    match filter {
+        StatFilter::Down => alarmed(Some(AlarmReason::Down)),
+        StatFilter::Alarming => alarmed(None),
+        StatFilter::LossAbove { percent } => stats.packet_loss_percent > percent,
+        StatFilter::LatencyAbove { ms } => stats.avg_latency_ms.is_some_and(|avg| avg > ms),
+    }
+}
+
+/// Sort statistics by a field
+///
+/// Targets without a value, e.g. no latency yet, come last in either
+/// direction. The sort is stable, so ties keep their order.
+pub fn sort_stats(stats: &mut [PingStatistics], sort_by: StatField, descending: bool) {
+    let direction = |ordering: Ordering| {
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    };
+    let by_value = |a: Option<f64>, b: Option<f64>| match (a, b) {
+        (Some(a), Some(b)) => direction(a.total_cmp(&b)),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    };
+
+    match sort_by {
+        StatField::Label => stats.sort_by(|a, b| {
+            direction(
+                a.display_label
+                    .to_lowercase()
+                    .cmp(&b.display_label.to_lowercase()),
+            )
+        }),
+        StatField::PacketLoss => {
+            stats.sort_by(|a, b| direction(a.packet_loss_percent.total_cmp(&b.packet_loss_percent)))
+        }
+        StatField::AvgLatency => stats.sort_by(|a, b| by_value(a.avg_latency_ms, b.avg_latency_ms)),
+        StatField::Jitter => stats.sort_by(|a, b| by_value(a.jitter_ms, b.jitter_ms)),
+        StatField::TotalPings => stats.sort_by(|a, b| direction(a.total_pings.cmp(&b.total_pings))),
+        StatField::LastPing => {
+            let millis = |s: &PingStatistics| s.last_ping.map(|t| t.timestamp_millis() as f64);
+            stats.sort_by(|a, b| by_value(millis(a), millis(b)))
+        }
+    }
+}
+
+/// Filter statistics, then sort what is left
+pub fn query_stats(
+    mut stats: Vec<PingStatistics>,
+    sort_by: StatField,
+    descending: bool,
+    filter: Option<StatFilter>,
+    alarms: &[AlarmInfo],
+) -> Vec<PingStatistics> {
+    if let Some(filter) = filter {
+        stats.retain(|s| matches_filter(s, filter, alarms));
+    }
+    sort_stats(&mut stats, sort_by, descending);
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn seeded() -> Vec<PingStatistics> {
+        [
+            ("1.1.1.1", "cloudflare", 0.0, Some(12.0)),
+            ("8.8.8.8", "Google", 25.0, Some(30.0)),
+            ("10.0.0.1", "Router", 100.0, None),
+            ("9.9.9.9", "Quad9", 5.0, Some(8.0)),
+        ]
+        .into_iter()
+        .map(|(target, label, loss, avg)| PingStatistics {
+            target: target.to_string(),
+            target_label: label.to_string(),
+            display_label: label.to_string(),
+            packet_loss_percent: loss,
+            avg_latency_ms: avg,
+            ..Default::default()
+        })
+        .collect()
+    }
+
+    fn targets(stats: &[PingStatistics]) -> Vec<&str> {
+        stats.iter().map(|s| s.target.as_str()).collect()
+    }
+
+    #[test]
+    fn test_sorting_keeps_missing_values_last() {
+        let slowest = query_stats(seeded(), StatField::AvgLatency, true, None, &[]);
+        assert_eq!(
+            targets(&slowest),
+            ["8.8.8.8", "1.1.1.1", "9.9.9.9", "10.0.0.1"]
+        );
+        let fastest = query_stats(seeded(), StatField::AvgLatency, false, None, &[]);
+        assert_eq!(
+            targets(&fastest),
+            ["9.9.9.9", "1.1.1.1", "8.8.8.8", "10.0.0.1"]
+        );
+
+        let by_label = query_stats(seeded(), StatField::Label, false, None, &[]);
+        assert_eq!(
+            targets(&by_label),
+            ["1.1.1.1", "8.8.8.8", "9.9.9.9", "10.0.0.1"]
+        );
+    }
+
+    #[test]
+    fn test_filters_narrow_before_sorting() {
+        let lossy = query_stats(
+            seeded(),
+            StatField::PacketLoss,
+            true,
+            Some(StatFilter::LossAbove { percent: 1.0 }),
+            &[],
+        );
+        assert_eq!(targets(&lossy), ["10.0.0.1", "8.8.8.8", "9.9.9.9"]);
+
+        let slow = Some(StatFilter::LatencyAbove { ms: 10.0 });
+        let slow = query_stats(seeded(), StatField::Label, true, slow, &[]);
+        assert_eq!(targets(&slow), ["8.8.8.8", "1.1.1.1"]);
+
+        let alarm = |target: &str, reason| AlarmInfo {
+            id: String::new(),
+            target: target.to_string(),
+            target_label: String::new(),
+            reason,
+            since: Utc::now(),
+            detail: String::new(),
+        };
+        let alarms = [
+            alarm("10.0.0.1", AlarmReason::Down),
+            alarm("8.8.8.8", AlarmReason::HighLoss),
+        ];
+        let down = query_stats(
+            seeded(),
+            StatField::Label,
+            false,
+            Some(StatFilter::Down),
+            &alarms,
+        );
+        assert_eq!(targets(&down), ["10.0.0.1"]);
+        let alarming = Some(StatFilter::Alarming);
+        let alarming = query_stats(seeded(), StatField::PacketLoss, false, alarming, &alarms);
+        assert_eq!(targets(&alarming), ["8.8.8.8", "10.0.0.1"]);
+    }
+}
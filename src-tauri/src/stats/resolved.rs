@@ -0,0 +1,168 @@
+use super::{ConfidenceThresholds, LatencyBounds, StatsCalculator};
+use crate::models::{PingResult, PingStatistics};
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+/// Most IPs a hostname keeps statistics for
+pub const MAX_IPS_PER_TARGET: usize = 16;
+
+/// Statistics of hostname targets per IP they resolved to
+///
+/// Results are recorded under their hostname and IP, so a CDN or anycast
+/// target whose edge changes shows how each edge performed. Beyond
+/// `MAX_IPS_PER_TARGET` IPs, the one probed least recently is forgotten.
+#[derive(Default)]
+pub struct ResolvedIpTracker {
+    /// IPs seen per hostname, in the order first seen, with the result
+    /// count when each was last probed
+    ips: HashMap<String, Vec<(IpAddr, u64)>>,
+    /// Results observed, for ordering the IPs by last use
+    observed: u64,
+    /// Statistics keyed by hostname and IP
+    stats: StatsCalculator,
+}
+
+/// Key of a hostname's statistics for one IP
+fn key(target: &str, ip: IpAddr) -> String {
+    format!("{} {}", target, ip)
+}
+
+impl ResolvedIpTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the thresholds used to grade latency
+    pub fn set_latency_bounds(&mut self, bounds: LatencyBounds) {
+        self.stats.set_latency_bounds(bounds);
+    }
+
+    /// Set the decimal places of `packet_loss_display`
+    pub fn set_loss_decimals(&mut self, decimals: u32) {
+        self.stats.set_loss_decimals(decimals);
+    }
+
+    /// Set the weight of each new result in `smoothed_loss_percent`
+    pub fn set_loss_alpha(&mut self, alpha: f64) {
+        self.stats.set_loss_alpha(alpha);
+    }
+
+    /// Set the sample counts that grade `sample_confidence`
+    pub fn set_confidence_thresholds(&mut self, thresholds: ConfidenceThresholds) {
+        self.stats.set_confidence_thresholds(thresholds);
+    }
+
+    /// Set the latencies kept per IP for the recent-window figures
+    pub fn set_recent_window(&mut self, window: usize) {
+        self.stats.set_recent_window(window);
+    }
+
+    /// Record a result under the IP it was probed at, if it was resolved
+    pub fn observe(&mut self, result: &PingResult) {
+        let Some(ip) = result.resolved_ip else {
+            return;
+        };
+        self.observed += 1;
+        let ips = self.ips.entry(result.target.clone()).or_default();
+        if let Some(seen) = ips.iter_mut().find(|(seen, _)| *seen == ip) {
+            seen.1 = self.observed;
+        } else {
+            if ips.len() >= MAX_IPS_PER_TARGET {
+                let oldest = (0..ips.len()).min_by_key(|&i| ips[i].1).unwrap_or(0);
+                let (evicted, _) = ips.remove(oldest);
+                self.stats.remove_target(&key(&result.target, evicted));
+            }
+            ips.push((ip, self.observed));
+        }
+        self.stats.update(&PingResult {
+            target: key(&result.target, ip),
+            ..result.clone()
+        });
+    }
+
+    /// Statistics of a hostname per IP, in the order the IPs were first seen
+    ///
+    /// Each entry's `target` is the IP.
+    pub fn breakdown(&self, target: &str) -> Vec<PingStatistics> {
+        let Some(ips) = self.ips.get(target) else {
+            return Vec::new();
+        };
+        ips.iter()
+            .filter_map(|&(ip, _)| {
+                let mut stats = self.stats.get_stats(&key(target, ip))?;
+                stats.target = ip.to_string();
+                Some(stats)
+            })
+            .collect()
+    }
+
+    /// Number of hostname and IP pairs, latencies held and approximate bytes
+    /// used
+    pub fn usage(&self) -> (usize, u64, usize) {
+        self.stats.usage()
+    }
+
+    /// Forget a hostname's statistics
+    pub fn remove_target(&mut self, target: &str) {
+        for (ip, _) in self.ips.remove(target).unwrap_or_default() {
+            self.stats.remove_target(&key(target, ip));
+        }
+    }
+
+    /// Forget all statistics
+    pub fn clear(&mut self) {
+        self.ips.clear();
+        self.stats.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PingTarget;
+
+    fn probed_at(target: &PingTarget, ip: [u8; 4]) -> PingResult {
+        let mut result = PingResult::success(target, 10.0, 1);
+        result.resolved_ip = Some(IpAddr::from(ip));
+        result
+    }
+
+    #[test]
+    fn test_statistics_are_kept_per_ip_in_first_seen_order() {
+        let mut tracker = ResolvedIpTracker::new();
+        let cdn = PingTarget::new("cdn.example".to_string(), "CDN".to_string());
+        tracker.observe(&probed_at(&cdn, [203, 0, 113, 2]));
+        tracker.observe(&probed_at(&cdn, [203, 0, 113, 1]));
+        tracker.observe(&probed_at(&cdn, [203, 0, 113, 2]));
+        tracker.observe(&PingResult::success(&cdn, 10.0, 1));
+
+        let breakdown = tracker.breakdown("cdn.example");
+        let ips: Vec<&str> = breakdown.iter().map(|s| s.target.as_str()).collect();
+        assert_eq!(ips, ["203.0.113.2", "203.0.113.1"]);
+        assert_eq!(breakdown[0].total_pings, 2);
+
+        tracker.remove_target("cdn.example");
+        assert!(tracker.breakdown("cdn.example").is_empty());
+        assert_eq!(tracker.usage().0, 0);
+    }
+
+    #[test]
+    fn test_least_recently_probed_ip_is_evicted_beyond_the_cap() {
+        let mut tracker = ResolvedIpTracker::new();
+        let cdn = PingTarget::new("cdn.example".to_string(), "CDN".to_string());
+        for i in 0..MAX_IPS_PER_TARGET as u8 {
+            tracker.observe(&probed_at(&cdn, [203, 0, 113, i]));
+        }
+        // The first IP is probed again, so the second is now the oldest
+        tracker.observe(&probed_at(&cdn, [203, 0, 113, 0]));
+        tracker.observe(&probed_at(&cdn, [198, 51, 100, 1]));
+
+        let breakdown = tracker.breakdown("cdn.example");
+        assert_eq!(breakdown.len(), MAX_IPS_PER_TARGET);
+        assert_eq!(tracker.usage().0, MAX_IPS_PER_TARGET);
+        assert_eq!(breakdown[0].target, "203.0.113.0");
+        assert_eq!(breakdown[0].total_pings, 2);
+        assert!(breakdown.iter().all(|s| s.target != "203.0.113.1"));
+        assert_eq!(breakdown[MAX_IPS_PER_TARGET - 1].target, "198.51.100.1");
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::VecDeque;
+
+/// Buffer of the most recent items, evicting the oldest beyond its capacity
+#[derive(Debug, Clone)]
+pub struct BoundedRing<T> {
+    capacity: usize,
+    items: VecDeque<T>,
+}
+
+impl<T> BoundedRing<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            items: VecDeque::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Change the capacity; shrinking drops the oldest items right away
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+        self.items.shrink_to(capacity);
+    }
+
+    /// Add an item as the newest, evicting the oldest if full
+    pub fn push(&mut self, item: T) {
+        self.items.push_back(item);
+        self.evict();
+    }
+
+    /// Take out the item at `index`, counted from the oldest
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        self.items.remove(index)
+    }
+
+    /// Newest item
+    pub fn back(&self) -> Option<&T> {
+        self.items.back()
+    }
+
+    /// Items from oldest to newest
+    pub fn iter(&self) -> std::collections::vec_deque::Iter<'_, T> {
+        self.items.iter()
+    }
+
+    /// Items from oldest to newest, mutably
+    pub fn iter_mut(&mut self) -> std::collections::vec_deque::IterMut<'_, T> {
+        self.items.iter_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    fn evict(&mut self) {
+        while self.items.len() > self.capacity {
+            self.items.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents(ring: &BoundedRing<u32>) -> Vec<u32> {
+        ring.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_push_evicts_oldest_beyond_capacity() {
+        let mut ring = BoundedRing::new(3);
+        for i in 0..5 {
+            ring.push(i);
+        }
+        assert_eq!(contents(&ring), vec![2, 3, 4]);
+        assert_eq!(ring.back(), Some(&4));
+
+        assert_eq!(ring.remove(0), Some(2));
+        ring.push(5);
+        assert_eq!(contents(&ring), vec![3, 4, 5]);
+
+        // A ring without capacity keeps nothing
+        let mut empty = BoundedRing::new(0);
+        empty.push(1);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_shrinking_capacity_trims_immediately() {
+        let mut ring = BoundedRing::new(5);
+        for i in 0..5 {
+            ring.push(i);
+        }
+
+        ring.set_capacity(2);
+        assert_eq!(ring.capacity(), 2);
+        assert_eq!(contents(&ring), vec![3, 4]);
+
+        // Growing keeps what is there and makes room for more
+        ring.set_capacity(4);
+        ring.push(5);
+        ring.push(6);
+        assert_eq!(contents(&ring), vec![3, 4, 5, 6]);
+        ring.push(7);
+        assert_eq!(ring.len(), 4);
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::HashSet;
+
+/// Most addresses a single template may expand to
+pub const MAX_TEMPLATE_EXPANSION: usize = 256;
+
+/// One piece of a parsed template
+enum Part {
+    Text(String),
+    Range(u64, u64),
+}
+
+/// Expand `{start..end}` ranges in an address template
+///
+/// `host{1..3}.example.com` gives `host1` to `host3`. Several ranges expand
+/// to every combination. Each `{}` in `label_template` is filled with the
+/// value of the matching range. Duplicate addresses are dropped, keeping the
+/// first.
+pub fn expand_template(
+    template: &str,
+    label_template: &str,
+) -> Result<Vec<(String, String)>, String> {
+    let parts = parse(template)?;
+
+    let mut total: usize = 1;
+    for part in &parts {
+        if let Part::Range(start, end) = part {
+            total = (end - start)
+                .checked_add(1)
+                .and_then(|len| usize::try_from(len).ok())
+                .and_then(|len| total.checked_mul(len))
+                .filter(|&n| n <= MAX_TEMPLATE_EXPANSION)
+                .ok_or_else(|| {
+                    format!(
+                        "Template expands to more than {} addresses",
+                        MAX_TEMPLATE_EXPANSION
+                    )
+                })?;
+        }
+    }
+
+    let mut combos: Vec<Vec<u64>> = vec![Vec::new()];
+    for part in &parts {
+        if let Part::Range(start, end) = part {
+            combos = combos
+                .into_iter()
+                .flat_map(|combo| {
+                    (*start..=*end).map(move |n| {
+                        let mut combo = combo.clone();
+                        combo.push(n);
+                        combo
+                    })
+                })
+                .collect();
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut expanded = Vec::with_capacity(total);
+    for values in combos {
+        let mut address = String::new();
+        let mut next_value = values.iter();
+        for part in &parts {
+            match part {
+                Part::Text(text) => address.push_str(text),
+                Part::Range(..) => {
+                    if let Some(n) = next_value.next() {
+                        address.push_str(&n.to_string());
+                    }
+                }
+            }
+        }
+
+        if seen.insert(address.clone()) {
+            let label = fill_label(label_template, &values);
+            expanded.push((address, label));
+        }
+    }
+
+    Ok(expanded)
+}
+
+/// Split a template into literal text and `{start..end}` ranges
+fn parse(template: &str) -> Result<Vec<Part>, String> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+
+    while let Some(open) = rest.find('{') {
+        let close = rest[open..]
+            .find('}')
+            .map(|i| open + i)
+            .ok_or_else(|| format!("Unclosed '{{' in template: {}", template))?;
+
+        if open > 0 {
+            parts.push(Part::Text(rest[..open].to_string()));
+        }
+
+        let range = &rest[open + 1..close];
+        let (start, end) = range
+            .split_once("..")
+            .and_then(|(start, end)| Some((start.trim().parse().ok()?, end.trim().parse().ok()?)))
+            .ok_or_else(|| format!("Invalid range '{{{}}}' in template", range))?;
+        if start > end {
+            return Err(format!("Range start is after its end: {{{}}}", range));
+        }
+        parts.push(Part::Range(start, end));
+
+        rest = &rest[close + 1..];
+    }
+
+    if !rest.is_empty() {
+        parts.push(Part::Text(rest.to_string()));
+    }
+
+    Ok(parts)
+}
+
+/// Replace each `{}` in a label with the next range value
+fn fill_label(label_template: &str, values: &[u64]) -> String {
+    let mut label = String::new();
+    let mut pieces = label_template.split("{}");
+    let mut values = values.iter();
+
+    if let Some(first) = pieces.next() {
+        label.push_str(first);
+    }
+    for piece in pieces {
+        match values.next() {
+            Some(n) => label.push_str(&n.to_string()),
+            None => label.push_str("{}"),
+        }
+        label.push_str(piece);
+    }
+
+    label
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_template() {
+        let expanded = expand_template("host{1..3}.example.com", "Host {}").unwrap();
+        assert_eq!(
+            expanded,
+            vec![
+                ("host1.example.com".to_string(), "Host 1".to_string()),
+                ("host2.example.com".to_string(), "Host 2".to_string()),
+                ("host3.example.com".to_string(), "Host 3".to_string()),
+            ]
+        );
+
+        let expanded = expand_template("10.0.{0..1}.{1..2}", "Rack {} node {}").unwrap();
+        assert_eq!(expanded.len(), 4);
+        assert_eq!(
+            expanded[3],
+            ("10.0.1.2".to_string(), "Rack 1 node 2".to_string())
+        );
+
+        // Plain addresses pass through and duplicates are dropped
+        assert_eq!(
+            expand_template("1.1.1.1", "One").unwrap(),
+            vec![("1.1.1.1".to_string(), "One".to_string())]
+        );
+        let expanded = expand_template("a{1..1}b{1..1}", "x").unwrap();
+        assert_eq!(expanded, vec![("a1b1".to_string(), "x".to_string())]);
+        assert!(expand_template("host{3..1}", "").is_err());
+        assert!(expand_template("host{1..", "").is_err());
+        assert!(expand_template("host{a..b}", "").is_err());
+    }
+
+    #[test]
+    fn test_expansion_size_guard() {
+        let limit = MAX_TEMPLATE_EXPANSION as u64;
+        assert_eq!(
+            expand_template(&format!("host{{1..{}}}", limit), "")
+                .unwrap()
+                .len(),
+            MAX_TEMPLATE_EXPANSION
+        );
+        assert!(expand_template(&format!("host{{1..{}}}", limit + 1), "").is_err());
+        assert!(expand_template("{1..100}.{1..100}", "").is_err());
+        assert!(expand_template("{0..18446744073709551615}", "").is_err());
+    }
+}
@@ -0,0 +1,461 @@
+use crate::models::{
+    AppConfig, BackendKind, ConfigIssue, ConfigIssueKind, EscalationAction, PingResult,
+    PingTarget, SchedulingMode,
+};
+use crate::ping::{check_zone, encode_query, is_valid_resolver};
+use crate::processing::build_processor;
+use crate::schema;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::Path;
+
+/// Shortest allowed ping interval
+pub const MIN_PING_INTERVAL_MS: u64 = 100;
+/// Longest allowed startup grace
+pub const MAX_STARTUP_GRACE_MS: u64 = 3_600_000;
+
+/// Check that an address is an IP address or a well-formed hostname
+///
+/// Link-local IPv6 addresses need a zone, e.g. `fe80::1%eth0`.
+pub fn validate_address(address: &str) -> Result<(), String> {
+    if address.is_empty() {
+        return Err("Address cannot be empty".to_string());
+    }
+    if address.contains('%') || address.parse::<IpAddr>().is_ok() {
+        return check_zone(address);
+    }
+    if address.len() > 253 {
+        return Err("Hostname is longer than 253 characters".to_string());
+    }
+
+    let hostname = address.strip_suffix('.').unwrap_or(address);
+    for label in hostname.split('.') {
+        let valid = !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+        if !valid {
+            return Err(format!(
+                "'{}' is not a valid IP address or hostname",
+                address
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Warning for a target whose label another of `targets` already has
+pub fn duplicate_label_warning(targets: &[PingTarget], target: &PingTarget) -> Option<String> {
+    let other = targets
+        .iter()
+        .find(|t| t.id != target.id && t.label_key() == target.label_key())?;
+    Some(format!(
+        "{} is also the label of {}; both are shown with their address",
+        target.label, other.address
+    ))
+}
+
+/// Validate a configuration without applying it
+pub fn validate_config(config: &AppConfig, log_dir: &Path) -> Vec<ConfigIssue> {
+    let mut issues = Vec::new();
+
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut seen_labels: HashMap<String, usize> = HashMap::new();
+    for (i, target) in config.targets.iter().enumerate() {
+        if let Err(message) = validate_address(&target.address) {
+            issues.push(ConfigIssue::new(
+                ConfigIssueKind::InvalidAddress,
+                format!("targets[{}].address", i),
+                format!("{}: {}", target.label, message),
+            ));
+        }
+
+        if let Some(ref dns) = target.dns_query {
+            let message = if encode_query(0, &dns.name).is_err() {
+                Some(format!("{}: invalid DNS name {:?}", target.label, dns.name))
+            } else if !is_valid_resolver(dns.resolver) {
+                Some(format!("{}: {} cannot be a resolver", target.label, dns.resolver))
+            } else {
+                None
+            };
+            if let Some(message) = message {
+                issues.push(ConfigIssue::new(
+                    ConfigIssueKind::InvalidValue,
+                    format!("targets[{}].dns_query", i),
+                    message,
+                ));
+            }
+        }
+
+        if target.expected_latency_ms.is_some_and(|ms| !(ms > 0.0 && ms.is_finite())) {
+            issues.push(ConfigIssue::new(
+                ConfigIssueKind::InvalidValue,
+                format!("targets[{}].expected_latency_ms", i),
+                format!("{}: expected latency must be greater than 0", target.label),
+            ));
+        }
+
+        if target.dont_fragment && config.backend == BackendKind::Native {
+            issues.push(ConfigIssue::new(
+                ConfigIssueKind::InvalidValue,
+                format!("targets[{}].dont_fragment", i),
+                format!("{}: don't-fragment needs the system backend", target.label),
+            ));
+        }
+
+        if let Some(first) = seen_labels.get(&target.label_key()) {
+            issues.push(ConfigIssue::new(
+                ConfigIssueKind::DuplicateLabel,
+                format!("targets[{}].label", i),
+                format!(
+                    "{} is also the label of targets[{}]; both are shown with their address",
+                    target.label, first
+                ),
+            ));
+        } else {
+            seen_labels.insert(target.label_key(), i);
+        }
+
+        let key = target.address.to_lowercase();
+        if let Some(first) = seen.get(&key) {
+            issues.push(ConfigIssue::new(
+                ConfigIssueKind::DuplicateAddress,
+                format!("targets[{}].address", i),
+                format!("{} is already used by targets[{}]", target.address, first),
+            ));
+        } else {
+            seen.insert(key, i);
+        }
+    }
+
+    for (i, rule) in config.quorum_alerts.iter().enumerate() {
+        if rule.quorum == 0 || rule.quorum > rule.target_ids.len() {
+            issues.push(ConfigIssue::new(
+                ConfigIssueKind::InvalidValue,
+                format!("quorum_alerts[{}].quorum", i),
+                format!(
+                    "{}: quorum must be between 1 and the {} watched targets",
+                    rule.name,
+                    rule.target_ids.len()
+                ),
+            ));
+        }
+        if let Some(id) = rule
+            .target_ids
+            .iter()
+            .find(|id| !config.targets.iter().any(|t| t.id == **id))
+        {
+            issues.push(ConfigIssue::new(
+                ConfigIssueKind::InvalidValue,
+                format!("quorum_alerts[{}].target_ids", i),
+                format!("{}: no target has id {}", rule.name, id),
+            ));
+        }
+    }
+
+    for (i, tier) in config.escalation_tiers.iter().enumerate() {
+        if let EscalationAction::Webhook { ref url } = tier.action {
+            if !url.starts_with("http://") {
+                issues.push(ConfigIssue::new(
+                    ConfigIssueKind::InvalidValue,
+                    format!("escalation_tiers[{}].action.url", i),
+                    format!("Only http:// webhook URLs are supported: {}", url),
+                ));
+            }
+        }
+    }
+
+    for (i, processor) in config.result_processors.iter().enumerate() {
+        if let Err(e) = build_processor(processor) {
+            issues.push(ConfigIssue::new(
+                ConfigIssueKind::InvalidValue,
+                format!("result_processors[{}]", i),
+                e,
+            ));
+        }
+    }
+
+    if config.startup_grace_ms > MAX_STARTUP_GRACE_MS {
+        issues.push(ConfigIssue::new(
+            ConfigIssueKind::InvalidValue,
+            "startup_grace_ms",
+            "The startup grace can be at most an hour",
+        ));
+    }
+
+    if config.ping_interval_ms < MIN_PING_INTERVAL_MS {
+        issues.push(ConfigIssue::new(
+            ConfigIssueKind::IntervalTooLow,
+            "ping_interval_ms",
+            format!("Interval must be at least {}ms", MIN_PING_INTERVAL_MS),
+        ));
+    }
+    if config.interval_jitter_ms > 0 && config.interval_jitter_ms >= config.ping_interval_ms {
+        issues.push(ConfigIssue::new(
+            ConfigIssueKind::InvalidValue,
+            "interval_jitter_ms",
+            "Jitter must be less than the ping interval",
+        ));
+    }
+    if config.timeout_ms == 0 {
+        issues.push(ConfigIssue::new(
+            ConfigIssueKind::InvalidValue,
+            "timeout_ms",
+            "Timeout must be greater than 0",
+        ));
+    }
+    if config.max_outstanding_probes == 0 {
+        issues.push(ConfigIssue::new(
+            ConfigIssueKind::InvalidValue,
+            "max_outstanding_probes",
+            "At least one probe per target must be allowed",
+        ));
+    }
+    if !(config.loss_smoothing_alpha > 0.0 && config.loss_smoothing_alpha <= 1.0) {
+        issues.push(ConfigIssue::new(
+            ConfigIssueKind::InvalidValue,
+            "loss_smoothing_alpha",
+            "Smoothing alpha must be greater than 0 and at most 1",
+        ));
+    }
+    if !(config.loss_alarm_percent > 0.0 && config.loss_alarm_percent <= 100.0) {
+        issues.push(ConfigIssue::new(
+            ConfigIssueKind::InvalidValue,
+            "loss_alarm_percent",
+            "Loss alarm threshold must be greater than 0 and at most 100",
+        ));
+    }
+    if config.max_history_size == 0 {
+        issues.push(ConfigIssue::new(
+            ConfigIssueKind::InvalidValue,
+            "max_history_size",
+            "History size must be greater than 0",
+        ));
+    }
+    if config.outage_history_size == 0 {
+        issues.push(ConfigIssue::new(
+            ConfigIssueKind::InvalidValue,
+            "outage_history_size",
+            "Outage history size must be greater than 0",
+        ));
+    }
+    if config.error_history_size == 0 {
+        issues.push(ConfigIssue::new(
+            ConfigIssueKind::InvalidValue,
+            "error_history_size",
+            "Error history size must be greater than 0",
+        ));
+    }
+    if config.scheduling_mode == SchedulingMode::RoundRobin && config.round_robin_batch_size == 0 {
+        issues.push(ConfigIssue::new(
+            ConfigIssueKind::InvalidValue,
+            "round_robin_batch_size",
+            "At least one target must be probed per interval",
+        ));
+    }
+    if config.max_pings_per_minute == Some(0) {
+        issues.push(ConfigIssue::new(
+            ConfigIssueKind::InvalidValue,
+            "max_pings_per_minute",
+            "The ping budget must allow at least one ping per minute",
+        ));
+    }
+    if config.confidence_low_samples > config.confidence_high_samples {
+        issues.push(ConfigIssue::new(
+            ConfigIssueKind::InvalidValue,
+            "confidence_high_samples",
+            "High confidence cannot need fewer samples than low confidence",
+        ));
+    }
+    if config.max_concurrent_probes == 0 {
+        issues.push(ConfigIssue::new(
+            ConfigIssueKind::InvalidValue,
+            "max_concurrent_probes",
+            "At least one target must be probed at a time",
+        ));
+    }
+    if let Some(ref fields) = config.result_emit_fields {
+        let known = schema::field_names::<PingResult>();
+        if let Some(unknown) = fields.iter().find(|field| !known.contains(field)) {
+            issues.push(ConfigIssue::new(
+                ConfigIssueKind::InvalidValue,
+                "result_emit_fields",
+                format!("{} is not a ping result field", unknown),
+            ));
+        }
+    }
+    if config.max_memory_mb == Some(0) {
+        issues.push(ConfigIssue::new(
+            ConfigIssueKind::InvalidValue,
+            "max_memory_mb",
+            "Memory budget must be greater than 0",
+        ));
+    }
+    if let Some(ref url) = config.pushgateway_url {
+        if !url.starts_with("http://") {
+            issues.push(ConfigIssue::new(
+                ConfigIssueKind::InvalidValue,
+                "pushgateway_url",
+                "Only http:// Pushgateway URLs are supported",
+            ));
+        }
+        if config.push_interval_secs == 0 {
+            issues.push(ConfigIssue::new(
+                ConfigIssueKind::InvalidValue,
+                "push_interval_secs",
+                "Push interval must be greater than 0",
+            ));
+        }
+    }
+
+    if !is_writable_dir(log_dir) {
+        issues.push(ConfigIssue::new(
+            ConfigIssueKind::LogDirUnwritable,
+            "log_dir",
+            format!("Log directory {} is not writable", log_dir.display()),
+        ));
+    }
+
+    issues
+}
+
+/// Check a configuration before applying it, failing with its errors
+///
+/// Duplicate labels are only a warning and let it through.
+pub fn check_config(config: &AppConfig, log_dir: &Path) -> Result<(), String> {
+    let errors: Vec<String> = validate_config(config, log_dir)
+        .into_iter()
+        .filter(|issue| issue.kind != ConfigIssueKind::DuplicateLabel)
+        .map(|issue| format!("{}: {}", issue.field, issue.message))
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+/// Whether `dir` is, or could be created as, a writable directory
+///
+/// Only inspects metadata; nothing is created.
+fn is_writable_dir(dir: &Path) -> bool {
+    for ancestor in dir.ancestors() {
+        if let Ok(metadata) = ancestor.metadata() {
+            return metadata.is_dir() && !metadata.permissions().readonly();
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PingTarget;
+
+    fn kinds(issues: &[ConfigIssue]) -> Vec<ConfigIssueKind> {
+        issues.iter().map(|issue| issue.kind).collect()
+    }
+
+    #[test]
+    fn test_default_config_is_valid() {
+        let issues = validate_config(&AppConfig::default(), &std::env::temp_dir());
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+
+    #[test]
+    fn test_reports_each_issue_kind() {
+        let mut config = AppConfig::default();
+        config
+            .targets
+            .push(PingTarget::new("bad host!".to_string(), "Bad".to_string()));
+        config
+            .targets
+            .push(PingTarget::new("1.1.1.1".to_string(), "Again".to_string()));
+        config.ping_interval_ms = 50;
+        config.timeout_ms = 0;
+        let file = std::env::temp_dir().join(format!("ping-validate-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&file, "").unwrap();
+
+        let issues = validate_config(&config, &file.join("logs"));
+
+        assert_eq!(
+            kinds(&issues),
+            vec![
+                ConfigIssueKind::InvalidAddress,
+                ConfigIssueKind::DuplicateAddress,
+                ConfigIssueKind::IntervalTooLow,
+                ConfigIssueKind::InvalidValue,
+                ConfigIssueKind::LogDirUnwritable,
+            ]
+        );
+        assert_eq!(issues[0].field, "targets[2].address");
+        assert_eq!(issues[1].field, "targets[3].address");
+        assert_eq!(issues[1].message, "1.1.1.1 is already used by targets[0]");
+        assert_eq!(issues[3].field, "timeout_ms");
+
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn test_duplicate_labels_are_reported() {
+        let mut config = AppConfig::default();
+        config
+            .targets
+            .push(PingTarget::new("9.9.9.9".to_string(), " google dns".to_string()));
+
+        let issues = validate_config(&config, &std::env::temp_dir());
+        assert_eq!(kinds(&issues), vec![ConfigIssueKind::DuplicateLabel]);
+        assert_eq!(issues[0].field, "targets[2].label");
+        assert!(issues[0].message.contains("targets[1]"));
+
+        // Only a warning, so the config can still be applied
+        assert_eq!(check_config(&config, &std::env::temp_dir()), Ok(()));
+        config.ping_interval_ms = 50;
+        let error = check_config(&config, &std::env::temp_dir()).unwrap_err();
+        assert!(error.starts_with("ping_interval_ms: "), "{}", error);
+    }
+
+    #[test]
+    fn test_dont_fragment_is_rejected_for_the_native_backend() {
+        let mut config = AppConfig::default();
+        config.targets[1].dont_fragment = true;
+        assert!(validate_config(&config, &std::env::temp_dir()).is_empty());
+
+        config.backend = BackendKind::Native;
+        let issues = validate_config(&config, &std::env::temp_dir());
+        assert_eq!(kinds(&issues), vec![ConfigIssueKind::InvalidValue]);
+        assert_eq!(issues[0].field, "targets[1].dont_fragment");
+    }
+
+    #[test]
+    fn test_result_emit_fields_must_name_result_fields() {
+        let mut config = AppConfig {
+            result_emit_fields: Some(vec!["target".to_string(), "latency_ms".to_string()]),
+            ..AppConfig::default()
+        };
+        assert!(validate_config(&config, &std::env::temp_dir()).is_empty());
+
+        config.result_emit_fields = Some(vec!["latency".to_string()]);
+        let issues = validate_config(&config, &std::env::temp_dir());
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].field, "result_emit_fields");
+        assert_eq!(issues[0].message, "latency is not a ping result field");
+    }
+
+    #[test]
+    fn test_validate_address() {
+        assert!(validate_address("1.1.1.1").is_ok());
+        assert!(validate_address("2606:4700:4700::1111").is_ok());
+        assert!(validate_address("dns.google").is_ok());
+        assert!(validate_address("example.com.").is_ok());
+        assert!(validate_address("").is_err());
+        assert!(validate_address("-bad.example.com").is_err());
+        assert!(validate_address("bad..example.com").is_err());
+        assert!(validate_address("has space.com").is_err());
+        assert!(validate_address("fe80::1%eth0").is_ok());
+        assert!(validate_address("fe80::1").is_err());
+        assert!(validate_address("host%eth0").is_err());
+    }
+}
@@ -69,6 +69,7 @@ async function init() {
     await loadTargets();
     await loadConfig();
     await loadPresets();
+    await loadStats();
     
     // Initialize chart
     initChart();
@@ -93,6 +94,15 @@ async function loadTargets() {
     }
 }
 
+// Load the full statistics snapshot; stats-update only carries changes
+async function loadStats() {
+    try {
+        handleStatsUpdate(await invoke('get_statistics'));
+    } catch (error) {
+        console.error('Failed to load statistics:', error);
+    }
+}
+
 // Load configuration
 async function loadConfig() {
     try {
@@ -230,6 +240,24 @@ async function setupTauriListeners() {
         const stats = event.payload;
         handleStatsUpdate(stats);
     });
+
+    // Listen for sound-enabled targets going down
+    await listen('play-sound', () => {
+        playAlertSound();
+    });
+}
+
+// Play a short two-tone beep
+function playAlertSound() {
+    const audio = new AudioContext();
+    const oscillator = audio.createOscillator();
+    oscillator.type = 'square';
+    oscillator.frequency.setValueAtTime(880, audio.currentTime);
+    oscillator.frequency.setValueAtTime(660, audio.currentTime + 0.2);
+    oscillator.connect(audio.destination);
+    oscillator.onended = () => audio.close();
+    oscillator.start();
+    oscillator.stop(audio.currentTime + 0.4);
 }
 
 // Handle incoming ping result
@@ -325,7 +353,7 @@ function rebuildChart() {
             }
             
             datasets.push({
-                label: target.label,
+                label: target.display_label || target.label,
                 data: data.data,
                 borderColor: state.colors[index % state.colors.length],
                 backgroundColor: state.colors[index % state.colors.length] + '20',
@@ -402,7 +430,7 @@ function updateStatsDisplay() {
         ? stats.avg_latency_ms.toFixed(1) : '--';
     elements.statJitter.textContent = stats.jitter_ms !== null
         ? stats.jitter_ms.toFixed(1) : '--';
-    elements.statLoss.textContent = stats.packet_loss_percent.toFixed(1);
+    elements.statLoss.textContent = stats.packet_loss_display;
     elements.statTotal.textContent = stats.total_pings;
     console.log('Display updated successfully');
 }